@@ -0,0 +1,189 @@
+//! `#[derive(FrameCodec)]` for `futures_codec`, re-exported there under the
+//! `derive` feature.
+//!
+//! See the documentation of the `FrameCodec` trait in `futures_codec`.
+
+extern crate proc_macro;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, LitStr, Type};
+
+#[proc_macro_derive(FrameCodec, attributes(frame))]
+pub fn derive_frame_codec(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Options of a `#[frame(...)]` attribute
+#[derive(Default, Clone, Copy)]
+struct Options {
+    little: Option<bool>,
+    prefix: Option<Prefix>,
+}
+
+#[derive(Clone, Copy)]
+enum Prefix {
+    U8,
+    U16,
+    U32,
+    Varint,
+}
+
+fn parse_options(attrs: &[Attribute], mut options: Options) -> syn::Result<Options> {
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("frame")) {
+        attr.parse_nested_meta(|meta| {
+            let value: LitStr = meta.value()?.parse()?;
+            if meta.path.is_ident("endian") {
+                options.little = Some(match value.value().as_str() {
+                    "big" => false,
+                    "little" => true,
+                    _ => return Err(Error::new_spanned(value, "expected \"big\" or \"little\"")),
+                });
+            } else if meta.path.is_ident("prefix") {
+                options.prefix = Some(match value.value().as_str() {
+                    "u8" => Prefix::U8,
+                    "u16" => Prefix::U16,
+                    "u32" => Prefix::U32,
+                    "varint" => Prefix::Varint,
+                    _ => {
+                        let msg = "expected \"u8\", \"u16\", \"u32\" or \"varint\"";
+                        return Err(Error::new_spanned(value, msg));
+                    }
+                });
+            } else {
+                return Err(meta.error("expected `endian` or `prefix`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+/// Expression for the `LengthPrefix` of a field
+fn length_prefix(options: Options) -> TokenStream {
+    let little = options.little.unwrap_or(false);
+    let variant = match (options.prefix.unwrap_or(Prefix::U16), little) {
+        (Prefix::U8, _) => quote!(U8),
+        (Prefix::U16, false) => quote!(U16Be),
+        (Prefix::U16, true) => quote!(U16Le),
+        (Prefix::U32, false) => quote!(U32Be),
+        (Prefix::U32, true) => quote!(U32Le),
+        (Prefix::Varint, _) => quote!(Varint),
+    };
+    quote!(::futures_codec::LengthPrefix::#variant)
+}
+
+/// Expressions reading and writing a field of type `ty`, the written value
+/// being `value`
+fn field_code(
+    ty: &Type,
+    options: Options,
+    value: &TokenStream,
+) -> syn::Result<(TokenStream, TokenStream)> {
+    let name = match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string()),
+        _ => None,
+    };
+    let little = options.little.unwrap_or(false);
+    let prefix = length_prefix(options);
+    let private = quote!(::futures_codec::__private);
+    Ok(match name.as_deref() {
+        Some("u8") | Some("i8") | Some("bool") => {
+            let method = format_ident!("{}", name.unwrap());
+            (
+                quote!(reader.#method()?),
+                quote!(#private::put_u8(dst, #value as u8)),
+            )
+        }
+        Some(int @ "u16") | Some(int @ "i16") | Some(int @ "u32") | Some(int @ "i32")
+        | Some(int @ "u64") | Some(int @ "i64") | Some(int @ "f32") | Some(int @ "f64") => {
+            let read = format_ident!("{}", int);
+            let write = format_ident!("put_{}", int);
+            (
+                quote!(reader.#read(#little)?),
+                quote!(#private::#write(dst, #value, #little)),
+            )
+        }
+        Some("String") => (
+            quote!(reader.string(#prefix)?),
+            quote!(#private::put_bytes(dst, #value.as_bytes(), #prefix)?),
+        ),
+        Some("Bytes") => (
+            quote!(reader.bytes(#prefix)?),
+            quote!(#private::put_bytes(dst, &#value[..], #prefix)?),
+        ),
+        Some("Vec") => (
+            quote!(reader.bytes(#prefix)?.to_vec()),
+            quote!(#private::put_bytes(dst, &#value[..], #prefix)?),
+        ),
+        _ => {
+            let msg = "unsupported field type, expected an integer, float, bool, String, Bytes or Vec<u8>";
+            return Err(Error::new_spanned(ty, msg));
+        }
+    })
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new(
+                Span::call_site(),
+                "FrameCodec can only be derived for structs",
+            ))
+        }
+    };
+    let options = parse_options(&input.attrs, Options::default())?;
+
+    let mut reads = Vec::new();
+    let mut writes = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = syn::Index::from(i);
+                quote!(#index)
+            }
+        };
+        let options = parse_options(&field.attrs, options)?;
+        let (read, write) = field_code(&field.ty, options, &quote!(self.#member))?;
+        reads.push(quote!(#member: #read));
+        writes.push(write);
+    }
+    let construct = match fields {
+        Fields::Unit => quote!(#name),
+        _ => quote!(#name { #(#reads,)* }),
+    };
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::futures_codec::FrameCodec for #name #ty_generics #where_clause {
+            fn decode_frame(
+                src: &[u8],
+            ) -> ::std::result::Result<::std::option::Option<(Self, usize)>, ::futures_codec::CodecError> {
+                #[allow(unused_mut)]
+                let mut reader = ::futures_codec::__private::Reader::new(src);
+                let frame = (|| -> ::std::result::Result<Self, ::futures_codec::__private::ReadError> {
+                    Ok(#construct)
+                })();
+                reader.finish(frame)
+            }
+
+            fn encode_frame(
+                &self,
+                dst: &mut ::futures_codec::__private::BytesMut,
+            ) -> ::std::result::Result<(), ::futures_codec::CodecError> {
+                #(#writes;)*
+                Ok(())
+            }
+        }
+    })
+}
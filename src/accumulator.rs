@@ -0,0 +1,258 @@
+use super::{Decoder, IncompleteEof};
+
+use bytes::BytesMut;
+use std::io::{Error, ErrorKind};
+
+/// The buffer management of the framed readers without any I/O.
+///
+/// Bytes are appended with `extend` as they arrive from wherever, and frames
+/// taken out with `decode`. At the end of the input, `decode_eof` returns the
+/// remaining frames and handles an incomplete frame left over as configured
+/// with `on_incomplete_eof`. `FramedRead` decodes by the same rules.
+///
+/// # Example
+/// ```
+/// use futures_codec::{FrameAccumulator, LinesCodec};
+///
+/// let mut frames = FrameAccumulator::new(LinesCodec::new());
+/// frames.extend(b"Hello\nWor");
+/// assert_eq!(frames.decode().unwrap().unwrap(), "Hello\n");
+/// assert!(frames.decode().unwrap().is_none());
+///
+/// frames.extend(b"ld");
+/// assert!(frames.decode_eof().is_err());
+/// ```
+#[derive(Debug)]
+pub struct FrameAccumulator<D> {
+    decoder: D,
+    frames: DecodeBuffer,
+}
+
+impl<D: Decoder> FrameAccumulator<D> {
+    /// Create an accumulator with an empty buffer that grows as needed
+    pub fn new(decoder: D) -> Self {
+        Self {
+            decoder,
+            frames: DecodeBuffer::new(BytesMut::new()),
+        }
+    }
+
+    /// Allocate the buffer once with room for `capacity` bytes, and fail
+    /// decoding with `InvalidData` once the buffered bytes fill it without
+    /// making up a complete frame, as `FramedRead::fixed_capacity` does
+    pub fn fixed_capacity(mut self, capacity: usize) -> Self {
+        self.frames.set_capacity(capacity);
+        self
+    }
+
+    /// What `decode_eof` does with bytes left over that don't make up a
+    /// frame, as `FramedRead::on_incomplete_eof`
+    pub fn on_incomplete_eof(mut self, policy: IncompleteEof) -> Self {
+        self.frames.incomplete_eof = policy;
+        self
+    }
+
+    /// Append received bytes
+    pub fn extend(&mut self, data: &[u8]) {
+        self.frames.buffer.extend_from_slice(data);
+    }
+
+    /// Decode the next frame from the buffered bytes, if it is complete
+    pub fn decode(&mut self) -> Result<Option<D::Item>, D::Error> {
+        let item = self.frames.decode(&mut self.decoder, false)?;
+        if item.is_none() {
+            let needed = self.decoder.bytes_needed(&self.frames.buffer);
+            self.frames.check_space(needed)?;
+        }
+        Ok(item)
+    }
+
+    /// Decode the next frame after the input has ended
+    ///
+    /// Returns `None` once the buffer is empty. Bytes that do not make up a
+    /// frame fail with `UnexpectedEof` by default, see `on_incomplete_eof`.
+    pub fn decode_eof(&mut self) -> Result<Option<D::Item>, D::Error> {
+        match self.frames.decode(&mut self.decoder, true)? {
+            Some(item) => Ok(Some(item)),
+            None => self.frames.end().map(|()| None),
+        }
+    }
+
+    /// The buffered bytes
+    pub fn buffer(&self) -> &BytesMut {
+        &self.frames.buffer
+    }
+
+    /// The buffered bytes, for filling the buffer in place
+    ///
+    /// As the bytes may be changed anywhere, the decoder is reset and the
+    /// next `decode` starts over at the front of the buffer.
+    pub fn buffer_mut(&mut self) -> &mut BytesMut {
+        self.frames.decode_at = 0;
+        self.decoder.reset();
+        &mut self.frames.buffer
+    }
+
+    /// Get a reference to the Decoder
+    pub fn decoder(&self) -> &D {
+        &self.decoder
+    }
+
+    /// Get a mutable reference to the Decoder
+    pub fn decoder_mut(&mut self) -> &mut D {
+        &mut self.decoder
+    }
+
+    /// Release the Decoder and any buffered bytes
+    pub fn into_parts(self) -> (D, BytesMut) {
+        (self.decoder, self.frames.buffer)
+    }
+}
+
+/// The read buffer and the rules for decoding from it, shared by
+/// `FrameAccumulator` and `FramedRead`
+#[derive(Debug)]
+pub(crate) struct DecodeBuffer {
+    pub(crate) buffer: BytesMut,
+    /// Buffer length below which `decode` is known to return `None`
+    pub(crate) decode_at: usize,
+    /// Size the buffer may not grow beyond
+    pub(crate) capacity: Option<usize>,
+    pub(crate) incomplete_eof: IncompleteEof,
+}
+
+impl DecodeBuffer {
+    pub(crate) fn new(buffer: BytesMut) -> Self {
+        Self {
+            buffer,
+            decode_at: 0,
+            capacity: None,
+            incomplete_eof: IncompleteEof::Error,
+        }
+    }
+
+    /// Limit the buffer to `capacity` bytes, allocating them up front
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        let mut buffer = BytesMut::with_capacity(capacity.max(self.buffer.len()));
+        buffer.extend_from_slice(&self.buffer);
+        self.buffer = buffer;
+        self.capacity = Some(capacity);
+    }
+
+    /// Bytes that may still be added to the buffer, failing if that is too
+    /// few for the `needed` bytes the decoder is waiting for
+    pub(crate) fn check_space(&self, needed: Option<usize>) -> Result<usize, Error> {
+        let space = match self.capacity {
+            Some(capacity) => capacity.saturating_sub(self.buffer.len()),
+            None => usize::MAX,
+        };
+        if space == 0 || needed.is_some_and(|needed| needed > space) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "frame exceeds the read buffer capacity",
+            ));
+        }
+        Ok(space)
+    }
+
+    /// Decode a frame, with `Decoder::decode_eof` after the input ended if
+    /// the policy says so
+    ///
+    /// Before the end, the decoder is not called until the buffer holds the
+    /// bytes it said it needs.
+    pub(crate) fn decode<D: Decoder>(
+        &mut self,
+        decoder: &mut D,
+        eof: bool,
+    ) -> Result<Option<D::Item>, D::Error> {
+        if !eof && self.buffer.len() < self.decode_at {
+            return Ok(None);
+        }
+        let item = if eof && self.incomplete_eof == IncompleteEof::Decode {
+            decoder.decode_eof(&mut self.buffer)?
+        } else {
+            decoder.decode(&mut self.buffer)?
+        };
+        match item {
+            Some(_) => self.decode_at = 0,
+            None => {
+                let needed = decoder.bytes_needed(&self.buffer).unwrap_or(0);
+                self.decode_at = self.buffer.len().saturating_add(needed);
+            }
+        }
+        Ok(item)
+    }
+
+    /// Handle the bytes left over once the input ended and `decode` returned
+    /// no more frames
+    pub(crate) fn end<E: From<Error>>(&mut self) -> Result<(), E> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        match self.incomplete_eof {
+            IncompleteEof::Discard => {
+                self.buffer.clear();
+                Ok(())
+            }
+            IncompleteEof::Error | IncompleteEof::Decode => Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "bytes remaining in stream",
+            )
+            .into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LinesCodec;
+
+    #[test]
+    fn fixed_capacity() {
+        let mut frames = FrameAccumulator::new(LinesCodec::new()).fixed_capacity(4);
+        frames.extend(b"Hey\n");
+        assert_eq!(frames.decode().unwrap().unwrap(), "Hey\n");
+
+        frames.extend(b"Hello\n");
+        assert_eq!(frames.decode().unwrap().unwrap(), "Hello\n");
+
+        frames.extend(b"Hello");
+        assert_eq!(frames.decode().unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn eof() {
+        let mut frames = FrameAccumulator::new(LinesCodec::new());
+        frames.extend(b"Hello\n");
+        assert_eq!(frames.decode_eof().unwrap().unwrap(), "Hello\n");
+        assert!(frames.decode_eof().unwrap().is_none());
+    }
+
+    #[test]
+    fn incomplete_eof_policies() {
+        let mut frames =
+            FrameAccumulator::new(LinesCodec::new()).on_incomplete_eof(IncompleteEof::Decode);
+        frames.extend(b"Hello\nWorld");
+        assert_eq!(frames.decode_eof().unwrap().unwrap(), "Hello\n");
+        assert_eq!(frames.decode_eof().unwrap().unwrap(), "World");
+        assert!(frames.decode_eof().unwrap().is_none());
+
+        let mut frames =
+            FrameAccumulator::new(LinesCodec::new()).on_incomplete_eof(IncompleteEof::Discard);
+        frames.extend(b"World");
+        assert!(frames.decode_eof().unwrap().is_none());
+        assert!(frames.buffer().is_empty());
+    }
+
+    #[test]
+    fn buffer_mut_resets_decoder() {
+        let mut frames = FrameAccumulator::new(LinesCodec::new());
+        frames.extend(b"Hello");
+        assert!(frames.decode().unwrap().is_none());
+
+        frames.buffer_mut().clear();
+        frames.extend(b"Hi\n");
+        assert_eq!(frames.decode().unwrap().unwrap(), "Hi\n");
+    }
+}
@@ -0,0 +1,140 @@
+//! Blocking framing over `std::io`.
+//!
+//! These adapters use the same `Encoder` and `Decoder` traits as their async
+//! counterparts, so codecs can be reused in synchronous code without an
+//! executor.
+//!
+//! ```
+//! use futures_codec::{blocking, LinesCodec};
+//!
+//! let mut buf = Vec::new();
+//! let mut framed = blocking::FramedWrite::new(&mut buf, LinesCodec::new());
+//! framed.send("Hello\n".to_owned()).unwrap();
+//!
+//! let mut framed = blocking::FramedRead::new(&buf[..], LinesCodec::new());
+//! assert_eq!(framed.next().unwrap().unwrap(), "Hello\n");
+//! assert!(framed.next().is_none());
+//! ```
+use super::{Decoder, Encoder, FrameAccumulator};
+
+use bytes::BytesMut;
+use std::io::{self, Read, Write};
+
+const INITIAL_CAPACITY: usize = 8 * 1024;
+
+/// An `Iterator` of messages decoded from a `Read`.
+pub struct FramedRead<T, D> {
+    inner: T,
+    frames: FrameAccumulator<D>,
+}
+
+impl<T, D> FramedRead<T, D>
+where
+    T: Read,
+    D: Decoder,
+{
+    pub fn new(inner: T, decoder: D) -> Self {
+        Self {
+            inner,
+            frames: FrameAccumulator::new(decoder),
+        }
+    }
+
+    /// Release the I/O and Decoder
+    pub fn release(self) -> (T, D) {
+        (self.inner, self.frames.into_parts().0)
+    }
+}
+
+impl<T, D> Iterator for FramedRead<T, D>
+where
+    T: Read,
+    D: Decoder,
+{
+    type Item = Result<D::Item, D::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = [0u8; INITIAL_CAPACITY];
+
+        loop {
+            match self.frames.decode() {
+                Ok(Some(item)) => return Some(Ok(item)),
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
+
+            let n = match self.inner.read(&mut buf) {
+                Ok(n) => n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(e.into())),
+            };
+
+            if n == 0 {
+                return self.frames.decode_eof().transpose();
+            }
+            self.frames.extend(&buf[..n]);
+        }
+    }
+}
+
+/// Frames encoded to a `Write`.
+pub struct FramedWrite<T, E> {
+    inner: T,
+    encoder: E,
+    buffer: BytesMut,
+}
+
+impl<T, E> FramedWrite<T, E>
+where
+    T: Write,
+    E: Encoder,
+{
+    pub fn new(inner: T, encoder: E) -> Self {
+        Self {
+            inner,
+            encoder,
+            buffer: BytesMut::with_capacity(INITIAL_CAPACITY),
+        }
+    }
+
+    /// Encode `item` and flush it to the I/O
+    pub fn send(&mut self, item: E::Item) -> Result<(), E::Error> {
+        self.encoder.encode(item, &mut self.buffer)?;
+        self.flush()
+    }
+
+    /// Write out all buffered frames and flush the I/O
+    pub fn flush(&mut self) -> Result<(), E::Error> {
+        self.inner.write_all(&self.buffer)?;
+        self.buffer.clear();
+        self.inner.flush().map_err(Into::into)
+    }
+
+    /// Release the I/O and Encoder
+    pub fn release(self) -> (T, E) {
+        (self.inner, self.encoder)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LinesCodec;
+
+    #[test]
+    fn incomplete_frame_at_eof() {
+        let mut framed = FramedRead::new(&b"Hello\nWorld"[..], LinesCodec::new());
+        assert_eq!(framed.next().unwrap().unwrap(), "Hello\n");
+
+        let err = framed.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn write_to_eof() {
+        let mut buf = [0u8; 8];
+        let mut framed = FramedWrite::new(&mut buf[..], LinesCodec::new());
+        framed.send("Hello\n".to_owned()).unwrap();
+        assert!(framed.send("World\n".to_owned()).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! Codecs over HTTP bodies.
+//!
+//! Enabled with the `body` feature. [`BodyChunks`] turns any
+//! `http_body::Body` into a stream of chunks for [`FramedStreamRead`], and
+//! [`EncodedBody`] encodes a stream of items into a `Body`, for SSE, ND-JSON
+//! or gRPC style bodies.
+//!
+//! `http-body` is built on `bytes` 1, so chunks are copied once when they
+//! cross over to this crate's `bytes` 0.4 buffers.
+//!
+//! # Example
+//! ```
+//! use futures::{executor, stream, TryStreamExt};
+//! use futures_codec::body::{BodyChunks, EncodedBody};
+//! use futures_codec::{CodecError, FramedStreamRead, LinesCodec};
+//!
+//! let lines = vec!["Hello\n".to_owned(), "World\n".to_owned()];
+//! let items = stream::iter(lines.into_iter().map(Ok::<_, CodecError>));
+//! let body = EncodedBody::new(items, LinesCodec::new());
+//!
+//! let mut framed = FramedStreamRead::new(BodyChunks::new(body), LinesCodec::new());
+//! executor::block_on(async move {
+//!     assert_eq!(framed.try_next().await.unwrap().unwrap(), "Hello\n");
+//!     assert_eq!(framed.try_next().await.unwrap().unwrap(), "World\n");
+//!     assert!(framed.try_next().await.unwrap().is_none());
+//! })
+//! ```
+//!
+//! [`FramedStreamRead`]: crate::FramedStreamRead
+use crate::Encoder;
+
+use bytes::BytesMut;
+use bytes1::{Buf, Bytes};
+use futures::{ready, Stream, TryStream};
+use http_body::{Body, Frame};
+use pin_project_lite::pin_project;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+pin_project! {
+    /// A `Stream` of the data chunks of a `Body`, skipping trailers.
+    #[derive(Debug)]
+    pub struct BodyChunks<B> {
+        #[pin]
+        body: B,
+    }
+}
+
+impl<B: Body> BodyChunks<B> {
+    pub fn new(body: B) -> Self {
+        Self { body }
+    }
+
+    /// Release the body
+    pub fn into_inner(self) -> B {
+        self.body
+    }
+}
+
+impl<B: Body> Stream for BodyChunks<B> {
+    type Item = Result<Bytes, B::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut body = self.project().body;
+        loop {
+            let frame = match ready!(body.as_mut().poll_frame(cx)) {
+                Some(frame) => frame?,
+                None => return Poll::Ready(None),
+            };
+            if let Ok(mut data) = frame.into_data() {
+                let chunk = data.copy_to_bytes(data.remaining());
+                return Poll::Ready(Some(Ok(chunk)));
+            }
+        }
+    }
+}
+
+pin_project! {
+    /// A `Body` of the frames encoded from a `TryStream` of items.
+    #[derive(Debug)]
+    pub struct EncodedBody<S, E> {
+        #[pin]
+        items: S,
+        encoder: E,
+        buffer: BytesMut,
+    }
+}
+
+impl<S, E> EncodedBody<S, E>
+where
+    S: TryStream<Ok = E::Item, Error = E::Error>,
+    E: Encoder,
+{
+    pub fn new(items: S, encoder: E) -> Self {
+        Self {
+            items,
+            encoder,
+            buffer: BytesMut::new(),
+        }
+    }
+
+    /// Release the item stream and Encoder
+    pub fn release(self) -> (S, E) {
+        (self.items, self.encoder)
+    }
+}
+
+impl<S, E> Body for EncodedBody<S, E>
+where
+    S: TryStream<Ok = E::Item, Error = E::Error>,
+    E: Encoder,
+{
+    type Data = Bytes;
+    type Error = E::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, E::Error>>> {
+        let this = self.project();
+        let item = match ready!(this.items.try_poll_next(cx)) {
+            Some(item) => item?,
+            None => return Poll::Ready(None),
+        };
+        this.encoder.encode(item, this.buffer)?;
+        let frame = Bytes::copy_from_slice(&this.buffer.take());
+        Poll::Ready(Some(Ok(Frame::data(frame))))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{BytesCodec, CodecError, FramedStreamRead, LinesCodec};
+    use futures::{executor, stream, TryStreamExt};
+
+    /// A body of one data frame followed by trailers
+    struct WithTrailers(u8);
+
+    impl Body for WithTrailers {
+        type Data = &'static [u8];
+        type Error = CodecError;
+
+        fn poll_frame(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+            self.0 += 1;
+            Poll::Ready(match self.0 {
+                1 => Some(Ok(Frame::data(&b"Hello\n"[..]))),
+                2 => Some(Ok(Frame::trailers(Default::default()))),
+                _ => None,
+            })
+        }
+    }
+
+    #[test]
+    fn trailers_are_skipped() {
+        let framed = FramedStreamRead::new(BodyChunks::new(WithTrailers(0)), LinesCodec::new());
+        let lines: Vec<_> = executor::block_on(framed.try_collect()).unwrap();
+        assert_eq!(lines, ["Hello\n"]);
+    }
+
+    #[test]
+    fn body_of_encoded_items() {
+        let items = stream::iter(vec![Ok(bytes::Bytes::from("one")), Ok("two".into())]);
+        let chunks = BodyChunks::new(EncodedBody::new(items, BytesCodec {}));
+        let chunks: Vec<_> = executor::block_on(chunks.try_collect()).unwrap();
+        assert_eq!(chunks, ["one", "two"]);
+    }
+}
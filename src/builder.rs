@@ -0,0 +1,140 @@
+use super::{Decoder, Encoder, FlushPolicy, Framed, IncompleteEof};
+
+use bytes::Bytes;
+use futures::io::{AsyncRead, AsyncWrite};
+
+/// Options for creating a `Framed`, collected before it is built.
+///
+/// Created by [`Framed::builder`]. Options that are not set keep the
+/// defaults of `Framed::new`.
+///
+/// Frame lengths are not limited as such, the read capacity bounds the bytes
+/// buffered while the decoder waits for a frame. Set a maximum length on the
+/// codec for that where it has one. Frame metadata is not an option here,
+/// `FramedRead::with_metadata` changes the item type and `Framed` does not
+/// offer it.
+///
+/// `FramedRead` and `FramedWrite` have no builder, they are configured with
+/// their own methods such as `fixed_capacity`, `on_incomplete_eof` and
+/// `set_flush_policy` instead.
+///
+/// # Example
+/// ```
+/// use futures::{executor, TryStreamExt};
+/// use futures_codec::{FlushPolicy, Framed, IncompleteEof, LinesCodec};
+/// use std::io::Cursor;
+///
+/// let io = Cursor::new(b"World".to_vec());
+/// let mut framed = Framed::builder(io, LinesCodec::new())
+///     .initial("Hello\n".into())
+///     .read_capacity(1024)
+///     .flush_policy(FlushPolicy::EveryFrame)
+///     .on_incomplete_eof(IncompleteEof::Decode)
+///     .build();
+///
+/// executor::block_on(async move {
+///     let lines: Vec<_> = framed.try_collect().await.unwrap();
+///     assert_eq!(lines, ["Hello\n", "World"]);
+/// })
+/// ```
+#[derive(Debug)]
+pub struct FramedBuilder<T, U> {
+    inner: T,
+    codec: U,
+    initial: Option<Bytes>,
+    read_capacity: Option<usize>,
+    write_capacity: Option<usize>,
+    flush_policy: FlushPolicy,
+    incomplete_eof: IncompleteEof,
+}
+
+impl<T, U> FramedBuilder<T, U>
+where
+    T: AsyncRead + AsyncWrite,
+    U: Decoder + Encoder,
+{
+    pub(crate) fn new(inner: T, codec: U) -> Self {
+        Self {
+            inner,
+            codec,
+            initial: None,
+            read_capacity: None,
+            write_capacity: None,
+            flush_policy: FlushPolicy::default(),
+            incomplete_eof: IncompleteEof::default(),
+        }
+    }
+
+    /// Start out with `initial` in the read buffer, bytes of the stream
+    /// already read from the I/O by an earlier stage
+    pub fn initial(mut self, initial: Bytes) -> Self {
+        self.initial = Some(initial);
+        self
+    }
+
+    /// Allocate the read buffer once with room for `capacity` bytes, and fail
+    /// with `InvalidData` once the buffered bytes fill it without making up a
+    /// frame, as `FramedRead::fixed_capacity` does
+    pub fn read_capacity(mut self, capacity: usize) -> Self {
+        self.read_capacity = Some(capacity);
+        self
+    }
+
+    /// Fail with `InvalidData` when an item would make the queued frames
+    /// take up more than `capacity` bytes, instead of buffering without limit
+    pub fn write_capacity(mut self, capacity: usize) -> Self {
+        self.write_capacity = Some(capacity);
+        self
+    }
+
+    /// When queued frames are written out, see `FlushPolicy`
+    pub fn flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.flush_policy = policy;
+        self
+    }
+
+    /// What happens to an incomplete frame at the end of the I/O
+    pub fn on_incomplete_eof(mut self, policy: IncompleteEof) -> Self {
+        self.incomplete_eof = policy;
+        self
+    }
+
+    /// Create the `Framed`
+    pub fn build(self) -> Framed<T, U> {
+        let mut framed = match self.initial {
+            Some(initial) => Framed::with_initial(self.inner, self.codec, initial),
+            None => Framed::new(self.inner, self.codec),
+        };
+        if let Some(capacity) = self.read_capacity {
+            framed.set_read_capacity(capacity);
+        }
+        if let Some(capacity) = self.write_capacity {
+            framed.set_write_capacity(capacity);
+        }
+        framed.set_flush_policy(self.flush_policy);
+        framed.set_incomplete_eof(self.incomplete_eof);
+        framed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LinesCodec;
+    use futures::{executor, TryStreamExt};
+    use std::io::{Cursor, ErrorKind};
+
+    #[test]
+    fn frame_larger_than_read_capacity_fails() {
+        let io = Cursor::new(b"short\nthis line is too long\n".to_vec());
+        let mut framed = Framed::builder(io, LinesCodec::new())
+            .read_capacity(8)
+            .build();
+
+        executor::block_on(async move {
+            assert_eq!(framed.try_next().await.unwrap().unwrap(), "short\n");
+            let err = std::io::Error::from(framed.try_next().await.unwrap_err());
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        })
+    }
+}
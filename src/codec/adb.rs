@@ -0,0 +1,204 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BufMut, ByteOrder, Bytes, BytesMut, LittleEndian};
+
+/// Size of the header in front of every message
+const HEADER_LEN: usize = 24;
+/// Largest payload accepted by current versions of adb
+const MAX_PAYLOAD: usize = 1024 * 1024;
+
+/// A codec for the wire protocol between adb and Android devices.
+///
+/// Every message is a header of six little endian `u32`, the command, its
+/// two arguments, the payload length and checksum and the command with all
+/// bits inverted, followed by the payload. The checksum is the sum of the
+/// payload bytes. Since protocol version `0x01000001` it is no longer
+/// checked and sent as zero, which [`without_checksums`](Self::without_checksums)
+/// matches.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{AdbMessage, AdbMessageCodec, Decoder, Encoder};
+///
+/// let mut codec = AdbMessageCodec::new();
+/// let mut buf = BytesMut::new();
+/// let connect = AdbMessage::new(AdbMessage::CNXN, 0x01000000, 4096, Bytes::from("host::\0"));
+/// codec.encode(connect.clone(), &mut buf).unwrap();
+/// assert_eq!(&buf[..4], b"CNXN");
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some(connect));
+/// ```
+#[derive(Debug)]
+pub struct AdbMessageCodec {
+    max_payload: usize,
+    checksums: bool,
+}
+
+/// A message of the adb wire protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdbMessage {
+    /// Command, see the associated constants
+    pub command: u32,
+    pub arg0: u32,
+    pub arg1: u32,
+    pub payload: Bytes,
+}
+
+impl AdbMessage {
+    pub const SYNC: u32 = 0x434e_5953;
+    pub const CNXN: u32 = 0x4e58_4e43;
+    pub const AUTH: u32 = 0x4854_5541;
+    pub const OPEN: u32 = 0x4e45_504f;
+    pub const OKAY: u32 = 0x5941_4b4f;
+    pub const CLSE: u32 = 0x4553_4c43;
+    pub const WRTE: u32 = 0x4554_5257;
+    pub const STLS: u32 = 0x534c_5453;
+
+    pub fn new(command: u32, arg0: u32, arg1: u32, payload: Bytes) -> Self {
+        Self {
+            command,
+            arg0,
+            arg1,
+            payload,
+        }
+    }
+}
+
+impl AdbMessageCodec {
+    pub fn new() -> Self {
+        Self {
+            max_payload: MAX_PAYLOAD,
+            checksums: true,
+        }
+    }
+
+    /// Accept payloads of up to `max` bytes
+    pub fn max_payload(mut self, max: usize) -> Self {
+        self.max_payload = max;
+        self
+    }
+
+    /// Send zero checksums and don't check the received ones, as adb does
+    /// since protocol version `0x01000001`
+    pub fn without_checksums(mut self) -> Self {
+        self.checksums = false;
+        self
+    }
+}
+
+impl Default for AdbMessageCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn checksum(payload: &[u8]) -> u32 {
+    payload
+        .iter()
+        .fold(0u32, |sum, &b| sum.wrapping_add(u32::from(b)))
+}
+
+impl Decoder for AdbMessageCodec {
+    type Item = AdbMessage;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.len() < HEADER_LEN {
+            return Ok(None);
+        }
+        let mut header = [0; 6];
+        LittleEndian::read_u32_into(&src[..HEADER_LEN], &mut header);
+        let [command, arg0, arg1, len, sum, magic] = header;
+        if magic != !command {
+            return Err(CodecError::Protocol("invalid message magic".to_owned()));
+        }
+        let len = len as usize;
+        if len > self.max_payload {
+            return Err(CodecError::FrameTooLong {
+                max: self.max_payload,
+            });
+        }
+        if src.len() < HEADER_LEN + len {
+            return Ok(None);
+        }
+
+        src.advance(HEADER_LEN);
+        let payload = src.split_to(len).freeze();
+        if self.checksums && checksum(&payload) != sum {
+            return Err(CodecError::Protocol("payload checksum mismatch".to_owned()));
+        }
+        Ok(Some(AdbMessage::new(command, arg0, arg1, payload)))
+    }
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        if src.len() < HEADER_LEN {
+            return None;
+        }
+        let len = LittleEndian::read_u32(&src[12..16]) as usize;
+        (HEADER_LEN + len).checked_sub(src.len())
+    }
+}
+
+impl Encoder for AdbMessageCodec {
+    type Item = AdbMessage;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if item.payload.len() > self.max_payload {
+            return Err(CodecError::FrameTooLong {
+                max: self.max_payload,
+            });
+        }
+        let sum = if self.checksums {
+            checksum(&item.payload)
+        } else {
+            0
+        };
+
+        dst.reserve(HEADER_LEN + item.payload.len());
+        dst.put_u32_le(item.command);
+        dst.put_u32_le(item.arg0);
+        dst.put_u32_le(item.arg1);
+        dst.put_u32_le(item.payload.len() as u32);
+        dst.put_u32_le(sum);
+        dst.put_u32_le(!item.command);
+        dst.put_slice(&item.payload);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn validates_checksum() {
+        let write = AdbMessage::new(AdbMessage::WRTE, 1, 2, Bytes::from("shell:ls\0"));
+        let mut buf = BytesMut::new();
+        AdbMessageCodec::new()
+            .encode(write.clone(), &mut buf)
+            .unwrap();
+        assert_eq!(codec_bytes_needed(&buf[..20]), None);
+        assert_eq!(codec_bytes_needed(&buf[..26]), Some(7));
+
+        let mut corrupted = buf.clone();
+        corrupted[HEADER_LEN] ^= 1;
+        assert!(AdbMessageCodec::new().decode(&mut corrupted).is_err());
+        let mut corrupted = buf.clone();
+        corrupted[HEADER_LEN] ^= 1;
+        let decoded = AdbMessageCodec::new()
+            .without_checksums()
+            .decode(&mut corrupted)
+            .unwrap()
+            .unwrap();
+        assert_eq!(&decoded.payload[..], b"rhell:ls\0");
+        assert_eq!(
+            AdbMessageCodec::new().decode(&mut buf).unwrap(),
+            Some(write)
+        );
+    }
+
+    fn codec_bytes_needed(src: &[u8]) -> Option<usize> {
+        AdbMessageCodec::new().bytes_needed(&BytesMut::from(src))
+    }
+}
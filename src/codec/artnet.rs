@@ -0,0 +1,217 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BigEndian, BufMut, ByteOrder, Bytes, BytesMut, LittleEndian};
+
+const ID: &[u8; 8] = b"Art-Net\0";
+/// Size of the ID and opcode in front of every packet
+const HEADER_LEN: usize = 10;
+const PROTOCOL_VERSION: u16 = 14;
+/// Most DMX slots in a universe
+const MAX_SLOTS: usize = 512;
+
+/// A codec for Art-Net packets.
+///
+/// Every packet starts with the ID `Art-Net\0` and a little endian opcode.
+/// `ArtDmx` and `ArtPoll` packets are decoded into their fields, others are
+/// yielded with their opcode and the rest of the packet. DMX data of odd
+/// length is padded with a zero slot when encoding, as the length has to
+/// be even. Like
+/// [`RtpCodec`](crate::RtpCodec), every call to `decode` takes all of the
+/// buffer as one packet, as with [`DatagramFramed`](crate::DatagramFramed).
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{ArtDmx, ArtNetCodec, ArtNetPacket, Decoder, Encoder};
+///
+/// let mut codec = ArtNetCodec::new();
+/// let mut buf = BytesMut::new();
+/// let dmx = ArtNetPacket::Dmx(ArtDmx::new(0x123, Bytes::from(vec![255; 4])));
+/// codec.encode(dmx.clone(), &mut buf).unwrap();
+/// assert_eq!(&buf[..10], b"Art-Net\0\x00\x50");
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some(dmx));
+/// ```
+#[derive(Debug, Default)]
+pub struct ArtNetCodec;
+
+/// An Art-Net packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArtNetPacket {
+    /// DMX data for a universe
+    Dmx(ArtDmx),
+    /// Discovery of the nodes on the network
+    Poll { flags: u8, diag_priority: u8 },
+    /// Any other packet, its data everything after the opcode
+    Other { opcode: u16, data: Bytes },
+}
+
+impl ArtNetPacket {
+    pub const OP_POLL: u16 = 0x2000;
+    pub const OP_POLL_REPLY: u16 = 0x2100;
+    pub const OP_DIAG_DATA: u16 = 0x2300;
+    pub const OP_DMX: u16 = 0x5000;
+    pub const OP_NZS: u16 = 0x5100;
+    pub const OP_SYNC: u16 = 0x5200;
+    pub const OP_ADDRESS: u16 = 0x6000;
+    pub const OP_TIME_CODE: u16 = 0x9700;
+}
+
+/// The DMX data of an `ArtDmx` packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtDmx {
+    /// Sequence number to reorder packets, 0 when disabled
+    pub sequence: u8,
+    /// Physical input port the data came from
+    pub physical: u8,
+    /// 15 bit port address, net, sub-net and universe
+    pub universe: u16,
+    /// Up to 512 slots
+    pub data: Bytes,
+}
+
+impl ArtDmx {
+    /// Data for `universe`, without sequence number
+    pub fn new(universe: u16, data: Bytes) -> Self {
+        Self {
+            sequence: 0,
+            physical: 0,
+            universe,
+            data,
+        }
+    }
+}
+
+impl ArtNetCodec {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+impl Decoder for ArtNetCodec {
+    type Item = ArtNetPacket;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+        let mut packet = src.take().freeze();
+        if packet.len() < HEADER_LEN || &packet[..8] != ID {
+            return Err(invalid("not an Art-Net packet"));
+        }
+        let opcode = LittleEndian::read_u16(&packet[8..10]);
+        packet.advance(HEADER_LEN);
+        let packet = match opcode {
+            ArtNetPacket::OP_DMX => {
+                if packet.len() < 8 {
+                    return Err(invalid("ArtDmx packet too short"));
+                }
+                let len = usize::from(BigEndian::read_u16(&packet[6..8]));
+                if len > MAX_SLOTS || packet.len() < 8 + len {
+                    return Err(invalid("invalid ArtDmx length"));
+                }
+                ArtNetPacket::Dmx(ArtDmx {
+                    sequence: packet[2],
+                    physical: packet[3],
+                    universe: LittleEndian::read_u16(&packet[4..6]) & 0x7fff,
+                    data: packet.slice(8, 8 + len),
+                })
+            }
+            ArtNetPacket::OP_POLL => {
+                if packet.len() < 4 {
+                    return Err(invalid("ArtPoll packet too short"));
+                }
+                ArtNetPacket::Poll {
+                    flags: packet[2],
+                    diag_priority: packet[3],
+                }
+            }
+            opcode => ArtNetPacket::Other {
+                opcode,
+                data: packet,
+            },
+        };
+        Ok(Some(packet))
+    }
+}
+
+impl Encoder for ArtNetCodec {
+    type Item = ArtNetPacket;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.reserve(HEADER_LEN);
+        dst.put_slice(ID);
+        match item {
+            ArtNetPacket::Dmx(dmx) => {
+                if dmx.data.len() > MAX_SLOTS {
+                    return Err(CodecError::FrameTooLong { max: MAX_SLOTS });
+                }
+                let len = dmx.data.len() + dmx.data.len() % 2;
+                dst.reserve(10 + len);
+                dst.put_u16_le(ArtNetPacket::OP_DMX);
+                dst.put_u16_be(PROTOCOL_VERSION);
+                dst.put_slice(&[dmx.sequence, dmx.physical]);
+                dst.put_u16_le(dmx.universe & 0x7fff);
+                dst.put_u16_be(len as u16);
+                dst.put_slice(&dmx.data);
+                if len != dmx.data.len() {
+                    dst.put_u8(0);
+                }
+            }
+            ArtNetPacket::Poll {
+                flags,
+                diag_priority,
+            } => {
+                dst.reserve(6);
+                dst.put_u16_le(ArtNetPacket::OP_POLL);
+                dst.put_u16_be(PROTOCOL_VERSION);
+                dst.put_slice(&[flags, diag_priority]);
+            }
+            ArtNetPacket::Other { opcode, data } => {
+                dst.reserve(2 + data.len());
+                dst.put_u16_le(opcode);
+                dst.put_slice(&data);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn odd_dmx_length_is_padded() {
+        let mut codec = ArtNetCodec::new();
+        let mut dmx = ArtDmx::new(1, Bytes::from(&b"\x01\x02\x03"[..]));
+        dmx.sequence = 9;
+        let mut buf = BytesMut::new();
+        codec.encode(ArtNetPacket::Dmx(dmx), &mut buf).unwrap();
+        assert_eq!(
+            &buf[10..],
+            b"\x00\x0e\x09\x00\x01\x00\x00\x04\x01\x02\x03\x00"
+        );
+
+        match codec.decode(&mut buf).unwrap() {
+            Some(ArtNetPacket::Dmx(dmx)) => {
+                assert_eq!(dmx.sequence, 9);
+                assert_eq!(&dmx.data[..], b"\x01\x02\x03\x00");
+            }
+            packet => panic!("unexpected {:?}", packet),
+        }
+        let mut reply = BytesMut::from(&b"Art-Net\0\x00\x21\xc0\xa8"[..]);
+        match codec.decode(&mut reply).unwrap() {
+            Some(ArtNetPacket::Other { opcode, data }) => {
+                assert_eq!(opcode, ArtNetPacket::OP_POLL_REPLY);
+                assert_eq!(&data[..], b"\xc0\xa8");
+            }
+            packet => panic!("unexpected {:?}", packet),
+        }
+    }
+}
@@ -1,6 +1,5 @@
-use crate::{Decoder, Encoder};
+use crate::{CodecError, Decoder, Encoder};
 use bytes::{Bytes, BytesMut};
-use std::io::Error;
 
 /// A simple codec that ships bytes around
 ///
@@ -31,7 +30,7 @@ pub struct BytesCodec {}
 
 impl Decoder for BytesCodec {
     type Item = Bytes;
-    type Error = Error;
+    type Error = CodecError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
         let len = src.len();
@@ -45,12 +44,20 @@ impl Decoder for BytesCodec {
 
 impl Encoder for BytesCodec {
     type Item = Bytes;
-    type Error = Error;
+    type Error = CodecError;
 
     fn encode(&mut self, src: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
         dst.extend_from_slice(&src);
         Ok(())
     }
+
+    fn encode_zero_copy(
+        &mut self,
+        src: Self::Item,
+        _header: &mut BytesMut,
+    ) -> Result<Option<Bytes>, Self::Error> {
+        Ok(Some(src))
+    }
 }
 
 #[cfg(test)]
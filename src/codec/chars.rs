@@ -0,0 +1,111 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BufMut, BytesMut};
+
+/// A codec yielding the characters of a UTF-8 stream one by one.
+///
+/// A multi-byte character split across reads is kept in the buffer until
+/// it is complete. Invalid sequences fail decoding, unless the codec is
+/// [`lossy`](Self::lossy), which yields U+FFFD REPLACEMENT CHARACTER for
+/// them instead, as does `String::from_utf8_lossy`.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{CharCodec, Decoder, Encoder};
+///
+/// let mut codec = CharCodec::new();
+/// let mut buf = BytesMut::from(&b"a\xc3"[..]);
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some('a'));
+/// assert_eq!(codec.decode(&mut buf).unwrap(), None);
+/// buf.extend_from_slice(b"\xa9");
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some('é'));
+///
+/// codec.encode('€', &mut buf).unwrap();
+/// assert_eq!(&buf[..], "€".as_bytes());
+/// ```
+#[derive(Debug, Default)]
+pub struct CharCodec {
+    lossy: bool,
+}
+
+impl CharCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace invalid sequences with U+FFFD instead of failing
+    pub fn lossy() -> Self {
+        Self { lossy: true }
+    }
+}
+
+impl Decoder for CharCodec {
+    type Item = char;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let head = &src[..src.len().min(4)];
+        let (c, len) = match std::str::from_utf8(head) {
+            Ok(s) => match s.chars().next() {
+                Some(c) => (c, c.len_utf8()),
+                None => return Ok(None),
+            },
+            Err(e) if e.valid_up_to() > 0 => {
+                let c = std::str::from_utf8(&head[..e.valid_up_to()])?;
+                let c = c.chars().next().unwrap();
+                (c, c.len_utf8())
+            }
+            // Incomplete character
+            Err(e) if e.error_len().is_none() => return Ok(None),
+            Err(e) if self.lossy => (char::REPLACEMENT_CHARACTER, e.error_len().unwrap()),
+            Err(e) => return Err(e.into()),
+        };
+        src.advance(len);
+        Ok(Some(c))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.decode(src)? {
+            Some(c) => Ok(Some(c)),
+            None if src.is_empty() => Ok(None),
+            None if self.lossy => {
+                src.clear();
+                Ok(Some(char::REPLACEMENT_CHARACTER))
+            }
+            None => Err(std::str::from_utf8(src).unwrap_err().into()),
+        }
+    }
+}
+
+impl Encoder for CharCodec {
+    type Item = char;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let mut buf = [0; 4];
+        dst.reserve(item.len_utf8());
+        dst.put_slice(item.encode_utf8(&mut buf).as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lossy_replaces_invalid_sequences() {
+        let mut buf = BytesMut::from(&b"\xff\xe2\x82x\xf0\x9f"[..]);
+        let mut strict = CharCodec::new();
+        assert!(strict.decode(&mut buf.clone()).is_err());
+
+        let mut codec = CharCodec::lossy();
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some('\u{fffd}'));
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some('\u{fffd}'));
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some('x'));
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(codec.decode_eof(&mut buf).unwrap(), Some('\u{fffd}'));
+        assert!(buf.is_empty());
+    }
+}
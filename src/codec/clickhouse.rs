@@ -0,0 +1,628 @@
+use super::varint::{put_varint, read_varint};
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{ByteOrder, Bytes, BytesMut, LittleEndian};
+
+/// Protocol revision whose packet layout the codec understands
+pub const CLICKHOUSE_REVISION: u64 = 54460;
+
+/// Default limit on the length of a packet
+const MAX_PACKET: usize = 256 * 1024 * 1024;
+
+/// Deepest nesting of `Nullable`, `Array`, `Map` and `Tuple` column types
+const MAX_TYPE_DEPTH: usize = 32;
+
+/// A codec framing the packets of the ClickHouse native TCP protocol, for
+/// proxies and load balancers that pass packets on without interpreting
+/// them.
+///
+/// Packets start with their VarUInt type and carry no length, so the codec
+/// walks the fields of every packet to find its end. The layout depends on
+/// the protocol revision, and the codec assumes [`CLICKHOUSE_REVISION`], so
+/// the client has to announce that revision in its Hello. Blocks of `Data`
+/// packets must be sent without compression, and their columns be of plain
+/// numeric, date, string, UUID, IP, enum or decimal types, or `Nullable`,
+/// `Array`, `Tuple` and `Map` of those, nested at most 32 deep.
+/// `LowCardinality` columns are not supported and fail to decode.
+///
+/// Packets are limited to 256 MiB by default, see `max_length`. Through
+/// `bytes_needed`, a framed reader walks an incomplete packet again only
+/// once the bytes it ran out at have arrived.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{ClickHouseFrameCodec, ClickHousePacket, Decoder};
+///
+/// let mut codec = ClickHouseFrameCodec::server();
+/// // Progress: 10 rows, 80 bytes, 100 total rows, nothing written, 5ns
+/// let mut buf = BytesMut::from(&b"\x03\x0a\x50\x64\x00\x00\x05\x04"[..]);
+///
+/// let progress = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(progress.kind, ClickHousePacket::PROGRESS);
+/// assert_eq!(&progress.body[..], b"\x0a\x50\x64\x00\x00\x05");
+/// let pong = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(pong.kind, ClickHousePacket::PONG);
+/// ```
+#[derive(Debug)]
+pub struct ClickHouseFrameCodec {
+    /// Whether packets sent by the server are decoded
+    server: bool,
+    max_length: usize,
+    /// Length the buffer has to reach before the next packet may be complete
+    needed: usize,
+}
+
+/// A packet of the ClickHouse native protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClickHousePacket {
+    /// Packet type, see the associated constants for the direction
+    pub kind: u64,
+    /// The fields of the packet, after the type
+    pub body: Bytes,
+}
+
+impl ClickHousePacket {
+    /// Hello, from either side
+    pub const HELLO: u64 = 0;
+    /// A block of data sent by the server
+    pub const DATA: u64 = 1;
+    pub const EXCEPTION: u64 = 2;
+    pub const PROGRESS: u64 = 3;
+    pub const PONG: u64 = 4;
+    pub const END_OF_STREAM: u64 = 5;
+    pub const PROFILE_INFO: u64 = 6;
+    pub const TOTALS: u64 = 7;
+    pub const EXTREMES: u64 = 8;
+    pub const LOG: u64 = 10;
+    pub const TABLE_COLUMNS: u64 = 11;
+    pub const PART_UUIDS: u64 = 12;
+    pub const READ_TASK_REQUEST: u64 = 13;
+    pub const PROFILE_EVENTS: u64 = 14;
+
+    /// A query sent by the client
+    pub const CLIENT_QUERY: u64 = 1;
+    /// A block of data sent by the client
+    pub const CLIENT_DATA: u64 = 2;
+    pub const CLIENT_CANCEL: u64 = 3;
+    pub const CLIENT_PING: u64 = 4;
+    pub const CLIENT_TABLES_STATUS_REQUEST: u64 = 5;
+}
+
+impl ClickHouseFrameCodec {
+    /// A codec decoding the packets sent by a server
+    pub fn server() -> Self {
+        Self {
+            server: true,
+            max_length: MAX_PACKET,
+            needed: 0,
+        }
+    }
+
+    /// A codec decoding the packets sent by a client
+    pub fn client() -> Self {
+        Self {
+            server: false,
+            ..Self::server()
+        }
+    }
+
+    /// Fail with `FrameTooLong` on packets longer than `max` bytes, 256 MiB
+    /// by default
+    pub fn max_length(mut self, max: usize) -> Self {
+        self.max_length = max;
+        self
+    }
+
+    fn too_long(&self) -> CodecError {
+        CodecError::FrameTooLong {
+            max: self.max_length,
+        }
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+/// Multiply counts read from the wire
+fn times(count: usize, size: usize) -> Result<usize, CodecError> {
+    count
+        .checked_mul(size)
+        .ok_or_else(|| invalid("length overflows"))
+}
+
+/// The position reached while walking the fields of a packet, or `None`
+/// once the buffer ran out
+type Walk = Result<Option<usize>, CodecError>;
+
+/// A cursor over the buffered bytes of a packet
+struct Fields<'a> {
+    src: &'a [u8],
+    at: usize,
+    /// Bytes the buffer fell short by when the walk ran out
+    missing: usize,
+}
+
+/// Return from the walk if the buffer ran out
+macro_rules! need {
+    ($e:expr) => {
+        match $e? {
+            Some(value) => value,
+            None => return Ok(None),
+        }
+    };
+}
+
+impl<'a> Fields<'a> {
+    fn skip(&mut self, n: usize) -> Result<Option<()>, CodecError> {
+        if self.src.len() - self.at < n {
+            self.missing = n - (self.src.len() - self.at);
+            return Ok(None);
+        }
+        self.at += n;
+        Ok(Some(()))
+    }
+
+    fn u8(&mut self) -> Result<Option<u8>, CodecError> {
+        let byte = self.src.get(self.at).copied();
+        self.at += byte.is_some() as usize;
+        self.missing = byte.is_none() as usize;
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> Result<Option<u64>, CodecError> {
+        self.missing = 1;
+        Ok(read_varint(&self.src[self.at..], 10)?.map(|(value, n)| {
+            self.at += n;
+            value
+        }))
+    }
+
+    fn count(&mut self) -> Result<Option<usize>, CodecError> {
+        match self.varint()? {
+            Some(count) if count > usize::MAX as u64 => Err(invalid("length overflows")),
+            count => Ok(count.map(|count| count as usize)),
+        }
+    }
+
+    fn string(&mut self) -> Result<Option<&'a [u8]>, CodecError> {
+        let len = need!(self.count());
+        let start = self.at;
+        need!(self.skip(len));
+        Ok(Some(&self.src[start..self.at]))
+    }
+
+    fn strings(&mut self, n: usize) -> Walk {
+        for _ in 0..n {
+            need!(self.string());
+        }
+        Ok(Some(self.at))
+    }
+
+    fn varints(&mut self, n: usize) -> Walk {
+        for _ in 0..n {
+            need!(self.varint());
+        }
+        Ok(Some(self.at))
+    }
+
+    /// The fields of a packet of type `kind`, sent by the server or not
+    fn packet(&mut self, server: bool, kind: u64) -> Walk {
+        match (server, kind) {
+            // Name, version, revision, time zone, display name, patch
+            (true, ClickHousePacket::HELLO) => {
+                need!(self.string());
+                need!(self.varints(3));
+                need!(self.strings(2));
+                self.varints(1)
+            }
+            (true, ClickHousePacket::DATA)
+            | (true, ClickHousePacket::TOTALS)
+            | (true, ClickHousePacket::EXTREMES)
+            | (true, ClickHousePacket::LOG)
+            | (true, ClickHousePacket::PROFILE_EVENTS)
+            | (false, ClickHousePacket::CLIENT_DATA) => self.data(),
+            (true, ClickHousePacket::EXCEPTION) => self.exception(),
+            (true, ClickHousePacket::PROGRESS) => self.varints(6),
+            (true, ClickHousePacket::PONG)
+            | (true, ClickHousePacket::END_OF_STREAM)
+            | (true, ClickHousePacket::READ_TASK_REQUEST)
+            | (false, ClickHousePacket::CLIENT_CANCEL)
+            | (false, ClickHousePacket::CLIENT_PING) => Ok(Some(self.at)),
+            // Rows, blocks, bytes, applied limit, rows before limit, calculated
+            (true, ClickHousePacket::PROFILE_INFO) => {
+                need!(self.varints(3));
+                need!(self.skip(1));
+                need!(self.varint());
+                need!(self.skip(1));
+                Ok(Some(self.at))
+            }
+            (true, ClickHousePacket::TABLE_COLUMNS) => self.strings(2),
+            (true, ClickHousePacket::PART_UUIDS) => {
+                let count = need!(self.count());
+                need!(self.skip(times(count, 16)?));
+                Ok(Some(self.at))
+            }
+            (false, ClickHousePacket::CLIENT_QUERY) => self.query(),
+            // Name, version, revision, database, user, password, quota key
+            (false, ClickHousePacket::HELLO) => {
+                need!(self.string());
+                need!(self.varints(3));
+                self.strings(4)
+            }
+            (false, ClickHousePacket::CLIENT_TABLES_STATUS_REQUEST) => {
+                let count = need!(self.count());
+                self.strings(times(count, 2)?)
+            }
+            _ => Err(CodecError::Protocol(format!(
+                "unsupported packet type {}",
+                kind
+            ))),
+        }
+    }
+
+    /// Settings or query parameters, up to an empty name
+    fn settings(&mut self) -> Walk {
+        while !need!(self.string()).is_empty() {
+            need!(self.varint());
+            need!(self.string());
+        }
+        Ok(Some(self.at))
+    }
+
+    /// The client info of a query, unless the kind says there is none
+    fn client_info(&mut self) -> Walk {
+        if need!(self.u8()) == 0 {
+            return Ok(Some(self.at));
+        }
+        // Initial user, query id and address, query start time
+        need!(self.strings(3));
+        need!(self.skip(8));
+        match need!(self.u8()) {
+            // TCP: OS user, host name, client name, version and revision
+            1 => {
+                need!(self.strings(3));
+                need!(self.varints(3));
+            }
+            // HTTP: method, user agent, forwarded for, referer
+            2 => {
+                need!(self.skip(1));
+                need!(self.strings(3));
+            }
+            _ => {}
+        }
+        // Quota key, distributed depth, version patch
+        need!(self.string());
+        need!(self.varints(2));
+        if need!(self.u8()) != 0 {
+            // Trace and span id, trace state, trace flags
+            need!(self.skip(24));
+            need!(self.string());
+            need!(self.skip(1));
+        }
+        // Parallel replicas
+        self.varints(3)
+    }
+
+    /// Query id, client info, settings, interserver secret, stage,
+    /// compression, query and parameters
+    fn query(&mut self) -> Walk {
+        need!(self.string());
+        need!(self.client_info());
+        need!(self.settings());
+        need!(self.string());
+        need!(self.varints(2));
+        need!(self.string());
+        self.settings()
+    }
+
+    fn exception(&mut self) -> Walk {
+        loop {
+            need!(self.skip(4));
+            need!(self.strings(3));
+            if need!(self.u8()) == 0 {
+                return Ok(Some(self.at));
+            }
+        }
+    }
+
+    /// A table name followed by a block
+    fn data(&mut self) -> Walk {
+        need!(self.string());
+        loop {
+            match need!(self.varint()) {
+                0 => break,
+                1 => need!(self.skip(1)),
+                2 => need!(self.skip(4)),
+                _ => return Err(invalid("unknown block info field")),
+            }
+        }
+        let columns = need!(self.varint());
+        let rows = need!(self.count());
+        for _ in 0..columns {
+            need!(self.string());
+            let column_type = need!(self.string());
+            let column_type = std::str::from_utf8(column_type)?;
+            if need!(self.u8()) != 0 {
+                return Err(invalid("custom column serialization is not supported"));
+            }
+            need!(self.column(column_type, rows, 0));
+        }
+        Ok(Some(self.at))
+    }
+
+    /// The data of `rows` values of a column of type `column_type`, nested
+    /// `depth` deep in another column type
+    fn column(&mut self, column_type: &str, rows: usize, depth: usize) -> Walk {
+        if depth > MAX_TYPE_DEPTH {
+            return Err(invalid("column type is nested too deep"));
+        }
+        if rows == 0 {
+            return Ok(Some(self.at));
+        }
+        let (name, args) = match column_type.find('(') {
+            Some(open) if column_type.ends_with(')') => (
+                &column_type[..open],
+                split_args(&column_type[open + 1..column_type.len() - 1]),
+            ),
+            _ => (column_type, Vec::new()),
+        };
+        let arg = |i: usize| {
+            args.get(i)
+                .copied()
+                .ok_or_else(|| invalid("missing type argument"))
+        };
+
+        let width = match name {
+            "UInt8" | "Int8" | "Bool" | "Enum8" => 1,
+            "UInt16" | "Int16" | "Date" | "Enum16" => 2,
+            "UInt32" | "Int32" | "Float32" | "Date32" | "DateTime" | "IPv4" | "Decimal32" => 4,
+            "UInt64" | "Int64" | "Float64" | "DateTime64" | "Decimal64" => 8,
+            "UInt128" | "Int128" | "UUID" | "IPv6" | "Decimal128" => 16,
+            "UInt256" | "Int256" | "Decimal256" => 32,
+            "Decimal" => match arg(0)?.parse::<u32>() {
+                Ok(1..=9) => 4,
+                Ok(10..=18) => 8,
+                Ok(19..=38) => 16,
+                Ok(39..=76) => 32,
+                _ => return Err(invalid("invalid decimal precision")),
+            },
+            "FixedString" => arg(0)?
+                .parse()
+                .map_err(|_| invalid("invalid string size"))?,
+            "String" => {
+                for _ in 0..rows {
+                    need!(self.string());
+                }
+                return Ok(Some(self.at));
+            }
+            "Nullable" => {
+                need!(self.skip(rows));
+                return self.column(arg(0)?, rows, depth + 1);
+            }
+            "Array" | "Map" => {
+                need!(self.skip(times(rows, 8)?));
+                let values = LittleEndian::read_u64(&self.src[self.at - 8..self.at]);
+                if values > usize::MAX as u64 {
+                    return Err(invalid("length overflows"));
+                }
+                let values = values as usize;
+                if name == "Array" {
+                    return self.column(arg(0)?, values, depth + 1);
+                }
+                for value_type in &[arg(0)?, arg(1)?] {
+                    need!(self.column(value_type, values, depth + 1));
+                }
+                return Ok(Some(self.at));
+            }
+            "Tuple" => {
+                for element in &args {
+                    // Named elements are `name Type`
+                    let element_type = match element.split_once(' ') {
+                        Some((name, ty)) if !name.contains('(') => ty,
+                        _ => element,
+                    };
+                    need!(self.column(element_type, rows, depth + 1));
+                }
+                return Ok(Some(self.at));
+            }
+            _ => {
+                return Err(CodecError::Protocol(format!(
+                    "unsupported column type {}",
+                    column_type
+                )))
+            }
+        };
+        need!(self.skip(times(rows, width)?));
+        Ok(Some(self.at))
+    }
+}
+
+/// Split the arguments of a type at the commas outside of parentheses and
+/// quotes
+fn split_args(args: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut quoted, mut start) = (0, false, 0);
+    let bytes = args.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'\'' if i == 0 || bytes[i - 1] != b'\\' => quoted = !quoted,
+            b'(' if !quoted => depth += 1,
+            b')' if !quoted => depth -= 1,
+            b',' if !quoted && depth == 0 => {
+                parts.push(args[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(args[start..].trim());
+    parts
+}
+
+impl Decoder for ClickHouseFrameCodec {
+    type Item = ClickHousePacket;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let (kind, n) = match read_varint(src, 10)? {
+            Some(kind) => kind,
+            None => {
+                self.needed = src.len() + 1;
+                return Ok(None);
+            }
+        };
+        let mut fields = Fields {
+            src: &src[..],
+            at: n,
+            missing: 0,
+        };
+        let end = fields.packet(self.server, kind);
+        let end = match end? {
+            Some(end) if end > self.max_length => return Err(self.too_long()),
+            Some(end) => end,
+            None if src.len().saturating_add(fields.missing) > self.max_length => {
+                return Err(self.too_long())
+            }
+            None => {
+                self.needed = src.len() + fields.missing;
+                return Ok(None);
+            }
+        };
+        self.needed = 0;
+
+        let mut packet = src.split_to(end);
+        packet.advance(n);
+        Ok(Some(ClickHousePacket {
+            kind,
+            body: packet.freeze(),
+        }))
+    }
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        self.needed
+            .checked_sub(src.len())
+            .filter(|&needed| needed > 0)
+    }
+
+    fn reset(&mut self) {
+        self.needed = 0;
+    }
+}
+
+impl Encoder for ClickHouseFrameCodec {
+    type Item = ClickHousePacket;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.reserve(10 + item.body.len());
+        put_varint(dst, item.kind);
+        dst.extend_from_slice(&item.body);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn data_block_with_nested_columns() {
+        let mut block = BytesMut::new();
+        // Table name, block info, 3 columns, 2 rows
+        block.extend_from_slice(b"\x00\x01\x00\x02\xff\xff\xff\xff\x00\x03\x02");
+        block.extend_from_slice(b"\x01s\x10Nullable(String)\x00\x00\x01\x02hi\x00");
+        block.extend_from_slice(b"\x01a\x0dArray(UInt16)\x00");
+        block
+            .extend_from_slice(b"\x01\x00\x00\x00\x00\x00\x00\x00\x03\x00\x00\x00\x00\x00\x00\x00");
+        block.extend_from_slice(b"\x01\x00\x02\x00\x03\x00");
+        block.extend_from_slice(b"\x01e\x18Enum8('a,' = 1, 'b' = 2)\x00\x01\x02");
+
+        let mut codec = ClickHouseFrameCodec::server();
+        let mut buf = BytesMut::from(&b"\x01"[..]);
+        buf.extend_from_slice(&block);
+        buf.extend_from_slice(b"\x05");
+        for len in 0..buf.len() - 1 {
+            let mut partial = BytesMut::from(&buf[..len]);
+            assert_eq!(codec.decode(&mut partial).unwrap(), None, "{} bytes", len);
+        }
+
+        let data = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(data.kind, ClickHousePacket::DATA);
+        assert_eq!(data.body, block.freeze());
+        assert_eq!(
+            codec.decode(&mut buf).unwrap().unwrap().kind,
+            ClickHousePacket::END_OF_STREAM
+        );
+    }
+
+    #[test]
+    fn client_query() {
+        let mut query = BytesMut::new();
+        // Query id, client info of a TCP client with its initial user, query
+        // id and address, start time, OS user, host and client name, version
+        query.extend_from_slice(b"\x00\x01\x00\x00\x01a\x00\x00\x00\x00\x00\x00\x00\x00");
+        query.extend_from_slice(b"\x01\x00\x00\x00\x01\x02\x03");
+        // Quota key, depth, patch, no trace, parallel replicas
+        query.extend_from_slice(b"\x00\x00\x00\x00\x00\x00\x00");
+        // A setting, interserver secret, stage, compression, query, parameters
+        query.extend_from_slice(b"\x01x\x00\x011\x00\x00\x02\x00\x08SELECT 1\x00");
+
+        let mut codec = ClickHouseFrameCodec::client();
+        let mut buf = BytesMut::from(&b"\x01"[..]);
+        buf.extend_from_slice(&query);
+        buf.extend_from_slice(b"\x04");
+        for len in 0..buf.len() - 1 {
+            let mut partial = BytesMut::from(&buf[..len]);
+            assert_eq!(codec.decode(&mut partial).unwrap(), None, "{} bytes", len);
+        }
+
+        let packet = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(packet.kind, ClickHousePacket::CLIENT_QUERY);
+        assert_eq!(packet.body, query.freeze());
+        assert_eq!(
+            codec.decode(&mut buf).unwrap().unwrap().kind,
+            ClickHousePacket::CLIENT_PING
+        );
+    }
+
+    #[test]
+    fn bytes_needed_and_max_length() {
+        let mut codec = ClickHouseFrameCodec::server();
+        // Part UUIDs, one of them
+        let mut buf = BytesMut::from(&b"\x0c\x01\x00\x00\x00\x00"[..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(codec.bytes_needed(&buf), Some(12));
+
+        let mut codec = ClickHouseFrameCodec::server().max_length(16);
+        let mut buf = BytesMut::from(&b"\x0c\x02"[..]);
+        match codec.decode(&mut buf) {
+            Err(CodecError::FrameTooLong { max }) => assert_eq!(max, 16),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn overflowing_row_count() {
+        // Data with no table name or block info and a UInt64 column
+        let mut buf = BytesMut::from(&b"\x01\x00\x00\x01"[..]);
+        put_varint(&mut buf, 1 << 62);
+        buf.extend_from_slice(b"\x01c\x06UInt64\x00");
+        let err = ClickHouseFrameCodec::server().decode(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn deeply_nested_type() {
+        let depth = MAX_TYPE_DEPTH + 2;
+        let column_type = format!("{}UInt8{}", "Nullable(".repeat(depth), ")".repeat(depth));
+        let mut buf = BytesMut::from(&b"\x01\x00\x00\x01\x01\x01c"[..]);
+        put_varint(&mut buf, column_type.len() as u64);
+        buf.extend_from_slice(column_type.as_bytes());
+        buf.extend_from_slice(&[0; 64]);
+        match ClickHouseFrameCodec::server().decode(&mut buf) {
+            Err(CodecError::Protocol(msg)) => assert!(msg.contains("too deep")),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
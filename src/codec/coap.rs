@@ -0,0 +1,220 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BigEndian, BufMut, ByteOrder, Bytes, BytesMut};
+
+/// Largest token allowed, longer token lengths are reserved
+const MAX_TOKEN_LEN: usize = 8;
+const MAX_MESSAGE_LEN: usize = 1024 * 1024;
+
+/// A codec for CoAP over TCP and TLS as specified in RFC 8323.
+///
+/// Every message starts with a byte holding the length of the options and
+/// payload in its upper and the token length in its lower four bits.
+/// Lengths 13, 14 and 15 announce an extended length of one, two or four
+/// bytes after it. The code and the token follow. Options and payload are
+/// not parsed, they are left as they are in [`CoapTcpMessage::data`], so the
+/// message can be handed to a CoAP implementation, for example after moving
+/// it to the UDP message format of a gateway.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{CoapTcpCodec, CoapTcpMessage, Decoder, Encoder};
+///
+/// let mut codec = CoapTcpCodec::new();
+/// let mut buf = BytesMut::new();
+/// // A GET with Uri-Path "temp"
+/// let get = CoapTcpMessage::new(0x01, Bytes::from("tk"), Bytes::from(&b"\xb4temp"[..]));
+/// codec.encode(get.clone(), &mut buf).unwrap();
+/// assert_eq!(&buf[..2], &[0x52, 0x01]);
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some(get));
+/// ```
+#[derive(Debug)]
+pub struct CoapTcpCodec {
+    max_message_len: usize,
+}
+
+/// A CoAP message as framed over TCP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoapTcpMessage {
+    /// Code, class in the upper three and detail in the lower five bits
+    pub code: u8,
+    /// Token, at most eight bytes
+    pub token: Bytes,
+    /// Options and payload, including the payload marker
+    pub data: Bytes,
+}
+
+impl CoapTcpMessage {
+    /// Capabilities and Settings signaling message
+    pub const CSM: u8 = 0xe1;
+    pub const PING: u8 = 0xe2;
+    pub const PONG: u8 = 0xe3;
+    pub const RELEASE: u8 = 0xe4;
+    pub const ABORT: u8 = 0xe5;
+
+    pub fn new(code: u8, token: Bytes, data: Bytes) -> Self {
+        Self { code, token, data }
+    }
+
+    /// Whether this is a signaling message, with a code of class 7
+    pub fn is_signaling(&self) -> bool {
+        self.code >> 5 == 7
+    }
+}
+
+impl CoapTcpCodec {
+    pub fn new() -> Self {
+        Self {
+            max_message_len: MAX_MESSAGE_LEN,
+        }
+    }
+
+    /// Accept options and payload of up to `max` bytes
+    pub fn max_message_len(mut self, max: usize) -> Self {
+        self.max_message_len = max;
+        self
+    }
+
+    /// Length of the header up to the token, the token and the options and
+    /// payload, once the header is complete
+    fn header(&self, src: &[u8]) -> Result<Option<(usize, usize, usize)>, CodecError> {
+        let first = match src.first() {
+            Some(&first) => first,
+            None => return Ok(None),
+        };
+        let token_len = usize::from(first & 0x0f);
+        if token_len > MAX_TOKEN_LEN {
+            return Err(CodecError::Protocol(
+                "reserved CoAP token length".to_owned(),
+            ));
+        }
+        let (extended, offset) = match first >> 4 {
+            13 => (1, 13),
+            14 => (2, 269),
+            15 => (4, 65805),
+            _ => (0, 0),
+        };
+        if src.len() < 1 + extended {
+            return Ok(None);
+        }
+        let len = match extended {
+            0 => usize::from(first >> 4),
+            1 => usize::from(src[1]),
+            2 => usize::from(BigEndian::read_u16(&src[1..3])),
+            _ => BigEndian::read_u32(&src[1..5]) as usize,
+        } + offset;
+        if len > self.max_message_len {
+            return Err(CodecError::FrameTooLong {
+                max: self.max_message_len,
+            });
+        }
+        // The code follows the length
+        Ok(Some((2 + extended, token_len, len)))
+    }
+}
+
+impl Default for CoapTcpCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for CoapTcpCodec {
+    type Item = CoapTcpMessage;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let (header_len, token_len, len) = match self.header(src)? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        if src.len() < header_len + token_len + len {
+            src.reserve(header_len + token_len + len - src.len());
+            return Ok(None);
+        }
+        let mut message = src.split_to(header_len + token_len + len).freeze();
+        let code = message[header_len - 1];
+        message.advance(header_len);
+        let token = message.split_to(token_len);
+        Ok(Some(CoapTcpMessage {
+            code,
+            token,
+            data: message,
+        }))
+    }
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        let (header_len, token_len, len) = self.header(src).ok()??;
+        (header_len + token_len + len).checked_sub(src.len())
+    }
+}
+
+impl Encoder for CoapTcpCodec {
+    type Item = CoapTcpMessage;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if item.token.len() > MAX_TOKEN_LEN {
+            return Err(CodecError::Protocol("CoAP token too long".to_owned()));
+        }
+        let len = item.data.len();
+        if len > self.max_message_len {
+            return Err(CodecError::FrameTooLong {
+                max: self.max_message_len,
+            });
+        }
+        dst.reserve(6 + item.token.len() + len);
+        let token_len = item.token.len() as u8;
+        match len {
+            0..=12 => dst.put_u8((len as u8) << 4 | token_len),
+            13..=268 => {
+                dst.put_u8(13 << 4 | token_len);
+                dst.put_u8((len - 13) as u8);
+            }
+            269..=65804 => {
+                dst.put_u8(14 << 4 | token_len);
+                dst.put_u16_be((len - 269) as u16);
+            }
+            _ => {
+                dst.put_u8(15 << 4 | token_len);
+                dst.put_u32_be((len - 65805) as u32);
+            }
+        }
+        dst.put_u8(item.code);
+        dst.put_slice(&item.token);
+        dst.put_slice(&item.data);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn extended_lengths() {
+        let mut codec = CoapTcpCodec::new();
+        let mut buf = BytesMut::new();
+        let messages: Vec<_> = [0, 13, 300, 70000]
+            .iter()
+            .map(|&len| {
+                let data = Bytes::from(vec![0xff; len]);
+                CoapTcpMessage::new(0x45, Bytes::from("token"), data)
+            })
+            .collect();
+        for message in &messages {
+            codec.encode(message.clone(), &mut buf).unwrap();
+        }
+        assert_eq!(&buf[..2], &[0x05, 0x45]);
+
+        let partial = BytesMut::from(&buf[..3]);
+        assert_eq!(codec.bytes_needed(&partial), Some(4));
+        let partial = BytesMut::from(&buf[7..10]);
+        assert_eq!(codec.bytes_needed(&partial), Some(18));
+        for message in messages {
+            assert_eq!(codec.decode(&mut buf).unwrap(), Some(message));
+        }
+        assert!(buf.is_empty());
+    }
+}
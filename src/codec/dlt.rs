@@ -0,0 +1,316 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BigEndian, BufMut, ByteOrder, Bytes, BytesMut, LittleEndian};
+
+/// Pattern starting the storage header of every message in a DLT file
+const PATTERN: &[u8] = b"DLT\x01";
+const STORAGE_HEADER_LEN: usize = 16;
+const STANDARD_HEADER_LEN: usize = 4;
+const EXTENDED_HEADER_LEN: usize = 10;
+
+/// A codec for AUTOSAR Diagnostic Log and Trace messages.
+///
+/// [`network`](Self::network) frames the messages as sent by an ECU over
+/// TCP, every message starting with the standard header.
+/// [`storage`](Self::storage) frames DLT files, where every message is
+/// preceded by a storage header starting with the `DLT\x01` pattern. When a
+/// stored message is corrupted, the codec skips ahead to the next pattern
+/// instead of failing.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, DltCodec};
+///
+/// let mut codec = DltCodec::network();
+/// // Standard header with an ECU id, followed by a 3 byte payload
+/// let mut buf = BytesMut::from(&b"\x24\x07\x00\x0bECU1abc"[..]);
+///
+/// let message = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(message.counter(), 7);
+/// assert_eq!(message.ecu_id(), Some(&b"ECU1"[..]));
+/// assert_eq!(message.payload(), b"abc");
+/// ```
+#[derive(Debug)]
+pub struct DltCodec {
+    storage: bool,
+}
+
+/// The storage header in front of a message in a DLT file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DltStorageHeader {
+    /// Time the message was stored, in seconds since the Unix epoch
+    pub seconds: u32,
+    pub microseconds: i32,
+    pub ecu_id: [u8; 4],
+}
+
+/// A DLT message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DltMessage {
+    /// Only set for messages of a DLT file
+    pub storage_header: Option<DltStorageHeader>,
+    /// The message, from the start of the standard header
+    pub data: Bytes,
+}
+
+impl DltMessage {
+    /// Header type bit for an extended header
+    pub const USE_EXTENDED_HEADER: u8 = 0x01;
+    /// Header type bit for a big endian payload
+    pub const MSB_FIRST: u8 = 0x02;
+    /// Header type bit for an ECU id in the standard header
+    pub const WITH_ECU_ID: u8 = 0x04;
+    /// Header type bit for a session id in the standard header
+    pub const WITH_SESSION_ID: u8 = 0x08;
+    /// Header type bit for a timestamp in the standard header
+    pub const WITH_TIMESTAMP: u8 = 0x10;
+
+    pub fn header_type(&self) -> u8 {
+        self.data[0]
+    }
+
+    /// Message counter, wrapping at 255
+    pub fn counter(&self) -> u8 {
+        self.data[1]
+    }
+
+    pub fn ecu_id(&self) -> Option<&[u8]> {
+        self.field(Self::WITH_ECU_ID)
+            .map(|at| &self.data[at..at + 4])
+    }
+
+    pub fn session_id(&self) -> Option<u32> {
+        self.field(Self::WITH_SESSION_ID)
+            .map(|at| BigEndian::read_u32(&self.data[at..]))
+    }
+
+    /// Time since the ECU started, in units of 0.1 milliseconds
+    pub fn timestamp(&self) -> Option<u32> {
+        self.field(Self::WITH_TIMESTAMP)
+            .map(|at| BigEndian::read_u32(&self.data[at..]))
+    }
+
+    /// Message info, argument count, application id and context id
+    pub fn extended_header(&self) -> Option<&[u8]> {
+        let at = standard_header_len(self.header_type());
+        self.has(Self::USE_EXTENDED_HEADER)
+            .then(|| &self.data[at..at + EXTENDED_HEADER_LEN])
+    }
+
+    pub fn app_id(&self) -> Option<&[u8]> {
+        self.extended_header().map(|header| &header[2..6])
+    }
+
+    pub fn context_id(&self) -> Option<&[u8]> {
+        self.extended_header().map(|header| &header[6..10])
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.data[headers_len(self.header_type())..]
+    }
+
+    fn has(&self, bit: u8) -> bool {
+        self.header_type() & bit != 0
+    }
+
+    /// Position of an optional field of the standard header
+    fn field(&self, bit: u8) -> Option<usize> {
+        let before = [
+            Self::WITH_ECU_ID,
+            Self::WITH_SESSION_ID,
+            Self::WITH_TIMESTAMP,
+        ]
+        .iter()
+        .take_while(|&&field| field != bit)
+        .filter(|&&field| self.has(field))
+        .count();
+        self.has(bit).then_some(STANDARD_HEADER_LEN + 4 * before)
+    }
+}
+
+fn standard_header_len(header_type: u8) -> usize {
+    let fields = (header_type
+        & (DltMessage::WITH_ECU_ID | DltMessage::WITH_SESSION_ID | DltMessage::WITH_TIMESTAMP))
+        .count_ones() as usize;
+    STANDARD_HEADER_LEN + 4 * fields
+}
+
+fn headers_len(header_type: u8) -> usize {
+    let extended = header_type & DltMessage::USE_EXTENDED_HEADER != 0;
+    standard_header_len(header_type) + EXTENDED_HEADER_LEN * extended as usize
+}
+
+/// Length of the message starting at `src`, if its headers fit in it
+fn message_len(src: &[u8]) -> Option<usize> {
+    let len = BigEndian::read_u16(&src[2..4]) as usize;
+    Some(len).filter(|&len| len >= headers_len(src[0]))
+}
+
+impl DltCodec {
+    /// A codec for messages sent over the network
+    pub fn network() -> Self {
+        Self { storage: false }
+    }
+
+    /// A codec for messages stored in DLT files
+    pub fn storage() -> Self {
+        Self { storage: true }
+    }
+
+    fn decode_storage(&mut self, src: &mut BytesMut) -> Option<DltMessage> {
+        loop {
+            // Skip to the next pattern, keeping a possible partial one
+            match src.windows(PATTERN.len()).position(|w| w == PATTERN) {
+                Some(start) => src.advance(start),
+                None => {
+                    let keep = (1..PATTERN.len())
+                        .rev()
+                        .find(|&n| src.ends_with(&PATTERN[..n]))
+                        .unwrap_or(0);
+                    src.advance(src.len() - keep);
+                    return None;
+                }
+            }
+            let header_len = STORAGE_HEADER_LEN + STANDARD_HEADER_LEN;
+            if src.len() < header_len {
+                return None;
+            }
+            let len = match message_len(&src[STORAGE_HEADER_LEN..]) {
+                Some(len) => len,
+                None => {
+                    src.advance(1);
+                    continue;
+                }
+            };
+            if src.len() < STORAGE_HEADER_LEN + len {
+                return None;
+            }
+
+            let header = src.split_to(STORAGE_HEADER_LEN);
+            let mut ecu_id = [0; 4];
+            ecu_id.copy_from_slice(&header[12..16]);
+            return Some(DltMessage {
+                storage_header: Some(DltStorageHeader {
+                    seconds: LittleEndian::read_u32(&header[4..8]),
+                    microseconds: LittleEndian::read_i32(&header[8..12]),
+                    ecu_id,
+                }),
+                data: src.split_to(len).freeze(),
+            });
+        }
+    }
+}
+
+impl Decoder for DltCodec {
+    type Item = DltMessage;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if self.storage {
+            return Ok(self.decode_storage(src));
+        }
+        if src.len() < STANDARD_HEADER_LEN {
+            return Ok(None);
+        }
+        let len = message_len(src)
+            .ok_or_else(|| CodecError::Protocol("message shorter than its headers".to_owned()))?;
+        if src.len() < len {
+            return Ok(None);
+        }
+        Ok(Some(DltMessage {
+            storage_header: None,
+            data: src.split_to(len).freeze(),
+        }))
+    }
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        let offset = if self.storage {
+            if !src.starts_with(PATTERN) {
+                return None;
+            }
+            STORAGE_HEADER_LEN
+        } else {
+            0
+        };
+        if src.len() < offset + STANDARD_HEADER_LEN {
+            return None;
+        }
+        let len = BigEndian::read_u16(&src[offset + 2..offset + 4]) as usize;
+        (offset + len).checked_sub(src.len())
+    }
+}
+
+impl Encoder for DltCodec {
+    type Item = DltMessage;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let valid = item.data.len() >= STANDARD_HEADER_LEN
+            && message_len(&item.data) == Some(item.data.len());
+        if !valid {
+            return Err(CodecError::Protocol(
+                "message length does not match its header".to_owned(),
+            ));
+        }
+        match (self.storage, item.storage_header) {
+            (true, Some(header)) => {
+                dst.reserve(STORAGE_HEADER_LEN);
+                dst.put_slice(PATTERN);
+                dst.put_u32_le(header.seconds);
+                dst.put_i32_le(header.microseconds);
+                dst.put_slice(&header.ecu_id);
+            }
+            (false, None) => {}
+            (true, None) => {
+                return Err(CodecError::Protocol("missing storage header".to_owned()));
+            }
+            (false, Some(_)) => {
+                return Err(CodecError::Protocol(
+                    "storage header on a network message".to_owned(),
+                ));
+            }
+        }
+        dst.extend_from_slice(&item.data);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn storage_resyncs_on_pattern() {
+        // Extended header and timestamp, "Hi" as payload
+        let data = Bytes::from(&b"\x31\x01\x00\x14\x00\x00\x00\x2a\x41\x01APP1CTX1Hi"[..]);
+        let message = DltMessage {
+            storage_header: Some(DltStorageHeader {
+                seconds: 1_600_000_000,
+                microseconds: 5,
+                ecu_id: *b"ECU1",
+            }),
+            data,
+        };
+        let mut codec = DltCodec::storage();
+        let mut stored = BytesMut::new();
+        codec.encode(message.clone(), &mut stored).unwrap();
+
+        let mut buf = BytesMut::from(&b"garbageDLT\x01"[..]);
+        // A corrupted message claiming to be shorter than its headers
+        buf.extend_from_slice(b"\0\0\0\0\0\0\0\0ECU1\x31\x00\x00\x02");
+        buf.extend_from_slice(&stored[..10]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(codec.bytes_needed(&buf), None);
+        assert_eq!(&buf[..], &stored[..10]);
+        buf.extend_from_slice(&stored[10..]);
+
+        let decoded = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(decoded, message);
+        assert_eq!(decoded.timestamp(), Some(42));
+        assert_eq!(decoded.ecu_id(), None);
+        assert_eq!(decoded.app_id(), Some(&b"APP1"[..]));
+        assert_eq!(decoded.context_id(), Some(&b"CTX1"[..]));
+        assert_eq!(decoded.payload(), b"Hi");
+        assert!(buf.is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BigEndian, BufMut, ByteOrder, Bytes, BytesMut};
+
+/// Size of the header in front of every frame
+const HEADER_LEN: usize = 8;
+/// Default limit on the length of a frame
+const MAX_FRAME: usize = 8 * 1024 * 1024;
+
+/// A codec for the stream multiplexing of `docker attach` and `docker logs`
+/// on containers without a TTY.
+///
+/// Every frame is an 8 byte header, holding the stream in its first byte
+/// and the length of the payload as a big endian `u32` in the last four,
+/// followed by the payload.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, DockerStdCopyCodec, Encoder, StdStream};
+///
+/// let mut codec = DockerStdCopyCodec::new();
+/// let mut buf = BytesMut::from(&b"\x01\x00\x00\x00\x00\x00\x00\x06Hello\n"[..]);
+///
+/// let (stream, data) = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(stream, StdStream::Stdout);
+/// assert_eq!(&data[..], b"Hello\n");
+///
+/// codec.encode((StdStream::Stderr, Bytes::from("oops\n")), &mut buf).unwrap();
+/// assert_eq!(&buf[..8], b"\x02\x00\x00\x00\x00\x00\x00\x05");
+/// ```
+#[derive(Debug)]
+pub struct DockerStdCopyCodec {
+    max_length: usize,
+}
+
+/// The stream a frame of multiplexed container output belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StdStream {
+    Stdin,
+    Stdout,
+    Stderr,
+    /// Errors of the daemon itself, sent by newer versions of docker
+    SystemErr,
+}
+
+impl StdStream {
+    fn from_byte(b: u8) -> Option<Self> {
+        Some(match b {
+            0 => StdStream::Stdin,
+            1 => StdStream::Stdout,
+            2 => StdStream::Stderr,
+            3 => StdStream::SystemErr,
+            _ => return None,
+        })
+    }
+}
+
+impl DockerStdCopyCodec {
+    pub fn new() -> Self {
+        Self {
+            max_length: MAX_FRAME,
+        }
+    }
+
+    /// Fail on frames with more than `max` bytes of payload
+    pub fn max_length(mut self, max: usize) -> Self {
+        self.max_length = max;
+        self
+    }
+}
+
+impl Default for DockerStdCopyCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for DockerStdCopyCodec {
+    type Item = (StdStream, Bytes);
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.len() < HEADER_LEN {
+            return Ok(None);
+        }
+        let stream = StdStream::from_byte(src[0])
+            .ok_or_else(|| CodecError::Protocol("unknown stream type".to_owned()))?;
+        let len = BigEndian::read_u32(&src[4..8]) as usize;
+        if len > self.max_length {
+            return Err(CodecError::FrameTooLong {
+                max: self.max_length,
+            });
+        }
+        if src.len() < HEADER_LEN + len {
+            return Ok(None);
+        }
+
+        src.advance(HEADER_LEN);
+        Ok(Some((stream, src.split_to(len).freeze())))
+    }
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        if src.len() < HEADER_LEN {
+            return None;
+        }
+        let len = BigEndian::read_u32(&src[4..8]) as usize;
+        (HEADER_LEN + len).checked_sub(src.len())
+    }
+}
+
+impl Encoder for DockerStdCopyCodec {
+    type Item = (StdStream, Bytes);
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let (stream, data) = item;
+        if data.len() > self.max_length.min(u32::MAX as usize) {
+            return Err(CodecError::FrameTooLong {
+                max: self.max_length,
+            });
+        }
+        dst.reserve(HEADER_LEN + data.len());
+        dst.put_slice(&[stream as u8, 0, 0, 0]);
+        dst.put_u32_be(data.len() as u32);
+        dst.put_slice(&data);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn demultiplexes_split_frames() {
+        let mut codec = DockerStdCopyCodec::new();
+        let mut frames = BytesMut::new();
+        codec
+            .encode((StdStream::Stdout, Bytes::from("out")), &mut frames)
+            .unwrap();
+        codec
+            .encode((StdStream::Stderr, Bytes::from("err")), &mut frames)
+            .unwrap();
+
+        let mut buf = BytesMut::from(&frames[..5]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(&frames[5..20]);
+        assert_eq!(codec.bytes_needed(&buf), None);
+        let out = codec.decode(&mut buf).unwrap();
+        assert_eq!(out, Some((StdStream::Stdout, Bytes::from("out"))));
+        assert_eq!(codec.bytes_needed(&buf), Some(2));
+        buf.extend_from_slice(&frames[20..]);
+        let err = codec.decode(&mut buf).unwrap();
+        assert_eq!(err, Some((StdStream::Stderr, Bytes::from("err"))));
+
+        let mut bad = BytesMut::from(&b"\x07\x00\x00\x00\x00\x00\x00\x00"[..]);
+        assert!(codec.decode(&mut bad).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{Bytes, BytesMut};
+
+/// Default limit on the length of a block as sent
+const MAX_BLOCK: usize = 64 * 1024 * 1024;
+
+/// A codec for multi-line blocks that end with a line holding a lone `.`,
+/// as sent by POP3 `RETR` and NNTP `ARTICLE`.
+///
+/// A line of the block starting with a dot is sent with an extra dot in
+/// front. Frames are the block with CRLF line endings, without the stuffing
+/// and without the terminating line.
+///
+/// Only blocks are framed, so read single line responses with another codec.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, DotTerminatedCodec};
+///
+/// let mut codec = DotTerminatedCodec::new();
+/// let mut buf = BytesMut::from(&b"+OK\r\nSubject: hi\r\n..hidden\r\n.\r\n"[..]);
+///
+/// let block = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(block, "+OK\r\nSubject: hi\r\n.hidden\r\n");
+/// ```
+#[derive(Debug)]
+pub struct DotTerminatedCodec {
+    /// Index into the buffer of the first line not checked for the
+    /// terminator yet
+    scan: usize,
+    max_length: usize,
+}
+
+impl DotTerminatedCodec {
+    pub fn new() -> Self {
+        Self {
+            scan: 0,
+            max_length: MAX_BLOCK,
+        }
+    }
+
+    /// Fail with `FrameTooLong` on blocks taking more than `max` bytes as
+    /// sent, stuffing and terminating line included, 64 MiB by default
+    pub fn max_length(mut self, max: usize) -> Self {
+        self.max_length = max;
+        self
+    }
+
+    fn too_long(&mut self) -> CodecError {
+        self.scan = 0;
+        CodecError::FrameTooLong {
+            max: self.max_length,
+        }
+    }
+}
+
+impl Default for DotTerminatedCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for DotTerminatedCodec {
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            let end = match find_crlf(&src[self.scan..]) {
+                Some(end) => self.scan + end,
+                None if src.len() > self.max_length => return Err(self.too_long()),
+                None => return Ok(None),
+            };
+            if end + 2 > self.max_length {
+                return Err(self.too_long());
+            }
+            if &src[self.scan..end] == b"." {
+                let stuffed = src.split_to(self.scan);
+                src.advance(3);
+                self.scan = 0;
+                return Ok(Some(unstuff(&stuffed)));
+            }
+            self.scan = end + 2;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.scan = 0;
+    }
+}
+
+impl Encoder for DotTerminatedCodec {
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.reserve(item.len() + 5);
+        for line in item.split_inclusive(|&b| b == b'\n') {
+            if line.starts_with(b".") {
+                dst.extend_from_slice(b".");
+            }
+            dst.extend_from_slice(line);
+        }
+        if !item.is_empty() && !item.ends_with(b"\r\n") {
+            dst.extend_from_slice(b"\r\n");
+        }
+        dst.extend_from_slice(b".\r\n");
+        Ok(())
+    }
+}
+
+/// Index of the first CRLF in `src`
+pub(crate) fn find_crlf(src: &[u8]) -> Option<usize> {
+    src.windows(2).position(|w| w == b"\r\n")
+}
+
+/// Remove the extra dot of lines starting with one
+fn unstuff(stuffed: &[u8]) -> Bytes {
+    let mut block = BytesMut::with_capacity(stuffed.len());
+    for line in stuffed.split_inclusive(|&b| b == b'\n') {
+        let line = if line.starts_with(b".") {
+            &line[1..]
+        } else {
+            line
+        };
+        block.extend_from_slice(line);
+    }
+    block.freeze()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn roundtrip_with_dot_lines() {
+        let block = Bytes::from("Subject: hi\r\n.\r\n..\r\n");
+        let mut codec = DotTerminatedCodec::new();
+        let mut buf = BytesMut::new();
+        codec.encode(block.clone(), &mut buf).unwrap();
+        assert_eq!(&buf[..], &b"Subject: hi\r\n..\r\n...\r\n.\r\n"[..]);
+
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(block));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn max_length() {
+        let mut codec = DotTerminatedCodec::new().max_length(8);
+        let mut buf = BytesMut::from(&b"Hi\r\n.\r\n"[..]);
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "Hi\r\n");
+
+        buf.extend_from_slice(b"Hello\r\n.\r\n");
+        match codec.decode(&mut buf) {
+            Err(CodecError::FrameTooLong { max }) => assert_eq!(max, 8),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let mut buf = BytesMut::from(&b"Hello, no line end"[..]);
+        let err = DotTerminatedCodec::new()
+            .max_length(8)
+            .decode(&mut buf)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
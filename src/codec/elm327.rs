@@ -0,0 +1,138 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::BytesMut;
+
+/// Default limit on the length of a response
+const MAX_RESPONSE: usize = 4096;
+
+/// A codec for ELM327 style OBD-II adapters, encoding commands and decoding
+/// the responses ending in the `>` prompt.
+///
+/// Commands are sent terminated by a carriage return. A response is split
+/// into its non-empty lines, whether the adapter ends them with `\r` or
+/// `\r\n`. When echo is enabled on the adapter, the response starts with the
+/// command it answers, and the codec removes that line. Null bytes, which
+/// some adapters send before the prompt, are ignored.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, Elm327Codec, Encoder};
+///
+/// let mut codec = Elm327Codec::new();
+/// let mut buf = BytesMut::new();
+/// codec.encode("010C".to_owned(), &mut buf).unwrap();
+/// assert_eq!(&buf[..], b"010C\r");
+///
+/// let mut buf = BytesMut::from(&b"010C\r41 0C 1A F8\r\r>"[..]);
+/// let response = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(response, ["41 0C 1A F8"]);
+/// ```
+#[derive(Debug)]
+pub struct Elm327Codec {
+    /// The last command sent, expected as echo
+    command: Option<String>,
+    max_length: usize,
+}
+
+impl Elm327Codec {
+    pub fn new() -> Self {
+        Self {
+            command: None,
+            max_length: MAX_RESPONSE,
+        }
+    }
+
+    /// Fail on responses longer than `max` bytes
+    pub fn max_length(mut self, max: usize) -> Self {
+        self.max_length = max;
+        self
+    }
+}
+
+impl Default for Elm327Codec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for Elm327Codec {
+    type Item = Vec<String>;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let end = match src.iter().position(|&b| b == b'>') {
+            Some(end) => end,
+            None if src.len() > self.max_length => {
+                return Err(CodecError::FrameTooLong {
+                    max: self.max_length,
+                })
+            }
+            None => return Ok(None),
+        };
+        let response = src.split_to(end + 1);
+        let text = std::str::from_utf8(&response[..end])?;
+
+        let mut lines: Vec<String> = text
+            .split(['\r', '\n'])
+            .map(|line| line.trim_matches('\0').trim())
+            .filter(|line| !line.is_empty())
+            .map(str::to_owned)
+            .collect();
+        if let Some(command) = self.command.take() {
+            if lines
+                .first()
+                .is_some_and(|line| line.eq_ignore_ascii_case(&command))
+            {
+                lines.remove(0);
+            }
+        }
+        Ok(Some(lines))
+    }
+}
+
+impl Encoder for Elm327Codec {
+    type Item = String;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if item.contains(['\r', '\n', '>']) {
+            return Err(CodecError::Protocol(
+                "line break or prompt in command".to_owned(),
+            ));
+        }
+        dst.reserve(item.len() + 1);
+        dst.extend_from_slice(item.as_bytes());
+        dst.extend_from_slice(b"\r");
+        self.command = Some(item.trim().to_owned());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn multi_line_response_without_echo() {
+        let mut codec = Elm327Codec::new();
+        codec
+            .encode("0902".to_owned(), &mut BytesMut::new())
+            .unwrap();
+
+        let mut buf = BytesMut::from(&b"SEARCHING...\r\n014\r\n0: 49 02 01 31 44 34\r\n"[..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(b"1: 47 50 30 30 52 35\r\n\r\n\0>ATZ");
+
+        let response = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(
+            response,
+            [
+                "SEARCHING...",
+                "014",
+                "0: 49 02 01 31 44 34",
+                "1: 47 50 30 30 52 35"
+            ]
+        );
+        assert_eq!(&buf[..], b"ATZ");
+    }
+}
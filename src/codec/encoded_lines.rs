@@ -0,0 +1,175 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BufMut, BytesMut};
+use encoding_rs::{CoderResult, Encoding, UTF_16BE, UTF_16LE};
+
+/// Default limit on the length of a line, in decoded bytes
+const MAX_LINE: usize = 64 * 1024;
+
+/// A codec that splits up text in a legacy encoding into lines, such as
+/// Latin-1, Shift JIS or UTF-16.
+///
+/// Decoding converts the input to UTF-8 as it arrives, keeping a character
+/// split across reads until it is complete, and yields lines including the
+/// trailing newline. Malformed sequences are replaced with U+FFFD, and a
+/// byte order mark at the start is removed. Encoding converts a `String`
+/// to the encoding and fails on characters the encoding can't represent.
+/// As the last line may lack its newline, use `decode_eof` at the end of
+/// the input.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use encoding_rs::{SHIFT_JIS, WINDOWS_1252};
+/// use futures_codec::{Decoder, EncodedLinesCodec, Encoder};
+///
+/// let mut codec = EncodedLinesCodec::new(WINDOWS_1252);
+/// let mut buf = BytesMut::from(&b"caf\xe9\nna"[..]);
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some("café\n".to_owned()));
+/// assert_eq!(codec.decode_eof(&mut buf).unwrap(), Some("na".to_owned()));
+///
+/// let mut codec = EncodedLinesCodec::new(SHIFT_JIS);
+/// codec.encode("日本\n".to_owned(), &mut buf).unwrap();
+/// assert_eq!(&buf[..], b"\x93\xfa\x96\x7b\n");
+/// ```
+#[derive(Debug)]
+pub struct EncodedLinesCodec {
+    encoding: &'static Encoding,
+    decoder: encoding_rs::Decoder,
+    /// Decoded text not yielded yet
+    text: String,
+    /// Length of `text` that was searched for a newline
+    searched: usize,
+    max_line_len: usize,
+}
+
+impl EncodedLinesCodec {
+    /// Codec for lines in `encoding`
+    pub fn new(encoding: &'static Encoding) -> Self {
+        Self {
+            encoding,
+            decoder: encoding.new_decoder_with_bom_removal(),
+            text: String::new(),
+            searched: 0,
+            max_line_len: MAX_LINE,
+        }
+    }
+
+    /// Fail with `FrameTooLong` on lines longer than `max` bytes once
+    /// decoded
+    pub fn max_line_len(mut self, max: usize) -> Self {
+        self.max_line_len = max;
+        self
+    }
+
+    /// Decode all of `src` into the pending text
+    fn decode_input(&mut self, src: &mut BytesMut, last: bool) {
+        let mut input = &src[..];
+        loop {
+            let needed = self.decoder.max_utf8_buffer_length(input.len());
+            self.text.reserve(needed.unwrap_or(input.len() * 3 + 16));
+            let (result, read, _) = self.decoder.decode_to_string(input, &mut self.text, last);
+            input = &input[read..];
+            if let CoderResult::InputEmpty = result {
+                break;
+            }
+        }
+        src.clear();
+    }
+
+    /// Split the next line off the pending text
+    fn next_line(&mut self) -> Result<Option<String>, CodecError> {
+        match self.text[self.searched..].find('\n') {
+            Some(end) => {
+                let rest = self.text.split_off(self.searched + end + 1);
+                self.searched = 0;
+                Ok(Some(std::mem::replace(&mut self.text, rest)))
+            }
+            None if self.text.len() > self.max_line_len => Err(CodecError::FrameTooLong {
+                max: self.max_line_len,
+            }),
+            None => {
+                self.searched = self.text.len();
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl Decoder for EncodedLinesCodec {
+    type Item = String;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.decode_input(src, false);
+        self.next_line()
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.decode_input(src, true);
+        if let Some(line) = self.next_line()? {
+            return Ok(Some(line));
+        }
+        self.decoder = self.encoding.new_decoder_with_bom_removal();
+        self.searched = 0;
+        if self.text.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(std::mem::take(&mut self.text)))
+    }
+}
+
+impl Encoder for EncodedLinesCodec {
+    type Item = String;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        // encoding_rs only decodes UTF-16
+        if self.encoding == UTF_16LE || self.encoding == UTF_16BE {
+            dst.reserve(item.len() * 2);
+            for unit in item.encode_utf16() {
+                if self.encoding == UTF_16LE {
+                    dst.put_u16_le(unit);
+                } else {
+                    dst.put_u16_be(unit);
+                }
+            }
+            return Ok(());
+        }
+        let (encoded, _, unmappable) = self.encoding.encode(&item);
+        if unmappable {
+            return Err(CodecError::Protocol(format!(
+                "line can't be represented in {}",
+                self.encoding.name()
+            )));
+        }
+        dst.extend_from_slice(&encoded);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use encoding_rs::WINDOWS_1252;
+
+    #[test]
+    fn utf16_split_across_reads() {
+        let mut codec = EncodedLinesCodec::new(UTF_16LE);
+        let mut encoded = BytesMut::new();
+        codec
+            .encode("\u{feff}𝄞 a\nb".to_owned(), &mut encoded)
+            .unwrap();
+
+        let mut buf = BytesMut::from(&encoded[..3]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(&encoded[3..7]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(&encoded[7..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some("𝄞 a\n".to_owned()));
+        assert_eq!(codec.decode_eof(&mut buf).unwrap(), Some("b".to_owned()));
+        assert_eq!(codec.decode_eof(&mut buf).unwrap(), None);
+
+        let mut latin1 = EncodedLinesCodec::new(WINDOWS_1252);
+        assert!(latin1.encode("日本".to_owned(), &mut buf).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BufMut, Bytes, BytesMut};
+
+/// Default limit on the length of a record
+const MAX_RECORD: usize = 64 * 1024;
+
+/// A codec for records of separated fields, with configurable separators,
+/// escape and quote characters, yielding the fields of every record.
+///
+/// A byte following the escape character is taken literally, as is
+/// everything between quote characters except the escape character and a
+/// doubled quote character, which stands for the quote character itself.
+/// Escape and quote characters are removed when decoding. When encoding,
+/// special bytes in a field are escaped if there is an escape character,
+/// otherwise fields holding them are quoted. Without either, such fields
+/// can't be encoded. As the last record may lack its separator, use
+/// `decode_eof` at the end of the input.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, Encoder, EscapedFieldCodec};
+///
+/// let mut codec = EscapedFieldCodec::new(b'|', b'\n').escape(b'\\');
+/// let mut buf = BytesMut::from(&b"a|b\\|c|\n"[..]);
+///
+/// let fields = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(fields, ["a", "b|c", ""]);
+///
+/// codec.encode(fields, &mut buf).unwrap();
+/// assert_eq!(&buf[..], b"a|b\\|c|\n");
+/// ```
+#[derive(Debug)]
+pub struct EscapedFieldCodec {
+    field_sep: u8,
+    record_sep: u8,
+    escape: Option<u8>,
+    quote: Option<u8>,
+    max_record_len: usize,
+}
+
+impl EscapedFieldCodec {
+    /// Codec for records of fields separated by `field_sep`, ending in
+    /// `record_sep`, without escape or quote character
+    pub fn new(field_sep: u8, record_sep: u8) -> Self {
+        Self {
+            field_sep,
+            record_sep,
+            escape: None,
+            quote: None,
+            max_record_len: MAX_RECORD,
+        }
+    }
+
+    /// Escape the following byte with `escape`
+    pub fn escape(mut self, escape: u8) -> Self {
+        self.escape = Some(escape);
+        self
+    }
+
+    /// Quote fields with `quote`
+    pub fn quote(mut self, quote: u8) -> Self {
+        self.quote = Some(quote);
+        self
+    }
+
+    /// Fail with `FrameTooLong` on records longer than `max` bytes
+    pub fn max_record_len(mut self, max: usize) -> Self {
+        self.max_record_len = max;
+        self
+    }
+
+    fn is_special(&self, b: u8) -> bool {
+        b == self.field_sep
+            || b == self.record_sep
+            || Some(b) == self.escape
+            || Some(b) == self.quote
+    }
+
+    /// Split the record at the start of `src` into its fields, returning
+    /// them with the length of the record, if it is complete
+    fn split(&self, src: &[u8], eof: bool) -> Result<Option<(Vec<Bytes>, usize)>, CodecError> {
+        let mut fields = Vec::new();
+        let mut field = Vec::new();
+        let mut quoted = false;
+        let mut i = 0;
+        while i < src.len() {
+            let b = src[i];
+            i += 1;
+            if Some(b) == self.escape {
+                match src.get(i) {
+                    Some(&next) => field.push(next),
+                    None if eof => return Err(invalid("escape character at the end")),
+                    None => return Ok(None),
+                }
+                i += 1;
+            } else if Some(b) == self.quote {
+                if quoted && src.get(i) == Some(&b) {
+                    field.push(b);
+                    i += 1;
+                } else if quoted && i == src.len() && !eof {
+                    // Might be the first of a doubled quote character
+                    return Ok(None);
+                } else {
+                    quoted = !quoted;
+                }
+            } else if quoted {
+                field.push(b);
+            } else if b == self.field_sep {
+                fields.push(Bytes::from(std::mem::take(&mut field)));
+            } else if b == self.record_sep {
+                fields.push(Bytes::from(field));
+                return Ok(Some((fields, i)));
+            } else {
+                field.push(b);
+            }
+        }
+        if !eof {
+            return Ok(None);
+        }
+        if quoted {
+            return Err(invalid("unterminated quoted field"));
+        }
+        fields.push(Bytes::from(field));
+        Ok(Some((fields, i)))
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+impl Decoder for EscapedFieldCodec {
+    type Item = Vec<Bytes>;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.split(src, false)? {
+            Some((fields, len)) => {
+                src.advance(len);
+                Ok(Some(fields))
+            }
+            None if src.len() > self.max_record_len => Err(CodecError::FrameTooLong {
+                max: self.max_record_len,
+            }),
+            None => Ok(None),
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if let Some(fields) = self.decode(src)? {
+            return Ok(Some(fields));
+        }
+        if src.is_empty() {
+            return Ok(None);
+        }
+        let (fields, _) = self.split(src, true)?.unwrap();
+        src.clear();
+        Ok(Some(fields))
+    }
+}
+
+impl Encoder for EscapedFieldCodec {
+    type Item = Vec<Bytes>;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        for (i, field) in item.iter().enumerate() {
+            let special = field.iter().filter(|&&b| self.is_special(b)).count();
+            dst.reserve(1 + field.len() + special + 2);
+            if i > 0 {
+                dst.put_u8(self.field_sep);
+            }
+            match (self.escape, self.quote) {
+                _ if special == 0 => dst.put_slice(field),
+                (Some(escape), _) => {
+                    for &b in field.iter() {
+                        if self.is_special(b) {
+                            dst.put_u8(escape);
+                        }
+                        dst.put_u8(b);
+                    }
+                }
+                (None, Some(quote)) => {
+                    dst.put_u8(quote);
+                    for &b in field.iter() {
+                        if b == quote {
+                            dst.put_u8(quote);
+                        }
+                        dst.put_u8(b);
+                    }
+                    dst.put_u8(quote);
+                }
+                (None, None) => return Err(invalid("field contains a separator")),
+            }
+        }
+        dst.reserve(1);
+        dst.put_u8(self.record_sep);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quoted_fields() {
+        let mut codec = EscapedFieldCodec::new(b'\t', b'\n').quote(b'"');
+        let mut buf = BytesMut::from(&b"\"a\tb\"\t\"say \"\"hi\"\"\"\nlast\t\"x"[..]);
+
+        let fields = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(fields, ["a\tb", "say \"hi\""]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert!(codec.decode_eof(&mut buf).is_err());
+
+        let mut out = BytesMut::new();
+        codec.encode(fields, &mut out).unwrap();
+        assert_eq!(&out[..], b"\"a\tb\"\t\"say \"\"hi\"\"\"\n");
+
+        let mut plain = EscapedFieldCodec::new(b',', b'\n');
+        let fields = vec![Bytes::from("a,b")];
+        assert!(plain.encode(fields, &mut out).is_err());
+    }
+}
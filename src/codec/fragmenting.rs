@@ -0,0 +1,268 @@
+use crate::{Decoder, Encoder};
+use bytes::{BigEndian, BufMut, ByteOrder, BytesMut};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Error, ErrorKind};
+
+/// Size of the header in front of every fragment
+const HEADER_LEN: usize = 8;
+/// Default limit on the number of messages reassembled at the same time
+const MAX_PENDING: usize = 64;
+/// Default limit on the payload buffered for messages not complete yet
+const MAX_BUFFERED: usize = 16 * 1024 * 1024;
+
+/// A codec wrapper that splits frames encoded by an inner codec into fragments
+/// of at most `mtu` bytes, and reassembles them on decode.
+///
+/// Every fragment starts with a header of four big endian `u16`s: the message
+/// id, the index of the fragment, the number of fragments in the message and
+/// the length of the fragment payload.
+///
+/// Decoding fails with `InvalidData` once more than 64 messages are pending
+/// or their fragments add up to more than 16 MiB, see `max_pending` and
+/// `max_buffered`. The pending messages are dropped then.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, Encoder, FragmentingCodec, LinesCodec};
+///
+/// let mut codec = FragmentingCodec::new(LinesCodec::new(), 12);
+/// let mut buf = BytesMut::new();
+/// codec.encode("Hello World!\n".to_owned(), &mut buf).unwrap();
+/// assert_eq!(buf.len(), 4 * 8 + 13);
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "Hello World!\n");
+/// ```
+#[derive(Debug)]
+pub struct FragmentingCodec<C> {
+    inner: C,
+    mtu: usize,
+    out_of_order: bool,
+    next_id: u16,
+    partial: HashMap<u16, Partial>,
+    max_pending: usize,
+    max_buffered: usize,
+    /// Payload bytes of all fragments in `partial`
+    buffered: usize,
+}
+
+#[derive(Debug)]
+struct Partial {
+    /// Fragments received so far by index
+    fragments: BTreeMap<usize, BytesMut>,
+    count: usize,
+}
+
+impl<C> FragmentingCodec<C> {
+    /// Wrap `inner`, producing fragments of at most `mtu` bytes including
+    /// their header.
+    ///
+    /// # Panics
+    /// If `mtu` leaves no room for payload after the header.
+    pub fn new(inner: C, mtu: usize) -> Self {
+        assert!(mtu > HEADER_LEN, "mtu must be larger than the fragment header");
+        Self {
+            inner,
+            mtu,
+            out_of_order: false,
+            next_id: 0,
+            partial: HashMap::new(),
+            max_pending: MAX_PENDING,
+            max_buffered: MAX_BUFFERED,
+            buffered: 0,
+        }
+    }
+
+    /// Accept fragments in any order and interleaved between messages,
+    /// instead of failing on anything but consecutive fragments.
+    pub fn out_of_order(mut self, enabled: bool) -> Self {
+        self.out_of_order = enabled;
+        self
+    }
+
+    /// Fail once fragments of more than `max` messages are waiting for the
+    /// rest of their message, 64 by default
+    pub fn max_pending(mut self, max: usize) -> Self {
+        self.max_pending = max;
+        self
+    }
+
+    /// Fail once the fragments waiting for the rest of their message take
+    /// up more than `max` bytes, 16 MiB by default
+    pub fn max_buffered(mut self, max: usize) -> Self {
+        self.max_buffered = max;
+        self
+    }
+
+    /// Drop all pending messages, returning an `InvalidData` error
+    fn fail(&mut self, msg: &str) -> Error {
+        self.partial.clear();
+        self.buffered = 0;
+        Error::new(ErrorKind::InvalidData, msg)
+    }
+
+    /// Release the inner codec
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+impl<C: Encoder> Encoder for FragmentingCodec<C> {
+    type Item = C::Item;
+    type Error = C::Error;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let mut frame = BytesMut::new();
+        self.inner.encode(item, &mut frame)?;
+
+        let max_payload = (self.mtu - HEADER_LEN).min(u16::MAX as usize);
+        let count = frame.len().div_ceil(max_payload).max(1);
+        if count > u16::MAX as usize {
+            return Err(Error::new(ErrorKind::InvalidInput, "frame has too many fragments").into());
+        }
+
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+
+        dst.reserve(frame.len() + count * HEADER_LEN);
+        for index in 0..count {
+            let payload = frame.split_to(max_payload.min(frame.len()));
+            dst.put_u16_be(id);
+            dst.put_u16_be(index as u16);
+            dst.put_u16_be(count as u16);
+            dst.put_u16_be(payload.len() as u16);
+            dst.extend_from_slice(&payload);
+        }
+        Ok(())
+    }
+}
+
+impl<C: Decoder> Decoder for FragmentingCodec<C> {
+    type Item = C::Item;
+    type Error = C::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            if src.len() < HEADER_LEN {
+                return Ok(None);
+            }
+            let id = BigEndian::read_u16(&src[0..2]);
+            let index = BigEndian::read_u16(&src[2..4]) as usize;
+            let count = BigEndian::read_u16(&src[4..6]) as usize;
+            let len = BigEndian::read_u16(&src[6..8]) as usize;
+            if src.len() < HEADER_LEN + len {
+                return Ok(None);
+            }
+            if index >= count {
+                return Err(Error::new(ErrorKind::InvalidData, "fragment index out of range").into());
+            }
+
+            src.advance(HEADER_LEN);
+            let payload = src.split_to(len);
+
+            if !self.out_of_order {
+                let expected = self.partial.get(&id).map_or(0, |p| p.fragments.len());
+                if index != expected || (index == 0 && !self.partial.is_empty()) {
+                    return Err(self.fail("fragment out of order").into());
+                }
+            }
+            if !self.partial.contains_key(&id) && self.partial.len() >= self.max_pending {
+                return Err(self.fail("too many pending messages").into());
+            }
+            if self.buffered + payload.len() > self.max_buffered {
+                return Err(self.fail("too many pending fragments").into());
+            }
+
+            let partial = self.partial.entry(id).or_insert_with(|| Partial {
+                fragments: BTreeMap::new(),
+                count,
+            });
+            if partial.count != count {
+                let partial = self.partial.remove(&id).expect("partial message");
+                self.buffered -= partial.fragments.values().map(BytesMut::len).sum::<usize>();
+                return Err(Error::new(ErrorKind::InvalidData, "fragment count mismatch").into());
+            }
+            self.buffered += payload.len();
+            if let Some(replaced) = partial.fragments.insert(index, payload) {
+                self.buffered -= replaced.len();
+            }
+
+            if partial.fragments.len() == count {
+                let partial = self.partial.remove(&id).expect("partial message");
+                let mut frame = BytesMut::new();
+                for fragment in partial.fragments.values() {
+                    frame.extend_from_slice(fragment);
+                }
+                self.buffered -= frame.len();
+
+                return match self.inner.decode(&mut frame)? {
+                    Some(item) => Ok(Some(item)),
+                    None => Err(Error::new(ErrorKind::InvalidData, "incomplete message").into()),
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::BytesCodec;
+    use bytes::Bytes;
+
+    #[test]
+    fn reassembles_out_of_order() {
+        let mut codec = FragmentingCodec::new(BytesCodec {}, 10).out_of_order(true);
+        let mut buf = BytesMut::new();
+        codec.encode(Bytes::from("Hello"), &mut buf).unwrap();
+        assert_eq!(buf.len(), 8 + 2 + 8 + 2 + 8 + 1);
+
+        // Swap the first and the last fragment
+        let first = buf.split_to(10);
+        let second = buf.split_to(10);
+        let mut swapped = buf;
+        swapped.extend_from_slice(&second);
+        swapped.extend_from_slice(&first);
+
+        assert_eq!(codec.decode(&mut swapped).unwrap().unwrap(), Bytes::from("Hello"));
+    }
+
+    #[test]
+    fn rejects_out_of_order() {
+        let mut codec = FragmentingCodec::new(BytesCodec {}, 10);
+        let mut buf = BytesMut::new();
+        codec.encode(Bytes::from("Hello"), &mut buf).unwrap();
+        buf.advance(10);
+
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn limits_pending_messages() {
+        let mut codec = FragmentingCodec::new(BytesCodec {}, 10)
+            .out_of_order(true)
+            .max_pending(2);
+        let mut buf = BytesMut::new();
+        for _ in 0..3 {
+            let mut message = BytesMut::new();
+            codec.encode(Bytes::from("Hello"), &mut message).unwrap();
+            buf.extend_from_slice(&message[..10]);
+        }
+
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(codec.partial.is_empty());
+    }
+
+    #[test]
+    fn limits_buffered_bytes() {
+        let mut codec = FragmentingCodec::new(BytesCodec {}, 10).max_buffered(3);
+        let mut buf = BytesMut::new();
+        codec.encode(Bytes::from("Hello"), &mut buf).unwrap();
+
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(codec.buffered, 0);
+    }
+}
@@ -0,0 +1,158 @@
+use super::dot::find_crlf;
+use crate::{CodecError, Decoder, Encoder};
+use bytes::BytesMut;
+
+/// Longest reply line accepted
+const MAX_LINE: usize = 8 * 1024;
+
+/// A codec for the control connection of an FTP client, decoding replies and
+/// encoding commands.
+///
+/// A multi-line reply starts with a line of the code followed by `-` and ends
+/// with a line of the same code followed by a space. The lines in between
+/// may hold any text. Reply text that is not UTF-8 is decoded lossily, as
+/// older servers send Latin-1.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, Encoder, FtpControlCodec};
+///
+/// let mut codec = FtpControlCodec::new();
+/// let mut buf = BytesMut::from(&b"211-Features:\r\n MDTM\r\n211 End\r\n"[..]);
+///
+/// let reply = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(reply.code, 211);
+/// assert_eq!(reply.lines, ["Features:", " MDTM", "End"]);
+///
+/// codec.encode("FEAT".to_owned(), &mut buf).unwrap();
+/// assert_eq!(&buf[..], b"FEAT\r\n");
+/// ```
+#[derive(Debug, Default)]
+pub struct FtpControlCodec {
+    /// Index into the buffer of the first line not checked for the end of
+    /// the reply yet
+    scan: usize,
+}
+
+/// A reply of an FTP server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FtpReply {
+    /// Three digit reply code
+    pub code: u16,
+    /// Text of every line, without the code and separator of the first and
+    /// the last line
+    pub lines: Vec<String>,
+}
+
+impl FtpControlCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn reply_code(line: &[u8]) -> Option<u16> {
+    let code = line.get(..3)?;
+    if !code.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    std::str::from_utf8(code).ok()?.parse().ok()
+}
+
+impl Decoder for FtpControlCodec {
+    type Item = FtpReply;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let first_end = match find_crlf(src) {
+            Some(end) => end,
+            None if src.len() > MAX_LINE => return Err(CodecError::FrameTooLong { max: MAX_LINE }),
+            None => return Ok(None),
+        };
+        let code = reply_code(&src[..first_end])
+            .ok_or_else(|| CodecError::Protocol("reply without a code".to_owned()))?;
+        let multiline = src.get(3) == Some(&b'-');
+
+        // Find the line ending the reply
+        let mut end = first_end;
+        if multiline {
+            let mut start = self.scan.max(first_end + 2);
+            loop {
+                let line_end = match find_crlf(&src[start..]) {
+                    Some(n) => start + n,
+                    None if src.len() - start > MAX_LINE => {
+                        return Err(CodecError::FrameTooLong { max: MAX_LINE })
+                    }
+                    None => {
+                        self.scan = start;
+                        return Ok(None);
+                    }
+                };
+                let line = &src[start..line_end];
+                if reply_code(line) == Some(code) && line.get(3).is_none_or(|&b| b == b' ') {
+                    end = line_end;
+                    break;
+                }
+                start = line_end + 2;
+            }
+        }
+        self.scan = 0;
+
+        let reply = src.split_to(end + 2);
+        let text = String::from_utf8_lossy(&reply[..end]);
+        let last = text.matches("\r\n").count();
+        let lines = text
+            .split("\r\n")
+            .enumerate()
+            .map(|(i, line)| match i {
+                0 => line.get(4..).unwrap_or(""),
+                i if i == last => line.get(4..).unwrap_or(""),
+                _ => line,
+            })
+            .map(str::to_owned)
+            .collect();
+        Ok(Some(FtpReply { code, lines }))
+    }
+
+    fn reset(&mut self) {
+        self.scan = 0;
+    }
+}
+
+impl Encoder for FtpControlCodec {
+    type Item = String;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if item.contains(['\r', '\n']) {
+            return Err(CodecError::Protocol(
+                "command contains a line break".to_owned(),
+            ));
+        }
+        dst.reserve(item.len() + 2);
+        dst.extend_from_slice(item.as_bytes());
+        dst.extend_from_slice(b"\r\n");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn multiline_reply_in_pieces() {
+        let mut codec = FtpControlCodec::new();
+        let mut buf = BytesMut::from(&b"230-Welcome\r\n230-ish line\r\n"[..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+
+        buf.extend_from_slice(b"230 Logged in\r\n200 OK\r\n");
+        let reply = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(reply.lines, ["Welcome", "230-ish line", "Logged in"]);
+        let reply = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(
+            (reply.code, &reply.lines[..]),
+            (200, &["OK".to_owned()][..])
+        );
+    }
+}
@@ -0,0 +1,458 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BigEndian, BufMut, ByteOrder, BytesMut, LittleEndian};
+use std::collections::{HashMap, VecDeque};
+
+/// Default limit on the size of a pickle batch, as in carbon
+const MAX_PICKLE: usize = 1024 * 1024;
+
+/// A codec for the Graphite carbon protocols, in the newline delimited
+/// plaintext format or the length prefixed pickle format.
+///
+/// Pickle batches are decoded into their metrics one by one. Only the
+/// subset of pickle that carbon clients produce is understood: lists and
+/// tuples of strings and numbers. Every encoded metric is a batch of its
+/// own.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, Encoder, GraphiteCodec, GraphiteMetric};
+///
+/// let mut codec = GraphiteCodec::plaintext();
+/// let mut buf = BytesMut::from(&b"servers.a.load 0.5 1700000000\n"[..]);
+///
+/// let metric = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(metric, GraphiteMetric::new("servers.a.load", 0.5, 1700000000));
+///
+/// let mut pickle = GraphiteCodec::pickle();
+/// pickle.encode(metric.clone(), &mut buf).unwrap();
+/// assert_eq!(pickle.decode(&mut buf).unwrap(), Some(metric));
+/// ```
+#[derive(Debug)]
+pub struct GraphiteCodec {
+    pickle: bool,
+    max_pickle: usize,
+    /// Metrics of a decoded batch that were not returned yet
+    pending: VecDeque<GraphiteMetric>,
+}
+
+/// A data point of a Graphite metric.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphiteMetric {
+    /// Dotted path of the metric
+    pub path: String,
+    pub value: f64,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl GraphiteMetric {
+    pub fn new(path: &str, value: f64, timestamp: u64) -> Self {
+        Self {
+            path: path.to_owned(),
+            value,
+            timestamp,
+        }
+    }
+}
+
+impl GraphiteCodec {
+    /// A codec for `path value timestamp` lines
+    pub fn plaintext() -> Self {
+        Self {
+            pickle: false,
+            max_pickle: MAX_PICKLE,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// A codec for pickled batches, each prefixed with a big endian `u32`
+    /// length
+    pub fn pickle() -> Self {
+        Self {
+            pickle: true,
+            ..Self::plaintext()
+        }
+    }
+
+    /// Fail with `FrameTooLong` on pickle batches longer than `max` bytes,
+    /// 1 MiB by default
+    pub fn max_pickle_len(mut self, max: usize) -> Self {
+        self.max_pickle = max;
+        self
+    }
+
+    fn decode_line(src: &mut BytesMut) -> Result<Option<GraphiteMetric>, CodecError> {
+        loop {
+            let end = match src.iter().position(|&b| b == b'\n') {
+                Some(end) => end,
+                None => return Ok(None),
+            };
+            let line = src.split_to(end + 1);
+            let line = std::str::from_utf8(&line)?.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<_> = line.split_whitespace().collect();
+            let (path, value, timestamp) = match fields[..] {
+                [path, value, timestamp] => (path, value, timestamp),
+                _ => return Err(invalid("metric line needs three fields")),
+            };
+            let value = value.parse().map_err(|_| invalid("invalid metric value"))?;
+            return Ok(Some(GraphiteMetric {
+                path: path.to_owned(),
+                value,
+                timestamp: parse_timestamp(timestamp)?,
+            }));
+        }
+    }
+
+    fn decode_batch(&mut self, src: &mut BytesMut) -> Result<bool, CodecError> {
+        if src.len() < 4 {
+            return Ok(false);
+        }
+        let len = BigEndian::read_u32(&src[..4]) as usize;
+        if len > self.max_pickle {
+            return Err(CodecError::FrameTooLong {
+                max: self.max_pickle,
+            });
+        }
+        if src.len() < 4 + len {
+            return Ok(false);
+        }
+        src.advance(4);
+        let batch = src.split_to(len);
+
+        let points = match Unpickler::new(&batch).load()? {
+            Value::List(points) | Value::Tuple(points) => points,
+            _ => return Err(invalid("pickle is not a list of metrics")),
+        };
+        for point in points {
+            let metric = match point {
+                Value::Tuple(mut pair) | Value::List(mut pair) if pair.len() == 2 => {
+                    let datapoint = pair.pop().unwrap();
+                    let path = match pair.pop().unwrap() {
+                        Value::Str(path) => path,
+                        _ => return Err(invalid("metric path is not a string")),
+                    };
+                    let (timestamp, value) = match datapoint {
+                        Value::Tuple(d) | Value::List(d) if d.len() == 2 => {
+                            (d[0].as_f64()?, d[1].as_f64()?)
+                        }
+                        _ => return Err(invalid("datapoint is not a pair")),
+                    };
+                    if timestamp < 0.0 {
+                        return Err(invalid("negative timestamp"));
+                    }
+                    GraphiteMetric {
+                        path,
+                        value,
+                        timestamp: timestamp as u64,
+                    }
+                }
+                _ => return Err(invalid("metric is not a pair")),
+            };
+            self.pending.push_back(metric);
+        }
+        Ok(true)
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+fn parse_timestamp(field: &str) -> Result<u64, CodecError> {
+    field
+        .parse()
+        .or_else(|_| field.parse::<f64>().map(|t| t as u64))
+        .map_err(|_| invalid("invalid timestamp"))
+}
+
+impl Decoder for GraphiteCodec {
+    type Item = GraphiteMetric;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if !self.pickle {
+            return Self::decode_line(src);
+        }
+        while self.pending.is_empty() {
+            if !self.decode_batch(src)? {
+                return Ok(None);
+            }
+        }
+        Ok(self.pending.pop_front())
+    }
+}
+
+impl Encoder for GraphiteCodec {
+    type Item = GraphiteMetric;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if item.path.contains(char::is_whitespace) {
+            return Err(invalid("metric path contains whitespace"));
+        }
+        if !self.pickle {
+            let line = format!("{} {} {}\n", item.path, item.value, item.timestamp);
+            dst.extend_from_slice(line.as_bytes());
+            return Ok(());
+        }
+
+        // [(path, (timestamp, value))] in pickle protocol 2
+        let path = item.path.as_bytes();
+        let len = 5 + 4 + path.len() + 10 + 9 + 4;
+        dst.reserve(4 + len);
+        dst.put_u32_be(len as u32);
+        dst.put_slice(b"\x80\x02](X");
+        dst.put_u32_le(path.len() as u32);
+        dst.put_slice(path);
+        dst.put_u8(b'\x8a');
+        dst.put_u8(8);
+        dst.put_u64_le(item.timestamp);
+        dst.put_u8(b'G');
+        dst.put_f64_be(item.value);
+        dst.put_slice(b"\x86\x86e.");
+        Ok(())
+    }
+}
+
+/// A value of the pickle subset used by carbon clients
+#[derive(Debug, Clone)]
+enum Value {
+    Mark,
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    List(Vec<Value>),
+    Tuple(Vec<Value>),
+}
+
+impl Value {
+    fn as_f64(&self) -> Result<f64, CodecError> {
+        match *self {
+            Value::Bool(b) => Ok(b as u8 as f64),
+            Value::Int(n) => Ok(n as f64),
+            Value::Float(f) => Ok(f),
+            _ => Err(invalid("datapoint is not a number")),
+        }
+    }
+}
+
+/// A stack machine for the pickle opcodes of protocols 0 to 4 that build
+/// lists and tuples of strings and numbers
+struct Unpickler<'a> {
+    src: &'a [u8],
+    stack: Vec<Value>,
+    memo: HashMap<u32, Value>,
+}
+
+impl<'a> Unpickler<'a> {
+    fn new(src: &'a [u8]) -> Self {
+        Self {
+            src,
+            stack: Vec::new(),
+            memo: HashMap::new(),
+        }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], CodecError> {
+        if self.src.len() < n {
+            return Err(invalid("truncated pickle"));
+        }
+        let (taken, rest) = self.src.split_at(n);
+        self.src = rest;
+        Ok(taken)
+    }
+
+    fn line(&mut self) -> Result<&'a str, CodecError> {
+        let end = self
+            .src
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| invalid("truncated pickle"))?;
+        let line = self.take(end + 1)?;
+        Ok(std::str::from_utf8(&line[..end])?)
+    }
+
+    fn string(&mut self, len: usize) -> Result<Value, CodecError> {
+        let bytes = self.take(len)?;
+        Ok(Value::Str(std::str::from_utf8(bytes)?.to_owned()))
+    }
+
+    fn pop(&mut self) -> Result<Value, CodecError> {
+        self.stack
+            .pop()
+            .ok_or_else(|| invalid("pickle stack underflow"))
+    }
+
+    /// Pop everything down to the topmost mark
+    fn pop_mark(&mut self) -> Result<Vec<Value>, CodecError> {
+        let mark = self
+            .stack
+            .iter()
+            .rposition(|v| matches!(v, Value::Mark))
+            .ok_or_else(|| invalid("pickle mark missing"))?;
+        let items = self.stack.split_off(mark + 1);
+        self.stack.pop();
+        Ok(items)
+    }
+
+    fn top(&mut self) -> Result<&mut Value, CodecError> {
+        self.stack
+            .last_mut()
+            .ok_or_else(|| invalid("pickle stack underflow"))
+    }
+
+    fn load(mut self) -> Result<Value, CodecError> {
+        loop {
+            let op = self.take(1)?[0];
+            let value = match op {
+                b'.' => return self.pop(),
+                b'\x80' => {
+                    self.take(1)?;
+                    continue;
+                }
+                b'\x95' => {
+                    self.take(8)?;
+                    continue;
+                }
+                b'(' => Value::Mark,
+                b'N' => Value::None,
+                b'\x88' => Value::Bool(true),
+                b'\x89' => Value::Bool(false),
+                b']' => Value::List(Vec::new()),
+                b')' => Value::Tuple(Vec::new()),
+                b'l' => Value::List(self.pop_mark()?),
+                b't' => Value::Tuple(self.pop_mark()?),
+                b'\x85' | b'\x86' | b'\x87' => {
+                    let n = (op - b'\x84') as usize;
+                    if self.stack.len() < n {
+                        return Err(invalid("pickle stack underflow"));
+                    }
+                    let at = self.stack.len() - n;
+                    Value::Tuple(self.stack.split_off(at))
+                }
+                b'a' | b'e' => {
+                    let items = if op == b'a' {
+                        vec![self.pop()?]
+                    } else {
+                        self.pop_mark()?
+                    };
+                    match self.top()? {
+                        Value::List(list) => list.extend(items),
+                        _ => return Err(invalid("append to a value that is not a list")),
+                    }
+                    continue;
+                }
+                b'K' => Value::Int(self.take(1)?[0] as i64),
+                b'M' => Value::Int(LittleEndian::read_u16(self.take(2)?) as i64),
+                b'J' => Value::Int(LittleEndian::read_i32(self.take(4)?) as i64),
+                b'\x8a' => {
+                    let n = self.take(1)?[0] as usize;
+                    if n > 8 {
+                        return Err(invalid("pickle integer too large"));
+                    }
+                    let bytes = self.take(n)?;
+                    Value::Int(if n == 0 {
+                        0
+                    } else {
+                        LittleEndian::read_int(bytes, n)
+                    })
+                }
+                b'G' => Value::Float(BigEndian::read_f64(self.take(8)?)),
+                b'I' | b'L' => {
+                    let line = self.line()?;
+                    match line.trim_end_matches('L') {
+                        "01" => Value::Bool(true),
+                        "00" => Value::Bool(false),
+                        n => Value::Int(n.parse().map_err(|_| invalid("invalid pickle integer"))?),
+                    }
+                }
+                b'F' => {
+                    let line = self.line()?;
+                    Value::Float(line.parse().map_err(|_| invalid("invalid pickle float"))?)
+                }
+                b'S' => {
+                    let line = self.line()?;
+                    let unquoted = line
+                        .strip_prefix('\'')
+                        .and_then(|l| l.strip_suffix('\''))
+                        .or_else(|| line.strip_prefix('"').and_then(|l| l.strip_suffix('"')))
+                        .ok_or_else(|| invalid("invalid pickle string"))?;
+                    Value::Str(unquoted.to_owned())
+                }
+                b'V' => Value::Str(self.line()?.to_owned()),
+                b'U' | b'C' | b'\x8c' => {
+                    let n = self.take(1)?[0] as usize;
+                    self.string(n)?
+                }
+                b'T' | b'X' | b'B' => {
+                    let n = LittleEndian::read_u32(self.take(4)?) as usize;
+                    self.string(n)?
+                }
+                b'\x8d' | b'\x8e' => {
+                    let n = LittleEndian::read_u64(self.take(8)?) as usize;
+                    self.string(n)?
+                }
+                b'p' | b'q' | b'r' | b'\x94' => {
+                    let key = match op {
+                        b'p' => self
+                            .line()?
+                            .parse()
+                            .map_err(|_| invalid("invalid memo key"))?,
+                        b'q' => self.take(1)?[0] as u32,
+                        b'r' => LittleEndian::read_u32(self.take(4)?),
+                        _ => self.memo.len() as u32,
+                    };
+                    let top = self.top()?.clone();
+                    self.memo.insert(key, top);
+                    continue;
+                }
+                b'g' | b'h' | b'j' => {
+                    let key = match op {
+                        b'g' => self
+                            .line()?
+                            .parse()
+                            .map_err(|_| invalid("invalid memo key"))?,
+                        b'h' => self.take(1)?[0] as u32,
+                        _ => LittleEndian::read_u32(self.take(4)?),
+                    };
+                    self.memo
+                        .get(&key)
+                        .cloned()
+                        .ok_or_else(|| invalid("unknown memo key"))?
+                }
+                _ => return Err(invalid("unsupported pickle opcode")),
+            };
+            self.stack.push(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn python_protocol_2_batch() {
+        // pickle.dumps([('a.b', (1700000000, 1.5)), ('c', (1700000001, 2))], 2)
+        let pickle = b"\x80\x02]q\x00(X\x03\x00\x00\x00a.bq\x01J\x00\xf1SeG?\xf8\x00\x00\x00\x00\x00\x00\x86q\x02\x86q\x03X\x01\x00\x00\x00cq\x04J\x01\xf1SeK\x02\x86q\x05\x86q\x06e.";
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(&(pickle.len() as u32).to_be_bytes());
+        buf.extend_from_slice(pickle);
+
+        let mut codec = GraphiteCodec::pickle();
+        let metrics: Vec<_> = codec.iter(&buf).map(Result::unwrap).collect();
+        assert_eq!(
+            metrics,
+            [
+                GraphiteMetric::new("a.b", 1.5, 1700000000),
+                GraphiteMetric::new("c", 2.0, 1700000001),
+            ]
+        );
+    }
+}
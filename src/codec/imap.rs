@@ -0,0 +1,169 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{Bytes, BytesMut};
+use std::io;
+
+/// Default limit on the length of a frame, literals included
+const MAX_FRAME: usize = 16 * 1024 * 1024;
+
+/// A codec for IMAP, framing complete responses or commands including their
+/// literals.
+///
+/// A line ending in a literal announcement, `{N}` or the LITERAL+ form
+/// `{N+}`, continues after the `N` bytes of the literal, so a frame holds
+/// the whole logical line with all literals and the final CRLF. Frames are
+/// encoded as is.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, ImapCodec};
+///
+/// let mut codec = ImapCodec::new();
+/// let mut buf = BytesMut::from(&b"* 1 FETCH (BODY[] {5}\r\nHello)\r\nA1 OK\r\n"[..]);
+///
+/// let fetch = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(fetch, "* 1 FETCH (BODY[] {5}\r\nHello)\r\n");
+/// let ok = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(ok, "A1 OK\r\n");
+/// ```
+#[derive(Debug)]
+pub struct ImapCodec {
+    /// Index into the buffer at which the next line of the frame starts
+    scan: usize,
+    max_length: usize,
+}
+
+impl ImapCodec {
+    pub fn new() -> Self {
+        Self {
+            scan: 0,
+            max_length: MAX_FRAME,
+        }
+    }
+
+    /// Fail with `FrameTooLong` on frames longer than `max` bytes, 16 MiB
+    /// by default
+    pub fn max_length(mut self, max: usize) -> Self {
+        self.max_length = max;
+        self
+    }
+
+    fn check_length(&self, len: usize) -> Result<(), CodecError> {
+        if len > self.max_length {
+            return Err(CodecError::FrameTooLong {
+                max: self.max_length,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for ImapCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Length of the literal announced at the end of `line`, if any
+fn literal_len(line: &[u8]) -> Option<usize> {
+    let inner = line.strip_suffix(b"}")?;
+    let open = inner.iter().rposition(|&b| b == b'{')?;
+    let digits = &inner[open + 1..];
+    let digits = digits.strip_suffix(b"+").unwrap_or(digits);
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    std::str::from_utf8(digits).ok()?.parse().ok()
+}
+
+impl Decoder for ImapCodec {
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            if self.scan > src.len() {
+                return Ok(None);
+            }
+            let end = match src[self.scan..].windows(2).position(|w| w == b"\r\n") {
+                Some(end) => self.scan + end,
+                None => {
+                    self.check_length(src.len())?;
+                    return Ok(None);
+                }
+            };
+            match literal_len(&src[self.scan..end]) {
+                Some(len) => {
+                    self.scan = (end + 2).checked_add(len).ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::InvalidData, "literal length overflows")
+                    })?;
+                    self.check_length(self.scan)?;
+                }
+                None => {
+                    self.check_length(end + 2)?;
+                    self.scan = 0;
+                    return Ok(Some(src.split_to(end + 2).freeze()));
+                }
+            }
+        }
+    }
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        self.scan.checked_sub(src.len()).filter(|&n| n > 0)
+    }
+
+    fn reset(&mut self) {
+        self.scan = 0;
+    }
+}
+
+impl Encoder for ImapCodec {
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.extend_from_slice(&item);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn literal_split_across_reads() {
+        let mut codec = ImapCodec::new();
+        let mut buf = BytesMut::from(&b"A1 APPEND INBOX {11+}\r\nHello"[..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(codec.bytes_needed(&buf), Some(6));
+
+        buf.extend_from_slice(b" World {2}\r\n\r\n\r\n");
+        let frame = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(
+            frame,
+            "A1 APPEND INBOX {11+}\r\nHello World {2}\r\n\r\n\r\n"
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn overflowing_literal_rejected() {
+        let mut codec = ImapCodec::new().max_length(usize::MAX);
+        let mut buf = BytesMut::from(&b"A1 APPEND INBOX {18446744073709551615}\r\n"[..]);
+        match codec.decode(&mut buf) {
+            Err(CodecError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::InvalidData),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn default_max_length() {
+        let mut codec = ImapCodec::new();
+        let mut buf = BytesMut::from(&b"A1 APPEND INBOX {4294967296}\r\n"[..]);
+        match codec.decode(&mut buf) {
+            Err(CodecError::FrameTooLong { max }) => assert_eq!(max, MAX_FRAME),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BufMut, BytesMut};
+use std::fmt::Write;
+
+/// Default limit on the length of a line
+const MAX_LINE: usize = 64 * 1024;
+
+/// A codec for the InfluxDB line protocol.
+///
+/// Every line is a point, `measurement,tag=value field=value timestamp`,
+/// with the tags and timestamp optional. Commas, spaces and, in keys and
+/// tag values, equal signs are escaped with a backslash. Field values are
+/// floats, integers with an `i` and unsigned integers with a `u` suffix,
+/// booleans and double quoted strings. Empty lines and comments starting
+/// with `#` are skipped. As the last line may lack its newline, use
+/// `decode_eof` at the end of the input.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, Encoder, InfluxLineCodec, InfluxPoint, InfluxValue};
+///
+/// let mut codec = InfluxLineCodec::new();
+/// let mut buf = BytesMut::from(&b"cpu,host=server\\ 1 usage=0.5,cores=4i 1465839830100400200\n"[..]);
+///
+/// let point = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(point.tag("host"), Some("server 1"));
+/// assert_eq!(point.field("cores"), Some(&InfluxValue::Integer(4)));
+///
+/// let point = InfluxPoint::new("disk")
+///     .with_field("path", InfluxValue::String("C:\\".to_owned()))
+///     .with_field("full", InfluxValue::Boolean(false));
+/// codec.encode(point, &mut buf).unwrap();
+/// assert_eq!(&buf[..], b"disk path=\"C:\\\\\",full=false\n");
+/// ```
+#[derive(Debug)]
+pub struct InfluxLineCodec {
+    max_line_len: usize,
+}
+
+/// A point of the InfluxDB line protocol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InfluxPoint {
+    pub measurement: String,
+    /// Tags in the order they appear
+    pub tags: Vec<(String, String)>,
+    /// Fields in the order they appear, at least one is needed
+    pub fields: Vec<(String, InfluxValue)>,
+    /// Nanoseconds since the Unix epoch, unless the writer chose another
+    /// precision
+    pub timestamp: Option<i64>,
+}
+
+/// The value of a field of an `InfluxPoint`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InfluxValue {
+    Float(f64),
+    Integer(i64),
+    UInteger(u64),
+    String(String),
+    Boolean(bool),
+}
+
+impl InfluxPoint {
+    /// A point without tags, fields or timestamp
+    pub fn new(measurement: &str) -> Self {
+        Self {
+            measurement: measurement.to_owned(),
+            tags: Vec::new(),
+            fields: Vec::new(),
+            timestamp: None,
+        }
+    }
+
+    /// Add a tag
+    pub fn with_tag(mut self, key: &str, value: &str) -> Self {
+        self.tags.push((key.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Add a field
+    pub fn with_field(mut self, key: &str, value: InfluxValue) -> Self {
+        self.fields.push((key.to_owned(), value));
+        self
+    }
+
+    /// Value of the tag `key`
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Value of the field `key`
+    pub fn field(&self, key: &str) -> Option<&InfluxValue> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == key)
+            .map(|(_, value)| value)
+    }
+}
+
+impl InfluxLineCodec {
+    pub fn new() -> Self {
+        Self {
+            max_line_len: MAX_LINE,
+        }
+    }
+
+    /// Fail with `FrameTooLong` on lines longer than `max` bytes
+    pub fn max_line_len(mut self, max: usize) -> Self {
+        self.max_line_len = max;
+        self
+    }
+}
+
+impl Default for InfluxLineCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+/// Split `s` at every `sep` that is neither escaped nor, if `quotes`,
+/// inside a double quoted string
+fn split_unescaped(s: &str, sep: char, quotes: bool) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut escaped, mut quoted) = (0, false, false);
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' if quotes => quoted = !quoted,
+            c if c == sep && !quoted => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Split `s` at its first unescaped `=`
+fn split_key(s: &str) -> Result<(&str, &str), CodecError> {
+    let key = split_unescaped(s, '=', false)[0];
+    if key.len() == s.len() {
+        return Err(invalid("key without value"));
+    }
+    Ok((key, &s[key.len() + 1..]))
+}
+
+/// Remove the backslash in front of every character in `special`
+fn unescape(s: &str, special: &[char]) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(next) if c == '\\' && special.contains(next) => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn escape(s: &str, special: &[char], out: &mut String) {
+    for c in s.chars() {
+        if special.contains(&c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+const MEASUREMENT: &[char] = &[',', ' '];
+const KEY: &[char] = &[',', '=', ' '];
+const STRING: &[char] = &['"', '\\'];
+
+fn parse_value(value: &str) -> Result<InfluxValue, CodecError> {
+    let number = |n: &str| invalid(&format!("invalid field value {}", n));
+    Ok(match value {
+        "t" | "T" | "true" | "True" | "TRUE" => InfluxValue::Boolean(true),
+        "f" | "F" | "false" | "False" | "FALSE" => InfluxValue::Boolean(false),
+        _ if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') => {
+            InfluxValue::String(unescape(&value[1..value.len() - 1], STRING))
+        }
+        _ if value.ends_with('i') => {
+            let n = &value[..value.len() - 1];
+            InfluxValue::Integer(n.parse().map_err(|_| number(value))?)
+        }
+        _ if value.ends_with('u') => {
+            let n = &value[..value.len() - 1];
+            InfluxValue::UInteger(n.parse().map_err(|_| number(value))?)
+        }
+        _ => {
+            let n: f64 = value.parse().map_err(|_| number(value))?;
+            if !n.is_finite() {
+                return Err(number(value));
+            }
+            InfluxValue::Float(n)
+        }
+    })
+}
+
+fn parse_line(line: &str) -> Result<InfluxPoint, CodecError> {
+    let sections = split_unescaped(line, ' ', true);
+    let (key, fields, timestamp) = match sections[..] {
+        [key, fields] => (key, fields, None),
+        [key, fields, timestamp] => (key, fields, Some(timestamp)),
+        _ => {
+            return Err(invalid(
+                "line needs a key, fields and an optional timestamp",
+            ))
+        }
+    };
+
+    let mut key = split_unescaped(key, ',', false).into_iter();
+    let measurement = key.next().filter(|m| !m.is_empty());
+    let measurement = measurement.ok_or_else(|| invalid("empty measurement"))?;
+    let mut point = InfluxPoint::new(&unescape(measurement, MEASUREMENT));
+    for tag in key {
+        let (key, value) = split_key(tag)?;
+        point.tags.push((unescape(key, KEY), unescape(value, KEY)));
+    }
+    for field in split_unescaped(fields, ',', true) {
+        let (key, value) = split_key(field)?;
+        point.fields.push((unescape(key, KEY), parse_value(value)?));
+    }
+    if let Some(timestamp) = timestamp {
+        point.timestamp = Some(
+            timestamp
+                .parse()
+                .map_err(|_| invalid("invalid timestamp"))?,
+        );
+    }
+    Ok(point)
+}
+
+/// The next line that is not empty or a comment
+fn next_line(src: &mut BytesMut, max: usize) -> Result<Option<BytesMut>, CodecError> {
+    loop {
+        let end = match src.iter().position(|&b| b == b'\n') {
+            Some(end) => end,
+            None if src.len() > max => return Err(CodecError::FrameTooLong { max }),
+            None => return Ok(None),
+        };
+        let line = src.split_to(end + 1);
+        let trimmed = std::str::from_utf8(&line)?.trim();
+        if !trimmed.is_empty() && !trimmed.starts_with('#') {
+            return Ok(Some(line));
+        }
+    }
+}
+
+impl Decoder for InfluxLineCodec {
+    type Item = InfluxPoint;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match next_line(src, self.max_line_len)? {
+            Some(line) => parse_line(std::str::from_utf8(&line)?.trim()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if let Some(point) = self.decode(src)? {
+            return Ok(Some(point));
+        }
+        let line = src.take();
+        match std::str::from_utf8(&line)?.trim() {
+            line if line.is_empty() || line.starts_with('#') => Ok(None),
+            line => parse_line(line).map(Some),
+        }
+    }
+}
+
+impl Encoder for InfluxLineCodec {
+    type Item = InfluxPoint;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if item.measurement.is_empty() || item.fields.is_empty() {
+            return Err(invalid("point needs a measurement and fields"));
+        }
+        let mut line = String::new();
+        escape(&item.measurement, MEASUREMENT, &mut line);
+        for (key, value) in &item.tags {
+            line.push(',');
+            escape(key, KEY, &mut line);
+            line.push('=');
+            escape(value, KEY, &mut line);
+        }
+        for (i, (key, value)) in item.fields.iter().enumerate() {
+            line.push(if i == 0 { ' ' } else { ',' });
+            escape(key, KEY, &mut line);
+            line.push('=');
+            let _ = match value {
+                InfluxValue::Float(n) if !n.is_finite() => {
+                    return Err(invalid("field value is not finite"))
+                }
+                InfluxValue::Float(n) => write!(line, "{}", n),
+                InfluxValue::Integer(n) => write!(line, "{}i", n),
+                InfluxValue::UInteger(n) => write!(line, "{}u", n),
+                InfluxValue::Boolean(b) => write!(line, "{}", b),
+                InfluxValue::String(s) => {
+                    line.push('"');
+                    escape(s, STRING, &mut line);
+                    write!(line, "\"")
+                }
+            };
+        }
+        if let Some(timestamp) = item.timestamp {
+            let _ = write!(line, " {}", timestamp);
+        }
+        dst.reserve(line.len() + 1);
+        dst.put_slice(line.as_bytes());
+        dst.put_u8(b'\n');
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escaped_keys_and_quoted_strings() {
+        let mut codec = InfluxLineCodec::new();
+        let line = "my\\ measure,a\\=b=c\\,d msg=\"say \\\"hi, there\\\"\",n=-3.5,u=7u,ok=T 12\n";
+        let mut buf = BytesMut::from(format!("# comment\n\n{}", line).as_bytes());
+
+        let point = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(point.measurement, "my measure");
+        assert_eq!(point.tag("a=b"), Some("c,d"));
+        assert_eq!(
+            point.field("msg"),
+            Some(&InfluxValue::String("say \"hi, there\"".to_owned()))
+        );
+        assert_eq!(point.field("n"), Some(&InfluxValue::Float(-3.5)));
+        assert_eq!(point.field("u"), Some(&InfluxValue::UInteger(7)));
+        assert_eq!(point.field("ok"), Some(&InfluxValue::Boolean(true)));
+        assert_eq!(point.timestamp, Some(12));
+
+        codec.encode(point.clone(), &mut buf).unwrap();
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(point));
+
+        let mut buf = BytesMut::from(&b"cpu value=1 2 3"[..]);
+        assert!(codec.decode_eof(&mut buf).is_err());
+    }
+}
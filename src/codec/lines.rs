@@ -1,13 +1,44 @@
-use crate::{Decoder, Encoder};
-use bytes::{BufMut, BytesMut};
-use std::io::{Error, ErrorKind};
+use crate::{CodecError, Decoder, DecoderRef, Encoder};
+use bytes::{BufMut, Bytes, BytesMut};
 
 /// A simple `Codec` implementation that splits up data into lines.
-pub struct LinesCodec {}
+///
+/// Lines that are not valid UTF-8 fail decoding, unless the codec is
+/// [`lossy`](Self::lossy).
+#[derive(Debug, Default)]
+pub struct LinesCodec {
+    inner: BytesLinesCodec,
+    lossy: bool,
+}
+
+impl LinesCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace invalid UTF-8 sequences with U+FFFD instead of failing, as
+    /// `String::from_utf8_lossy` does.
+    ///
+    /// Borrowed lines, decoded through `DecoderRef`, can't be replaced and
+    /// still fail.
+    pub fn lossy() -> Self {
+        Self {
+            lossy: true,
+            ..Self::default()
+        }
+    }
+
+    fn to_string(&self, line: Bytes) -> Result<String, CodecError> {
+        if self.lossy {
+            return Ok(String::from_utf8_lossy(&line).into_owned());
+        }
+        String::from_utf8(line.to_vec()).map_err(|e| e.utf8_error().into())
+    }
+}
 
 impl Encoder for LinesCodec {
     type Item = String;
-    type Error = Error;
+    type Error = CodecError;
 
     fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
         dst.put(item);
@@ -17,19 +48,156 @@ impl Encoder for LinesCodec {
 
 impl Decoder for LinesCodec {
     type Item = String;
-    type Error = Error;
+    type Error = CodecError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        match src.iter().position(|b| b == &b'\n') {
-            Some(pos) if !src.is_empty() => {
-                let buf = src.split_to(pos + 1);
-                String::from_utf8(buf.to_vec())
-                    .map(Some)
-                    .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        match self.inner.decode(src)? {
+            Some(line) => self.to_string(line).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.inner.decode_eof(src)? {
+            Some(line) => self.to_string(line).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.inner.next_index = 0;
+    }
+}
+
+/// A `Codec` that splits up data into lines without copying or validating them.
+///
+/// Lines are split off the read buffer as `Bytes`, including the trailing
+/// newline.
+///
+/// # Example
+/// ```
+/// use bytes::Bytes;
+/// use futures::{executor, TryStreamExt};
+/// use futures_codec::{BytesLinesCodec, FramedRead};
+///
+/// let buf = b"Hello\nWorld\n";
+/// let mut framed = FramedRead::new(&buf[..], BytesLinesCodec::new());
+///
+/// executor::block_on(async move {
+///     let line = framed.try_next().await.unwrap().unwrap();
+///     assert_eq!(line, Bytes::from("Hello\n"));
+/// })
+/// ```
+#[derive(Debug, Default)]
+pub struct BytesLinesCodec {
+    /// Index into the buffer up to which no newline has been found yet
+    next_index: usize,
+}
+
+impl BytesLinesCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Encoder for BytesLinesCodec {
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.extend_from_slice(&item);
+        Ok(())
+    }
+
+    fn encode_zero_copy(
+        &mut self,
+        item: Self::Item,
+        _header: &mut BytesMut,
+    ) -> Result<Option<Bytes>, Self::Error> {
+        Ok(Some(item))
+    }
+}
+
+impl Decoder for BytesLinesCodec {
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.frame_len(src)? {
+            Some(len) => Ok(Some(src.split_to(len).freeze())),
+            None => Ok(None),
+        }
+    }
+
+    /// Yields the last line even if it is not terminated by a newline
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.decode(src)? {
+            Some(line) => Ok(Some(line)),
+            None if src.is_empty() => Ok(None),
+            None => {
+                self.next_index = 0;
+                Ok(Some(src.take().freeze()))
             }
-            _ => Ok(None),
         }
     }
+
+    fn reset(&mut self) {
+        self.next_index = 0;
+    }
+}
+
+impl DecoderRef for LinesCodec {
+    type Item<'a> = &'a str;
+    type Error = CodecError;
+
+    fn frame_len(&mut self, src: &[u8]) -> Result<Option<usize>, Self::Error> {
+        self.inner.frame_len(src)
+    }
+
+    fn decode_ref<'a>(&mut self, frame: &'a [u8]) -> Result<Self::Item<'a>, Self::Error> {
+        std::str::from_utf8(frame).map_err(Into::into)
+    }
+
+    fn reset(&mut self) {
+        self.inner.next_index = 0;
+    }
+}
+
+impl DecoderRef for BytesLinesCodec {
+    type Item<'a> = &'a [u8];
+    type Error = CodecError;
+
+    fn frame_len(&mut self, src: &[u8]) -> Result<Option<usize>, Self::Error> {
+        let start = self.next_index.min(src.len());
+        match find_newline(&src[start..]) {
+            Some(offset) => {
+                self.next_index = 0;
+                Ok(Some(start + offset + 1))
+            }
+            None => {
+                self.next_index = src.len();
+                Ok(None)
+            }
+        }
+    }
+
+    fn decode_ref<'a>(&mut self, frame: &'a [u8]) -> Result<Self::Item<'a>, Self::Error> {
+        Ok(frame)
+    }
+
+    fn reset(&mut self) {
+        self.next_index = 0;
+    }
+}
+
+#[cfg(feature = "memchr")]
+fn find_newline(buf: &[u8]) -> Option<usize> {
+    memchr::memchr(b'\n', buf)
+}
+
+#[cfg(not(feature = "memchr"))]
+fn find_newline(buf: &[u8]) -> Option<usize> {
+    buf.iter().position(|b| b == &b'\n')
 }
 
 #[cfg(test)]
@@ -44,7 +212,7 @@ mod test {
         let buf = "Hello\nWorld\nError".to_owned();
         let cur = Cursor::new(buf);
 
-        let mut framed = FramedRead::new(cur, LinesCodec {});
+        let mut framed = FramedRead::new(cur, LinesCodec::new());
         let next = executor::block_on(framed.try_next()).unwrap().unwrap();
         assert_eq!(next, "Hello\n");
         let next = executor::block_on(framed.try_next()).unwrap().unwrap();
@@ -52,4 +220,39 @@ mod test {
 
         assert!(executor::block_on(framed.try_next()).is_err());
     }
+
+    #[test]
+    fn resumes_scan() {
+        let mut codec = LinesCodec::new();
+        let mut buf = BytesMut::from(&b"Hel"[..]);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        assert_eq!(codec.inner.next_index, 3);
+
+        buf.extend_from_slice(b"lo\nWorld\n");
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "Hello\n");
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "World\n");
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+    }
+
+    #[test]
+    fn lossy_lines() {
+        let mut buf = BytesMut::from(&b"bad \xff byte\nok\n"[..]);
+        assert!(LinesCodec::new().decode(&mut buf.clone()).is_err());
+
+        let mut codec = LinesCodec::lossy();
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "bad \u{fffd} byte\n");
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "ok\n");
+    }
+
+    #[test]
+    fn borrowed_lines() {
+        let buf = "Hello\nWorld\n".to_owned();
+        let mut framed = FramedRead::new(Cursor::new(buf), LinesCodec::new());
+
+        executor::block_on(async move {
+            assert_eq!(framed.next_borrowed().await.unwrap().unwrap(), "Hello\n");
+            assert_eq!(framed.next_borrowed().await.unwrap().unwrap(), "World\n");
+            assert!(framed.next_borrowed().await.is_none());
+        })
+    }
 }
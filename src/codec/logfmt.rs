@@ -0,0 +1,192 @@
+use super::BytesLinesCodec;
+use crate::{CodecError, Decoder, Encoder};
+use bytes::BytesMut;
+
+/// The pairs of a logfmt record, in order. A key without `=` has no value.
+pub type LogfmtRecord = Vec<(String, Option<String>)>;
+
+/// A codec for newline delimited logfmt records, such as
+/// `level=info msg="request done" took=12ms cached`.
+///
+/// Values are quoted on encode when they are empty or contain spaces, `=`,
+/// quotes or control characters.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, Encoder, LogfmtCodec};
+///
+/// let mut codec = LogfmtCodec::new();
+/// let mut buf = BytesMut::from(&b"level=info msg=\"a \\\"b\\\"\" cached\n"[..]);
+///
+/// let record = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(record[1], ("msg".to_owned(), Some("a \"b\"".to_owned())));
+/// assert_eq!(record[2], ("cached".to_owned(), None));
+///
+/// codec.encode(record, &mut buf).unwrap();
+/// assert_eq!(&buf[..], &b"level=info msg=\"a \\\"b\\\"\" cached\n"[..]);
+/// ```
+#[derive(Debug, Default)]
+pub struct LogfmtCodec {
+    inner: BytesLinesCodec,
+}
+
+impl LogfmtCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+/// Parse the pairs of a single line
+fn parse(line: &str) -> Result<LogfmtRecord, CodecError> {
+    let mut record = Vec::new();
+    let mut chars = line.trim_end_matches(['\r', '\n']).chars().peekable();
+    loop {
+        while chars.peek() == Some(&' ') {
+            chars.next();
+        }
+        let mut key = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == ' ' || c == '=' {
+                break;
+            }
+            if c == '"' {
+                return Err(invalid("quote in key"));
+            }
+            key.push(c);
+            chars.next();
+        }
+        if key.is_empty() {
+            return match chars.next() {
+                None => Ok(record),
+                Some(_) => Err(invalid("value without a key")),
+            };
+        }
+        if chars.peek() != Some(&'=') {
+            record.push((key, None));
+            continue;
+        }
+        chars.next();
+
+        let mut value = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => value.push(match chars.next() {
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some(c @ '"') | Some(c @ '\\') => c,
+                        _ => return Err(invalid("invalid escape in value")),
+                    }),
+                    Some(c) => value.push(c),
+                    None => return Err(invalid("unterminated quoted value")),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c == ' ' {
+                    break;
+                }
+                value.push(c);
+                chars.next();
+            }
+        }
+        record.push((key, Some(value)));
+    }
+}
+
+fn decode_line(line: Option<bytes::Bytes>) -> Result<Option<LogfmtRecord>, CodecError> {
+    match line {
+        Some(line) => parse(std::str::from_utf8(&line)?).map(Some),
+        None => Ok(None),
+    }
+}
+
+impl Decoder for LogfmtCodec {
+    type Item = LogfmtRecord;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        decode_line(self.inner.decode(src)?)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        decode_line(self.inner.decode_eof(src)?)
+    }
+}
+
+impl Encoder for LogfmtCodec {
+    type Item = LogfmtRecord;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let mut line = String::new();
+        for (i, (key, value)) in item.iter().enumerate() {
+            let bad_key = |c: char| c == ' ' || c == '=' || c == '"' || c.is_control();
+            if key.is_empty() || key.contains(bad_key) {
+                return Err(invalid("key can not be encoded"));
+            }
+            if i > 0 {
+                line.push(' ');
+            }
+            line.push_str(key);
+            let value = match value {
+                Some(value) => value,
+                None => continue,
+            };
+            line.push('=');
+            let needs_quotes = |c: char| c == ' ' || c == '=' || c == '"' || c.is_control();
+            if !value.is_empty() && !value.contains(needs_quotes) {
+                line.push_str(value);
+                continue;
+            }
+            line.push('"');
+            for c in value.chars() {
+                match c {
+                    '"' => line.push_str("\\\""),
+                    '\\' => line.push_str("\\\\"),
+                    '\n' => line.push_str("\\n"),
+                    '\r' => line.push_str("\\r"),
+                    '\t' => line.push_str("\\t"),
+                    c => line.push(c),
+                }
+            }
+            line.push('"');
+        }
+        line.push('\n');
+        dst.extend_from_slice(line.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quotes_values_that_need_it() {
+        let record = vec![
+            ("empty".to_owned(), Some(String::new())),
+            ("eq".to_owned(), Some("a=b".to_owned())),
+            ("nl".to_owned(), Some("a\nb".to_owned())),
+            ("plain".to_owned(), Some("a\\b".to_owned())),
+        ];
+        let mut codec = LogfmtCodec::new();
+        let mut buf = BytesMut::new();
+        codec.encode(record.clone(), &mut buf).unwrap();
+        assert_eq!(
+            &buf[..],
+            &b"empty=\"\" eq=\"a=b\" nl=\"a\\nb\" plain=a\\b\n"[..]
+        );
+
+        // Backslashes only escape inside quotes
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(record));
+    }
+}
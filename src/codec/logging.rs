@@ -0,0 +1,151 @@
+use crate::{Decoder, Encoder};
+use bytes::BytesMut;
+use std::fmt::Write;
+
+/// A codec wrapper that logs every frame of an inner codec as a `tracing`
+/// event with its length and a hex dump of its first bytes.
+///
+/// Enabled with the `tracing` feature. Events are emitted at debug level with
+/// a `direction` of either `"in"` or `"out"`.
+///
+/// # Example
+/// ```
+/// use futures_codec::{LinesCodec, LoggingCodec};
+///
+/// // Only log decoded frames, dumping up to 16 bytes of each
+/// let codec = LoggingCodec::new(LinesCodec::new())
+///     .outbound(false)
+///     .max_dump(16);
+/// ```
+#[derive(Debug)]
+pub struct LoggingCodec<C> {
+    inner: C,
+    inbound: bool,
+    outbound: bool,
+    max_dump: usize,
+}
+
+impl<C> LoggingCodec<C> {
+    /// Wrap `inner`, logging both directions and dumping up to 64 bytes
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            inbound: true,
+            outbound: true,
+            max_dump: 64,
+        }
+    }
+
+    /// Whether to log decoded frames
+    pub fn inbound(mut self, enabled: bool) -> Self {
+        self.inbound = enabled;
+        self
+    }
+
+    /// Whether to log encoded frames
+    pub fn outbound(mut self, enabled: bool) -> Self {
+        self.outbound = enabled;
+        self
+    }
+
+    /// Maximum number of bytes of every frame to include in the dump
+    pub fn max_dump(mut self, max: usize) -> Self {
+        self.max_dump = max;
+        self
+    }
+
+    /// Release the inner codec
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+impl<C: Encoder> Encoder for LoggingCodec<C> {
+    type Item = C::Item;
+    type Error = C::Error;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let start = dst.len();
+        self.inner.encode(item, dst)?;
+
+        if self.outbound {
+            let frame = &dst[start..];
+            let dump = hex_dump(frame, self.max_dump);
+            tracing::debug!(direction = "out", len = frame.len(), %dump, "frame");
+        }
+        Ok(())
+    }
+
+    fn encode_close(&mut self, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.inner.encode_close(dst)
+    }
+}
+
+impl<C: Decoder> Decoder for LoggingCodec<C> {
+    type Item = C::Item;
+    type Error = C::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if !self.inbound {
+            return self.inner.decode(src);
+        }
+
+        let before = src.len();
+        let head = BytesMut::from(&src[..before.min(self.max_dump)]);
+        let item = self.inner.decode(src)?;
+
+        if item.is_some() {
+            let len = before - src.len();
+            let dump = hex_dump(&head[..head.len().min(len)], self.max_dump);
+            tracing::debug!(direction = "in", len, %dump, "frame");
+        }
+        Ok(item)
+    }
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        self.inner.bytes_needed(src)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.inner.decode_eof(src)
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset()
+    }
+}
+
+/// Format up to `max` bytes of `frame` as space separated hex
+fn hex_dump(frame: &[u8], max: usize) -> String {
+    let mut dump = String::with_capacity(frame.len().min(max) * 3 + 3);
+    for (i, byte) in frame.iter().take(max).enumerate() {
+        if i > 0 {
+            dump.push(' ');
+        }
+        let _ = write!(dump, "{:02x}", byte);
+    }
+    if frame.len() > max {
+        dump.push_str(" ..");
+    }
+    dump
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LinesCodec;
+
+    #[test]
+    fn truncated_dump() {
+        assert_eq!(hex_dump(b"\x00\x01\xff", 8), "00 01 ff");
+        assert_eq!(hex_dump(b"Hello", 2), "48 65 ..");
+    }
+
+    #[test]
+    fn passes_frames_through() {
+        let mut codec = LoggingCodec::new(LinesCodec::new()).max_dump(2);
+        let mut buf = BytesMut::new();
+        codec.encode("Hello\n".to_owned(), &mut buf).unwrap();
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "Hello\n");
+    }
+}
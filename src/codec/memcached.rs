@@ -0,0 +1,352 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BigEndian, BufMut, ByteOrder, Bytes, BytesMut};
+
+/// Size of the header in front of every packet
+const HEADER_LEN: usize = 24;
+/// Default limit on the body of a packet, leaving room for the 1 MiB item
+/// size limit of memcached
+const MAX_BODY_LEN: usize = 2 * 1024 * 1024;
+
+const REQUEST_MAGIC: u8 = 0x80;
+const RESPONSE_MAGIC: u8 = 0x81;
+
+/// A codec for the memcached binary protocol.
+///
+/// Every packet is a 24 byte header followed by the extras, the key and the
+/// value. The magic byte of the header tells requests and responses apart,
+/// so the same codec serves clients and servers.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, Encoder, MemcachedBinaryCodec, MemcachedFrame, MemcachedRequest};
+///
+/// let mut codec = MemcachedBinaryCodec::new();
+/// let mut buf = BytesMut::new();
+/// let get = MemcachedRequest::new(MemcachedRequest::GET, Bytes::from("Hello"), Bytes::new());
+/// codec.encode(get.clone().into(), &mut buf).unwrap();
+/// assert_eq!(buf.len(), 24 + 5);
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some(MemcachedFrame::Request(get)));
+/// ```
+#[derive(Debug)]
+pub struct MemcachedBinaryCodec {
+    max_body_len: usize,
+}
+
+/// A packet of the memcached binary protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemcachedFrame {
+    Request(MemcachedRequest),
+    Response(MemcachedResponse),
+}
+
+/// A request sent to a memcached server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemcachedRequest {
+    /// Command, see the associated constants
+    pub opcode: u8,
+    pub data_type: u8,
+    pub vbucket: u16,
+    /// Echoed in the response
+    pub opaque: u32,
+    pub cas: u64,
+    pub extras: Bytes,
+    pub key: Bytes,
+    pub value: Bytes,
+}
+
+/// A response of a memcached server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemcachedResponse {
+    /// Command of the request
+    pub opcode: u8,
+    pub data_type: u8,
+    /// Outcome of the request, see the associated constants
+    pub status: u16,
+    /// Copied from the request
+    pub opaque: u32,
+    pub cas: u64,
+    pub extras: Bytes,
+    pub key: Bytes,
+    /// The value, or an error message if the request failed
+    pub value: Bytes,
+}
+
+impl MemcachedRequest {
+    pub const GET: u8 = 0x00;
+    pub const SET: u8 = 0x01;
+    pub const ADD: u8 = 0x02;
+    pub const REPLACE: u8 = 0x03;
+    pub const DELETE: u8 = 0x04;
+    pub const INCREMENT: u8 = 0x05;
+    pub const DECREMENT: u8 = 0x06;
+    pub const QUIT: u8 = 0x07;
+    pub const FLUSH: u8 = 0x08;
+    pub const GETQ: u8 = 0x09;
+    pub const NOOP: u8 = 0x0a;
+    pub const VERSION: u8 = 0x0b;
+    pub const GETK: u8 = 0x0c;
+    pub const GETKQ: u8 = 0x0d;
+    pub const APPEND: u8 = 0x0e;
+    pub const PREPEND: u8 = 0x0f;
+    pub const STAT: u8 = 0x10;
+
+    /// A request without extras
+    pub fn new(opcode: u8, key: Bytes, value: Bytes) -> Self {
+        Self {
+            opcode,
+            data_type: 0,
+            vbucket: 0,
+            opaque: 0,
+            cas: 0,
+            extras: Bytes::new(),
+            key,
+            value,
+        }
+    }
+}
+
+impl MemcachedResponse {
+    pub const NO_ERROR: u16 = 0x00;
+    pub const KEY_NOT_FOUND: u16 = 0x01;
+    pub const KEY_EXISTS: u16 = 0x02;
+    pub const VALUE_TOO_LARGE: u16 = 0x03;
+    pub const INVALID_ARGUMENTS: u16 = 0x04;
+    pub const ITEM_NOT_STORED: u16 = 0x05;
+    pub const NON_NUMERIC_VALUE: u16 = 0x06;
+    pub const UNKNOWN_COMMAND: u16 = 0x81;
+    pub const OUT_OF_MEMORY: u16 = 0x82;
+
+    /// A response without extras or key
+    pub fn new(opcode: u8, status: u16, value: Bytes) -> Self {
+        Self {
+            opcode,
+            data_type: 0,
+            status,
+            opaque: 0,
+            cas: 0,
+            extras: Bytes::new(),
+            key: Bytes::new(),
+            value,
+        }
+    }
+}
+
+impl From<MemcachedRequest> for MemcachedFrame {
+    fn from(request: MemcachedRequest) -> Self {
+        MemcachedFrame::Request(request)
+    }
+}
+
+impl From<MemcachedResponse> for MemcachedFrame {
+    fn from(response: MemcachedResponse) -> Self {
+        MemcachedFrame::Response(response)
+    }
+}
+
+impl MemcachedBinaryCodec {
+    pub fn new() -> Self {
+        Self {
+            max_body_len: MAX_BODY_LEN,
+        }
+    }
+
+    /// Limit the body of a packet, extras, key and value together, to `max`
+    /// bytes
+    pub fn max_body_len(mut self, max: usize) -> Self {
+        self.max_body_len = max;
+        self
+    }
+}
+
+impl Default for MemcachedBinaryCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+impl Decoder for MemcachedBinaryCodec {
+    type Item = MemcachedFrame;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.len() < HEADER_LEN {
+            return Ok(None);
+        }
+        if src[0] != REQUEST_MAGIC && src[0] != RESPONSE_MAGIC {
+            return Err(invalid("invalid magic byte"));
+        }
+        let key_len = BigEndian::read_u16(&src[2..4]) as usize;
+        let extras_len = src[4] as usize;
+        let body_len = BigEndian::read_u32(&src[8..12]) as usize;
+        if body_len > self.max_body_len {
+            return Err(CodecError::FrameTooLong {
+                max: self.max_body_len,
+            });
+        }
+        if extras_len + key_len > body_len {
+            return Err(invalid("key and extras longer than the body"));
+        }
+        if src.len() < HEADER_LEN + body_len {
+            return Ok(None);
+        }
+
+        let header = src.split_to(HEADER_LEN);
+        let extras = src.split_to(extras_len).freeze();
+        let key = src.split_to(key_len).freeze();
+        let value = src.split_to(body_len - extras_len - key_len).freeze();
+        let (opcode, data_type) = (header[1], header[5]);
+        let status = BigEndian::read_u16(&header[6..8]);
+        let opaque = BigEndian::read_u32(&header[12..16]);
+        let cas = BigEndian::read_u64(&header[16..24]);
+        Ok(Some(if header[0] == REQUEST_MAGIC {
+            MemcachedFrame::Request(MemcachedRequest {
+                opcode,
+                data_type,
+                vbucket: status,
+                opaque,
+                cas,
+                extras,
+                key,
+                value,
+            })
+        } else {
+            MemcachedFrame::Response(MemcachedResponse {
+                opcode,
+                data_type,
+                status,
+                opaque,
+                cas,
+                extras,
+                key,
+                value,
+            })
+        }))
+    }
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        if src.len() < HEADER_LEN {
+            return None;
+        }
+        let body_len = BigEndian::read_u32(&src[8..12]) as usize;
+        (HEADER_LEN + body_len).checked_sub(src.len())
+    }
+}
+
+impl Encoder for MemcachedBinaryCodec {
+    type Item = MemcachedFrame;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let (magic, opcode, data_type, status, opaque, cas, extras, key, value) = match item {
+            MemcachedFrame::Request(r) => {
+                let MemcachedRequest {
+                    opcode,
+                    data_type,
+                    vbucket,
+                    opaque,
+                    cas,
+                    extras,
+                    key,
+                    value,
+                } = r;
+                (
+                    REQUEST_MAGIC,
+                    opcode,
+                    data_type,
+                    vbucket,
+                    opaque,
+                    cas,
+                    extras,
+                    key,
+                    value,
+                )
+            }
+            MemcachedFrame::Response(r) => {
+                let MemcachedResponse {
+                    opcode,
+                    data_type,
+                    status,
+                    opaque,
+                    cas,
+                    extras,
+                    key,
+                    value,
+                } = r;
+                (
+                    RESPONSE_MAGIC,
+                    opcode,
+                    data_type,
+                    status,
+                    opaque,
+                    cas,
+                    extras,
+                    key,
+                    value,
+                )
+            }
+        };
+        if key.len() > u16::MAX as usize || extras.len() > u8::MAX as usize {
+            return Err(invalid("key or extras too long"));
+        }
+        let body_len = extras.len() + key.len() + value.len();
+        if body_len > self.max_body_len {
+            return Err(CodecError::FrameTooLong {
+                max: self.max_body_len,
+            });
+        }
+
+        dst.reserve(HEADER_LEN + body_len);
+        dst.put_u8(magic);
+        dst.put_u8(opcode);
+        dst.put_u16_be(key.len() as u16);
+        dst.put_u8(extras.len() as u8);
+        dst.put_u8(data_type);
+        dst.put_u16_be(status);
+        dst.put_u32_be(body_len as u32);
+        dst.put_u32_be(opaque);
+        dst.put_u64_be(cas);
+        dst.put_slice(&extras);
+        dst.put_slice(&key);
+        dst.put_slice(&value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn response_with_extras() {
+        let mut response = MemcachedResponse::new(
+            MemcachedRequest::GETK,
+            MemcachedResponse::NO_ERROR,
+            Bytes::from("World"),
+        );
+        response.extras = Bytes::from(&b"\xde\xad\xbe\xef"[..]);
+        response.key = Bytes::from("Hello");
+        response.opaque = 42;
+        response.cas = 7;
+
+        let mut codec = MemcachedBinaryCodec::new();
+        let mut buf = BytesMut::new();
+        codec.encode(response.clone().into(), &mut buf).unwrap();
+        assert_eq!(
+            &buf[..12],
+            b"\x81\x0c\x00\x05\x04\x00\x00\x00\x00\x00\x00\x0e"
+        );
+
+        let mut partial = BytesMut::from(&buf[..30]);
+        assert_eq!(codec.decode(&mut partial).unwrap(), None);
+        assert_eq!(codec.bytes_needed(&partial), Some(8));
+        let decoded = codec.decode(&mut buf).unwrap();
+        assert_eq!(decoded, Some(MemcachedFrame::Response(response)));
+        assert!(buf.is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{Bytes, BytesMut};
+
+const SYSEX_START: u8 = 0xf0;
+const SYSEX_END: u8 = 0xf7;
+/// Default limit on the length of a System Exclusive message
+const MAX_SYSEX: usize = 64 * 1024;
+
+/// A codec for MIDI messages on a serial byte stream, as sent by DIN and
+/// USB-serial MIDI interfaces.
+///
+/// Every message is yielded with its status byte, also when the sender
+/// relied on running status and left it out. System Exclusive messages are
+/// yielded whole, from `0xf0` to `0xf7`. Real-time messages, which may
+/// appear in the middle of other messages, are yielded as soon as they are
+/// read. Data bytes without a status to apply to are dropped.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, MidiCodec};
+///
+/// let mut codec = MidiCodec::new();
+/// // Two note ons, the second with running status, with a clock in between
+/// let mut buf = BytesMut::from(&b"\x90\x3c\x40\x3e\xf8\x40"[..]);
+///
+/// let first = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(&first[..], b"\x90\x3c\x40");
+/// let clock = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(&clock[..], b"\xf8");
+/// let second = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(&second[..], b"\x90\x3e\x40");
+/// ```
+#[derive(Debug)]
+pub struct MidiCodec {
+    /// Status of the last channel message read
+    running_status: Option<u8>,
+    /// Status of the last channel message written
+    sent_status: Option<u8>,
+    use_running_status: bool,
+    max_sysex_len: usize,
+}
+
+impl MidiCodec {
+    pub fn new() -> Self {
+        Self {
+            running_status: None,
+            sent_status: None,
+            use_running_status: false,
+            max_sysex_len: MAX_SYSEX,
+        }
+    }
+
+    /// Leave out the status byte of encoded channel messages with the same
+    /// status as the one before
+    pub fn with_running_status(mut self) -> Self {
+        self.use_running_status = true;
+        self
+    }
+
+    /// Fail on System Exclusive messages longer than `max` bytes
+    pub fn max_sysex_len(mut self, max: usize) -> Self {
+        self.max_sysex_len = max;
+        self
+    }
+}
+
+impl Default for MidiCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_real_time(b: u8) -> bool {
+    b >= 0xf8
+}
+
+fn is_status(b: u8) -> bool {
+    b >= 0x80
+}
+
+/// Number of data bytes following `status`
+fn data_len(status: u8) -> usize {
+    match status {
+        0xc0..=0xdf | 0xf1 | 0xf3 => 1,
+        0x80..=0xef | 0xf2 => 2,
+        _ => 0,
+    }
+}
+
+/// Take the byte at `at` out of `src`
+fn remove(src: &mut BytesMut, at: usize) -> Bytes {
+    let tail = src.split_off(at + 1);
+    let byte = src.split_off(at);
+    src.extend_from_slice(&tail);
+    byte.freeze()
+}
+
+impl Decoder for MidiCodec {
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            let status = match src.first() {
+                Some(&b) if is_real_time(b) => return Ok(Some(src.split_to(1).freeze())),
+                Some(&SYSEX_END) => {
+                    src.advance(1);
+                    continue;
+                }
+                Some(&b) if is_status(b) => b,
+                Some(_) => match self.running_status {
+                    Some(status) => status,
+                    None => {
+                        src.advance(1);
+                        continue;
+                    }
+                },
+                None => return Ok(None),
+            };
+            // Offset of the first data byte
+            let start = is_status(src[0]) as usize;
+
+            let mut end = start;
+            let complete = loop {
+                if status != SYSEX_START && end - start == data_len(status) {
+                    break true;
+                }
+                let b = match src.get(end) {
+                    Some(&b) => b,
+                    None => break false,
+                };
+                if is_real_time(b) {
+                    return Ok(Some(remove(src, end)));
+                }
+                if status == SYSEX_START {
+                    if b == SYSEX_END {
+                        end += 1;
+                        break true;
+                    }
+                    // Any other status ends System Exclusive as well
+                    if is_status(b) {
+                        break true;
+                    }
+                    if end >= self.max_sysex_len {
+                        src.advance(end);
+                        return Err(CodecError::FrameTooLong {
+                            max: self.max_sysex_len,
+                        });
+                    }
+                } else if is_status(b) {
+                    // An incomplete message, interrupted by a new one
+                    break false;
+                }
+                end += 1;
+            };
+            if !complete {
+                if src.len() == end {
+                    return Ok(None);
+                }
+                src.advance(end);
+                continue;
+            }
+
+            self.running_status = match status {
+                0x80..=0xef => Some(status),
+                _ => None,
+            };
+            if start == 1 {
+                return Ok(Some(src.split_to(end).freeze()));
+            }
+            let mut message = BytesMut::with_capacity(1 + end);
+            message.extend_from_slice(&[status]);
+            message.extend_from_slice(&src.split_to(end));
+            return Ok(Some(message.freeze()));
+        }
+    }
+}
+
+impl Encoder for MidiCodec {
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let status = match item.first() {
+            Some(&b) if is_status(b) => b,
+            _ => {
+                return Err(CodecError::Protocol(
+                    "MIDI message without status".to_owned(),
+                ))
+            }
+        };
+        let skip_status = self.use_running_status && self.sent_status == Some(status);
+        if !is_real_time(status) {
+            self.sent_status = Some(status).filter(|s| (0x80..=0xef).contains(s));
+        }
+        dst.extend_from_slice(&item[skip_status as usize..]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sysex_across_reads() {
+        let mut codec = MidiCodec::new();
+        let mut buf = BytesMut::from(&b"\xb0\x07\x64\xf0\x7e\x7f"[..]);
+        assert_eq!(
+            codec.decode(&mut buf).unwrap().unwrap(),
+            &b"\xb0\x07\x64"[..]
+        );
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+
+        buf.extend_from_slice(b"\x06\xfe\x01\xf7\x07\x7f");
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), &b"\xfe"[..]);
+        let sysex = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(sysex, &b"\xf0\x7e\x7f\x06\x01\xf7"[..]);
+        // System Exclusive cancels running status
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert!(buf.is_empty());
+
+        let mut out = BytesMut::new();
+        let mut codec = MidiCodec::new().with_running_status();
+        for message in &[
+            &b"\x90\x3c\x40"[..],
+            b"\xf8",
+            b"\x90\x3e\x40",
+            b"\x80\x3c\x00",
+        ] {
+            codec.encode(Bytes::from(*message), &mut out).unwrap();
+        }
+        assert_eq!(&out[..], b"\x90\x3c\x40\xf8\x3e\x40\x80\x3c\x00");
+    }
+}
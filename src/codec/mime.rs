@@ -0,0 +1,299 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{Bytes, BytesMut};
+
+/// Default limit on the length of a header block
+const MAX_HEADER: usize = 64 * 1024;
+
+/// A block of header fields, optionally following a start line such as the
+/// request line of SIP or RTSP.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MimeHeaders {
+    pub start_line: Option<String>,
+    /// Field names and values in order, folded lines joined by a space
+    pub fields: Vec<(String, String)>,
+}
+
+impl MimeHeaders {
+    /// The value of the first field named `name`, ignoring case
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The value of `Content-Length`, or of its `alias` such as the compact
+    /// form of SIP
+    pub(crate) fn content_length(&self, alias: Option<&str>) -> Result<Option<u64>, CodecError> {
+        let value = self
+            .get("Content-Length")
+            .or_else(|| alias.and_then(|alias| self.get(alias)));
+        match value {
+            Some(value) => value
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|_| CodecError::Protocol("invalid Content-Length".to_owned())),
+            None => Ok(None),
+        }
+    }
+
+    /// Parse the lines of a block, without the empty line ending it
+    pub(crate) fn parse(block: &[u8], start_line: bool) -> Result<Self, CodecError> {
+        let block = std::str::from_utf8(block)?;
+        let mut lines = block.split("\r\n");
+        let mut headers = MimeHeaders {
+            start_line: if start_line {
+                lines.next().map(str::to_owned)
+            } else {
+                None
+            },
+            fields: Vec::new(),
+        };
+        for line in lines {
+            if line.starts_with([' ', '\t']) {
+                let (_, value) = headers
+                    .fields
+                    .last_mut()
+                    .ok_or_else(|| CodecError::Protocol("folded first header line".to_owned()))?;
+                value.push(' ');
+                value.push_str(line.trim());
+                continue;
+            }
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| CodecError::Protocol("header line without a colon".to_owned()))?;
+            headers
+                .fields
+                .push((name.trim().to_owned(), value.trim().to_owned()));
+        }
+        Ok(headers)
+    }
+
+    /// Write the block with the empty line ending it, leaving out fields
+    /// for which `skip` returns true
+    pub(crate) fn write(
+        &self,
+        dst: &mut BytesMut,
+        skip: impl Fn(&str) -> bool,
+    ) -> Result<(), CodecError> {
+        let mut block = String::new();
+        if let Some(line) = &self.start_line {
+            block.push_str(line);
+            block.push_str("\r\n");
+        }
+        for (name, value) in &self.fields {
+            if name.is_empty() || name.contains([':', '\r', '\n']) || value.contains(['\r', '\n']) {
+                return Err(CodecError::Protocol(
+                    "header field can not be encoded".to_owned(),
+                ));
+            }
+            if !skip(name) {
+                block.push_str(&format!("{}: {}\r\n", name, value));
+            }
+        }
+        dst.extend_from_slice(block.as_bytes());
+        Ok(())
+    }
+}
+
+/// Find the empty line ending a header block, starting the search at
+/// `from`. Returns the length of the block and of the block with the
+/// empty line.
+pub(crate) fn find_header_end(src: &[u8], from: usize) -> Option<(usize, usize)> {
+    let from = from.saturating_sub(3);
+    src[from..]
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|at| (from + at, from + at + 4))
+}
+
+/// A frame of a MIME style message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MimeFrame {
+    Headers(MimeHeaders),
+    /// A part of the body, as it arrived
+    Body(Bytes),
+}
+
+/// What the decoder expects next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Headers,
+    Body(u64),
+    BodyUntilEof,
+}
+
+/// A codec for messages made of a header block, ended by an empty line, and
+/// a body.
+///
+/// The header block is yielded parsed, followed by the body in frames of
+/// whatever was read of it. The body is `Content-Length` bytes long, after
+/// which the next message starts. Without a `Content-Length`, the body lasts
+/// until the end of the stream, or is empty with
+/// [`empty_body_without_length`](Self::empty_body_without_length). Encoding
+/// writes header blocks and body frames as they are.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, MimeCodec, MimeFrame};
+///
+/// let mut codec = MimeCodec::new();
+/// let mut buf = BytesMut::from(&b"Content-Type: text/plain\r\nContent-Length: 11\r\n\r\nHello"[..]);
+///
+/// match codec.decode(&mut buf).unwrap() {
+///     Some(MimeFrame::Headers(headers)) => assert_eq!(headers.get("content-type"), Some("text/plain")),
+///     frame => panic!("unexpected {:?}", frame),
+/// }
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some(MimeFrame::Body(Bytes::from("Hello"))));
+/// buf.extend_from_slice(b" World");
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some(MimeFrame::Body(Bytes::from(" World"))));
+/// ```
+#[derive(Debug)]
+pub struct MimeCodec {
+    start_line: bool,
+    body_until_eof: bool,
+    max_header_len: usize,
+    state: State,
+    /// Offset up to which the buffer holds no header end
+    scan: usize,
+}
+
+impl MimeCodec {
+    pub fn new() -> Self {
+        Self {
+            start_line: false,
+            body_until_eof: true,
+            max_header_len: MAX_HEADER,
+            state: State::Headers,
+            scan: 0,
+        }
+    }
+
+    /// Read the first line of every header block as start line, as in SIP,
+    /// RTSP and HTTP
+    pub fn with_start_line(mut self) -> Self {
+        self.start_line = true;
+        self
+    }
+
+    /// Treat a message without `Content-Length` as having no body, instead
+    /// of one lasting until the end of the stream
+    pub fn empty_body_without_length(mut self) -> Self {
+        self.body_until_eof = false;
+        self
+    }
+
+    /// Fail on header blocks longer than `max` bytes
+    pub fn max_header_len(mut self, max: usize) -> Self {
+        self.max_header_len = max;
+        self
+    }
+}
+
+impl Default for MimeCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for MimeCodec {
+    type Item = MimeFrame;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.state {
+            State::Headers => {
+                let (len, end) = match find_header_end(src, self.scan) {
+                    Some(end) => end,
+                    None if src.len() > self.max_header_len => {
+                        return Err(CodecError::FrameTooLong {
+                            max: self.max_header_len,
+                        })
+                    }
+                    None => {
+                        self.scan = src.len();
+                        return Ok(None);
+                    }
+                };
+                self.scan = 0;
+                let block = src.split_to(end);
+                let headers = MimeHeaders::parse(&block[..len], self.start_line)?;
+                self.state = match headers.content_length(None)? {
+                    Some(0) => State::Headers,
+                    Some(len) => State::Body(len),
+                    None if self.body_until_eof => State::BodyUntilEof,
+                    None => State::Headers,
+                };
+                Ok(Some(MimeFrame::Headers(headers)))
+            }
+            State::Body(remaining) => {
+                if src.is_empty() {
+                    return Ok(None);
+                }
+                let n = remaining.min(src.len() as u64);
+                self.state = match remaining - n {
+                    0 => State::Headers,
+                    remaining => State::Body(remaining),
+                };
+                Ok(Some(MimeFrame::Body(src.split_to(n as usize).freeze())))
+            }
+            State::BodyUntilEof if src.is_empty() => Ok(None),
+            State::BodyUntilEof => Ok(Some(MimeFrame::Body(src.take().freeze()))),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.scan = 0;
+    }
+}
+
+impl Encoder for MimeCodec {
+    type Item = MimeFrame;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        match item {
+            MimeFrame::Headers(headers) => {
+                headers.write(dst, |_| false)?;
+                dst.extend_from_slice(b"\r\n");
+            }
+            MimeFrame::Body(data) => dst.extend_from_slice(&data),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn folded_headers_and_body_until_eof() {
+        let mut codec = MimeCodec::new().with_start_line();
+        let mut buf =
+            BytesMut::from(&b"DESCRIBE rtsp://cam/1 RTSP/1.0\r\nCSeq: 2\r\nX-Long: a\r\n"[..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(b"\tb\r\n\r\nrest");
+
+        let headers = match codec.decode(&mut buf).unwrap() {
+            Some(MimeFrame::Headers(headers)) => headers,
+            frame => panic!("unexpected {:?}", frame),
+        };
+        assert_eq!(
+            headers.start_line.as_deref(),
+            Some("DESCRIBE rtsp://cam/1 RTSP/1.0")
+        );
+        assert_eq!(headers.get("x-long"), Some("a b"));
+        let body = codec.decode_eof(&mut buf).unwrap();
+        assert_eq!(body, Some(MimeFrame::Body(Bytes::from("rest"))));
+
+        let mut out = BytesMut::new();
+        codec.encode(MimeFrame::Headers(headers), &mut out).unwrap();
+        assert_eq!(
+            &out[..],
+            &b"DESCRIBE rtsp://cam/1 RTSP/1.0\r\nCSeq: 2\r\nX-Long: a b\r\n\r\n"[..]
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use super::varint::{put_varint, read_varint};
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{Bytes, BytesMut};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+/// Longest packet, the largest length a three byte VarInt holds
+const MAX_PACKET: usize = (1 << 21) - 1;
+/// Largest size of a decompressed packet accepted
+const MAX_UNCOMPRESSED: usize = 1 << 23;
+
+/// A codec for the packets of the Minecraft Java Edition protocol.
+///
+/// Packets are prefixed with their VarInt length and start with their VarInt
+/// id. Once compression is enabled, packets of at least `threshold` bytes are
+/// zlib compressed, and every packet carries the length it decompresses to,
+/// or zero if it was sent as is.
+///
+/// Compression is switched on by the Set Compression packet during login.
+/// Decode it, release the framer with `release_with_buffers`, and continue
+/// with a codec created by `with_compression` and the buffered bytes.
+///
+/// Enabled with the `minecraft` feature.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, Encoder, McPacket, McPacketCodec};
+///
+/// let mut codec = McPacketCodec::with_compression(256);
+/// let mut buf = BytesMut::new();
+/// let chunk = McPacket { id: 0x20, data: Bytes::from(vec![0; 4096]) };
+/// codec.encode(chunk.clone(), &mut buf).unwrap();
+/// assert!(buf.len() < 100);
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some(chunk));
+/// ```
+#[derive(Debug, Default)]
+pub struct McPacketCodec {
+    threshold: Option<usize>,
+}
+
+/// A packet of the Minecraft protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McPacket {
+    pub id: i32,
+    /// The fields of the packet, after the id
+    pub data: Bytes,
+}
+
+impl McPacketCodec {
+    /// A codec for packets without compression
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A codec compressing packets of at least `threshold` bytes
+    pub fn with_compression(threshold: usize) -> Self {
+        Self {
+            threshold: Some(threshold),
+        }
+    }
+
+    /// Switch compression on or off for the following packets
+    pub fn set_compression(&mut self, threshold: Option<usize>) {
+        self.threshold = threshold;
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+/// Split the packet id off the front of an uncompressed packet
+fn split_id(mut body: BytesMut) -> Result<McPacket, CodecError> {
+    let (id, n) = read_varint(&body, 5)?.ok_or_else(|| invalid("packet without an id"))?;
+    body.advance(n);
+    Ok(McPacket {
+        id: id as u32 as i32,
+        data: body.freeze(),
+    })
+}
+
+impl Decoder for McPacketCodec {
+    type Item = McPacket;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let (len, n) = match read_varint(src, 3)? {
+            Some(prefix) => prefix,
+            None => return Ok(None),
+        };
+        let len = len as usize;
+        if len > MAX_PACKET {
+            return Err(CodecError::FrameTooLong { max: MAX_PACKET });
+        }
+        if src.len() < n + len {
+            return Ok(None);
+        }
+        src.advance(n);
+        let mut packet = src.split_to(len);
+
+        let threshold = match self.threshold {
+            Some(threshold) => threshold,
+            None => return split_id(packet).map(Some),
+        };
+        let (data_len, n) = read_varint(&packet, 5)?
+            .ok_or_else(|| invalid("compressed packet without a data length"))?;
+        packet.advance(n);
+        let data_len = data_len as usize;
+        if data_len == 0 {
+            return split_id(packet).map(Some);
+        }
+        if data_len < threshold || data_len > MAX_UNCOMPRESSED {
+            return Err(invalid("compressed packet size out of range"));
+        }
+
+        let mut body = Vec::with_capacity(data_len);
+        ZlibDecoder::new(&packet[..])
+            .take(data_len as u64 + 1)
+            .read_to_end(&mut body)?;
+        if body.len() != data_len {
+            return Err(invalid("packet does not decompress to its data length"));
+        }
+        split_id(BytesMut::from(body)).map(Some)
+    }
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        let (len, n) = read_varint(src, 3).ok()??;
+        (n + len as usize).checked_sub(src.len())
+    }
+}
+
+impl Encoder for McPacketCodec {
+    type Item = McPacket;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let mut body = BytesMut::with_capacity(5 + item.data.len());
+        put_varint(&mut body, u64::from(item.id as u32));
+        body.extend_from_slice(&item.data);
+
+        let packet = match self.threshold {
+            None => body,
+            Some(threshold) => {
+                let mut packet = BytesMut::with_capacity(5 + body.len());
+                if body.len() < threshold {
+                    put_varint(&mut packet, 0);
+                    packet.extend_from_slice(&body);
+                } else {
+                    put_varint(&mut packet, body.len() as u64);
+                    let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+                    zlib.write_all(&body)?;
+                    packet.extend_from_slice(&zlib.finish()?);
+                }
+                packet
+            }
+        };
+        if packet.len() > MAX_PACKET {
+            return Err(CodecError::FrameTooLong { max: MAX_PACKET });
+        }
+        dst.reserve(3 + packet.len());
+        put_varint(dst, packet.len() as u64);
+        dst.extend_from_slice(&packet);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn small_packet_sent_as_is() {
+        let mut codec = McPacketCodec::with_compression(256);
+        let mut buf = BytesMut::new();
+        let packet = McPacket {
+            id: 0x1b,
+            data: Bytes::from(&b"ping"[..]),
+        };
+        codec.encode(packet.clone(), &mut buf).unwrap();
+        assert_eq!(&buf[..], b"\x06\x00\x1bping");
+
+        buf.truncate(3);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(codec.bytes_needed(&buf), Some(4));
+        buf.extend_from_slice(b"ping");
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(packet));
+    }
+}
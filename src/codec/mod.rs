@@ -2,4 +2,145 @@ mod bytes;
 pub use self::bytes::BytesCodec;
 
 mod lines;
-pub use self::lines::LinesCodec;
\ No newline at end of file
+pub use self::lines::{BytesLinesCodec, LinesCodec};
+
+#[cfg(feature = "regex")]
+mod regex;
+#[cfg(feature = "regex")]
+pub use self::regex::RegexDelimiterCodec;
+
+#[cfg(feature = "encoding")]
+mod encoded_lines;
+#[cfg(feature = "encoding")]
+pub use self::encoded_lines::EncodedLinesCodec;
+
+mod fragmenting;
+pub use self::fragmenting::FragmentingCodec;
+
+mod sequenced;
+pub use self::sequenced::{SequenceError, SequencedCodec};
+
+#[cfg(feature = "tracing")]
+mod logging;
+#[cfg(feature = "tracing")]
+pub use self::logging::LoggingCodec;
+
+mod proxy;
+pub use self::proxy::{ProxyCommand, ProxyFrame, ProxyHeader, ProxyProtocolCodec};
+
+mod smtp;
+pub use self::smtp::{SmtpCodec, SmtpFrame, SmtpReply};
+
+mod imap;
+pub use self::imap::ImapCodec;
+
+mod dot;
+pub use self::dot::DotTerminatedCodec;
+
+mod ftp;
+pub use self::ftp::{FtpControlCodec, FtpReply};
+
+mod graphite;
+pub use self::graphite::{GraphiteCodec, GraphiteMetric};
+
+mod statsd;
+pub use self::statsd::{StatsdCodec, StatsdDatagramCodec, StatsdKind, StatsdMetric};
+
+mod logfmt;
+pub use self::logfmt::{LogfmtCodec, LogfmtRecord};
+
+mod varint;
+
+#[cfg(feature = "minecraft")]
+mod minecraft;
+#[cfg(feature = "minecraft")]
+pub use self::minecraft::{McPacket, McPacketCodec};
+
+mod rcon;
+pub use self::rcon::{RconCodec, RconPacket};
+
+mod tds;
+pub use self::tds::{TdsMessage, TdsPacketCodec};
+
+mod clickhouse;
+pub use self::clickhouse::{ClickHouseFrameCodec, ClickHousePacket, CLICKHOUSE_REVISION};
+
+mod memcached;
+pub use self::memcached::{
+    MemcachedBinaryCodec, MemcachedFrame, MemcachedRequest, MemcachedResponse,
+};
+
+mod adb;
+pub use self::adb::{AdbMessage, AdbMessageCodec};
+
+mod dlt;
+pub use self::dlt::{DltCodec, DltMessage, DltStorageHeader};
+
+mod elm327;
+pub use self::elm327::Elm327Codec;
+
+mod sml;
+pub use self::sml::SmlCodec;
+
+mod midi;
+pub use self::midi::MidiCodec;
+
+mod nmea;
+pub use self::nmea::{AisCodec, AisMessage, NmeaCodec, NmeaSentence};
+
+mod wayland;
+pub use self::wayland::{WaylandMessage, WaylandMessageCodec};
+
+mod docker;
+pub use self::docker::{DockerStdCopyCodec, StdStream};
+
+mod wal;
+pub use self::wal::WalRecordCodec;
+
+mod tar;
+pub use self::tar::{TarEntry, TarEntryCodec, TarHeader};
+
+mod mime;
+pub use self::mime::{MimeCodec, MimeFrame, MimeHeaders};
+
+mod sip;
+pub use self::sip::{SipCodec, SipMessage};
+
+mod rtsp;
+pub use self::rtsp::{RtspCodec, RtspFrame, RtspMessage};
+
+mod rtp;
+pub use self::rtp::{RtpCodec, RtpPacket};
+
+mod coap;
+pub use self::coap::{CoapTcpCodec, CoapTcpMessage};
+
+mod semtech;
+pub use self::semtech::{SemtechPacket, SemtechUdpCodec};
+
+mod xbee;
+pub use self::xbee::{XbeeApiCodec, XbeeFrame};
+
+mod artnet;
+pub use self::artnet::{ArtDmx, ArtNetCodec, ArtNetPacket};
+
+mod sacn;
+pub use self::sacn::{SacnCodec, SacnData, SacnPacket};
+
+mod prometheus;
+pub use self::prometheus::{PromRecord, PromSample, PromTextCodec};
+
+mod influx;
+pub use self::influx::{InfluxLineCodec, InfluxPoint, InfluxValue};
+
+mod escaped_field;
+pub use self::escaped_field::EscapedFieldCodec;
+
+mod scan;
+pub use self::scan::ScanCodec;
+
+mod chars;
+pub use self::chars::CharCodec;
+
+mod prefixed_string;
+pub use self::prefixed_string::{LengthPrefix, PrefixedStringCodec};
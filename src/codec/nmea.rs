@@ -0,0 +1,346 @@
+use super::BytesLinesCodec;
+use crate::{CodecError, Decoder, Encoder};
+use bytes::BytesMut;
+use std::fmt;
+
+/// Longest payload put into one AIS sentence, keeping it within the 82
+/// characters of NMEA 0183
+const MAX_AIS_PAYLOAD: usize = 60;
+
+/// A codec for NMEA 0183 sentences, one per line.
+///
+/// The checksum after `*` is validated when present and always written on
+/// encode. Empty lines are skipped.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, Encoder, NmeaCodec};
+///
+/// let mut codec = NmeaCodec::new();
+/// let mut buf = BytesMut::from(&b"$GPGLL,4916.45,N,12311.12,W,225444,A*31\r\n"[..]);
+///
+/// let sentence = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(sentence.address, "GPGLL");
+/// assert_eq!(sentence.fields[..2], ["4916.45", "N"]);
+///
+/// codec.encode(sentence, &mut buf).unwrap();
+/// assert_eq!(&buf[..], &b"$GPGLL,4916.45,N,12311.12,W,225444,A*31\r\n"[..]);
+/// ```
+#[derive(Debug, Default)]
+pub struct NmeaCodec {
+    lines: BytesLinesCodec,
+}
+
+/// An NMEA 0183 sentence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NmeaSentence {
+    /// Whether the sentence starts with `!` and carries encapsulated data,
+    /// as AIS sentences do, instead of `$`
+    pub encapsulated: bool,
+    /// Talker and sentence type, such as `GPGGA`
+    pub address: String,
+    pub fields: Vec<String>,
+}
+
+impl NmeaCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+fn checksum(body: &str) -> u8 {
+    body.bytes().fold(0, |sum, b| sum ^ b)
+}
+
+impl NmeaSentence {
+    fn parse(line: &str) -> Result<Self, CodecError> {
+        let encapsulated = match line.as_bytes().first() {
+            Some(b'$') => false,
+            Some(b'!') => true,
+            _ => return Err(invalid("sentence does not start with $ or !")),
+        };
+        let body = match line[1..].split_once('*') {
+            Some((body, sum)) => {
+                let sum = u8::from_str_radix(sum, 16).map_err(|_| invalid("invalid checksum"))?;
+                if checksum(body) != sum {
+                    return Err(invalid("checksum mismatch"));
+                }
+                body
+            }
+            None => &line[1..],
+        };
+        let mut fields = body.split(',').map(str::to_owned);
+        Ok(Self {
+            encapsulated,
+            address: fields.next().unwrap_or_default(),
+            fields: fields.collect(),
+        })
+    }
+}
+
+impl fmt::Display for NmeaSentence {
+    /// Format the sentence with its checksum, without line ending
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut body = self.address.clone();
+        for field in &self.fields {
+            body.push(',');
+            body.push_str(field);
+        }
+        let start = if self.encapsulated { '!' } else { '$' };
+        write!(f, "{}{}*{:02X}", start, body, checksum(&body))
+    }
+}
+
+impl Decoder for NmeaCodec {
+    type Item = NmeaSentence;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        while let Some(line) = self.lines.decode(src)? {
+            let line = std::str::from_utf8(&line)?.trim_end_matches(['\r', '\n']);
+            if !line.is_empty() {
+                return NmeaSentence::parse(line).map(Some);
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Encoder for NmeaCodec {
+    type Item = NmeaSentence;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let reserved = |c: char| matches!(c, '$' | '!' | '*' | ',' | '\r' | '\n');
+        if item.address.contains(reserved) || item.fields.iter().any(|f| f.contains(reserved)) {
+            return Err(invalid("reserved character in sentence"));
+        }
+        dst.extend_from_slice(format!("{}\r\n", item).as_bytes());
+        Ok(())
+    }
+}
+
+/// A codec for AIS messages carried in `!xxVDM` and `!xxVDO` sentences,
+/// reassembling messages split over several sentences.
+///
+/// Fragments of a message are matched by sentence address and sequential
+/// message id, and must arrive in order. A message missing a fragment is
+/// dropped, as are sentences other than AIS ones, which [`NmeaCodec`]
+/// decodes. Messages too long for one sentence are split when encoding.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{AisCodec, Decoder};
+///
+/// let mut codec = AisCodec::new();
+/// let mut buf = BytesMut::from(&b"!AIVDM,2,1,3,B,55P5TL01VIaAL@7WKO@mBplU@<PDhh000000001S;AJ::4A80?4i@E53,0*3E\r\n"[..]);
+/// assert_eq!(codec.decode(&mut buf).unwrap(), None);
+/// buf.extend_from_slice(b"!AIVDM,2,2,3,B,1@0000000000000,2*55\r\n");
+///
+/// let message = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(message.payload.len(), 71);
+/// assert_eq!(message.fill_bits, 2);
+/// ```
+#[derive(Debug, Default)]
+pub struct AisCodec {
+    sentences: NmeaCodec,
+    partial: Vec<Partial>,
+    /// Sequential message id of the next message split when encoding
+    next_id: u8,
+}
+
+/// A message missing fragments
+#[derive(Debug)]
+struct Partial {
+    address: String,
+    id: String,
+    count: usize,
+    received: usize,
+    payload: String,
+}
+
+/// An AIS message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AisMessage {
+    /// Address of the sentences carrying it, such as `AIVDM`
+    pub address: String,
+    /// Radio channel, `A` or `B`, if known
+    pub channel: String,
+    /// The message, armored to six bits per character
+    pub payload: String,
+    /// Bits to ignore at the end of the payload
+    pub fill_bits: u8,
+}
+
+impl AisMessage {
+    /// Whether the message was sent by the receiving station itself
+    pub fn is_own_vessel(&self) -> bool {
+        self.address.ends_with("VDO")
+    }
+}
+
+impl AisCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a fragment, returning the message if it was the last
+    fn reassemble(&mut self, sentence: NmeaSentence) -> Result<Option<AisMessage>, CodecError> {
+        let fields = &sentence.fields;
+        if fields.len() < 6 {
+            return Err(invalid("AIS sentence with missing fields"));
+        }
+        let number = |i: usize| -> Result<usize, CodecError> {
+            fields[i]
+                .parse()
+                .map_err(|_| invalid("invalid AIS fragment number"))
+        };
+        let (count, number) = (number(0)?, number(1)?);
+        if number == 0 || number > count {
+            return Err(invalid("invalid AIS fragment number"));
+        }
+        let (address, id) = (&sentence.address, &fields[2]);
+        let index = self
+            .partial
+            .iter()
+            .position(|p| p.address == *address && p.id == *id);
+
+        let mut partial = match index {
+            _ if number == 1 => {
+                if let Some(index) = index {
+                    self.partial.swap_remove(index);
+                }
+                Partial {
+                    address: address.clone(),
+                    id: id.clone(),
+                    count,
+                    received: 0,
+                    payload: String::new(),
+                }
+            }
+            Some(index) => self.partial.swap_remove(index),
+            None => return Ok(None),
+        };
+        if partial.count != count || partial.received + 1 != number {
+            return Ok(None);
+        }
+        partial.received = number;
+        partial.payload.push_str(&fields[4]);
+        if number < count {
+            self.partial.push(partial);
+            return Ok(None);
+        }
+        Ok(Some(AisMessage {
+            address: partial.address,
+            channel: fields[3].clone(),
+            payload: partial.payload,
+            fill_bits: fields[5]
+                .parse()
+                .map_err(|_| invalid("invalid AIS fill bits"))?,
+        }))
+    }
+}
+
+impl Decoder for AisCodec {
+    type Item = AisMessage;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        while let Some(sentence) = self.sentences.decode(src)? {
+            let ais = sentence.address.ends_with("VDM") || sentence.address.ends_with("VDO");
+            if !sentence.encapsulated || !ais {
+                continue;
+            }
+            if let Some(message) = self.reassemble(sentence)? {
+                return Ok(Some(message));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Encoder for AisCodec {
+    type Item = AisMessage;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if !item.payload.is_ascii() {
+            return Err(invalid("AIS payload is not armored"));
+        }
+        let mut chunks: Vec<_> = item
+            .payload
+            .as_bytes()
+            .chunks(MAX_AIS_PAYLOAD)
+            .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+            .collect();
+        if chunks.is_empty() {
+            chunks.push(String::new());
+        }
+        let count = chunks.len();
+        let id = if count > 1 {
+            self.next_id = (self.next_id + 1) % 10;
+            self.next_id.to_string()
+        } else {
+            String::new()
+        };
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            let fill_bits = if i + 1 == count { item.fill_bits } else { 0 };
+            let sentence = NmeaSentence {
+                encapsulated: true,
+                address: item.address.clone(),
+                fields: vec![
+                    count.to_string(),
+                    (i + 1).to_string(),
+                    id.clone(),
+                    item.channel.clone(),
+                    chunk,
+                    fill_bits.to_string(),
+                ],
+            };
+            self.sentences.encode(sentence, dst)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn splits_and_reassembles_interleaved() {
+        let long = AisMessage {
+            address: "AIVDM".to_owned(),
+            channel: "A".to_owned(),
+            payload: "5".repeat(MAX_AIS_PAYLOAD + 10),
+            fill_bits: 2,
+        };
+        let short = AisMessage {
+            address: "AIVDO".to_owned(),
+            channel: "B".to_owned(),
+            payload: "13u?etPv2;0n:dDPwUM1U1Cb069D".to_owned(),
+            fill_bits: 0,
+        };
+        let mut codec = AisCodec::new();
+        let mut long_buf = BytesMut::new();
+        codec.encode(long.clone(), &mut long_buf).unwrap();
+        let mut buf = BytesMut::new();
+        codec.encode(short.clone(), &mut buf).unwrap();
+        assert!(buf.starts_with(b"!AIVDO,1,1,,B,13u"));
+
+        // A position report between the fragments, and a GPS fix
+        let second = long_buf.split_off(long_buf.iter().position(|&b| b == b'\n').unwrap() + 1);
+        long_buf.extend_from_slice(b"$GPGLL,4916.45,N,12311.12,W,225444,A*31\r\n");
+        long_buf.extend_from_slice(&buf);
+        long_buf.extend_from_slice(&second);
+        assert_eq!(codec.decode(&mut long_buf).unwrap(), Some(short));
+        assert_eq!(codec.decode(&mut long_buf).unwrap(), Some(long));
+        assert!(long_buf.is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+use super::varint::{put_varint, read_varint};
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BigEndian, BufMut, ByteOrder, BytesMut, LittleEndian};
+
+/// Default limit on the length of a string
+const MAX_LEN: usize = 8 * 1024 * 1024;
+
+/// The length prefix of a [`PrefixedStringCodec`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthPrefix {
+    U8,
+    U16Be,
+    U16Le,
+    U32Be,
+    U32Le,
+    /// Unsigned LEB128, as in protobuf
+    Varint,
+}
+
+impl LengthPrefix {
+    /// Largest length the prefix can hold
+    pub(crate) fn max(self) -> u64 {
+        match self {
+            LengthPrefix::U8 => u64::from(u8::MAX),
+            LengthPrefix::U16Be | LengthPrefix::U16Le => u64::from(u16::MAX),
+            LengthPrefix::U32Be | LengthPrefix::U32Le => u64::from(u32::MAX),
+            LengthPrefix::Varint => u64::MAX,
+        }
+    }
+
+    /// Read the prefix, returning the length with the size of the prefix
+    pub(crate) fn read(self, src: &[u8]) -> Result<Option<(u64, usize)>, CodecError> {
+        let size = match self {
+            LengthPrefix::Varint => return read_varint(src, 10),
+            LengthPrefix::U8 => 1,
+            LengthPrefix::U16Be | LengthPrefix::U16Le => 2,
+            LengthPrefix::U32Be | LengthPrefix::U32Le => 4,
+        };
+        if src.len() < size {
+            return Ok(None);
+        }
+        let len = match self {
+            LengthPrefix::U8 => u64::from(src[0]),
+            LengthPrefix::U16Be => u64::from(BigEndian::read_u16(src)),
+            LengthPrefix::U16Le => u64::from(LittleEndian::read_u16(src)),
+            LengthPrefix::U32Be => u64::from(BigEndian::read_u32(src)),
+            _ => u64::from(LittleEndian::read_u32(src)),
+        };
+        Ok(Some((len, size)))
+    }
+
+    pub(crate) fn write(self, dst: &mut BytesMut, len: usize) {
+        dst.reserve(10);
+        match self {
+            LengthPrefix::U8 => dst.put_u8(len as u8),
+            LengthPrefix::U16Be => dst.put_u16_be(len as u16),
+            LengthPrefix::U16Le => dst.put_u16_le(len as u16),
+            LengthPrefix::U32Be => dst.put_u32_be(len as u32),
+            LengthPrefix::U32Le => dst.put_u32_le(len as u32),
+            LengthPrefix::Varint => put_varint(dst, len as u64),
+        }
+    }
+}
+
+/// A codec for UTF-8 strings prefixed with their length in bytes.
+///
+/// Decoded strings are validated, invalid UTF-8 fails decoding.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, Encoder, LengthPrefix, PrefixedStringCodec};
+///
+/// let mut codec = PrefixedStringCodec::new(LengthPrefix::U16Be);
+/// let mut buf = BytesMut::new();
+/// codec.encode("héllo".to_owned(), &mut buf).unwrap();
+/// assert_eq!(&buf[..], b"\x00\x06h\xc3\xa9llo");
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some("héllo".to_owned()));
+/// ```
+#[derive(Debug)]
+pub struct PrefixedStringCodec {
+    prefix: LengthPrefix,
+    max_len: usize,
+}
+
+impl PrefixedStringCodec {
+    /// Codec for strings prefixed with `prefix`
+    pub fn new(prefix: LengthPrefix) -> Self {
+        Self {
+            prefix,
+            max_len: MAX_LEN,
+        }
+    }
+
+    /// Fail with `FrameTooLong` on strings longer than `max` bytes
+    pub fn max_len(mut self, max: usize) -> Self {
+        self.max_len = max;
+        self
+    }
+
+    /// Read the prefix, returning the length of the string with the size of
+    /// the prefix, which add up without overflowing
+    fn header(&self, src: &[u8]) -> Result<Option<(usize, usize)>, CodecError> {
+        match self.prefix.read(src)? {
+            Some((len, size))
+                if len > self.max_len as u64 || size.checked_add(len as usize).is_none() =>
+            {
+                Err(CodecError::FrameTooLong { max: self.max_len })
+            }
+            Some((len, size)) => Ok(Some((len as usize, size))),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Decoder for PrefixedStringCodec {
+    type Item = String;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let (len, size) = match self.header(src)? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        if src.len() < size + len {
+            return Ok(None);
+        }
+        src.advance(size);
+        let string = src.split_to(len);
+        Ok(Some(std::str::from_utf8(&string)?.to_owned()))
+    }
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        let (len, size) = self.header(src).ok()??;
+        (size + len).checked_sub(src.len())
+    }
+}
+
+impl Encoder for PrefixedStringCodec {
+    type Item = String;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let max = self.prefix.max().min(self.max_len as u64);
+        if item.len() as u64 > max {
+            return Err(CodecError::FrameTooLong { max: max as usize });
+        }
+        self.prefix.write(dst, item.len());
+        dst.extend_from_slice(item.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn prefixes() {
+        let expected: [(LengthPrefix, &[u8]); 4] = [
+            (LengthPrefix::U8, b"\x02"),
+            (LengthPrefix::U16Le, b"\x02\x00"),
+            (LengthPrefix::U32Be, b"\x00\x00\x00\x02"),
+            (LengthPrefix::Varint, b"\x02"),
+        ];
+        for &(prefix, encoded) in &expected {
+            let mut codec = PrefixedStringCodec::new(prefix);
+            let mut buf = BytesMut::new();
+            codec.encode("ok".to_owned(), &mut buf).unwrap();
+            assert_eq!(&buf[..encoded.len()], encoded);
+            assert_eq!(codec.decode(&mut buf).unwrap(), Some("ok".to_owned()));
+        }
+
+        let mut codec = PrefixedStringCodec::new(LengthPrefix::U8);
+        assert!(codec.encode("x".repeat(256), &mut BytesMut::new()).is_err());
+        let mut buf = BytesMut::from(&b"\x03a"[..]);
+        assert_eq!(codec.bytes_needed(&buf), Some(2));
+        buf.extend_from_slice(b"\xffc");
+        assert!(codec.decode(&mut buf).is_err());
+    }
+
+    #[test]
+    fn length_overflowing_with_prefix() {
+        let mut codec = PrefixedStringCodec::new(LengthPrefix::Varint).max_len(usize::MAX);
+        let mut buf = BytesMut::new();
+        put_varint(&mut buf, u64::MAX);
+        match codec.decode(&mut buf) {
+            Err(CodecError::FrameTooLong { max }) => assert_eq!(max, usize::MAX),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(codec.bytes_needed(&buf), None);
+    }
+}
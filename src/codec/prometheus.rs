@@ -0,0 +1,391 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BufMut, BytesMut};
+use std::fmt::Write;
+
+/// Default limit on the length of a line
+const MAX_LINE: usize = 64 * 1024;
+
+/// A codec for the Prometheus text exposition format, yielding one record
+/// per line.
+///
+/// Lines are samples, `# HELP` and `# TYPE` lines or other comments. Empty
+/// lines are skipped. Label values are unescaped when decoding and escaped
+/// when encoding, as is the text of `# HELP` lines. Values `+Inf`, `-Inf`
+/// and `NaN` are understood. As the last line of an exposition may lack its
+/// newline, use `decode_eof` at the end of the body.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, Encoder, PromRecord, PromSample, PromTextCodec};
+///
+/// let mut codec = PromTextCodec::new();
+/// let mut buf = BytesMut::from(&b"# TYPE http_requests_total counter\n"[..]);
+/// buf.extend_from_slice(b"http_requests_total{method=\"post\",code=\"200\"} 1027 1395066363000\n");
+///
+/// let kind = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(kind, PromRecord::Type {
+///     metric: "http_requests_total".to_owned(),
+///     kind: "counter".to_owned(),
+/// });
+/// match codec.decode(&mut buf).unwrap() {
+///     Some(PromRecord::Sample(sample)) => {
+///         assert_eq!(sample.label("code"), Some("200"));
+///         assert_eq!(sample.value, 1027.0);
+///     }
+///     record => panic!("unexpected {:?}", record),
+/// }
+///
+/// let sample = PromSample::new("up", 1.0).with_label("job", "node");
+/// codec.encode(PromRecord::Sample(sample), &mut buf).unwrap();
+/// assert_eq!(&buf[..], b"up{job=\"node\"} 1\n");
+/// ```
+#[derive(Debug)]
+pub struct PromTextCodec {
+    max_line_len: usize,
+}
+
+/// A line of the Prometheus text exposition format.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PromRecord {
+    Sample(PromSample),
+    /// `# HELP` line, its text unescaped
+    Help {
+        metric: String,
+        text: String,
+    },
+    /// `# TYPE` line, `kind` being `counter`, `gauge` and so on
+    Type {
+        metric: String,
+        kind: String,
+    },
+    /// Any other comment, without the `#` and following whitespace
+    Comment(String),
+}
+
+/// A sample of the Prometheus text exposition format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromSample {
+    pub name: String,
+    /// Labels in the order they appear, their values unescaped
+    pub labels: Vec<(String, String)>,
+    pub value: f64,
+    /// Milliseconds since the Unix epoch
+    pub timestamp: Option<i64>,
+}
+
+impl PromSample {
+    /// A sample without labels or timestamp
+    pub fn new(name: &str, value: f64) -> Self {
+        Self {
+            name: name.to_owned(),
+            labels: Vec::new(),
+            value,
+            timestamp: None,
+        }
+    }
+
+    /// Add a label
+    pub fn with_label(mut self, name: &str, value: &str) -> Self {
+        self.labels.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Value of the label `name`
+    pub fn label(&self, name: &str) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|(label, _)| label == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl PromTextCodec {
+    pub fn new() -> Self {
+        Self {
+            max_line_len: MAX_LINE,
+        }
+    }
+
+    /// Fail with `FrameTooLong` on lines longer than `max` bytes
+    pub fn max_line_len(mut self, max: usize) -> Self {
+        self.max_line_len = max;
+        self
+    }
+}
+
+impl Default for PromTextCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+/// Undo the escaping of `\\`, `\n` and, if `quote`, `\"`
+fn unescape(s: &str, quote: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('\\')) => out.push('\\'),
+            ('\\', Some('n')) => out.push('\n'),
+            ('\\', Some('"')) if quote => out.push('"'),
+            _ => {
+                out.push(c);
+                continue;
+            }
+        }
+        chars.next();
+    }
+    out
+}
+
+fn escape(s: &str, quote: bool, out: &mut String) {
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '"' if quote => out.push_str("\\\""),
+            c => out.push(c),
+        }
+    }
+}
+
+fn parse_value(value: &str) -> Result<f64, CodecError> {
+    match value {
+        "+Inf" => Ok(f64::INFINITY),
+        "-Inf" => Ok(f64::NEG_INFINITY),
+        "NaN" => Ok(f64::NAN),
+        value => value.parse().map_err(|_| invalid("invalid sample value")),
+    }
+}
+
+fn parse_comment(comment: &str) -> PromRecord {
+    let comment = comment.trim_start();
+    let mut parts = comment.splitn(3, [' ', '\t']);
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("HELP"), Some(metric), text) => PromRecord::Help {
+            metric: metric.to_owned(),
+            text: unescape(text.unwrap_or(""), false),
+        },
+        (Some("TYPE"), Some(metric), Some(kind)) => PromRecord::Type {
+            metric: metric.to_owned(),
+            kind: kind.trim().to_owned(),
+        },
+        _ => PromRecord::Comment(comment.to_owned()),
+    }
+}
+
+fn parse_sample(line: &str) -> Result<PromSample, CodecError> {
+    let name_end = line
+        .find(|c: char| c == '{' || c.is_whitespace())
+        .ok_or_else(|| invalid("sample without value"))?;
+    let mut sample = PromSample::new(&line[..name_end], 0.0);
+    let mut rest = &line[name_end..];
+
+    if rest.starts_with('{') {
+        rest = &rest[1..];
+        loop {
+            rest = rest.trim_start();
+            if rest.starts_with('}') {
+                rest = &rest[1..];
+                break;
+            }
+            let eq = rest
+                .find('=')
+                .ok_or_else(|| invalid("label without value"))?;
+            let name = rest[..eq].trim();
+            rest = rest[eq + 1..].trim_start();
+            if !rest.starts_with('"') {
+                return Err(invalid("label value is not quoted"));
+            }
+            // Find the closing quote, skipping escaped characters
+            let mut end = None;
+            let mut escaped = false;
+            for (i, c) in rest.char_indices().skip(1) {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '"' => {
+                        end = Some(i);
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            let end = end.ok_or_else(|| invalid("unterminated label value"))?;
+            sample
+                .labels
+                .push((name.to_owned(), unescape(&rest[1..end], true)));
+            rest = rest[end + 1..].trim_start();
+            if rest.starts_with(',') {
+                rest = &rest[1..];
+            } else if !rest.starts_with('}') {
+                return Err(invalid("expected , or } after label"));
+            }
+        }
+    }
+
+    let mut fields = rest.split_whitespace();
+    sample.value = parse_value(
+        fields
+            .next()
+            .ok_or_else(|| invalid("sample without value"))?,
+    )?;
+    if let Some(timestamp) = fields.next() {
+        sample.timestamp = Some(
+            timestamp
+                .parse()
+                .map_err(|_| invalid("invalid timestamp"))?,
+        );
+    }
+    if fields.next().is_some() {
+        return Err(invalid("trailing data after sample timestamp"));
+    }
+    Ok(sample)
+}
+
+fn parse_line(line: &str) -> Result<PromRecord, CodecError> {
+    match line.strip_prefix('#') {
+        Some(comment) => Ok(parse_comment(comment)),
+        None => parse_sample(line).map(PromRecord::Sample),
+    }
+}
+
+impl Decoder for PromTextCodec {
+    type Item = PromRecord;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            let end = match src.iter().position(|&b| b == b'\n') {
+                Some(end) => end,
+                None if src.len() > self.max_line_len => {
+                    return Err(CodecError::FrameTooLong {
+                        max: self.max_line_len,
+                    })
+                }
+                None => return Ok(None),
+            };
+            let line = src.split_to(end + 1);
+            let line = std::str::from_utf8(&line)?.trim();
+            if !line.is_empty() {
+                return parse_line(line).map(Some);
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if let Some(record) = self.decode(src)? {
+            return Ok(Some(record));
+        }
+        let line = src.take();
+        match std::str::from_utf8(&line)?.trim() {
+            "" => Ok(None),
+            line => parse_line(line).map(Some),
+        }
+    }
+}
+
+impl Encoder for PromTextCodec {
+    type Item = PromRecord;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let mut line = String::new();
+        match item {
+            PromRecord::Sample(sample) => {
+                line.push_str(&sample.name);
+                if !sample.labels.is_empty() {
+                    line.push('{');
+                    for (i, (name, value)) in sample.labels.iter().enumerate() {
+                        if i > 0 {
+                            line.push(',');
+                        }
+                        line.push_str(name);
+                        line.push_str("=\"");
+                        escape(value, true, &mut line);
+                        line.push('"');
+                    }
+                    line.push('}');
+                }
+                let _ = match sample.value {
+                    v if v.is_nan() => write!(line, " NaN"),
+                    v if v == f64::INFINITY => write!(line, " +Inf"),
+                    v if v == f64::NEG_INFINITY => write!(line, " -Inf"),
+                    v => write!(line, " {}", v),
+                };
+                if let Some(timestamp) = sample.timestamp {
+                    let _ = write!(line, " {}", timestamp);
+                }
+            }
+            PromRecord::Help { metric, text } => {
+                line.push_str("# HELP ");
+                line.push_str(&metric);
+                line.push(' ');
+                escape(&text, false, &mut line);
+            }
+            PromRecord::Type { metric, kind } => {
+                let _ = write!(line, "# TYPE {} {}", metric, kind);
+            }
+            PromRecord::Comment(comment) => {
+                if comment.contains('\n') {
+                    return Err(invalid("comment spans lines"));
+                }
+                let _ = write!(line, "# {}", comment);
+            }
+        }
+        dst.reserve(line.len() + 1);
+        dst.put_slice(line.as_bytes());
+        dst.put_u8(b'\n');
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escaping_and_special_values() {
+        let mut codec = PromTextCodec::new();
+        let mut buf = BytesMut::from(&b"# HELP msg Line\\none \\\\ two\n\n"[..]);
+        buf.extend_from_slice(b"msg{path=\"C:\\\\a\\\"b\\\"\",} -Inf\n");
+        buf.extend_from_slice(b"# just a comment\nlast NaN");
+
+        let help = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(
+            help,
+            PromRecord::Help {
+                metric: "msg".to_owned(),
+                text: "Line\none \\ two".to_owned(),
+            }
+        );
+        let sample = match codec.decode(&mut buf).unwrap() {
+            Some(PromRecord::Sample(sample)) => sample,
+            record => panic!("unexpected {:?}", record),
+        };
+        assert_eq!(sample.label("path"), Some("C:\\a\"b\""));
+        assert_eq!(sample.value, f64::NEG_INFINITY);
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(PromRecord::Comment("just a comment".to_owned()))
+        );
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        match codec.decode_eof(&mut buf).unwrap() {
+            Some(PromRecord::Sample(last)) => assert!(last.value.is_nan()),
+            record => panic!("unexpected {:?}", record),
+        }
+
+        let mut out = BytesMut::new();
+        codec.encode(PromRecord::Sample(sample), &mut out).unwrap();
+        codec.encode(help, &mut out).unwrap();
+        assert_eq!(
+            &out[..],
+            &b"msg{path=\"C:\\\\a\\\"b\\\"\"} -Inf\n# HELP msg Line\\none \\\\ two\n"[..]
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BigEndian, BufMut, ByteOrder, Bytes, BytesMut};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// Signature starting a version 2 header
+const V2_SIGNATURE: &[u8; 12] = b"\r\n\r\n\x00\r\nQUIT\n";
+/// Longest possible version 1 header, including the CRLF
+const V1_MAX_LEN: usize = 107;
+
+/// A codec for the HAProxy PROXY protocol, decoding the header a load
+/// balancer sends in front of the proxied connection.
+///
+/// The header, in text version 1 or binary version 2, is the first frame.
+/// Everything after it is passed through as `ProxyFrame::Data`. To decode the
+/// rest with another codec, release the `FramedRead` with
+/// `release_with_buffers` after the header and hand the buffered bytes to
+/// `FramedRead::with_initial`.
+///
+/// # Example
+/// ```
+/// use futures::{executor, TryStreamExt};
+/// use futures_codec::{FramedRead, LinesCodec, ProxyFrame, ProxyProtocolCodec};
+///
+/// let buf = b"PROXY TCP4 192.0.2.1 192.0.2.2 56324 443\r\nHello\n";
+/// let mut framed = FramedRead::new(&buf[..], ProxyProtocolCodec::new());
+///
+/// executor::block_on(async move {
+///     let header = match framed.try_next().await.unwrap().unwrap() {
+///         ProxyFrame::Header(header) => header,
+///         ProxyFrame::Data(_) => panic!("no header"),
+///     };
+///     assert_eq!(header.source.unwrap().to_string(), "192.0.2.1:56324");
+///
+///     let (io, _, rest) = framed.release_with_buffers();
+///     let mut framed = FramedRead::with_initial(io, LinesCodec::new(), rest.freeze());
+///     assert_eq!(framed.try_next().await.unwrap().unwrap(), "Hello\n");
+/// })
+/// ```
+#[derive(Debug, Default)]
+pub struct ProxyProtocolCodec {
+    optional: bool,
+    header_done: bool,
+}
+
+/// A frame of `ProxyProtocolCodec`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyFrame {
+    /// The PROXY header
+    Header(ProxyHeader),
+    /// Bytes of the proxied connection
+    Data(Bytes),
+}
+
+/// The connection details of a PROXY header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyHeader {
+    /// Protocol version, 1 or 2
+    pub version: u8,
+    /// Whether the connection is proxied, or a health check of the proxy
+    pub command: ProxyCommand,
+    /// Address of the client, if known
+    pub source: Option<SocketAddr>,
+    /// Address the client connected to, if known
+    pub destination: Option<SocketAddr>,
+    /// Raw TLVs following the addresses of a version 2 header
+    pub tlvs: Bytes,
+}
+
+/// The command of a PROXY header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyCommand {
+    /// A connection made by the proxy itself, without a client
+    Local,
+    /// A proxied connection
+    Proxy,
+}
+
+impl ProxyProtocolCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept connections without a header, passing their bytes through
+    pub fn optional(mut self, optional: bool) -> Self {
+        self.optional = optional;
+        self
+    }
+
+    fn decode_v1(src: &mut BytesMut) -> Result<Option<ProxyHeader>, CodecError> {
+        let end = match src.windows(2).position(|w| w == b"\r\n") {
+            Some(end) => end,
+            None if src.len() >= V1_MAX_LEN => {
+                return Err(CodecError::FrameTooLong { max: V1_MAX_LEN })
+            }
+            None => return Ok(None),
+        };
+        let line = src.split_to(end + 2);
+        let line = std::str::from_utf8(&line[..end])?;
+        let fields: Vec<_> = line.split(' ').collect();
+
+        let (source, destination) = match fields[..] {
+            ["PROXY", "UNKNOWN", ..] => (None, None),
+            ["PROXY", "TCP4", src, dst, sport, dport]
+            | ["PROXY", "TCP6", src, dst, sport, dport] => {
+                let addr = |ip: &str, port: &str| -> Result<SocketAddr, CodecError> {
+                    let ip = ip
+                        .parse::<IpAddr>()
+                        .map_err(|_| invalid("invalid address"))?;
+                    let port = port.parse().map_err(|_| invalid("invalid port"))?;
+                    Ok(SocketAddr::new(ip, port))
+                };
+                (Some(addr(src, sport)?), Some(addr(dst, dport)?))
+            }
+            _ => return Err(invalid("malformed version 1 header")),
+        };
+        Ok(Some(ProxyHeader {
+            version: 1,
+            command: ProxyCommand::Proxy,
+            source,
+            destination,
+            tlvs: Bytes::new(),
+        }))
+    }
+
+    fn decode_v2(src: &mut BytesMut) -> Result<Option<ProxyHeader>, CodecError> {
+        if src.len() < 16 {
+            return Ok(None);
+        }
+        let len = BigEndian::read_u16(&src[14..16]) as usize;
+        if src.len() < 16 + len {
+            return Ok(None);
+        }
+        let command = match src[12] {
+            0x20 => ProxyCommand::Local,
+            0x21 => ProxyCommand::Proxy,
+            _ => return Err(invalid("unsupported version 2 command")),
+        };
+        let family = src[13] >> 4;
+        let mut header = src.split_to(16 + len);
+        header.advance(16);
+
+        let addr_len = match family {
+            0x1 => 12,
+            0x2 => 36,
+            0x3 => 216,
+            _ => 0,
+        };
+        if header.len() < addr_len {
+            return Err(invalid("version 2 addresses exceed the header"));
+        }
+        let addrs = header.split_to(addr_len);
+        let (source, destination) = match family {
+            0x1 => {
+                let ip = |at: usize| {
+                    IpAddr::V4(Ipv4Addr::new(
+                        addrs[at],
+                        addrs[at + 1],
+                        addrs[at + 2],
+                        addrs[at + 3],
+                    ))
+                };
+                let src = SocketAddr::new(ip(0), BigEndian::read_u16(&addrs[8..10]));
+                let dst = SocketAddr::new(ip(4), BigEndian::read_u16(&addrs[10..12]));
+                (Some(src), Some(dst))
+            }
+            0x2 => {
+                let ip = |at: usize| {
+                    let mut octets = [0; 16];
+                    octets.copy_from_slice(&addrs[at..at + 16]);
+                    IpAddr::V6(Ipv6Addr::from(octets))
+                };
+                let src = SocketAddr::new(ip(0), BigEndian::read_u16(&addrs[32..34]));
+                let dst = SocketAddr::new(ip(16), BigEndian::read_u16(&addrs[34..36]));
+                (Some(src), Some(dst))
+            }
+            _ => (None, None),
+        };
+        Ok(Some(ProxyHeader {
+            version: 2,
+            command,
+            source,
+            destination,
+            tlvs: header.freeze(),
+        }))
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+impl Decoder for ProxyProtocolCodec {
+    type Item = ProxyFrame;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if self.header_done {
+            if src.is_empty() {
+                return Ok(None);
+            }
+            return Ok(Some(ProxyFrame::Data(src.take().freeze())));
+        }
+
+        let is_prefix = |signature: &[u8]| {
+            let n = src.len().min(signature.len());
+            src[..n] == signature[..n]
+        };
+        let header = if is_prefix(b"PROXY ") {
+            if src.len() < 6 {
+                return Ok(None);
+            }
+            Self::decode_v1(src)?
+        } else if is_prefix(V2_SIGNATURE) {
+            if src.len() < V2_SIGNATURE.len() {
+                return Ok(None);
+            }
+            Self::decode_v2(src)?
+        } else if self.optional {
+            self.header_done = true;
+            return self.decode(src);
+        } else {
+            return Err(invalid("missing PROXY header"));
+        };
+
+        if header.is_some() {
+            self.header_done = true;
+        }
+        Ok(header.map(ProxyFrame::Header))
+    }
+}
+
+impl Encoder for ProxyProtocolCodec {
+    type Item = ProxyFrame;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let header = match item {
+            ProxyFrame::Data(data) => {
+                dst.extend_from_slice(&data);
+                return Ok(());
+            }
+            ProxyFrame::Header(header) => header,
+        };
+        let addrs = match (header.source, header.destination) {
+            (Some(src), Some(dst)) if src.is_ipv4() == dst.is_ipv4() => Some((src, dst)),
+            (None, None) => None,
+            _ => return Err(invalid("source and destination must be of the same family")),
+        };
+
+        if header.version == 1 {
+            let line = match addrs {
+                Some((src, dst)) => {
+                    let family = if src.is_ipv4() { "TCP4" } else { "TCP6" };
+                    format!(
+                        "PROXY {} {} {} {} {}\r\n",
+                        family,
+                        src.ip(),
+                        dst.ip(),
+                        src.port(),
+                        dst.port()
+                    )
+                }
+                None => "PROXY UNKNOWN\r\n".to_owned(),
+            };
+            dst.extend_from_slice(line.as_bytes());
+            return Ok(());
+        }
+
+        let mut body = BytesMut::with_capacity(36 + header.tlvs.len());
+        let family = match addrs {
+            Some((src, dst)) => {
+                for addr in &[src, dst] {
+                    match addr.ip() {
+                        IpAddr::V4(ip) => body.put_slice(&ip.octets()),
+                        IpAddr::V6(ip) => body.put_slice(&ip.octets()),
+                    }
+                }
+                body.put_u16_be(src.port());
+                body.put_u16_be(dst.port());
+                if src.is_ipv4() {
+                    0x11
+                } else {
+                    0x21
+                }
+            }
+            None => 0x00,
+        };
+        body.extend_from_slice(&header.tlvs);
+        if body.len() > u16::MAX as usize {
+            return Err(CodecError::FrameTooLong {
+                max: u16::MAX as usize,
+            });
+        }
+
+        dst.reserve(16 + body.len());
+        dst.put_slice(V2_SIGNATURE);
+        dst.put_u8(match header.command {
+            ProxyCommand::Local => 0x20,
+            ProxyCommand::Proxy => 0x21,
+        });
+        dst.put_u8(family);
+        dst.put_u16_be(body.len() as u16);
+        dst.put_slice(&body);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn v2_roundtrip() {
+        let header = ProxyHeader {
+            version: 2,
+            command: ProxyCommand::Proxy,
+            source: Some("[2001:db8::1]:56324".parse().unwrap()),
+            destination: Some("[2001:db8::2]:443".parse().unwrap()),
+            tlvs: Bytes::from(&b"\x04\x00\x01x"[..]),
+        };
+        let mut codec = ProxyProtocolCodec::new();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(ProxyFrame::Header(header.clone()), &mut buf)
+            .unwrap();
+        buf.extend_from_slice(b"GET");
+
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(ProxyFrame::Header(header))
+        );
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(ProxyFrame::Data("GET".into()))
+        );
+    }
+
+    #[test]
+    fn missing_header() {
+        let mut buf = BytesMut::from(&b"GET / HTTP/1.1\r\n"[..]);
+        assert!(ProxyProtocolCodec::new().decode(&mut buf).is_err());
+
+        let mut codec = ProxyProtocolCodec::new().optional(true);
+        let frame = codec.decode(&mut buf).unwrap();
+        assert_eq!(frame, Some(ProxyFrame::Data("GET / HTTP/1.1\r\n".into())));
+    }
+}
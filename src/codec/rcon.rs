@@ -0,0 +1,171 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BufMut, ByteOrder, BytesMut, LittleEndian};
+
+/// Size of the smallest packet, with an empty body
+const MIN_SIZE: usize = 10;
+/// Default limit on the size of a packet, as in the Source engine
+const MAX_SIZE: usize = 4096;
+
+/// A codec for the Source RCON protocol, also used by Minecraft servers.
+///
+/// Every packet is a little endian `i32` size, request id and type followed
+/// by a null terminated body and an empty null terminated string.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, Encoder, RconCodec, RconPacket};
+///
+/// let mut codec = RconCodec::new();
+/// let mut buf = BytesMut::new();
+/// let login = RconPacket::new(1, RconPacket::AUTH, "hunter2");
+/// codec.encode(login.clone(), &mut buf).unwrap();
+/// assert_eq!(&buf[..4], &[17, 0, 0, 0]);
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some(login));
+/// ```
+#[derive(Debug)]
+pub struct RconCodec {
+    max_size: usize,
+}
+
+/// A packet of the RCON protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RconPacket {
+    /// Id chosen by the client, echoed in responses, or `-1` for a failed
+    /// login
+    pub id: i32,
+    /// Packet type, see the associated constants
+    pub kind: i32,
+    pub body: String,
+}
+
+impl RconPacket {
+    /// Type of a login request
+    pub const AUTH: i32 = 3;
+    /// Type of a login response
+    pub const AUTH_RESPONSE: i32 = 2;
+    /// Type of a command request
+    pub const EXEC_COMMAND: i32 = 2;
+    /// Type of a command response
+    pub const RESPONSE_VALUE: i32 = 0;
+
+    pub fn new(id: i32, kind: i32, body: &str) -> Self {
+        Self {
+            id,
+            kind,
+            body: body.to_owned(),
+        }
+    }
+}
+
+impl RconCodec {
+    pub fn new() -> Self {
+        Self { max_size: MAX_SIZE }
+    }
+
+    /// Accept packets of up to `max` bytes, for servers sending larger
+    /// responses than the 4096 bytes of the Source engine
+    pub fn max_packet_size(mut self, max: usize) -> Self {
+        self.max_size = max;
+        self
+    }
+}
+
+impl Default for RconCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for RconCodec {
+    type Item = RconPacket;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.len() < 4 {
+            return Ok(None);
+        }
+        let size = LittleEndian::read_i32(&src[..4]);
+        if size < MIN_SIZE as i32 {
+            return Err(CodecError::Protocol("packet size too small".to_owned()));
+        }
+        let size = size as usize;
+        if size > self.max_size {
+            return Err(CodecError::FrameTooLong { max: self.max_size });
+        }
+        if src.len() < 4 + size {
+            return Ok(None);
+        }
+
+        src.advance(4);
+        let packet = src.split_to(size);
+        if packet[size - 2..] != [0, 0] {
+            return Err(CodecError::Protocol(
+                "packet body is not terminated".to_owned(),
+            ));
+        }
+        let body = &packet[8..size - 2];
+        let body = match body.iter().position(|&b| b == 0) {
+            Some(_) => return Err(CodecError::Protocol("null byte in packet body".to_owned())),
+            None => std::str::from_utf8(body)?,
+        };
+        Ok(Some(RconPacket {
+            id: LittleEndian::read_i32(&packet[0..4]),
+            kind: LittleEndian::read_i32(&packet[4..8]),
+            body: body.to_owned(),
+        }))
+    }
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        if src.len() < 4 {
+            return None;
+        }
+        let size = LittleEndian::read_i32(&src[..4]).max(0) as usize;
+        (4 + size).checked_sub(src.len())
+    }
+}
+
+impl Encoder for RconCodec {
+    type Item = RconPacket;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if item.body.contains('\0') {
+            return Err(CodecError::Protocol("null byte in packet body".to_owned()));
+        }
+        let size = MIN_SIZE + item.body.len();
+        if size > self.max_size {
+            return Err(CodecError::FrameTooLong { max: self.max_size });
+        }
+        dst.reserve(4 + size);
+        dst.put_i32_le(size as i32);
+        dst.put_i32_le(item.id);
+        dst.put_i32_le(item.kind);
+        dst.put_slice(item.body.as_bytes());
+        dst.put_slice(&[0, 0]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rejects_oversized_packet() {
+        let mut buf = BytesMut::new();
+        let packet = RconPacket::new(7, RconPacket::RESPONSE_VALUE, &"x".repeat(5000));
+        let err = RconCodec::new()
+            .encode(packet.clone(), &mut buf)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        let mut codec = RconCodec::new().max_packet_size(8192);
+        codec.encode(packet.clone(), &mut buf).unwrap();
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(packet));
+        assert!(RconCodec::new()
+            .decode(&mut BytesMut::from(&[0xff, 0x13, 0, 0][..]))
+            .is_err());
+    }
+}
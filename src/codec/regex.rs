@@ -0,0 +1,153 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{Bytes, BytesMut};
+use regex::bytes::Regex;
+
+/// Default limit on the length of a frame
+const MAX_FRAME: usize = 1024 * 1024;
+/// Default limit on the length of a boundary match
+const LOOKAHEAD: usize = 256;
+
+/// A codec that starts a new frame wherever a regex matches, such as log
+/// records starting with a timestamp, keeping multi-line records like stack
+/// traces in one frame.
+///
+/// Frames run from one match to the next, so they start with the matched
+/// boundary. Bytes before the first match make up a frame of their own. As
+/// the last frame only ends with the input, use `decode_eof` at the end.
+/// When more data arrives, the search resumes the look-ahead window before
+/// the end of the previous search, set it to the longest match the regex
+/// can have. Use `(?m)^` to match at the start of lines. Encoding passes
+/// frames through unchanged.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, RegexDelimiterCodec};
+/// use regex::bytes::Regex;
+///
+/// let start = Regex::new(r"(?m)^\d{4}-\d{2}-\d{2} ").unwrap();
+/// let mut codec = RegexDelimiterCodec::new(start).lookahead(11);
+/// let mut buf = BytesMut::from(&b"2024-01-01 panic\n  at main.rs:1\n2024-01-01 ok\n"[..]);
+///
+/// let record = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(record, Bytes::from("2024-01-01 panic\n  at main.rs:1\n"));
+/// assert_eq!(codec.decode(&mut buf).unwrap(), None);
+/// assert_eq!(codec.decode_eof(&mut buf).unwrap(), Some(Bytes::from("2024-01-01 ok\n")));
+/// ```
+#[derive(Debug)]
+pub struct RegexDelimiterCodec {
+    boundary: Regex,
+    lookahead: usize,
+    max_frame_len: usize,
+    /// Length of the buffer searched so far
+    searched: usize,
+}
+
+impl RegexDelimiterCodec {
+    /// Codec starting a frame at every match of `boundary`
+    pub fn new(boundary: Regex) -> Self {
+        Self {
+            boundary,
+            lookahead: LOOKAHEAD,
+            max_frame_len: MAX_FRAME,
+            searched: 0,
+        }
+    }
+
+    /// Resume searching `len` bytes before the end of the previous search,
+    /// 256 by default
+    pub fn lookahead(mut self, len: usize) -> Self {
+        self.lookahead = len;
+        self
+    }
+
+    /// Fail with `FrameTooLong` on frames longer than `max` bytes
+    pub fn max_frame_len(mut self, max: usize) -> Self {
+        self.max_frame_len = max;
+        self
+    }
+}
+
+impl Decoder for RegexDelimiterCodec {
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        // A match at the start is the boundary of the current frame
+        let from = self.searched.saturating_sub(self.lookahead).max(1);
+        let next = if from <= src.len() {
+            self.boundary.find_at(src, from)
+        } else {
+            None
+        };
+        match next {
+            Some(next) => {
+                self.searched = 0;
+                Ok(Some(src.split_to(next.start()).freeze()))
+            }
+            None if src.len() > self.max_frame_len => Err(CodecError::FrameTooLong {
+                max: self.max_frame_len,
+            }),
+            None => {
+                self.searched = src.len();
+                Ok(None)
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.decode(src)? {
+            Some(frame) => Ok(Some(frame)),
+            None if src.is_empty() => Ok(None),
+            None => {
+                self.searched = 0;
+                Ok(Some(src.take().freeze()))
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.searched = 0;
+    }
+}
+
+impl Encoder for RegexDelimiterCodec {
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.extend_from_slice(&item);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn boundary_split_across_reads() {
+        let boundary = Regex::new(r"(?m)^\[\d+\]").unwrap();
+        let mut codec = RegexDelimiterCodec::new(boundary).lookahead(8);
+        let mut buf = BytesMut::from(&b"preamble\n[1] one\n  more\n[2"[..]);
+
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(Bytes::from("preamble\n"))
+        );
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(b"2] two\n[3] three");
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(Bytes::from("[1] one\n  more\n"))
+        );
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(Bytes::from("[22] two\n"))
+        );
+        assert_eq!(
+            codec.decode_eof(&mut buf).unwrap(),
+            Some(Bytes::from("[3] three"))
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BigEndian, BufMut, ByteOrder, Bytes, BytesMut};
+
+/// Size of the fixed header
+const HEADER_LEN: usize = 12;
+const VERSION: u8 = 2;
+
+/// A codec for RTP packets.
+///
+/// Every call to `decode` takes all of the buffer as one packet, as with
+/// [`DatagramFramed`](crate::DatagramFramed), where one datagram holds one
+/// packet. For RTP interleaved in an RTSP connection, decode the data of
+/// every interleaved frame the same way. Padding is removed when decoding
+/// and not added when encoding.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, Encoder, RtpCodec, RtpPacket};
+///
+/// let mut codec = RtpCodec::new();
+/// let mut packet = RtpPacket::new(96, 1000, 90000, 0x1234_5678, Bytes::from("frame"));
+/// packet.marker = true;
+/// let mut buf = BytesMut::new();
+/// codec.encode(packet.clone(), &mut buf).unwrap();
+/// assert_eq!(&buf[..2], &[0x80, 0x80 | 96]);
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some(packet));
+/// ```
+#[derive(Debug, Default)]
+pub struct RtpCodec;
+
+/// An RTP packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtpPacket {
+    pub marker: bool,
+    pub payload_type: u8,
+    pub sequence_number: u16,
+    pub timestamp: u32,
+    pub ssrc: u32,
+    /// Contributing sources, at most 15
+    pub csrc: Vec<u32>,
+    /// Profile specific id and data of the header extension, the data a
+    /// multiple of four bytes
+    pub extension: Option<(u16, Bytes)>,
+    pub payload: Bytes,
+}
+
+impl RtpPacket {
+    /// A packet without contributing sources or extension
+    pub fn new(
+        payload_type: u8,
+        sequence_number: u16,
+        timestamp: u32,
+        ssrc: u32,
+        payload: Bytes,
+    ) -> Self {
+        Self {
+            marker: false,
+            payload_type,
+            sequence_number,
+            timestamp,
+            ssrc,
+            csrc: Vec::new(),
+            extension: None,
+            payload,
+        }
+    }
+}
+
+impl RtpCodec {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+impl Decoder for RtpCodec {
+    type Item = RtpPacket;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+        let packet = src.take().freeze();
+        if packet.len() < HEADER_LEN {
+            return Err(invalid("RTP packet shorter than its header"));
+        }
+        if packet[0] >> 6 != VERSION {
+            return Err(invalid("unsupported RTP version"));
+        }
+        let padding = packet[0] & 0x20 != 0;
+        let has_extension = packet[0] & 0x10 != 0;
+        let csrc_count = usize::from(packet[0] & 0x0f);
+
+        let mut end = packet.len();
+        if padding {
+            let pad = usize::from(packet[end - 1]);
+            end = end
+                .checked_sub(pad)
+                .filter(|&end| pad > 0 && end >= HEADER_LEN)
+                .ok_or_else(|| invalid("invalid RTP padding"))?;
+        }
+        let mut at = HEADER_LEN + 4 * csrc_count;
+        if at > end {
+            return Err(invalid("RTP packet shorter than its CSRC list"));
+        }
+        let csrc = packet[HEADER_LEN..at]
+            .chunks(4)
+            .map(BigEndian::read_u32)
+            .collect();
+        let extension = if has_extension {
+            if at + 4 > end {
+                return Err(invalid("RTP packet shorter than its extension"));
+            }
+            let profile = BigEndian::read_u16(&packet[at..]);
+            let len = 4 * usize::from(BigEndian::read_u16(&packet[at + 2..]));
+            if at + 4 + len > end {
+                return Err(invalid("RTP packet shorter than its extension"));
+            }
+            let data = packet.slice(at + 4, at + 4 + len);
+            at += 4 + len;
+            Some((profile, data))
+        } else {
+            None
+        };
+
+        Ok(Some(RtpPacket {
+            marker: packet[1] & 0x80 != 0,
+            payload_type: packet[1] & 0x7f,
+            sequence_number: BigEndian::read_u16(&packet[2..4]),
+            timestamp: BigEndian::read_u32(&packet[4..8]),
+            ssrc: BigEndian::read_u32(&packet[8..12]),
+            csrc,
+            extension,
+            payload: packet.slice(at, end),
+        }))
+    }
+}
+
+impl Encoder for RtpCodec {
+    type Item = RtpPacket;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if item.csrc.len() > 15 || item.payload_type > 0x7f {
+            return Err(invalid("too many CSRCs or invalid payload type"));
+        }
+        if let Some((_, data)) = &item.extension {
+            if data.len() % 4 != 0 || data.len() / 4 > u16::MAX as usize {
+                return Err(invalid("invalid RTP header extension length"));
+            }
+        }
+        let extension_len = item
+            .extension
+            .as_ref()
+            .map_or(0, |(_, data)| 4 + data.len());
+        dst.reserve(HEADER_LEN + 4 * item.csrc.len() + extension_len + item.payload.len());
+
+        let extension_bit = if item.extension.is_some() { 0x10 } else { 0 };
+        dst.put_u8(VERSION << 6 | extension_bit | item.csrc.len() as u8);
+        dst.put_u8(u8::from(item.marker) << 7 | item.payload_type);
+        dst.put_u16_be(item.sequence_number);
+        dst.put_u32_be(item.timestamp);
+        dst.put_u32_be(item.ssrc);
+        for csrc in item.csrc {
+            dst.put_u32_be(csrc);
+        }
+        if let Some((profile, data)) = item.extension {
+            dst.put_u16_be(profile);
+            dst.put_u16_be((data.len() / 4) as u16);
+            dst.put_slice(&data);
+        }
+        dst.put_slice(&item.payload);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn csrc_extension_and_padding() {
+        let mut packet = BytesMut::from(&b"\xb2\x60\x00\x07\x00\x00\x00\x09\x00\x00\x00\x01"[..]);
+        // Two CSRCs, a one word extension, payload and three bytes of padding
+        packet.extend_from_slice(b"\x00\x00\x00\x02\x00\x00\x00\x03");
+        packet.extend_from_slice(b"\xbe\xde\x00\x01\x10\xaa\x00\x00");
+        packet.extend_from_slice(b"data\x00\x00\x03");
+
+        let decoded = RtpCodec::new().decode(&mut packet).unwrap().unwrap();
+        assert!(packet.is_empty());
+        assert_eq!(decoded.payload_type, 96);
+        assert_eq!(decoded.sequence_number, 7);
+        assert_eq!(decoded.csrc, [2, 3]);
+        assert_eq!(decoded.extension.as_ref().unwrap().0, 0xbede);
+        assert_eq!(&decoded.payload[..], b"data");
+
+        let mut buf = BytesMut::new();
+        RtpCodec::new().encode(decoded.clone(), &mut buf).unwrap();
+        assert_eq!(buf[0], 0x92);
+        assert_eq!(RtpCodec::new().decode(&mut buf).unwrap(), Some(decoded));
+    }
+}
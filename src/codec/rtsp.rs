@@ -0,0 +1,243 @@
+use super::mime::{find_header_end, MimeHeaders};
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BigEndian, BufMut, ByteOrder, Bytes, BytesMut};
+
+/// Default limit on the length of a message
+const MAX_MESSAGE: usize = 64 * 1024;
+
+/// A codec for RTSP connections, carrying both requests and responses and
+/// RTP and RTCP packets interleaved with them.
+///
+/// An interleaved frame is a `$`, the channel and a big endian `u16` length
+/// followed by the packet. Anything else is a message: its start line and
+/// header fields, ended by an empty line, followed by a body of
+/// `Content-Length` bytes. Encoding sets `Content-Length` for messages with
+/// a body.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, RtspCodec, RtspFrame};
+///
+/// let mut codec = RtspCodec::new();
+/// let mut buf = BytesMut::from(&b"$\x00\x00\x02\x80\x60RTSP/1.0 200 OK\r\nCSeq: 3\r\n\r\n"[..]);
+///
+/// let rtp = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(rtp, RtspFrame::Interleaved { channel: 0, data: Bytes::from(&b"\x80\x60"[..]) });
+/// match codec.decode(&mut buf).unwrap().unwrap() {
+///     RtspFrame::Message(response) => assert_eq!(response.cseq(), Some(3)),
+///     frame => panic!("unexpected {:?}", frame),
+/// }
+/// ```
+#[derive(Debug)]
+pub struct RtspCodec {
+    max_len: usize,
+    /// Offset up to which the buffer holds no header end
+    scan: usize,
+}
+
+/// A frame of an RTSP connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RtspFrame {
+    Message(RtspMessage),
+    /// An RTP or RTCP packet on the channel negotiated by `SETUP`
+    Interleaved {
+        channel: u8,
+        data: Bytes,
+    },
+}
+
+/// An RTSP request or response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtspMessage {
+    /// The request or status line and header fields
+    pub headers: MimeHeaders,
+    pub body: Bytes,
+}
+
+impl RtspMessage {
+    /// The sequence number matching a response to its request
+    pub fn cseq(&self) -> Option<u32> {
+        self.headers.get("CSeq")?.parse().ok()
+    }
+
+    /// The status code of a response
+    pub fn status_code(&self) -> Option<u16> {
+        let mut parts = self.headers.start_line.as_deref()?.split(' ');
+        if !parts.next()?.starts_with("RTSP/") {
+            return None;
+        }
+        parts.next()?.parse().ok()
+    }
+}
+
+impl RtspCodec {
+    pub fn new() -> Self {
+        Self {
+            max_len: MAX_MESSAGE,
+            scan: 0,
+        }
+    }
+
+    /// Fail on messages longer than `max` bytes
+    pub fn max_message_len(mut self, max: usize) -> Self {
+        self.max_len = max;
+        self
+    }
+}
+
+impl Default for RtspCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for RtspCodec {
+    type Item = RtspFrame;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.first() == Some(&b'$') {
+            if src.len() < 4 {
+                return Ok(None);
+            }
+            let len = BigEndian::read_u16(&src[2..4]) as usize;
+            if src.len() < 4 + len {
+                return Ok(None);
+            }
+            let channel = src[1];
+            src.advance(4);
+            let data = src.split_to(len).freeze();
+            return Ok(Some(RtspFrame::Interleaved { channel, data }));
+        }
+
+        let (len, end) = match find_header_end(src, self.scan) {
+            Some(end) => end,
+            None if src.len() > self.max_len => {
+                return Err(CodecError::FrameTooLong { max: self.max_len })
+            }
+            None => {
+                self.scan = src.len();
+                return Ok(None);
+            }
+        };
+        let headers = MimeHeaders::parse(&src[..len], true)?;
+        let body_len = headers.content_length(None)?.unwrap_or(0);
+        if body_len > self.max_len.saturating_sub(end) as u64 {
+            return Err(CodecError::FrameTooLong { max: self.max_len });
+        }
+        let body_len = body_len as usize;
+        if src.len() < end + body_len {
+            return Ok(None);
+        }
+
+        self.scan = 0;
+        src.advance(end);
+        let body = src.split_to(body_len).freeze();
+        Ok(Some(RtspFrame::Message(RtspMessage { headers, body })))
+    }
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        if src.len() < 4 || src[0] != b'$' {
+            return None;
+        }
+        let len = BigEndian::read_u16(&src[2..4]) as usize;
+        (4 + len).checked_sub(src.len())
+    }
+
+    fn reset(&mut self) {
+        self.scan = 0;
+    }
+}
+
+impl Encoder for RtspCodec {
+    type Item = RtspFrame;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        match item {
+            RtspFrame::Interleaved { channel, data } => {
+                if data.len() > u16::MAX as usize {
+                    return Err(CodecError::FrameTooLong {
+                        max: u16::MAX as usize,
+                    });
+                }
+                dst.reserve(4 + data.len());
+                dst.put_slice(&[b'$', channel]);
+                dst.put_u16_be(data.len() as u16);
+                dst.put_slice(&data);
+            }
+            RtspFrame::Message(message) => {
+                if message.headers.start_line.is_none() {
+                    return Err(CodecError::Protocol(
+                        "RTSP message without start line".to_owned(),
+                    ));
+                }
+                let is_length = |name: &str| name.eq_ignore_ascii_case("Content-Length");
+                message.headers.write(dst, is_length)?;
+                if !message.body.is_empty() {
+                    let length = format!("Content-Length: {}\r\n", message.body.len());
+                    dst.extend_from_slice(length.as_bytes());
+                }
+                dst.extend_from_slice(b"\r\n");
+                dst.extend_from_slice(&message.body);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn interleaved_between_split_message() {
+        let describe = RtspMessage {
+            headers: MimeHeaders {
+                start_line: Some("RTSP/1.0 200 OK".to_owned()),
+                fields: vec![("CSeq".to_owned(), "2".to_owned())],
+            },
+            body: Bytes::from("v=0\r\n"),
+        };
+        let rtcp = RtspFrame::Interleaved {
+            channel: 1,
+            data: Bytes::from(&b"\x81\xc9\x00\x01"[..]),
+        };
+        let mut codec = RtspCodec::new();
+        let mut frames = BytesMut::new();
+        codec.encode(rtcp.clone(), &mut frames).unwrap();
+        codec
+            .encode(RtspFrame::Message(describe.clone()), &mut frames)
+            .unwrap();
+        assert!(frames.ends_with(b"CSeq: 2\r\nContent-Length: 5\r\n\r\nv=0\r\n"));
+
+        let mut buf = BytesMut::from(&frames[..6]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(codec.bytes_needed(&buf), Some(2));
+        buf.extend_from_slice(&frames[6..frames.len() - 2]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(rtcp));
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(&frames[frames.len() - 2..]);
+        match codec.decode(&mut buf).unwrap() {
+            Some(RtspFrame::Message(message)) => {
+                assert_eq!(message.headers.get("content-length"), Some("5"));
+                assert_eq!(message.cseq(), Some(2));
+                assert_eq!(message.body, describe.body);
+            }
+            frame => panic!("unexpected {:?}", frame),
+        }
+    }
+
+    #[test]
+    fn rejects_overflowing_content_length() {
+        let mut codec = RtspCodec::new();
+        let mut buf = BytesMut::from(
+            &b"RTSP/1.0 200 OK\r\nContent-Length: 18446744073709551615\r\n\r\n"[..],
+        );
+        match codec.decode(&mut buf) {
+            Err(CodecError::FrameTooLong { .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BigEndian, BufMut, ByteOrder, Bytes, BytesMut};
+
+const PREAMBLE: &[u8; 16] = b"\x00\x10\x00\x00ASC-E1.17\x00\x00\x00";
+/// Size of the root layer
+const ROOT_LEN: usize = 38;
+/// Size of all layers up to the DMX start code
+const DATA_HEADER_LEN: usize = 125;
+const SOURCE_NAME_LEN: usize = 64;
+const MAX_SLOTS: usize = 512;
+
+const VECTOR_ROOT_DATA: u32 = 0x0000_0004;
+const VECTOR_FRAMING_DATA: u32 = 0x0000_0002;
+const VECTOR_DMP_SET_PROPERTY: u8 = 0x02;
+
+/// A codec for sACN (ANSI E1.31, Streaming ACN) packets.
+///
+/// Every packet starts with the ACN root layer, holding a vector that tells
+/// what follows and the CID of the sender. Data packets, carrying the DMX
+/// slots of a universe in their framing and DMP layers, are decoded into
+/// their fields. Synchronization and discovery packets are yielded with the
+/// root layer vector and everything after the root layer. Like
+/// [`RtpCodec`](crate::RtpCodec), every call to `decode` takes all of the
+/// buffer as one packet, as with [`DatagramFramed`](crate::DatagramFramed).
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, Encoder, SacnCodec, SacnData, SacnPacket};
+///
+/// let mut codec = SacnCodec::new();
+/// let mut buf = BytesMut::new();
+/// let data = SacnData::new([7; 16], "console", 1, Bytes::from(vec![255; 4]));
+/// codec.encode(SacnPacket::Data(data.clone()), &mut buf).unwrap();
+/// assert_eq!(buf.len(), 126 + 4);
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some(SacnPacket::Data(data)));
+/// ```
+#[derive(Debug, Default)]
+pub struct SacnCodec;
+
+/// An sACN packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SacnPacket {
+    /// DMX data for a universe
+    Data(SacnData),
+    /// Any other packet, its data everything after the root layer
+    Other {
+        vector: u32,
+        cid: [u8; 16],
+        data: Bytes,
+    },
+}
+
+impl SacnPacket {
+    pub const VECTOR_ROOT_DATA: u32 = VECTOR_ROOT_DATA;
+    pub const VECTOR_ROOT_EXTENDED: u32 = 0x0000_0008;
+}
+
+/// The fields of an sACN data packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SacnData {
+    /// Component identifier, a UUID of the sender
+    pub cid: [u8; 16],
+    /// Name of the sender, at most 63 bytes
+    pub source_name: String,
+    pub priority: u8,
+    /// Universe of the synchronization packets to wait for, 0 for none
+    pub sync_address: u16,
+    pub sequence: u8,
+    pub options: u8,
+    pub universe: u16,
+    /// DMX start code, 0 for slot levels
+    pub start_code: u8,
+    /// Up to 512 slots
+    pub data: Bytes,
+}
+
+impl SacnData {
+    /// Slot levels of `universe` with the default priority of 100
+    pub fn new(cid: [u8; 16], source_name: &str, universe: u16, data: Bytes) -> Self {
+        Self {
+            cid,
+            source_name: source_name.to_owned(),
+            priority: 100,
+            sync_address: 0,
+            sequence: 0,
+            options: 0,
+            universe,
+            start_code: 0,
+            data,
+        }
+    }
+}
+
+impl SacnCodec {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+/// Write flags and length of a PDU that runs until the end of the packet
+fn put_flags_and_length(dst: &mut BytesMut, len: usize) {
+    dst.put_u16_be(0x7000 | len as u16);
+}
+
+fn decode_data(cid: [u8; 16], packet: &[u8]) -> Result<SacnData, CodecError> {
+    if packet.len() < DATA_HEADER_LEN + 1
+        || BigEndian::read_u32(&packet[40..44]) != VECTOR_FRAMING_DATA
+        || packet[117] != VECTOR_DMP_SET_PROPERTY
+    {
+        return Err(invalid("invalid sACN data packet"));
+    }
+    let count = usize::from(BigEndian::read_u16(&packet[123..125]));
+    if count == 0 || count > MAX_SLOTS + 1 || packet.len() < DATA_HEADER_LEN + count {
+        return Err(invalid("invalid sACN property value count"));
+    }
+    let name = &packet[44..44 + SOURCE_NAME_LEN];
+    let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+    Ok(SacnData {
+        cid,
+        source_name: std::str::from_utf8(name)?.to_owned(),
+        priority: packet[108],
+        sync_address: BigEndian::read_u16(&packet[109..111]),
+        sequence: packet[111],
+        options: packet[112],
+        universe: BigEndian::read_u16(&packet[113..115]),
+        start_code: packet[125],
+        data: Bytes::from(&packet[DATA_HEADER_LEN + 1..DATA_HEADER_LEN + count]),
+    })
+}
+
+impl Decoder for SacnCodec {
+    type Item = SacnPacket;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+        let packet = src.take().freeze();
+        if packet.len() < ROOT_LEN || &packet[..16] != PREAMBLE {
+            return Err(invalid("not an sACN packet"));
+        }
+        let vector = BigEndian::read_u32(&packet[18..22]);
+        let mut cid = [0; 16];
+        cid.copy_from_slice(&packet[22..ROOT_LEN]);
+        let packet = if vector == VECTOR_ROOT_DATA {
+            SacnPacket::Data(decode_data(cid, &packet)?)
+        } else {
+            SacnPacket::Other {
+                vector,
+                cid,
+                data: packet.slice_from(ROOT_LEN),
+            }
+        };
+        Ok(Some(packet))
+    }
+}
+
+impl Encoder for SacnCodec {
+    type Item = SacnPacket;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        match item {
+            SacnPacket::Data(data) => {
+                if data.data.len() > MAX_SLOTS {
+                    return Err(CodecError::FrameTooLong { max: MAX_SLOTS });
+                }
+                if data.source_name.len() >= SOURCE_NAME_LEN {
+                    return Err(invalid("sACN source name too long"));
+                }
+                let len = DATA_HEADER_LEN + 1 + data.data.len();
+                dst.reserve(len);
+                dst.put_slice(PREAMBLE);
+                put_flags_and_length(dst, len - 16);
+                dst.put_u32_be(VECTOR_ROOT_DATA);
+                dst.put_slice(&data.cid);
+
+                put_flags_and_length(dst, len - ROOT_LEN);
+                dst.put_u32_be(VECTOR_FRAMING_DATA);
+                dst.put_slice(data.source_name.as_bytes());
+                dst.put_slice(&[0; SOURCE_NAME_LEN][data.source_name.len()..]);
+                dst.put_u8(data.priority);
+                dst.put_u16_be(data.sync_address);
+                dst.put_slice(&[data.sequence, data.options]);
+                dst.put_u16_be(data.universe);
+
+                put_flags_and_length(dst, len - 115);
+                // Set property, address and data type, first address and
+                // increment
+                dst.put_slice(&[VECTOR_DMP_SET_PROPERTY, 0xa1, 0, 0, 0, 1]);
+                dst.put_u16_be(1 + data.data.len() as u16);
+                dst.put_u8(data.start_code);
+                dst.put_slice(&data.data);
+            }
+            SacnPacket::Other { vector, cid, data } => {
+                dst.reserve(ROOT_LEN + data.len());
+                dst.put_slice(PREAMBLE);
+                put_flags_and_length(dst, ROOT_LEN - 16 + data.len());
+                dst.put_u32_be(vector);
+                dst.put_slice(&cid);
+                dst.put_slice(&data);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn layer_lengths() {
+        let data = SacnData::new([1; 16], "desk", 7, Bytes::from(vec![128; 512]));
+        let mut buf = BytesMut::new();
+        SacnCodec::new()
+            .encode(SacnPacket::Data(data), &mut buf)
+            .unwrap();
+        assert_eq!(buf.len(), 638);
+        assert_eq!(&buf[16..18], &[0x72, 0x6e]);
+        assert_eq!(&buf[38..40], &[0x72, 0x58]);
+        assert_eq!(&buf[115..117], &[0x72, 0x0b]);
+        assert_eq!(&buf[123..126], &[0x02, 0x01, 0x00]);
+
+        buf.truncate(200);
+        assert!(SacnCodec::new().decode(&mut buf).is_err());
+    }
+}
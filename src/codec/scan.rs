@@ -0,0 +1,102 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{Bytes, BytesMut};
+
+/// A codec built from a closure that finds the next frame in the buffer.
+///
+/// The closure is given the buffered bytes and returns the start and end of
+/// the next frame, or `None` if the buffer doesn't hold a complete frame
+/// yet. The frame is split off the buffer, dropping the bytes before its
+/// start, so skipping garbage or delimiters only needs the right offsets.
+/// Encoding passes frames through unchanged.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, ScanCodec};
+///
+/// // Frames between STX and ETX
+/// let mut codec = ScanCodec::new(|buf: &[u8]| {
+///     let start = buf.iter().position(|&b| b == 0x02)? + 1;
+///     let end = start + buf[start..].iter().position(|&b| b == 0x03)?;
+///     Some((start, end))
+/// });
+/// let mut buf = BytesMut::from(&b"noise\x02hello\x03\x02wor"[..]);
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some(Bytes::from("hello")));
+/// assert_eq!(codec.decode(&mut buf).unwrap(), None);
+/// ```
+pub struct ScanCodec<F> {
+    scan: F,
+}
+
+impl<F> ScanCodec<F>
+where
+    F: FnMut(&[u8]) -> Option<(usize, usize)>,
+{
+    /// Split frames at the offsets returned by `scan`
+    pub fn new(scan: F) -> Self {
+        Self { scan }
+    }
+}
+
+impl<F> Decoder for ScanCodec<F>
+where
+    F: FnMut(&[u8]) -> Option<(usize, usize)>,
+{
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let (start, end) = match (self.scan)(src) {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+        if start > end || end > src.len() {
+            return Err(CodecError::Protocol(format!(
+                "scanned frame {}..{} outside of the buffer of {} bytes",
+                start,
+                end,
+                src.len()
+            )));
+        }
+        let mut frame = src.split_to(end);
+        frame.advance(start);
+        Ok(Some(frame.freeze()))
+    }
+}
+
+impl<F> Encoder for ScanCodec<F> {
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.extend_from_slice(&item);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn length_prefix_closure() {
+        let mut codec = ScanCodec::new(|buf: &[u8]| {
+            let len = usize::from(*buf.first()?);
+            if buf.len() > len {
+                Some((1, 1 + len))
+            } else {
+                None
+            }
+        });
+        let mut buf = BytesMut::from(&b"\x02hi\x00\x03ye"[..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(Bytes::from("hi")));
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(Bytes::new()));
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(b"s");
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(Bytes::from("yes")));
+
+        let mut broken = ScanCodec::new(|_: &[u8]| Some((0, 10)));
+        assert!(broken.decode(&mut buf).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BigEndian, BufMut, ByteOrder, Bytes, BytesMut};
+
+/// Size of the header in front of every packet
+const HEADER_LEN: usize = 4;
+/// Size of the gateway identifier that follows it in some packets
+const EUI_LEN: usize = 8;
+
+/// A codec for the UDP protocol between the Semtech LoRa packet forwarder
+/// and a network server.
+///
+/// Every packet starts with the protocol version, a random token matching
+/// acknowledgements to their packets and the packet identifier. Packets
+/// sent by the gateway follow it with the 8 byte EUI of the gateway, which
+/// together make up the 12 byte header, and most packets end in a JSON
+/// object. The JSON is not parsed, it is left as it is in
+/// [`SemtechPacket::json`].
+///
+/// Like [`RtpCodec`](crate::RtpCodec), every call to `decode` takes all of
+/// the buffer as one packet, as with [`DatagramFramed`](crate::DatagramFramed).
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, Encoder, SemtechPacket, SemtechUdpCodec};
+///
+/// let mut codec = SemtechUdpCodec::new();
+/// let mut buf = BytesMut::new();
+/// let push = SemtechPacket::new(SemtechPacket::PUSH_DATA, 0x1234)
+///     .gateway(0xaa55_5a00_0000_0101)
+///     .json(Bytes::from(r#"{"stat":{"rxnb":0}}"#));
+/// codec.encode(push.clone(), &mut buf).unwrap();
+/// assert_eq!(&buf[..4], &[2, 0x12, 0x34, 0]);
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some(push));
+/// ```
+#[derive(Debug, Default)]
+pub struct SemtechUdpCodec;
+
+/// A packet of the Semtech packet forwarder protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemtechPacket {
+    /// Protocol version, 1 or 2
+    pub version: u8,
+    pub token: u16,
+    /// Packet identifier, see the associated constants
+    pub identifier: u8,
+    /// EUI of the gateway, sent in the packets from the gateway
+    pub gateway_eui: Option<u64>,
+    /// JSON object, empty in packets without one
+    pub json: Bytes,
+}
+
+impl SemtechPacket {
+    pub const PUSH_DATA: u8 = 0x00;
+    pub const PUSH_ACK: u8 = 0x01;
+    pub const PULL_DATA: u8 = 0x02;
+    pub const PULL_RESP: u8 = 0x03;
+    pub const PULL_ACK: u8 = 0x04;
+    pub const TX_ACK: u8 = 0x05;
+
+    /// A packet of version 2, without gateway EUI or JSON
+    pub fn new(identifier: u8, token: u16) -> Self {
+        Self {
+            version: 2,
+            token,
+            identifier,
+            gateway_eui: None,
+            json: Bytes::new(),
+        }
+    }
+
+    /// Set the EUI of the gateway
+    pub fn gateway(mut self, eui: u64) -> Self {
+        self.gateway_eui = Some(eui);
+        self
+    }
+
+    /// Set the JSON object
+    pub fn json(mut self, json: Bytes) -> Self {
+        self.json = json;
+        self
+    }
+
+    /// Whether packets with `identifier` carry the gateway EUI in `version`
+    fn has_eui(version: u8, identifier: u8) -> bool {
+        match identifier {
+            Self::PUSH_DATA | Self::PULL_DATA | Self::TX_ACK => true,
+            Self::PULL_ACK => version >= 2,
+            _ => false,
+        }
+    }
+}
+
+impl SemtechUdpCodec {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+impl Decoder for SemtechUdpCodec {
+    type Item = SemtechPacket;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+        let mut packet = src.take().freeze();
+        if packet.len() < HEADER_LEN {
+            return Err(invalid("packet shorter than its header"));
+        }
+        let (version, identifier) = (packet[0], packet[3]);
+        if version != 1 && version != 2 {
+            return Err(invalid("unsupported packet forwarder protocol version"));
+        }
+        if identifier > SemtechPacket::TX_ACK {
+            return Err(invalid("unknown packet identifier"));
+        }
+        let token = BigEndian::read_u16(&packet[1..3]);
+        packet.advance(HEADER_LEN);
+        let gateway_eui = if SemtechPacket::has_eui(version, identifier) {
+            if packet.len() < EUI_LEN {
+                return Err(invalid("packet shorter than its gateway EUI"));
+            }
+            Some(BigEndian::read_u64(&packet.split_to(EUI_LEN)))
+        } else {
+            None
+        };
+        Ok(Some(SemtechPacket {
+            version,
+            token,
+            identifier,
+            gateway_eui,
+            json: packet,
+        }))
+    }
+}
+
+impl Encoder for SemtechUdpCodec {
+    type Item = SemtechPacket;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if SemtechPacket::has_eui(item.version, item.identifier) != item.gateway_eui.is_some() {
+            return Err(invalid("gateway EUI missing or not allowed in packet"));
+        }
+        dst.reserve(HEADER_LEN + EUI_LEN + item.json.len());
+        dst.put_u8(item.version);
+        dst.put_u16_be(item.token);
+        dst.put_u8(item.identifier);
+        if let Some(eui) = item.gateway_eui {
+            dst.put_u64_be(eui);
+        }
+        dst.put_slice(&item.json);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn eui_depends_on_identifier() {
+        let mut codec = SemtechUdpCodec::new();
+        let mut ack = BytesMut::from(&b"\x02\xab\xcd\x01"[..]);
+        let ack = codec.decode(&mut ack).unwrap().unwrap();
+        assert_eq!(ack, SemtechPacket::new(SemtechPacket::PUSH_ACK, 0xabcd));
+
+        let mut pull_ack = BytesMut::from(&b"\x01\x00\x01\x04"[..]);
+        assert!(codec.decode(&mut pull_ack).unwrap().is_some());
+        let mut pull_ack = BytesMut::from(&b"\x02\x00\x01\x04"[..]);
+        assert!(codec.decode(&mut pull_ack).is_err());
+
+        let pull = SemtechPacket::new(SemtechPacket::PULL_DATA, 1);
+        assert!(codec.encode(pull, &mut BytesMut::new()).is_err());
+    }
+}
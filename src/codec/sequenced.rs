@@ -0,0 +1,192 @@
+use crate::{Decoder, Encoder};
+use bytes::{BigEndian, BufMut, ByteOrder, BytesMut};
+use std::{error, fmt, io};
+
+/// A codec wrapper that prepends a big endian sequence number to every frame
+/// of an inner codec, and checks on decode that no frames went missing.
+///
+/// Sequence numbers start at zero and are either 4 or 8 bytes wide.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, Encoder, LinesCodec, SequenceError, SequencedCodec};
+///
+/// let mut codec = SequencedCodec::new_u32(LinesCodec::new());
+/// let mut buf = BytesMut::new();
+/// codec.encode("Hello\n".to_owned(), &mut buf).unwrap();
+/// codec.encode("World\n".to_owned(), &mut buf).unwrap();
+///
+/// // Drop the first frame
+/// buf.advance(4 + 6);
+///
+/// match codec.decode(&mut buf) {
+///     Err(SequenceError::Gap { expected: 0, received: 1 }) => {}
+///     _ => panic!("gap not detected"),
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SequencedCodec<C> {
+    inner: C,
+    width: usize,
+    next_out: u64,
+    next_in: u64,
+    /// Sequence number of a frame whose body has not been decoded yet
+    pending: Option<u64>,
+}
+
+impl<C> SequencedCodec<C> {
+    /// Wrap `inner` with 4 byte sequence numbers
+    pub fn new_u32(inner: C) -> Self {
+        Self::with_width(inner, 4)
+    }
+
+    /// Wrap `inner` with 8 byte sequence numbers
+    pub fn new_u64(inner: C) -> Self {
+        Self::with_width(inner, 8)
+    }
+
+    fn with_width(inner: C, width: usize) -> Self {
+        Self {
+            inner,
+            width,
+            next_out: 0,
+            next_in: 0,
+            pending: None,
+        }
+    }
+
+    /// Release the inner codec
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    fn next(&self, seq: u64) -> u64 {
+        if self.width == 4 {
+            u64::from((seq as u32).wrapping_add(1))
+        } else {
+            seq.wrapping_add(1)
+        }
+    }
+}
+
+impl<C: Encoder> Encoder for SequencedCodec<C> {
+    type Item = C::Item;
+    type Error = SequenceError<C::Error>;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.reserve(self.width);
+        if self.width == 4 {
+            dst.put_u32_be(self.next_out as u32);
+        } else {
+            dst.put_u64_be(self.next_out);
+        }
+        self.next_out = self.next(self.next_out);
+        self.inner.encode(item, dst).map_err(SequenceError::Codec)
+    }
+}
+
+impl<C: Decoder> Decoder for SequencedCodec<C> {
+    type Item = C::Item;
+    type Error = SequenceError<C::Error>;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if self.pending.is_none() {
+            if src.len() < self.width {
+                return Ok(None);
+            }
+            let header = src.split_to(self.width);
+            self.pending = Some(if self.width == 4 {
+                u64::from(BigEndian::read_u32(&header))
+            } else {
+                BigEndian::read_u64(&header)
+            });
+        }
+
+        let item = match self.inner.decode(src).map_err(SequenceError::Codec)? {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+
+        let received = self.pending.take().expect("sequence number");
+        let expected = self.next_in;
+        self.next_in = self.next(received);
+
+        if received != expected {
+            return Err(SequenceError::Gap { expected, received });
+        }
+        Ok(Some(item))
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset()
+    }
+}
+
+/// Errors of a `SequencedCodec`.
+#[derive(Debug)]
+pub enum SequenceError<E> {
+    /// A frame arrived out of sequence, decoding continues after it.
+    Gap { expected: u64, received: u64 },
+    /// The inner codec failed.
+    Codec(E),
+}
+
+impl<E: From<io::Error>> From<io::Error> for SequenceError<E> {
+    fn from(e: io::Error) -> Self {
+        SequenceError::Codec(e.into())
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for SequenceError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SequenceError::Gap { expected, received } => {
+                write!(f, "expected frame {} but received {}", expected, received)
+            }
+            SequenceError::Codec(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E: error::Error + 'static> error::Error for SequenceError<E> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SequenceError::Gap { .. } => None,
+            SequenceError::Codec(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LinesCodec;
+
+    #[test]
+    fn partial_frame_keeps_sequence() {
+        let mut codec = SequencedCodec::new_u64(LinesCodec::new());
+        let mut buf = BytesMut::new();
+        codec.encode("Hello\n".to_owned(), &mut buf).unwrap();
+        codec.encode("World\n".to_owned(), &mut buf).unwrap();
+
+        let mut partial = buf.split_to(8 + 3);
+        assert!(codec.decode(&mut partial).unwrap().is_none());
+        partial.extend_from_slice(&buf);
+
+        assert_eq!(codec.decode(&mut partial).unwrap().unwrap(), "Hello\n");
+        assert_eq!(codec.decode(&mut partial).unwrap().unwrap(), "World\n");
+    }
+
+    #[test]
+    fn resumes_after_gap() {
+        let mut codec = SequencedCodec::new_u32(LinesCodec::new());
+        let mut buf = BytesMut::from(&b"\0\0\0\x05Hello\n\0\0\0\x06World\n"[..]);
+
+        match codec.decode(&mut buf) {
+            Err(SequenceError::Gap { expected: 0, received: 5 }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "World\n");
+    }
+}
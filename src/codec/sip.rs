@@ -0,0 +1,238 @@
+use super::mime::{find_header_end, MimeHeaders};
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{Bytes, BytesMut};
+
+/// Default limit on the length of a message
+const MAX_MESSAGE: usize = 64 * 1024;
+
+/// Compact forms of SIP header names, RFC 3261 section 7.3.3 and later
+const COMPACT: &[(&str, &str)] = &[
+    ("a", "Accept-Contact"),
+    ("b", "Referred-By"),
+    ("c", "Content-Type"),
+    ("d", "Request-Disposition"),
+    ("e", "Content-Encoding"),
+    ("f", "From"),
+    ("i", "Call-ID"),
+    ("j", "Reject-Contact"),
+    ("k", "Supported"),
+    ("l", "Content-Length"),
+    ("m", "Contact"),
+    ("o", "Event"),
+    ("r", "Refer-To"),
+    ("s", "Subject"),
+    ("t", "To"),
+    ("u", "Allow-Events"),
+    ("v", "Via"),
+    ("x", "Session-Expires"),
+    ("y", "Identity"),
+];
+
+/// A codec for SIP requests and responses on stream transports.
+///
+/// A message is its start line and header fields, ended by an empty line,
+/// followed by a body of `Content-Length` bytes, or of none when the field
+/// is missing. Compact header names, such as `l` for `Content-Length`, are
+/// understood. Empty lines between messages, which RFC 5626 uses as
+/// keep-alives, are skipped. Encoding sets `Content-Length` to the length of
+/// the body.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, SipCodec};
+///
+/// let mut codec = SipCodec::new();
+/// let mut buf = BytesMut::from(&b"\r\n\r\nSIP/2.0 200 OK\r\nv: SIP/2.0/TCP host\r\nl: 2\r\n\r\nhi"[..]);
+///
+/// let message = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(message.status_code(), Some(200));
+/// assert_eq!(message.header("Via"), Some("SIP/2.0/TCP host"));
+/// assert_eq!(&message.body[..], b"hi");
+/// ```
+#[derive(Debug)]
+pub struct SipCodec {
+    max_len: usize,
+    /// Offset up to which the buffer holds no header end
+    scan: usize,
+}
+
+/// A SIP request or response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SipMessage {
+    /// The request or status line and header fields
+    pub headers: MimeHeaders,
+    pub body: Bytes,
+}
+
+impl SipMessage {
+    fn start_line(&self) -> &str {
+        self.headers.start_line.as_deref().unwrap_or_default()
+    }
+
+    /// The method of a request
+    pub fn method(&self) -> Option<&str> {
+        if self.start_line().starts_with("SIP/") {
+            return None;
+        }
+        self.start_line().split(' ').next()
+    }
+
+    /// The status code of a response
+    pub fn status_code(&self) -> Option<u16> {
+        let mut parts = self.start_line().split(' ');
+        if !parts.next()?.starts_with("SIP/") {
+            return None;
+        }
+        parts.next()?.parse().ok()
+    }
+
+    /// The value of the first header field `name`, in its full or compact
+    /// form
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let alias = COMPACT.iter().find_map(|&(compact, full)| {
+            if full.eq_ignore_ascii_case(name) {
+                Some(compact)
+            } else if compact.eq_ignore_ascii_case(name) {
+                Some(full)
+            } else {
+                None
+            }
+        });
+        self.headers
+            .fields
+            .iter()
+            .find(|(field, _)| {
+                field.eq_ignore_ascii_case(name)
+                    || alias.is_some_and(|alias| field.eq_ignore_ascii_case(alias))
+            })
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl SipCodec {
+    pub fn new() -> Self {
+        Self {
+            max_len: MAX_MESSAGE,
+            scan: 0,
+        }
+    }
+
+    /// Fail on messages longer than `max` bytes
+    pub fn max_message_len(mut self, max: usize) -> Self {
+        self.max_len = max;
+        self
+    }
+}
+
+impl Default for SipCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for SipCodec {
+    type Item = SipMessage;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        while src.starts_with(b"\r\n") {
+            src.advance(2);
+            self.scan = 0;
+        }
+        let (len, end) = match find_header_end(src, self.scan) {
+            Some(end) => end,
+            None if src.len() > self.max_len => {
+                return Err(CodecError::FrameTooLong { max: self.max_len })
+            }
+            None => {
+                self.scan = src.len();
+                return Ok(None);
+            }
+        };
+        let headers = MimeHeaders::parse(&src[..len], true)?;
+        let body_len = headers.content_length(Some("l"))?.unwrap_or(0);
+        if body_len > self.max_len.saturating_sub(end) as u64 {
+            return Err(CodecError::FrameTooLong { max: self.max_len });
+        }
+        let body_len = body_len as usize;
+        if src.len() < end + body_len {
+            return Ok(None);
+        }
+
+        self.scan = 0;
+        src.advance(end);
+        Ok(Some(SipMessage {
+            headers,
+            body: src.split_to(body_len).freeze(),
+        }))
+    }
+
+    fn reset(&mut self) {
+        self.scan = 0;
+    }
+}
+
+impl Encoder for SipCodec {
+    type Item = SipMessage;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if item.headers.start_line.is_none() {
+            return Err(CodecError::Protocol(
+                "SIP message without start line".to_owned(),
+            ));
+        }
+        let is_length = |name: &str| {
+            name.eq_ignore_ascii_case("Content-Length") || name.eq_ignore_ascii_case("l")
+        };
+        item.headers.write(dst, is_length)?;
+        dst.extend_from_slice(format!("Content-Length: {}\r\n\r\n", item.body.len()).as_bytes());
+        dst.extend_from_slice(&item.body);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn replaces_content_length_on_encode() {
+        let headers = MimeHeaders {
+            start_line: Some("MESSAGE sip:bob@example.com SIP/2.0".to_owned()),
+            fields: vec![
+                ("i".to_owned(), "a84b4c76e66710".to_owned()),
+                ("l".to_owned(), "99".to_owned()),
+            ],
+        };
+        let message = SipMessage {
+            headers,
+            body: Bytes::from("Hello Bob"),
+        };
+
+        let mut codec = SipCodec::new();
+        let mut buf = BytesMut::new();
+        codec.encode(message.clone(), &mut buf).unwrap();
+        assert!(buf.ends_with(b"i: a84b4c76e66710\r\nContent-Length: 9\r\n\r\nHello Bob"));
+
+        let mut partial = BytesMut::from(&buf[..buf.len() - 1]);
+        assert_eq!(codec.decode(&mut partial).unwrap(), None);
+        let decoded = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(decoded.method(), Some("MESSAGE"));
+        assert_eq!(decoded.header("call-id"), Some("a84b4c76e66710"));
+        assert_eq!(decoded.body, message.body);
+    }
+
+    #[test]
+    fn rejects_overflowing_content_length() {
+        let mut codec = SipCodec::new();
+        let mut buf = BytesMut::from(
+            &b"SIP/2.0 200 OK\r\nContent-Length: 18446744073709551615\r\n\r\n"[..],
+        );
+        match codec.decode(&mut buf) {
+            Err(CodecError::FrameTooLong { max }) => assert_eq!(max, MAX_MESSAGE),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
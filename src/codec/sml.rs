@@ -0,0 +1,218 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BufMut, Bytes, BytesMut};
+
+const ESCAPE: [u8; 4] = [0x1b; 4];
+const VERSION_1: [u8; 4] = [0x01; 4];
+const END: u8 = 0x1a;
+/// Default limit on the size of an SML file, escaped
+const MAX_FILE: usize = 64 * 1024;
+
+/// A codec for the SML (Smart Message Language) transport of electricity
+/// meters, yielding the content of every SML file.
+///
+/// A file starts with an escape sequence of four `0x1b` bytes and four
+/// `0x01` bytes. It ends with the escape sequence, `0x1a`, the number of
+/// fill bytes that pad the content to a multiple of four bytes, and a
+/// CRC-16/X-25 over the whole file, least significant byte first. Escape
+/// sequences in the content are doubled. Bytes before a start sequence, as
+/// when the meter is read mid-transmission, are skipped. A file failing the
+/// CRC is dropped with an error, and decoding continues with the next one.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, Encoder, SmlCodec};
+///
+/// let mut codec = SmlCodec::new();
+/// let mut buf = BytesMut::from(&b"\x00\x1b"[..]);
+/// codec.encode(Bytes::from(&b"\x76\x05\x01"[..]), &mut buf).unwrap();
+/// assert_eq!(&buf[2..10], b"\x1b\x1b\x1b\x1b\x01\x01\x01\x01");
+///
+/// let file = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(&file[..], b"\x76\x05\x01");
+/// ```
+#[derive(Debug)]
+pub struct SmlCodec {
+    max_len: usize,
+    /// Offset of the first aligned word not scanned for an escape sequence
+    /// yet, or zero when no file has started
+    scan: usize,
+}
+
+impl SmlCodec {
+    pub fn new() -> Self {
+        Self {
+            max_len: MAX_FILE,
+            scan: 0,
+        }
+    }
+
+    /// Fail on files longer than `max` bytes
+    pub fn max_len(mut self, max: usize) -> Self {
+        self.max_len = max;
+        self
+    }
+}
+
+impl Default for SmlCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// CRC-16/X-25, as used by the SML transport
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xffff;
+    for &b in data {
+        crc ^= u16::from(b);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0x8408
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn is_start(word: &[u8]) -> bool {
+    word[..4] == ESCAPE && word[4..8] == VERSION_1
+}
+
+impl Decoder for SmlCodec {
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if self.scan == 0 {
+            // Skip to the start of a file, keeping a possible partial one
+            match src.windows(8).position(is_start) {
+                Some(start) => src.advance(start),
+                None => {
+                    let keep = src.len().min(7);
+                    src.advance(src.len() - keep);
+                    return Ok(None);
+                }
+            }
+            self.scan = 8;
+        }
+
+        let end = loop {
+            if self.scan > self.max_len {
+                self.scan = 0;
+                src.advance(8);
+                return Err(CodecError::FrameTooLong { max: self.max_len });
+            }
+            if src.len() < self.scan + 8 {
+                return Ok(None);
+            }
+            let at = self.scan;
+            if src[at..at + 4] != ESCAPE {
+                self.scan += 4;
+                continue;
+            }
+            if src[at + 4..at + 8] == ESCAPE {
+                self.scan += 8;
+            } else if src[at + 4] == END {
+                break at + 8;
+            } else {
+                // A new file starts within a broken one
+                self.scan = 0;
+                src.advance(at);
+                return if is_start(&src[..8]) {
+                    Err(CodecError::Protocol("unterminated SML file".to_owned()))
+                } else {
+                    src.advance(4);
+                    Err(CodecError::Protocol(
+                        "invalid SML escape sequence".to_owned(),
+                    ))
+                };
+            }
+        };
+        self.scan = 0;
+        let file = src.split_to(end);
+
+        let crc = u16::from(file[end - 2]) | u16::from(file[end - 1]) << 8;
+        if crc16(&file[..end - 2]) != crc {
+            return Err(CodecError::Protocol("SML CRC mismatch".to_owned()));
+        }
+        let mut content = BytesMut::with_capacity(end - 16);
+        let mut at = 8;
+        while at < end - 8 {
+            content.extend_from_slice(&file[at..at + 4]);
+            at += if file[at..at + 4] == ESCAPE { 8 } else { 4 };
+        }
+        let fill = usize::from(file[end - 3]);
+        if fill > 3 || fill > content.len() {
+            return Err(CodecError::Protocol(
+                "invalid SML fill byte count".to_owned(),
+            ));
+        }
+        content.truncate(content.len() - fill);
+        Ok(Some(content.freeze()))
+    }
+
+    fn reset(&mut self) {
+        self.scan = 0;
+    }
+}
+
+impl Encoder for SmlCodec {
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let start = dst.len();
+        dst.reserve(item.len() + 32);
+        dst.put_slice(&ESCAPE);
+        dst.put_slice(&VERSION_1);
+        for word in item.chunks(4) {
+            if word == ESCAPE {
+                dst.put_slice(&ESCAPE);
+            }
+            dst.put_slice(word);
+        }
+        let fill = (4 - item.len() % 4) % 4;
+        dst.put_slice(&[0; 3][..fill]);
+        if dst.len() - start + 8 > self.max_len {
+            dst.truncate(start);
+            return Err(CodecError::FrameTooLong { max: self.max_len });
+        }
+        dst.put_slice(&ESCAPE);
+        dst.put_slice(&[END, fill as u8]);
+        let crc = crc16(&dst[start..]);
+        dst.put_u16_le(crc);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escaped_content_and_resync() {
+        let content = Bytes::from(&b"\x1b\x1b\x1b\x1b\x76\x05\x1b\x1b\x1b\x1b"[..]);
+        let mut codec = SmlCodec::new();
+        let mut file = BytesMut::new();
+        codec.encode(content.clone(), &mut file).unwrap();
+        assert_eq!(file.len(), 8 + 16 + 8);
+        assert_eq!(&file[24..28], b"\x1b\x1b\x1b\x1b");
+        assert_eq!(&file[28..30], b"\x1a\x02");
+
+        // A truncated file, then a complete one split across reads
+        let mut buf = BytesMut::from(&file[..20]);
+        buf.extend_from_slice(&file[..16]);
+        assert!(codec.decode(&mut buf).is_err());
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(&file[16..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(content));
+        assert!(buf.is_empty());
+
+        let mut corrupted = file.clone();
+        corrupted[16] ^= 1;
+        assert!(codec.decode(&mut corrupted).is_err());
+        assert!(corrupted.is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+use super::dot::{find_crlf, DotTerminatedCodec};
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{Bytes, BytesMut};
+
+/// Longest command or reply line allowed by RFC 5321, including the CRLF
+const MAX_LINE: usize = 1000;
+
+/// Most lines accepted in a single reply
+const MAX_REPLY_LINES: usize = 100;
+
+/// A codec for SMTP, framing CRLF command lines, multi-line replies and the
+/// dot-stuffed message that follows `DATA`.
+///
+/// A server codec decodes commands and encodes replies. Encoding a `354`
+/// reply switches it to decoding the message, which is yielded as one
+/// `SmtpFrame::Data` with the stuffing removed, after which it decodes
+/// commands again. A client codec decodes replies of at most 100 lines, and
+/// encodes commands and messages.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, Encoder, SmtpCodec, SmtpFrame, SmtpReply};
+///
+/// let mut codec = SmtpCodec::server();
+/// let mut buf = BytesMut::from(&b"DATA\r\nSubject: hi\r\n..dot\r\n.\r\n"[..]);
+///
+/// let command = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(command, SmtpFrame::Command("DATA".to_owned()));
+///
+/// let reply = SmtpReply::new(354, "Go ahead");
+/// codec.encode(SmtpFrame::Reply(reply), &mut BytesMut::new()).unwrap();
+///
+/// let message = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(message, SmtpFrame::Data("Subject: hi\r\n.dot\r\n".into()));
+/// ```
+#[derive(Debug)]
+pub struct SmtpCodec {
+    server: bool,
+    in_data: bool,
+    data: DotTerminatedCodec,
+}
+
+/// A frame of `SmtpCodec`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SmtpFrame {
+    /// A command line without its CRLF
+    Command(String),
+    /// A reply of one or more lines
+    Reply(SmtpReply),
+    /// A message sent after `DATA`, with CRLF line endings and without
+    /// stuffing
+    Data(Bytes),
+}
+
+/// An SMTP reply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmtpReply {
+    /// Three digit reply code
+    pub code: u16,
+    /// Text of every line, without the code and separator
+    pub lines: Vec<String>,
+}
+
+impl SmtpReply {
+    /// A reply of a single line
+    pub fn new(code: u16, text: &str) -> Self {
+        Self {
+            code,
+            lines: vec![text.to_owned()],
+        }
+    }
+}
+
+impl SmtpCodec {
+    /// A codec for the server side of a connection
+    pub fn server() -> Self {
+        Self {
+            server: true,
+            in_data: false,
+            data: DotTerminatedCodec::new(),
+        }
+    }
+
+    /// A codec for the client side of a connection
+    pub fn client() -> Self {
+        Self {
+            server: false,
+            ..Self::server()
+        }
+    }
+
+    fn decode_reply(src: &mut BytesMut) -> Result<Option<SmtpReply>, CodecError> {
+        let mut start = 0;
+        let mut reply = SmtpReply {
+            code: 0,
+            lines: Vec::new(),
+        };
+        loop {
+            let end = match find_crlf(&src[start..]) {
+                Some(end) => start + end,
+                None if src.len() - start >= MAX_LINE => {
+                    return Err(CodecError::FrameTooLong { max: MAX_LINE })
+                }
+                None => return Ok(None),
+            };
+            let line = std::str::from_utf8(&src[start..end])?;
+            let code = line
+                .get(..3)
+                .and_then(|code| code.parse().ok())
+                .ok_or_else(|| CodecError::Protocol("reply without a code".to_owned()))?;
+            if start > 0 && code != reply.code {
+                return Err(CodecError::Protocol(
+                    "reply code changed between lines".to_owned(),
+                ));
+            }
+            reply.code = code;
+            reply.lines.push(line.get(4..).unwrap_or("").to_owned());
+            start = end + 2;
+
+            match line.as_bytes().get(3) {
+                Some(b'-') if reply.lines.len() >= MAX_REPLY_LINES => {
+                    return Err(CodecError::Protocol("reply has too many lines".to_owned()))
+                }
+                Some(b'-') => continue,
+                None | Some(b' ') => {
+                    src.advance(start);
+                    return Ok(Some(reply));
+                }
+                Some(_) => return Err(CodecError::Protocol("malformed reply line".to_owned())),
+            }
+        }
+    }
+}
+
+impl Decoder for SmtpCodec {
+    type Item = SmtpFrame;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if self.in_data {
+            let message = self.data.decode(src)?;
+            self.in_data = message.is_none();
+            return Ok(message.map(SmtpFrame::Data));
+        }
+        if !self.server {
+            return Ok(Self::decode_reply(src)?.map(SmtpFrame::Reply));
+        }
+
+        let end = match find_crlf(src) {
+            Some(end) => end,
+            None if src.len() >= MAX_LINE => {
+                return Err(CodecError::FrameTooLong { max: MAX_LINE })
+            }
+            None => return Ok(None),
+        };
+        let line = src.split_to(end + 2);
+        let command = std::str::from_utf8(&line[..end])?;
+        Ok(Some(SmtpFrame::Command(command.to_owned())))
+    }
+}
+
+impl Encoder for SmtpCodec {
+    type Item = SmtpFrame;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        match item {
+            SmtpFrame::Command(command) => {
+                dst.extend_from_slice(command.as_bytes());
+                dst.extend_from_slice(b"\r\n");
+            }
+            SmtpFrame::Reply(reply) => {
+                if self.server && reply.code == 354 {
+                    self.in_data = true;
+                }
+                let last = reply.lines.len().saturating_sub(1);
+                for (i, text) in reply.lines.iter().enumerate() {
+                    let separator = if i == last { ' ' } else { '-' };
+                    let line = format!("{:03}{}{}\r\n", reply.code, separator, text);
+                    dst.extend_from_slice(line.as_bytes());
+                }
+                if reply.lines.is_empty() {
+                    dst.extend_from_slice(format!("{:03}\r\n", reply.code).as_bytes());
+                }
+            }
+            SmtpFrame::Data(message) => self.data.encode(message, dst)?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn multiline_reply() {
+        let mut codec = SmtpCodec::client();
+        let mut buf = BytesMut::from(&b"250-mx.example.com\r\n250-PIPELINING\r\n250 "[..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+
+        buf.extend_from_slice(b"8BITMIME\r\n");
+        let reply = match codec.decode(&mut buf).unwrap() {
+            Some(SmtpFrame::Reply(reply)) => reply,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(reply.code, 250);
+        assert_eq!(reply.lines, ["mx.example.com", "PIPELINING", "8BITMIME"]);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn data_roundtrip() {
+        let message = Bytes::from("Subject: hi\r\n.\r\n..\r\n");
+        let mut buf = BytesMut::new();
+        SmtpCodec::client()
+            .encode(SmtpFrame::Data(message.clone()), &mut buf)
+            .unwrap();
+        assert_eq!(&buf[..], &b"Subject: hi\r\n..\r\n...\r\n.\r\n"[..]);
+
+        let mut codec = SmtpCodec::server();
+        codec.in_data = true;
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(SmtpFrame::Data(message))
+        );
+        assert!(!codec.in_data);
+    }
+
+    #[test]
+    fn too_many_reply_lines() {
+        let mut codec = SmtpCodec::client();
+        let mut buf = BytesMut::new();
+        for _ in 0..MAX_REPLY_LINES {
+            buf.extend_from_slice(b"250-EXT\r\n");
+        }
+        buf.extend_from_slice(b"250 ");
+        match codec.decode(&mut buf) {
+            Err(CodecError::Protocol(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
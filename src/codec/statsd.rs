@@ -0,0 +1,275 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::BytesMut;
+use std::fmt;
+
+/// A codec for StatsD metrics sent as newline terminated lines over a
+/// stream, `name:value|type|@rate|#tags`.
+///
+/// Over UDP, where a datagram holds any number of lines and the last one
+/// need not be terminated, use [`StatsdDatagramCodec`] with `DatagramFramed`.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, StatsdCodec, StatsdKind};
+///
+/// let mut codec = StatsdCodec::new();
+/// let mut buf = BytesMut::from(&b"api.hits:1|c|@0.5|#env:prod,canary\n"[..]);
+///
+/// let metric = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!((&metric.name[..], metric.kind.clone()), ("api.hits", StatsdKind::Counter));
+/// assert_eq!(metric.sample_rate, Some(0.5));
+/// assert_eq!(metric.to_string(), "api.hits:1|c|@0.5|#env:prod,canary");
+/// ```
+#[derive(Debug, Default)]
+pub struct StatsdCodec {
+    /// Index into the buffer up to which no newline has been found yet
+    next_index: usize,
+}
+
+/// A codec for StatsD datagrams, decoding every metric of a datagram into a
+/// single frame.
+#[derive(Debug, Default)]
+pub struct StatsdDatagramCodec;
+
+/// A StatsD metric.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsdMetric {
+    pub name: String,
+    /// The value as sent, such as `1`, `+3` for a gauge delta or the member
+    /// of a set
+    pub value: String,
+    pub kind: StatsdKind,
+    pub sample_rate: Option<f64>,
+    /// DogStatsD tags, with their value if they have one
+    pub tags: Vec<(String, Option<String>)>,
+}
+
+/// The type of a StatsD metric.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatsdKind {
+    Counter,
+    Gauge,
+    Timer,
+    Histogram,
+    Distribution,
+    Set,
+    /// A type this codec does not know
+    Other(String),
+}
+
+impl StatsdCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StatsdDatagramCodec {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl StatsdMetric {
+    /// A metric without sample rate or tags
+    pub fn new(name: &str, value: impl ToString, kind: StatsdKind) -> Self {
+        Self {
+            name: name.to_owned(),
+            value: value.to_string(),
+            kind,
+            sample_rate: None,
+            tags: Vec::new(),
+        }
+    }
+
+    /// The value as a number, if it is one
+    pub fn value_f64(&self) -> Option<f64> {
+        self.value.parse().ok()
+    }
+
+    /// Parse a single metric line, without its newline
+    pub fn parse(line: &str) -> Result<Self, CodecError> {
+        let (name, rest) = line
+            .split_once(':')
+            .ok_or_else(|| invalid("metric without a value"))?;
+        let mut fields = rest.split('|');
+        let value = fields.next().unwrap_or("");
+        let kind = match fields.next() {
+            Some("c") => StatsdKind::Counter,
+            Some("g") => StatsdKind::Gauge,
+            Some("ms") => StatsdKind::Timer,
+            Some("h") => StatsdKind::Histogram,
+            Some("d") => StatsdKind::Distribution,
+            Some("s") => StatsdKind::Set,
+            Some(kind) if !kind.is_empty() => StatsdKind::Other(kind.to_owned()),
+            _ => return Err(invalid("metric without a type")),
+        };
+        if name.is_empty() || value.is_empty() {
+            return Err(invalid("metric without a name or value"));
+        }
+
+        let mut metric = Self::new(name, value, kind);
+        for field in fields {
+            if let Some(rate) = field.strip_prefix('@') {
+                let rate = rate.parse().map_err(|_| invalid("invalid sample rate"))?;
+                metric.sample_rate = Some(rate);
+            } else if let Some(tags) = field.strip_prefix('#') {
+                metric.tags = tags
+                    .split(',')
+                    .filter(|tag| !tag.is_empty())
+                    .map(|tag| match tag.split_once(':') {
+                        Some((key, value)) => (key.to_owned(), Some(value.to_owned())),
+                        None => (tag.to_owned(), None),
+                    })
+                    .collect();
+            }
+        }
+        Ok(metric)
+    }
+}
+
+impl fmt::Display for StatsdKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            StatsdKind::Counter => "c",
+            StatsdKind::Gauge => "g",
+            StatsdKind::Timer => "ms",
+            StatsdKind::Histogram => "h",
+            StatsdKind::Distribution => "d",
+            StatsdKind::Set => "s",
+            StatsdKind::Other(kind) => kind,
+        })
+    }
+}
+
+/// Formats the metric as a line, without a newline
+impl fmt::Display for StatsdMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}|{}", self.name, self.value, self.kind)?;
+        if let Some(rate) = self.sample_rate {
+            write!(f, "|@{}", rate)?;
+        }
+        for (i, (key, value)) in self.tags.iter().enumerate() {
+            f.write_str(if i == 0 { "|#" } else { "," })?;
+            f.write_str(key)?;
+            if let Some(value) = value {
+                write!(f, ":{}", value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+fn encode_line(metric: &StatsdMetric, dst: &mut BytesMut) -> Result<(), CodecError> {
+    let line = metric.to_string();
+    if line.contains('\n') {
+        return Err(invalid("metric contains a newline"));
+    }
+    dst.extend_from_slice(line.as_bytes());
+    Ok(())
+}
+
+impl Decoder for StatsdCodec {
+    type Item = StatsdMetric;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            let end = match src[self.next_index..].iter().position(|&b| b == b'\n') {
+                Some(end) => self.next_index + end,
+                None => {
+                    self.next_index = src.len();
+                    return Ok(None);
+                }
+            };
+            self.next_index = 0;
+            let line = src.split_to(end + 1);
+            let line = std::str::from_utf8(&line[..end])?.trim_end_matches('\r');
+            if !line.is_empty() {
+                return StatsdMetric::parse(line).map(Some);
+            }
+        }
+    }
+
+    /// Yields the last line even if it is not terminated by a newline
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.decode(src)? {
+            Some(metric) => Ok(Some(metric)),
+            None if src.is_empty() => Ok(None),
+            None => {
+                self.next_index = 0;
+                StatsdMetric::parse(std::str::from_utf8(&src.take())?).map(Some)
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.next_index = 0;
+    }
+}
+
+impl Encoder for StatsdCodec {
+    type Item = StatsdMetric;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        encode_line(&item, dst)?;
+        dst.extend_from_slice(b"\n");
+        Ok(())
+    }
+}
+
+impl Decoder for StatsdDatagramCodec {
+    type Item = Vec<StatsdMetric>;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+        let datagram = src.take();
+        std::str::from_utf8(&datagram)?
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(StatsdMetric::parse)
+            .collect::<Result<_, _>>()
+            .map(Some)
+    }
+}
+
+impl Encoder for StatsdDatagramCodec {
+    type Item = Vec<StatsdMetric>;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        for (i, metric) in item.iter().enumerate() {
+            if i > 0 {
+                dst.extend_from_slice(b"\n");
+            }
+            encode_line(metric, dst)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn datagram_of_several_metrics() {
+        let mut codec = StatsdDatagramCodec::new();
+        let mut buf = BytesMut::from(&b"a:1|c\nb:+2|g\nc:user1|s"[..]);
+        let metrics = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(metrics.len(), 3);
+        assert_eq!(metrics[1].value_f64(), Some(2.0));
+        assert_eq!(metrics[2].kind, StatsdKind::Set);
+
+        codec.encode(metrics, &mut buf).unwrap();
+        assert_eq!(&buf[..], b"a:1|c\nb:+2|g\nc:user1|s");
+    }
+}
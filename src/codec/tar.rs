@@ -0,0 +1,314 @@
+use crate::{CodecError, Decoder};
+use bytes::{Bytes, BytesMut};
+
+const BLOCK: usize = 512;
+/// Default limit on the size of an entry buffered whole
+const MAX_ENTRY: u64 = 64 * 1024 * 1024;
+
+/// A decoder for tar archives, yielding an entry per header.
+///
+/// By default the data of every entry is buffered and yielded with its
+/// header. With [`streaming`](Self::streaming), only headers are yielded and
+/// the data is read with [`FramedRead::body`](crate::FramedRead::body),
+/// which suits large files extracted from a socket. The padding after the
+/// data is skipped by the decoder either way. The archive ends at two zero
+/// blocks, and anything after them is ignored.
+///
+/// Headers are parsed as ustar, including the name prefix, with base-256
+/// sizes as written by GNU tar. GNU long names and PAX extended headers are
+/// yielded as entries of their own type, for the caller to apply to the
+/// next entry.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{Decoder, TarEntryCodec};
+///
+/// let mut header = [0; 512];
+/// header[..9].copy_from_slice(b"hello.txt");
+/// header[124..136].copy_from_slice(b"00000000005\0");
+/// header[156] = b'0';
+/// header[148..156].copy_from_slice(b"        ");
+/// let sum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+/// header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+///
+/// let mut buf = BytesMut::from(&header[..]);
+/// buf.extend_from_slice(b"Hello");
+/// buf.extend_from_slice(&[0; 507 + 1024]);
+///
+/// let mut codec = TarEntryCodec::new();
+/// let entry = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(entry.header.path, "hello.txt");
+/// assert_eq!(&entry.data.unwrap()[..], b"Hello");
+/// assert_eq!(codec.decode(&mut buf).unwrap(), None);
+/// assert!(buf.is_empty());
+/// ```
+#[derive(Debug)]
+pub struct TarEntryCodec {
+    streaming: bool,
+    max_entry: u64,
+    /// Bytes of padding to skip before the next header
+    skip: usize,
+    /// Whether the end of the archive was reached
+    ended: bool,
+}
+
+/// An entry of a tar archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TarEntry {
+    pub header: TarHeader,
+    /// The data of the entry, `None` when streaming
+    pub data: Option<Bytes>,
+}
+
+/// The header of a tar entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TarHeader {
+    /// Path of the entry, with the ustar prefix
+    pub path: String,
+    pub mode: u32,
+    pub uid: u64,
+    pub gid: u64,
+    /// Length of the data following the header
+    pub size: u64,
+    /// Modification time, in seconds since the Unix epoch
+    pub mtime: u64,
+    /// Type flag, see the associated constants
+    pub kind: u8,
+    pub link_name: String,
+    /// The header block as read, for fields not parsed
+    pub raw: Bytes,
+}
+
+impl TarHeader {
+    pub const REGULAR: u8 = b'0';
+    pub const HARD_LINK: u8 = b'1';
+    pub const SYMLINK: u8 = b'2';
+    pub const CHAR_DEVICE: u8 = b'3';
+    pub const BLOCK_DEVICE: u8 = b'4';
+    pub const DIRECTORY: u8 = b'5';
+    pub const FIFO: u8 = b'6';
+    /// PAX extended header for the next entry
+    pub const PAX: u8 = b'x';
+    /// PAX extended header for the whole archive
+    pub const PAX_GLOBAL: u8 = b'g';
+    /// GNU long name for the next entry
+    pub const GNU_LONG_NAME: u8 = b'L';
+    /// GNU long link name for the next entry
+    pub const GNU_LONG_LINK: u8 = b'K';
+
+    fn parse(block: Bytes) -> Result<Self, CodecError> {
+        let expected = number(&block[148..156])?;
+        let sum: u64 = block
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    32
+                } else {
+                    u64::from(b)
+                }
+            })
+            .sum();
+        if sum != expected {
+            return Err(CodecError::Protocol(
+                "tar header checksum mismatch".to_owned(),
+            ));
+        }
+
+        let name = text(&block[..100]);
+        let ustar = &block[257..262] == b"ustar";
+        let path = match text(&block[345..500]) {
+            prefix if ustar && !prefix.is_empty() => format!("{}/{}", prefix, name),
+            _ => name,
+        };
+        // Old archives mark regular files with a null byte
+        let kind = match block[156] {
+            0 => Self::REGULAR,
+            kind => kind,
+        };
+        Ok(Self {
+            path,
+            mode: number(&block[100..108])? as u32,
+            uid: number(&block[108..116])?,
+            gid: number(&block[116..124])?,
+            size: number(&block[124..136])?,
+            mtime: number(&block[136..148])?,
+            kind,
+            link_name: text(&block[157..257]),
+            raw: block,
+        })
+    }
+
+    /// Whether the entry has data following its header
+    fn has_data(&self) -> bool {
+        !matches!(
+            self.kind,
+            Self::HARD_LINK
+                | Self::SYMLINK
+                | Self::CHAR_DEVICE
+                | Self::BLOCK_DEVICE
+                | Self::DIRECTORY
+                | Self::FIFO
+        )
+    }
+}
+
+/// A null terminated string field
+fn text(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// An octal number field, or a base-256 one if the high bit is set
+fn number(field: &[u8]) -> Result<u64, CodecError> {
+    if field[0] & 0x80 != 0 {
+        return Ok(field[1..]
+            .iter()
+            .fold(u64::from(field[0] & 0x7f), |n, &b| n << 8 | u64::from(b)));
+    }
+    let digits = std::str::from_utf8(field)?.trim_matches(|c| c == '\0' || c == ' ');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(digits, 8)
+        .map_err(|_| CodecError::Protocol("invalid number in tar header".to_owned()))
+}
+
+fn padding(size: u64) -> usize {
+    (BLOCK - (size % BLOCK as u64) as usize) % BLOCK
+}
+
+impl TarEntryCodec {
+    pub fn new() -> Self {
+        Self {
+            streaming: false,
+            max_entry: MAX_ENTRY,
+            skip: 0,
+            ended: false,
+        }
+    }
+
+    /// Only yield headers, leaving the data of every entry to be read with
+    /// `FramedRead::body` before the next entry is decoded
+    pub fn streaming(mut self) -> Self {
+        self.streaming = true;
+        self
+    }
+
+    /// Fail on entries with more than `max` bytes of data, unless streaming
+    pub fn max_entry_len(mut self, max: u64) -> Self {
+        self.max_entry = max;
+        self
+    }
+}
+
+impl Default for TarEntryCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for TarEntryCodec {
+    type Item = TarEntry;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let skipped = self.skip.min(src.len());
+        src.advance(skipped);
+        self.skip -= skipped;
+        if self.ended {
+            src.clear();
+            return Ok(None);
+        }
+        if self.skip > 0 || src.len() < BLOCK {
+            return Ok(None);
+        }
+        if src[..BLOCK].iter().all(|&b| b == 0) {
+            if src.len() < 2 * BLOCK {
+                return Ok(None);
+            }
+            if src[BLOCK..2 * BLOCK].iter().all(|&b| b == 0) {
+                self.ended = true;
+                src.clear();
+                return Ok(None);
+            }
+            return Err(CodecError::Protocol(
+                "zero block inside tar archive".to_owned(),
+            ));
+        }
+
+        let header = TarHeader::parse(Bytes::from(&src[..BLOCK]))?;
+        let size = if header.has_data() { header.size } else { 0 };
+        if self.streaming {
+            src.advance(BLOCK);
+            self.skip = padding(size);
+            return Ok(Some(TarEntry { header, data: None }));
+        }
+        if size > self.max_entry {
+            return Err(CodecError::FrameTooLong {
+                max: self.max_entry as usize,
+            });
+        }
+        let size = size as usize;
+        if src.len() < BLOCK + size {
+            return Ok(None);
+        }
+
+        src.advance(BLOCK);
+        let data = src.split_to(size).freeze();
+        self.skip = padding(size as u64);
+        Ok(Some(TarEntry {
+            header,
+            data: Some(data),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::FramedRead;
+    use futures::{executor, AsyncReadExt, TryStreamExt};
+
+    fn header(path: &str, kind: u8, size: u64) -> [u8; BLOCK] {
+        let mut block = [0; BLOCK];
+        block[..path.len()].copy_from_slice(path.as_bytes());
+        block[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+        block[156] = kind;
+        block[257..263].copy_from_slice(b"ustar\0");
+        block[148..156].copy_from_slice(b"        ");
+        let sum: u32 = block.iter().map(|&b| u32::from(b)).sum();
+        block[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+        block
+    }
+
+    #[test]
+    fn streams_entry_bodies() {
+        let mut archive = Vec::new();
+        archive.extend_from_slice(&header("dir", TarHeader::DIRECTORY, 0));
+        archive.extend_from_slice(&header("dir/big", TarHeader::REGULAR, 600));
+        archive.extend(std::iter::repeat_n(b'x', 600));
+        archive.extend_from_slice(&[0; 424]);
+        archive.extend_from_slice(&header("dir/small", TarHeader::REGULAR, 2));
+        archive.extend_from_slice(b"hi");
+        archive.extend_from_slice(&[0; 510 + 2 * BLOCK + 4096]);
+
+        let mut framed = FramedRead::new(&archive[..], TarEntryCodec::new().streaming());
+        let paths = executor::block_on(async {
+            let mut paths = Vec::new();
+            while let Some(entry) = framed.try_next().await.unwrap() {
+                let mut data = Vec::new();
+                framed
+                    .body(entry.header.size)
+                    .read_to_end(&mut data)
+                    .await
+                    .unwrap();
+                assert_eq!(data.len() as u64, entry.header.size);
+                paths.push(entry.header.path);
+            }
+            paths
+        });
+        assert_eq!(paths, ["dir", "dir/big", "dir/small"]);
+    }
+}
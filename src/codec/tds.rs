@@ -0,0 +1,247 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BigEndian, BufMut, ByteOrder, Bytes, BytesMut};
+
+/// Size of the header in front of every packet
+const HEADER_LEN: usize = 8;
+/// Status bit marking the last packet of a message
+const STATUS_EOM: u8 = 0x01;
+/// Packet size used before the client and server negotiated one
+const DEFAULT_PACKET_SIZE: usize = 4096;
+/// Default limit on the length of a reassembled message
+const MAX_MESSAGE: usize = 64 * 1024 * 1024;
+
+/// A codec for the packets of the Tabular Data Stream protocol of SQL
+/// Server, reassembling them into messages.
+///
+/// Every packet starts with an eight byte header: the message type, a status
+/// byte, the big endian packet length including the header, the server
+/// process id, a packet counter and an unused window byte. The last packet of
+/// a message has the end of message status bit set.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, Encoder, TdsMessage, TdsPacketCodec};
+///
+/// let mut codec = TdsPacketCodec::new().packet_size(512);
+/// let mut buf = BytesMut::new();
+/// let batch = TdsMessage::new(TdsMessage::SQL_BATCH, Bytes::from(vec![b'x'; 1000]));
+/// codec.encode(batch.clone(), &mut buf).unwrap();
+/// assert_eq!(buf.len(), 1000 + 2 * 8);
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some(batch));
+/// ```
+#[derive(Debug)]
+pub struct TdsPacketCodec {
+    packet_size: usize,
+    max_message_len: usize,
+    /// Packets of a message whose last packet did not arrive yet
+    partial: Option<TdsMessage>,
+    partial_data: BytesMut,
+    next_packet_id: u8,
+}
+
+/// A message of the TDS protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TdsMessage {
+    /// Message type, see the associated constants
+    pub kind: u8,
+    /// Server process id of the connection, zero from the client
+    pub spid: u16,
+    /// The reassembled payload of all packets
+    pub data: Bytes,
+}
+
+impl TdsMessage {
+    pub const SQL_BATCH: u8 = 0x01;
+    pub const RPC: u8 = 0x03;
+    pub const TABULAR_RESULT: u8 = 0x04;
+    pub const ATTENTION: u8 = 0x06;
+    pub const BULK_LOAD: u8 = 0x07;
+    pub const TRANSACTION_MANAGER: u8 = 0x0e;
+    pub const LOGIN7: u8 = 0x10;
+    pub const SSPI: u8 = 0x11;
+    pub const PRELOGIN: u8 = 0x12;
+
+    pub fn new(kind: u8, data: Bytes) -> Self {
+        Self {
+            kind,
+            spid: 0,
+            data,
+        }
+    }
+}
+
+impl TdsPacketCodec {
+    pub fn new() -> Self {
+        Self {
+            packet_size: DEFAULT_PACKET_SIZE,
+            max_message_len: MAX_MESSAGE,
+            partial: None,
+            partial_data: BytesMut::new(),
+            next_packet_id: 1,
+        }
+    }
+
+    /// Split encoded messages into packets of at most `size` bytes, the
+    /// packet size negotiated at login
+    ///
+    /// # Panics
+    /// If `size` leaves no room for payload after the header, or does not
+    /// fit the length field.
+    pub fn packet_size(mut self, size: usize) -> Self {
+        self.set_packet_size(size);
+        self
+    }
+
+    /// Change the packet size after it was negotiated, see `packet_size`
+    pub fn set_packet_size(&mut self, size: usize) {
+        assert!(
+            size > HEADER_LEN && size <= u16::MAX as usize,
+            "invalid packet size"
+        );
+        self.packet_size = size;
+    }
+
+    /// Fail with `FrameTooLong` on messages longer than `max` bytes, 64 MiB
+    /// by default
+    pub fn max_message_len(mut self, max: usize) -> Self {
+        self.max_message_len = max;
+        self
+    }
+}
+
+impl Default for TdsPacketCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for TdsPacketCodec {
+    type Item = TdsMessage;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            if src.len() < HEADER_LEN {
+                return Ok(None);
+            }
+            let len = BigEndian::read_u16(&src[2..4]) as usize;
+            if len < HEADER_LEN {
+                return Err(CodecError::Protocol(
+                    "packet shorter than its header".to_owned(),
+                ));
+            }
+            if src.len() < len {
+                return Ok(None);
+            }
+
+            let mut packet = src.split_to(len);
+            let kind = packet[0];
+            let status = packet[1];
+            let spid = BigEndian::read_u16(&packet[4..6]);
+            packet.advance(HEADER_LEN);
+
+            match &self.partial {
+                Some(partial) if partial.kind != kind => {
+                    self.partial = None;
+                    self.partial_data.clear();
+                    return Err(CodecError::Protocol(
+                        "message type changed between packets".to_owned(),
+                    ));
+                }
+                Some(_) => {}
+                None => self.partial = Some(TdsMessage::new(kind, Bytes::new())),
+            }
+            if self.partial_data.len() + packet.len() > self.max_message_len {
+                self.partial = None;
+                self.partial_data.clear();
+                return Err(CodecError::FrameTooLong {
+                    max: self.max_message_len,
+                });
+            }
+            self.partial_data.extend_from_slice(&packet);
+
+            if status & STATUS_EOM != 0 {
+                let mut message = self.partial.take().expect("partial message");
+                message.spid = spid;
+                message.data = self.partial_data.take().freeze();
+                return Ok(Some(message));
+            }
+        }
+    }
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        if src.len() < HEADER_LEN {
+            return None;
+        }
+        let len = BigEndian::read_u16(&src[2..4]) as usize;
+        len.checked_sub(src.len())
+    }
+}
+
+impl Encoder for TdsPacketCodec {
+    type Item = TdsMessage;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let max_payload = self.packet_size - HEADER_LEN;
+        let count = item.data.len().div_ceil(max_payload).max(1);
+        dst.reserve(item.data.len() + count * HEADER_LEN);
+
+        let mut data = item.data;
+        for index in 0..count {
+            let payload = data.split_to(max_payload.min(data.len()));
+            let status = if index + 1 == count { STATUS_EOM } else { 0 };
+            dst.put_u8(item.kind);
+            dst.put_u8(status);
+            dst.put_u16_be((HEADER_LEN + payload.len()) as u16);
+            dst.put_u16_be(item.spid);
+            dst.put_u8(self.next_packet_id);
+            dst.put_u8(0);
+            dst.put_slice(&payload);
+            self.next_packet_id = self.next_packet_id.wrapping_add(1);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reassembles_split_packets() {
+        let mut codec = TdsPacketCodec::new();
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"\x04\x00\x00\x0b\x00\x34\x01\x00abc");
+        buf.extend_from_slice(b"\x04\x01\x00\x0a\x00\x34\x02\x00de");
+        let end = buf.split_off(19);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(codec.bytes_needed(&buf), Some(2));
+
+        buf.extend_from_slice(&end);
+        let message = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(message.kind, TdsMessage::TABULAR_RESULT);
+        assert_eq!(message.spid, 0x34);
+        assert_eq!(message.data, "abcde");
+    }
+
+    #[test]
+    fn message_without_end_is_limited() {
+        let mut codec = TdsPacketCodec::new();
+        let mut packet = BytesMut::from(&b"\x07\x00\xff\xff\x00\x00\x01\x00"[..]);
+        packet.resize(u16::MAX as usize, 0);
+
+        let packets = MAX_MESSAGE / (packet.len() - HEADER_LEN) + 1;
+        for _ in 0..packets {
+            let mut buf = packet.clone();
+            match codec.decode(&mut buf) {
+                Ok(None) => continue,
+                Err(CodecError::FrameTooLong { max }) => return assert_eq!(max, MAX_MESSAGE),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        panic!("message grew past the limit");
+    }
+}
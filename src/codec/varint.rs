@@ -0,0 +1,31 @@
+use crate::CodecError;
+use bytes::BytesMut;
+
+/// Read an unsigned LEB128 integer of at most `max_len` bytes from the start
+/// of `src`, returning it with the number of bytes it took.
+pub(crate) fn read_varint(src: &[u8], max_len: usize) -> Result<Option<(u64, usize)>, CodecError> {
+    let mut value = 0u64;
+    for (i, &byte) in src.iter().enumerate().take(max_len) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(Some((value, i + 1)));
+        }
+    }
+    if src.len() >= max_len {
+        return Err(CodecError::Protocol("varint is too long".to_owned()));
+    }
+    Ok(None)
+}
+
+/// Append `value` as an unsigned LEB128 integer
+pub(crate) fn put_varint(dst: &mut BytesMut, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            dst.extend_from_slice(&[byte]);
+            return;
+        }
+        dst.extend_from_slice(&[byte | 0x80]);
+    }
+}
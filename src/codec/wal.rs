@@ -0,0 +1,207 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BufMut, ByteOrder, Bytes, BytesMut, LittleEndian};
+
+/// Default limit on the length of a record
+const MAX_RECORD: usize = 16 * 1024 * 1024;
+
+/// A codec for the records of a write-ahead log.
+///
+/// Every record is a little endian `u32` length, a CRC-32C of the length
+/// and the payload, and the payload. Including the length in the checksum
+/// makes zero filled space at the end of a preallocated file fail it.
+///
+/// A crash while appending can leave a torn record at the end of the log.
+/// `decode_eof` drops a last record that is incomplete or fails its
+/// checksum and ends the log there, so read logs with
+/// [`IncompleteEof::Decode`](crate::IncompleteEof::Decode). Afterwards,
+/// [`valid_len`](Self::valid_len) is the length to truncate the file to
+/// before appending again. A record failing its checksum that is followed
+/// by more than zeros is corruption and fails to decode.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, Encoder, WalRecordCodec};
+///
+/// let mut codec = WalRecordCodec::new();
+/// let mut buf = BytesMut::new();
+/// codec.encode(Bytes::from("insert 1"), &mut buf).unwrap();
+/// codec.encode(Bytes::from("insert 2"), &mut buf).unwrap();
+/// // The second record was torn by a crash
+/// buf.truncate(buf.len() - 3);
+///
+/// assert_eq!(codec.decode_eof(&mut buf).unwrap(), Some(Bytes::from("insert 1")));
+/// assert_eq!(codec.decode_eof(&mut buf).unwrap(), None);
+/// assert_eq!(codec.valid_len(), 16);
+/// assert_eq!(codec.torn_len(), 13);
+/// ```
+#[derive(Debug)]
+pub struct WalRecordCodec {
+    checksums: bool,
+    max_len: usize,
+    valid_len: u64,
+    torn_len: usize,
+}
+
+impl WalRecordCodec {
+    pub fn new() -> Self {
+        Self {
+            checksums: true,
+            max_len: MAX_RECORD,
+            valid_len: 0,
+            torn_len: 0,
+        }
+    }
+
+    /// Leave out the checksum of every record, for logs on storage that
+    /// checks integrity itself. Torn records are then only detected when
+    /// incomplete.
+    pub fn without_checksums(mut self) -> Self {
+        self.checksums = false;
+        self
+    }
+
+    /// Fail on records longer than `max` bytes
+    pub fn max_record_len(mut self, max: usize) -> Self {
+        self.max_len = max;
+        self
+    }
+
+    /// Number of bytes of the records decoded so far
+    pub fn valid_len(&self) -> u64 {
+        self.valid_len
+    }
+
+    /// Number of bytes of a torn record dropped at the end of the log
+    pub fn torn_len(&self) -> usize {
+        self.torn_len
+    }
+
+    fn header_len(&self) -> usize {
+        if self.checksums {
+            8
+        } else {
+            4
+        }
+    }
+}
+
+impl Default for WalRecordCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// CRC-32C (Castagnoli) of `data`, continuing from `crc`
+fn crc32c(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &b in data {
+        crc ^= u32::from(b);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0x82f6_3b78 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+impl Decoder for WalRecordCodec {
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let header_len = self.header_len();
+        if src.len() < header_len {
+            return Ok(None);
+        }
+        let len = LittleEndian::read_u32(&src[..4]) as usize;
+        if len > self.max_len {
+            return Err(CodecError::FrameTooLong { max: self.max_len });
+        }
+        let end = header_len + len;
+        if src.len() < end {
+            return Ok(None);
+        }
+        if self.checksums {
+            let crc = crc32c(crc32c(0, &src[..4]), &src[8..end]);
+            if crc != LittleEndian::read_u32(&src[4..8]) {
+                // A torn last record, unless more than zero fill follows
+                if src[end..].iter().all(|&b| b == 0) {
+                    return Ok(None);
+                }
+                return Err(CodecError::Protocol(
+                    "WAL record checksum mismatch".to_owned(),
+                ));
+            }
+        }
+
+        src.advance(header_len);
+        self.valid_len += end as u64;
+        Ok(Some(src.split_to(len).freeze()))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let record = self.decode(src)?;
+        if record.is_none() {
+            self.torn_len = src.len();
+            src.clear();
+        }
+        Ok(record)
+    }
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        if src.len() < self.header_len() {
+            return None;
+        }
+        let len = LittleEndian::read_u32(&src[..4]) as usize;
+        (self.header_len() + len).checked_sub(src.len())
+    }
+}
+
+impl Encoder for WalRecordCodec {
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if item.len() > self.max_len.min(u32::MAX as usize) {
+            return Err(CodecError::FrameTooLong { max: self.max_len });
+        }
+        let mut len = [0; 4];
+        LittleEndian::write_u32(&mut len, item.len() as u32);
+        dst.reserve(self.header_len() + item.len());
+        dst.put_slice(&len);
+        if self.checksums {
+            dst.put_u32_le(crc32c(crc32c(0, &len), &item));
+        }
+        dst.put_slice(&item);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{FramedRead, IncompleteEof};
+    use futures::{executor, TryStreamExt};
+
+    #[test]
+    fn zero_filled_tail_is_torn() {
+        assert_eq!(crc32c(0, b"123456789"), 0xe306_9283);
+
+        let mut codec = WalRecordCodec::new();
+        let mut log = BytesMut::new();
+        for record in &["one", "two"] {
+            codec.encode(Bytes::from(*record), &mut log).unwrap();
+        }
+        let mut corrupt = log.clone();
+        log.extend_from_slice(&[0; 4096]);
+        let framed = FramedRead::new(&log[..], WalRecordCodec::new())
+            .on_incomplete_eof(IncompleteEof::Decode);
+        let records: Vec<_> = executor::block_on(framed.try_collect()).unwrap();
+        assert_eq!(records, ["one", "two"]);
+
+        corrupt[9] ^= 1;
+        let mut codec = WalRecordCodec::new();
+        assert!(codec.decode_eof(&mut corrupt).is_err());
+        assert_eq!(codec.valid_len(), 0);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{Bytes, BytesMut};
+use std::convert::TryInto;
+
+/// Size of the header in front of every message
+const HEADER_LEN: usize = 8;
+
+/// A codec for messages of the Wayland wire protocol.
+///
+/// Every message starts with the id of the object it is sent to or from and
+/// a word holding the size of the message in the upper 16 bits and the
+/// opcode in the lower ones, all in native byte order. The arguments are
+/// passed on unparsed, as their types are only known from the protocol
+/// description. File descriptors travel beside the messages in ancillary
+/// data of the socket and are not handled by the codec.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, Encoder, WaylandMessage, WaylandMessageCodec};
+///
+/// let mut codec = WaylandMessageCodec::new();
+/// let mut buf = BytesMut::new();
+/// // wl_display.get_registry with new id 2
+/// let get_registry = WaylandMessage::new(1, 1, Bytes::from(&2u32.to_ne_bytes()[..]));
+/// codec.encode(get_registry.clone(), &mut buf).unwrap();
+/// assert_eq!(buf.len(), 12);
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some(get_registry));
+/// ```
+#[derive(Debug, Default)]
+pub struct WaylandMessageCodec;
+
+/// A message of the Wayland wire protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaylandMessage {
+    /// Id of the object the message is a request to or an event of
+    pub object: u32,
+    pub opcode: u16,
+    /// The encoded arguments, padded to a multiple of four bytes
+    pub args: Bytes,
+}
+
+impl WaylandMessage {
+    pub fn new(object: u32, opcode: u16, args: Bytes) -> Self {
+        Self {
+            object,
+            opcode,
+            args,
+        }
+    }
+}
+
+impl WaylandMessageCodec {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+fn read_u32(src: &[u8]) -> u32 {
+    u32::from_ne_bytes(src[..4].try_into().unwrap())
+}
+
+impl Decoder for WaylandMessageCodec {
+    type Item = WaylandMessage;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.len() < HEADER_LEN {
+            return Ok(None);
+        }
+        let word = read_u32(&src[4..8]);
+        let size = (word >> 16) as usize;
+        if size < HEADER_LEN || !size.is_multiple_of(4) {
+            return Err(CodecError::Protocol("invalid message size".to_owned()));
+        }
+        if src.len() < size {
+            return Ok(None);
+        }
+
+        let header = src.split_to(HEADER_LEN);
+        Ok(Some(WaylandMessage {
+            object: read_u32(&header[..4]),
+            opcode: word as u16,
+            args: src.split_to(size - HEADER_LEN).freeze(),
+        }))
+    }
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        if src.len() < HEADER_LEN {
+            return None;
+        }
+        let size = (read_u32(&src[4..8]) >> 16) as usize;
+        size.checked_sub(src.len())
+    }
+}
+
+impl Encoder for WaylandMessageCodec {
+    type Item = WaylandMessage;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let size = HEADER_LEN + item.args.len();
+        if size > u16::MAX as usize {
+            return Err(CodecError::FrameTooLong {
+                max: u16::MAX as usize - HEADER_LEN,
+            });
+        }
+        if !size.is_multiple_of(4) {
+            return Err(CodecError::Protocol(
+                "arguments not padded to 32 bits".to_owned(),
+            ));
+        }
+        dst.reserve(size);
+        dst.extend_from_slice(&item.object.to_ne_bytes());
+        dst.extend_from_slice(&((size as u32) << 16 | u32::from(item.opcode)).to_ne_bytes());
+        dst.extend_from_slice(&item.args);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rejects_unaligned_size() {
+        let mut codec = WaylandMessageCodec::new();
+        let mut buf = BytesMut::new();
+        let message = WaylandMessage::new(3, 0, Bytes::from(&b"abcdefgh"[..]));
+        codec.encode(message.clone(), &mut buf).unwrap();
+        assert_eq!(codec.bytes_needed(&BytesMut::from(&buf[..10])), Some(6));
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(message));
+
+        let odd = WaylandMessage::new(3, 0, Bytes::from(&b"abc"[..]));
+        assert!(codec.encode(odd, &mut buf).is_err());
+        let mut buf = BytesMut::from(&[3, 0, 0, 0][..]);
+        buf.extend_from_slice(&(10u32 << 16).to_ne_bytes());
+        assert!(codec.decode(&mut buf).is_err());
+    }
+}
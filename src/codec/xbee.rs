@@ -0,0 +1,223 @@
+use crate::{CodecError, Decoder, Encoder};
+use bytes::{BigEndian, BufMut, ByteOrder, Bytes, BytesMut};
+
+const START: u8 = 0x7e;
+const ESCAPE: u8 = 0x7d;
+/// Bytes escaped in API mode 2: start delimiter, escape, XON and XOFF
+const ESCAPED: [u8; 4] = [START, ESCAPE, 0x11, 0x13];
+
+/// A codec for the API frames of Digi XBee radio modules.
+///
+/// A frame starts with the delimiter `0x7e`, followed by the big endian
+/// length of the frame data, the frame data starting with the frame type
+/// and a checksum, `0xff` minus the sum of the frame data. In API mode 2,
+/// set with [`escaped`](Self::escaped), every other byte that is one of
+/// `0x7e`, `0x7d`, `0x11` and `0x13` is sent as `0x7d` followed by the byte
+/// xor `0x20`.
+///
+/// Bytes before a start delimiter are skipped. A frame failing its checksum
+/// is dropped with an error, and decoding continues with the next start
+/// delimiter, which may be inside the dropped frame when its length was
+/// corrupted. In API mode 2 a start delimiter inside a frame means that the
+/// frame was cut off, it is dropped the same way.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, Encoder, XbeeApiCodec, XbeeFrame};
+///
+/// let mut codec = XbeeApiCodec::escaped();
+/// let mut buf = BytesMut::new();
+/// // AT command NJ, with frame id 0x7d which is escaped
+/// let command = XbeeFrame::new(XbeeFrame::AT_COMMAND, Bytes::from("\x7dNJ"));
+/// codec.encode(command.clone(), &mut buf).unwrap();
+/// assert_eq!(&buf[..], b"\x7e\x00\x04\x08\x7d\x5dNJ\xe2");
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap(), Some(command));
+/// ```
+#[derive(Debug)]
+pub struct XbeeApiCodec {
+    escaped: bool,
+    max_frame_len: usize,
+}
+
+/// An API frame of an XBee module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XbeeFrame {
+    /// Frame type, see the associated constants
+    pub frame_type: u8,
+    /// Frame data after the frame type
+    pub data: Bytes,
+}
+
+impl XbeeFrame {
+    pub const AT_COMMAND: u8 = 0x08;
+    pub const AT_COMMAND_QUEUE: u8 = 0x09;
+    pub const TRANSMIT_REQUEST: u8 = 0x10;
+    pub const EXPLICIT_ADDRESSING_COMMAND: u8 = 0x11;
+    pub const REMOTE_AT_COMMAND: u8 = 0x17;
+    pub const AT_COMMAND_RESPONSE: u8 = 0x88;
+    pub const MODEM_STATUS: u8 = 0x8a;
+    pub const TRANSMIT_STATUS: u8 = 0x8b;
+    pub const RECEIVE_PACKET: u8 = 0x90;
+    pub const EXPLICIT_RX_INDICATOR: u8 = 0x91;
+    pub const IO_SAMPLE_RX_INDICATOR: u8 = 0x92;
+    pub const REMOTE_AT_COMMAND_RESPONSE: u8 = 0x97;
+
+    pub fn new(frame_type: u8, data: Bytes) -> Self {
+        Self { frame_type, data }
+    }
+}
+
+impl XbeeApiCodec {
+    /// Codec for API mode 1, without escaping
+    pub fn new() -> Self {
+        Self {
+            escaped: false,
+            max_frame_len: u16::MAX as usize,
+        }
+    }
+
+    /// Codec for API mode 2, with escaping
+    pub fn escaped() -> Self {
+        Self {
+            escaped: true,
+            ..Self::new()
+        }
+    }
+
+    /// Drop frames with more than `max` bytes of frame data
+    pub fn max_frame_len(mut self, max: usize) -> Self {
+        self.max_frame_len = max;
+        self
+    }
+}
+
+impl Default for XbeeApiCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+impl Decoder for XbeeApiCodec {
+    type Item = XbeeFrame;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match src.iter().position(|&b| b == START) {
+            Some(start) => src.advance(start),
+            None => {
+                src.clear();
+                return Ok(None);
+            }
+        }
+
+        // Unescaped length, frame data and checksum
+        let mut frame = Vec::new();
+        let mut needed = 2;
+        let mut at = 1;
+        while frame.len() < needed {
+            let mut b = match src.get(at) {
+                Some(&b) => b,
+                None => return Ok(None),
+            };
+            if self.escaped && b == START {
+                src.advance(at);
+                return Err(invalid("XBee frame cut off by a start delimiter"));
+            }
+            if self.escaped && b == ESCAPE {
+                b = match src.get(at + 1) {
+                    Some(&b) => b ^ 0x20,
+                    None => return Ok(None),
+                };
+                at += 1;
+            }
+            at += 1;
+            frame.push(b);
+
+            if frame.len() == 2 {
+                let len = usize::from(BigEndian::read_u16(&frame));
+                if len == 0 || len > self.max_frame_len {
+                    src.advance(1);
+                    return Err(invalid("invalid XBee frame length"));
+                }
+                needed = 2 + len + 1;
+            }
+        }
+
+        let sum = frame[2..].iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+        if sum != 0xff {
+            src.advance(1);
+            return Err(invalid("XBee frame checksum mismatch"));
+        }
+        src.advance(at);
+        Ok(Some(XbeeFrame {
+            frame_type: frame[2],
+            data: Bytes::from(&frame[3..needed - 1]),
+        }))
+    }
+}
+
+impl Encoder for XbeeApiCodec {
+    type Item = XbeeFrame;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let len = 1 + item.data.len();
+        if len > self.max_frame_len {
+            return Err(CodecError::FrameTooLong {
+                max: self.max_frame_len,
+            });
+        }
+        let sum = item
+            .data
+            .iter()
+            .fold(item.frame_type, |sum, &b| sum.wrapping_add(b));
+
+        let mut frame = Vec::with_capacity(2 + len + 1);
+        frame.extend_from_slice(&[(len >> 8) as u8, len as u8, item.frame_type]);
+        frame.extend_from_slice(&item.data);
+        frame.push(0xff - sum);
+
+        dst.reserve(1 + 2 * frame.len());
+        dst.put_u8(START);
+        for b in frame {
+            if self.escaped && ESCAPED.contains(&b) {
+                dst.put_slice(&[ESCAPE, b ^ 0x20]);
+            } else {
+                dst.put_u8(b);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resyncs_after_bad_frames() {
+        let mut codec = XbeeApiCodec::escaped();
+        let status = XbeeFrame::new(XbeeFrame::MODEM_STATUS, Bytes::from("\x06"));
+        let mut frame = BytesMut::new();
+        codec.encode(status.clone(), &mut frame).unwrap();
+
+        // Garbage, a cut off frame, a corrupted frame and a good one split
+        let mut buf = BytesMut::from(&b"\x00\x13"[..]);
+        buf.extend_from_slice(&frame[..3]);
+        buf.extend_from_slice(&frame[..4]);
+        buf.extend_from_slice(b"\x00");
+        buf.extend_from_slice(&frame[..2]);
+        assert!(codec.decode(&mut buf).is_err());
+        assert!(codec.decode(&mut buf).is_err());
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(&frame[2..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(status));
+        assert!(buf.is_empty());
+    }
+}
@@ -0,0 +1,93 @@
+//! Interoperability with the tokio codec traits.
+//!
+//! Enabled with the `tokio` feature.
+use crate::{Decoder, Encoder};
+use bytes::BytesMut;
+use std::io::{Error, ErrorKind};
+
+/// Wraps a codec written against this crate's `Encoder` and `Decoder` so it
+/// can be used with tokio's `Framed`, `FramedRead` and `FramedWrite`.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures_codec::{compat::TokioCompat, LinesCodec};
+/// use tokio_codec::Decoder;
+///
+/// let mut codec = TokioCompat::new(LinesCodec::new());
+/// let mut buf = BytesMut::from(&b"Hello\n"[..]);
+///
+/// let line = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(line, "Hello\n");
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioCompat<C>(pub C);
+
+impl<C> TokioCompat<C> {
+    /// Wrap `codec` for use with tokio
+    pub fn new(codec: C) -> Self {
+        TokioCompat(codec)
+    }
+
+    /// Release the wrapped codec
+    pub fn into_inner(self) -> C {
+        self.0
+    }
+}
+
+impl<C: Decoder> tokio_codec::Decoder for TokioCompat<C> {
+    type Item = C::Item;
+    type Error = C::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.0.decode(src)
+    }
+
+    /// Forwards to `Decoder::decode_eof`, failing with `UnexpectedEof` if it
+    /// leaves bytes that do not make up a frame, as tokio's default does
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.0.decode_eof(src)? {
+            Some(item) => Ok(Some(item)),
+            None if src.is_empty() => Ok(None),
+            None => Err(Error::new(ErrorKind::UnexpectedEof, "bytes remaining in stream").into()),
+        }
+    }
+}
+
+impl<C: Encoder> tokio_codec::Encoder for TokioCompat<C> {
+    type Item = C::Item;
+    type Error = C::Error;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.0.encode(item, dst)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TokioCompat;
+    use crate::LinesCodec;
+    use bytes::BytesMut;
+    use tokio_codec::{Decoder, Encoder};
+
+    #[test]
+    fn roundtrip() {
+        let mut codec = TokioCompat::new(LinesCodec::new());
+        let mut buf = BytesMut::new();
+        codec.encode("Hello\n".to_owned(), &mut buf).unwrap();
+        codec.encode("World\n".to_owned(), &mut buf).unwrap();
+
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "Hello\n");
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "World\n");
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+    }
+
+    #[test]
+    fn forwards_decode_eof() {
+        let mut codec = TokioCompat::new(LinesCodec::new());
+        let mut buf = BytesMut::from(&b"Hello"[..]);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        assert_eq!(codec.decode_eof(&mut buf).unwrap().unwrap(), "Hello");
+        assert!(codec.decode_eof(&mut buf).unwrap().is_none());
+    }
+}
@@ -0,0 +1,156 @@
+//! Compressed transports built on `async-compression`.
+//!
+//! Enabled with the `compression` feature. `async-compression` implements
+//! the I/O traits of the released `futures-io` 0.3, so [`decompress`] and
+//! [`compress`] put its `bufread` decoders and `write` encoders between the
+//! I/O and a framer, bridging both sides with [`IoCompat`].
+//!
+//! Neither side adds a buffer of its own. The decoder takes its input
+//! straight from the buffer of the `AsyncBufRead` it wraps and decompresses
+//! into the read buffer of `FramedRead`, so do not wrap it in another
+//! `BufReader`. The encoder compresses straight from the write buffer of
+//! `FramedWrite`. [`CompressedTransport`] joins the two halves into I/O for
+//! `Framed`.
+//!
+//! # Example
+//! ```
+//! use async_compression::futures::{bufread::GzipDecoder, write::GzipEncoder};
+//! use futures::io::{AsyncReadExt, BufReader};
+//! use futures::{executor, SinkExt, TryStreamExt};
+//! use futures_codec::compression::{compress, decompress, CompressedTransport};
+//! use futures_codec::{Framed, LinesCodec};
+//! use romio::TcpStream;
+//!
+//! async fn echo(stream: TcpStream) {
+//!     let (read, write) = stream.split();
+//!     let io = CompressedTransport::new(
+//!         decompress(BufReader::new(read), GzipDecoder::new),
+//!         compress(write, GzipEncoder::new),
+//!     );
+//!     let mut framed = Framed::new(io, LinesCodec::new());
+//!
+//!     while let Some(line) = framed.try_next().await.unwrap() {
+//!         framed.send(line).await.unwrap();
+//!     }
+//!     framed.close().await.unwrap();
+//! }
+//! ```
+use crate::IoCompat;
+
+use futures::io::{AsyncBufRead, AsyncRead, AsyncWrite};
+use pin_project_lite::pin_project;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Decompress `io` with a `bufread` decoder of `async-compression`, such as
+/// `GzipDecoder::new`.
+pub fn decompress<T, D, F>(io: T, decoder: F) -> IoCompat<D>
+where
+    T: AsyncBufRead,
+    D: futures_io::AsyncRead,
+    F: FnOnce(IoCompat<T>) -> D,
+{
+    IoCompat::new(decoder(IoCompat::new(io)))
+}
+
+/// Compress writes to `io` with a `write` encoder of `async-compression`,
+/// such as `GzipEncoder::new`.
+///
+/// The encoder only writes its trailer when it is closed, so close the
+/// framer when done.
+pub fn compress<T, E, F>(io: T, encoder: F) -> IoCompat<E>
+where
+    T: AsyncWrite,
+    E: futures_io::AsyncWrite,
+    F: FnOnce(IoCompat<T>) -> E,
+{
+    IoCompat::new(encoder(IoCompat::new(io)))
+}
+
+pin_project! {
+    /// Joins a decompressing reader and a compressing writer into a single
+    /// transport for `Framed`.
+    #[derive(Debug)]
+    pub struct CompressedTransport<R, W> {
+        #[pin]
+        read: R,
+        #[pin]
+        write: W,
+    }
+}
+
+impl<R, W> CompressedTransport<R, W>
+where
+    R: AsyncRead,
+    W: AsyncWrite,
+{
+    pub fn new(read: R, write: W) -> Self {
+        Self { read, write }
+    }
+
+    /// Release the reader and writer
+    pub fn into_inner(self) -> (R, W) {
+        (self.read, self.write)
+    }
+}
+
+impl<R: AsyncRead, W> AsyncRead for CompressedTransport<R, W> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().read.poll_read(cx, buf)
+    }
+}
+
+impl<R, W: AsyncWrite> AsyncWrite for CompressedTransport<R, W> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.project().write.poll_write(cx, buf)
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        self.project().write.poll_flush(cx)
+    }
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        self.project().write.poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Framed, FramedRead, FramedWrite, LinesCodec};
+    use async_compression::futures::{bufread::GzipDecoder, write::GzipEncoder};
+    use futures::{executor, SinkExt, TryStreamExt};
+
+    #[test]
+    fn transport_through_gzip() {
+        let mut framed =
+            FramedWrite::new(compress(Vec::new(), GzipEncoder::new), LinesCodec::new());
+        executor::block_on(framed.send("Hello\n".to_owned())).unwrap();
+        executor::block_on(framed.close()).unwrap();
+        let gzipped = framed.release().0.into_inner().into_inner().into_inner();
+
+        let io = CompressedTransport::new(
+            decompress(&gzipped[..], GzipDecoder::new),
+            compress(Vec::new(), GzipEncoder::new),
+        );
+        let mut framed = Framed::new(io, LinesCodec::new());
+        executor::block_on(async {
+            assert_eq!(framed.try_next().await.unwrap().unwrap(), "Hello\n");
+            assert!(framed.try_next().await.unwrap().is_none());
+            framed.send("World\n".to_owned()).await.unwrap();
+            framed.close().await.unwrap();
+        });
+        let (io, _) = framed.release();
+        let gzipped = io.into_inner().1.into_inner().into_inner().into_inner();
+
+        let framed = FramedRead::new(
+            decompress(&gzipped[..], GzipDecoder::new),
+            LinesCodec::new(),
+        );
+        let lines: Vec<_> = executor::block_on(framed.try_collect()).unwrap();
+        assert_eq!(lines, ["World\n"]);
+    }
+}
@@ -0,0 +1,184 @@
+use super::{Decoder, Encoder};
+
+use bytes::BytesMut;
+use futures::{ready, Sink, Stream};
+use std::io::{Error, ErrorKind};
+use std::marker::Unpin;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Datagram based I/O, for use with `DatagramFramed`.
+///
+/// Every call sends or receives exactly one datagram along with the address of
+/// the peer.
+pub trait AsyncDatagram {
+    /// The type of peer addresses
+    type Addr;
+
+    /// Attempt to send the datagram in `buf` to `addr`
+    fn poll_send_to(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+        addr: &Self::Addr,
+    ) -> Poll<Result<usize, Error>>;
+
+    /// Attempt to receive a single datagram into `buf`
+    fn poll_recv_from(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<(usize, Self::Addr), Error>>;
+}
+
+const MAX_DATAGRAM_SIZE: usize = 64 * 1024;
+
+/// A unified `Stream` and `Sink` interface to a datagram socket, using the
+/// `Encoder` and `Decoder` traits to encode and decode one frame per datagram.
+///
+/// Items are paired with the address they were received from or should be
+/// sent to. A datagram the decoder can not make a frame out of is dropped.
+pub struct DatagramFramed<T: AsyncDatagram, C> {
+    inner: T,
+    codec: C,
+    read_buf: Vec<u8>,
+    write_buf: BytesMut,
+    out_addr: Option<T::Addr>,
+}
+
+impl<T, C> DatagramFramed<T, C>
+where
+    T: AsyncDatagram,
+    C: Decoder + Encoder,
+{
+    pub fn new(inner: T, codec: C) -> Self {
+        Self {
+            inner,
+            codec,
+            read_buf: vec![0; MAX_DATAGRAM_SIZE],
+            write_buf: BytesMut::new(),
+            out_addr: None,
+        }
+    }
+
+    /// Release the socket and Codec
+    pub fn release(self) -> (T, C) {
+        (self.inner, self.codec)
+    }
+}
+
+impl<T, C> Stream for DatagramFramed<T, C>
+where
+    T: AsyncDatagram + Unpin,
+    T::Addr: Unpin,
+    C: Decoder + Unpin,
+{
+    type Item = Result<(C::Item, T::Addr), C::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            let recv = Pin::new(&mut this.inner).poll_recv_from(cx, &mut this.read_buf);
+            let (n, addr) = ready!(recv)?;
+            let mut datagram = BytesMut::from(&this.read_buf[..n]);
+
+            if let Some(item) = this.codec.decode(&mut datagram)? {
+                return Poll::Ready(Some(Ok((item, addr))));
+            }
+        }
+    }
+}
+
+impl<T, C> Sink<(C::Item, T::Addr)> for DatagramFramed<T, C>
+where
+    T: AsyncDatagram + Unpin,
+    T::Addr: Unpin,
+    C: Encoder + Unpin,
+{
+    type Error = C::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        if self.out_addr.is_some() {
+            ready!(self.poll_flush(cx))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+    fn start_send(mut self: Pin<&mut Self>, item: (C::Item, T::Addr)) -> Result<(), Self::Error> {
+        let this = &mut *self;
+        let (frame, addr) = item;
+        this.codec.encode(frame, &mut this.write_buf)?;
+        this.out_addr = Some(addr);
+        Ok(())
+    }
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        let this = &mut *self;
+        let addr = match this.out_addr {
+            Some(ref addr) => addr,
+            None => return Poll::Ready(Ok(())),
+        };
+
+        let n = ready!(Pin::new(&mut this.inner).poll_send_to(cx, &this.write_buf, addr))?;
+        let len = this.write_buf.len();
+        this.write_buf.clear();
+        this.out_addr = None;
+
+        if n != len {
+            return Poll::Ready(Err(
+                Error::new(ErrorKind::WriteZero, "failed to write entire datagram").into()
+            ));
+        }
+        Poll::Ready(Ok(()))
+    }
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.poll_flush(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::BytesCodec;
+
+    use bytes::Bytes;
+    use futures::{executor, SinkExt, TryStreamExt};
+    use std::collections::VecDeque;
+
+    struct Loopback(VecDeque<(Vec<u8>, u16)>);
+
+    impl AsyncDatagram for Loopback {
+        type Addr = u16;
+
+        fn poll_send_to(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+            addr: &u16,
+        ) -> Poll<Result<usize, Error>> {
+            self.0.push_back((buf.to_vec(), *addr));
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_recv_from(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<Result<(usize, u16), Error>> {
+            let (datagram, addr) = self.0.pop_front().expect("no datagram queued");
+            buf[..datagram.len()].copy_from_slice(&datagram);
+            Poll::Ready(Ok((datagram.len(), addr)))
+        }
+    }
+
+    #[test]
+    fn frame_per_datagram() {
+        let mut framed = DatagramFramed::new(Loopback(VecDeque::new()), BytesCodec {});
+        executor::block_on(framed.send((Bytes::from("Hello"), 1))).unwrap();
+        executor::block_on(framed.send((Bytes::from("World"), 2))).unwrap();
+
+        let next = executor::block_on(framed.try_next()).unwrap().unwrap();
+        assert_eq!(next, (Bytes::from("Hello"), 1));
+        let next = executor::block_on(framed.try_next()).unwrap().unwrap();
+        assert_eq!(next, (Bytes::from("World"), 2));
+    }
+}
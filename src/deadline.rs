@@ -0,0 +1,179 @@
+use super::{Encoder, FramedWrite};
+
+use futures::io::AsyncWrite;
+use futures::Sink;
+use pin_project_lite::pin_project;
+use std::future::Future;
+use std::io::{self, ErrorKind};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::{error, fmt};
+
+/// A frame that was not written before its deadline, usually because the
+/// peer stopped reading.
+///
+/// Returned inside an `io::Error` of kind `TimedOut` by the adapter created
+/// with [`FramedWrite::with_write_deadline`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteTimedOut {
+    frame: u64,
+}
+
+impl WriteTimedOut {
+    /// Index of the frame that was stuck, counting from zero
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+}
+
+impl fmt::Display for WriteTimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "writing frame {} timed out", self.frame)
+    }
+}
+
+impl error::Error for WriteTimedOut {}
+
+impl From<WriteTimedOut> for io::Error {
+    fn from(err: WriteTimedOut) -> Self {
+        io::Error::new(ErrorKind::TimedOut, err)
+    }
+}
+
+pin_project! {
+    /// A `FramedWrite` that fails when a single frame takes too long to write.
+    ///
+    /// Created by [`FramedWrite::with_write_deadline`].
+    pub struct WriteDeadline<S, D, F> {
+        #[pin]
+        framed: S,
+        timer: D,
+        #[pin]
+        deadline: Option<F>,
+        // Frame the deadline is running for
+        frame: u64,
+    }
+}
+
+impl<S, D, F> WriteDeadline<S, D, F> {
+    pub(crate) fn new(framed: S, timer: D) -> Self {
+        Self {
+            framed,
+            timer,
+            deadline: None,
+            frame: 0,
+        }
+    }
+
+    /// Return the underlying framer
+    pub fn into_inner(self) -> S {
+        self.framed
+    }
+}
+
+impl<T, E, D, F> WriteDeadline<FramedWrite<T, E>, D, F>
+where
+    T: AsyncWrite,
+    E: Encoder,
+    D: FnMut() -> F,
+    F: Future<Output = ()>,
+{
+    /// Poll a write of the framer, timing out if the frame being written
+    /// does not complete before its deadline.
+    fn poll_deadline(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        mut write: impl FnMut(Pin<&mut FramedWrite<T, E>>, &mut Context) -> Poll<Result<(), E::Error>>,
+    ) -> Poll<Result<(), E::Error>> {
+        let mut this = self.project();
+        if let Poll::Ready(done) = write(this.framed.as_mut(), cx) {
+            this.deadline.set(None);
+            return Poll::Ready(done);
+        }
+
+        // Restart the deadline whenever a frame was completed
+        let frame = this.framed.frames_written();
+        if frame != *this.frame || this.deadline.is_none() {
+            *this.frame = frame;
+            this.deadline.set(Some((this.timer)()));
+        }
+        if let Some(deadline) = this.deadline.as_mut().as_pin_mut() {
+            if deadline.poll(cx).is_ready() {
+                this.deadline.set(None);
+                let err = io::Error::from(WriteTimedOut { frame });
+                return Poll::Ready(Err(err.into()));
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl<T, E, D, F> Sink<E::Item> for WriteDeadline<FramedWrite<T, E>, D, F>
+where
+    T: AsyncWrite,
+    E: Encoder,
+    D: FnMut() -> F,
+    F: Future<Output = ()>,
+{
+    type Error = E::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.poll_deadline(cx, Sink::poll_ready)
+    }
+    fn start_send(self: Pin<&mut Self>, item: E::Item) -> Result<(), Self::Error> {
+        self.project().framed.start_send(item)
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.poll_deadline(cx, Sink::poll_flush)
+    }
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.poll_deadline(cx, Sink::poll_close)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LinesCodec;
+    use futures::{executor, future, SinkExt};
+
+    /// Accepts `remaining` bytes, then never again
+    struct StopsReading {
+        remaining: usize,
+    }
+
+    impl AsyncWrite for StopsReading {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            if self.remaining == 0 {
+                return Poll::Pending;
+            }
+            let n = buf.len().min(self.remaining);
+            self.remaining -= n;
+            Poll::Ready(Ok(n))
+        }
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn stuck_frame_times_out() {
+        let io = StopsReading { remaining: 8 };
+        let mut framed = FramedWrite::new(io, LinesCodec::new())
+            .with_write_deadline(|| future::ready(()));
+
+        executor::block_on(framed.send("Hello\n".to_owned())).unwrap();
+        let err = executor::block_on(framed.send("World\n".to_owned())).unwrap_err();
+        let err = io::Error::from(err);
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        let timed_out = err.get_ref().unwrap().downcast_ref::<WriteTimedOut>().unwrap();
+        assert_eq!(timed_out.frame(), 1);
+    }
+}
@@ -1,4 +1,4 @@
-use std::io::Error;
+use std::io::{Error, ErrorKind};
 use bytes::BytesMut;
 use super::framed::Fuse;
 use super::framed_write::FramedWrite2;
@@ -12,6 +12,111 @@ pub trait Decoder {
 
     /// Decode an item from the src `BytesMut` into an item
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error>;
+
+    /// A hint of how many more bytes must be appended to `src` before the
+    /// next frame is complete, if the decoder knows it.
+    ///
+    /// `FramedRead` uses this to read large frames straight into its buffer,
+    /// and will not call `decode` again until that many bytes have arrived.
+    fn bytes_needed(&self, _src: &BytesMut) -> Option<usize> {
+        None
+    }
+
+    /// Decode an item from what is left in `src` after the I/O has ended
+    ///
+    /// Only called by a `FramedRead` configured with `IncompleteEof::Decode`.
+    /// The default implementation is `decode`.
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.decode(src)
+    }
+
+    /// Forget anything remembered about the bytes in the buffer, such as how
+    /// far it was already searched
+    ///
+    /// `FramedRead` calls this before decoding again whenever bytes were added
+    /// to or taken from its buffer by something other than `decode`. State
+    /// about frames already decoded should be kept.
+    fn reset(&mut self) {}
+
+    /// Decode the frames of a complete buffer without any I/O
+    ///
+    /// The iterator ends after the last frame, or fails with `UnexpectedEof`
+    /// if `src` ends in an incomplete frame.
+    ///
+    /// ```
+    /// use futures_codec::{Decoder, LinesCodec};
+    ///
+    /// let mut codec = LinesCodec::new();
+    /// let lines: Vec<_> = codec.iter(b"Hello\nWorld\n").map(Result::unwrap).collect();
+    /// assert_eq!(lines, ["Hello\n", "World\n"]);
+    /// ```
+    fn iter<'a>(&'a mut self, src: &[u8]) -> DecodeIter<'a, Self>
+    where
+        Self: Sized,
+    {
+        DecodeIter {
+            decoder: self,
+            buffer: BytesMut::from(src),
+            done: false,
+        }
+    }
+
+    /// Decode all frames of a complete buffer, see `iter`
+    fn decode_all(&mut self, src: &[u8]) -> Result<Vec<Self::Item>, Self::Error>
+    where
+        Self: Sized,
+    {
+        self.iter(src).collect()
+    }
+}
+
+/// An `Iterator` of the frames in a buffer, returned by `Decoder::iter`.
+#[derive(Debug)]
+pub struct DecodeIter<'a, D> {
+    decoder: &'a mut D,
+    buffer: BytesMut,
+    done: bool,
+}
+
+impl<D: Decoder> Iterator for DecodeIter<'_, D> {
+    type Item = Result<D::Item, D::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let next = match self.decoder.decode(&mut self.buffer) {
+            Ok(Some(item)) => return Some(Ok(item)),
+            Ok(None) if self.buffer.is_empty() => None,
+            Ok(None) => Some(Err(Error::new(ErrorKind::UnexpectedEof, "bytes remaining in stream").into())),
+            Err(e) => Some(Err(e)),
+        };
+        self.done = true;
+        next
+    }
+}
+
+/// Decoding of frames that borrow from the read buffer, for use with
+/// `FramedRead::next_borrowed`.
+///
+/// Decoding happens in two steps: `frame_len` finds the extent of the next
+/// frame, after which `decode_ref` turns exactly those bytes into an item.
+pub trait DecoderRef {
+    /// The type of items returned by `decode_ref`, borrowing from the buffer
+    type Item<'a>;
+    /// The type of decoding errors.
+    type Error: From<Error>;
+
+    /// Return the length of the next complete frame at the start of `src`
+    fn frame_len(&mut self, src: &[u8]) -> Result<Option<usize>, Self::Error>;
+
+    /// Decode a complete frame as delimited by `frame_len`
+    fn decode_ref<'a>(&mut self, frame: &'a [u8]) -> Result<Self::Item<'a>, Self::Error>;
+
+    /// Forget anything remembered about the bytes in the buffer, see
+    /// `Decoder::reset`
+    fn reset(&mut self) {}
 }
 
 impl<T, U: Decoder> Decoder for Fuse<T, U> {
@@ -21,6 +126,35 @@ impl<T, U: Decoder> Decoder for Fuse<T, U> {
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
         self.1.decode(src)
     }
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        self.1.bytes_needed(src)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.1.decode_eof(src)
+    }
+
+    fn reset(&mut self) {
+        Decoder::reset(&mut self.1)
+    }
+}
+
+impl<T, U: DecoderRef> DecoderRef for Fuse<T, U> {
+    type Item<'a> = U::Item<'a>;
+    type Error = U::Error;
+
+    fn frame_len(&mut self, src: &[u8]) -> Result<Option<usize>, Self::Error> {
+        self.1.frame_len(src)
+    }
+
+    fn decode_ref<'a>(&mut self, frame: &'a [u8]) -> Result<Self::Item<'a>, Self::Error> {
+        self.1.decode_ref(frame)
+    }
+
+    fn reset(&mut self) {
+        DecoderRef::reset(&mut self.1)
+    }
 }
 
 impl<T: Decoder> Decoder for FramedWrite2<T> {
@@ -30,4 +164,31 @@ impl<T: Decoder> Decoder for FramedWrite2<T> {
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
         self.inner.decode(src)
     }
-}
\ No newline at end of file
+
+    fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+        self.inner.bytes_needed(src)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.inner.decode_eof(src)
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LinesCodec;
+
+    #[test]
+    fn decode_all_incomplete() {
+        let mut codec = LinesCodec::new();
+        assert_eq!(codec.decode_all(b"Hello\n").unwrap(), ["Hello\n"]);
+
+        let err = codec.decode_all(b"Hello\nWorld").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,144 @@
+//! Framing over the `embedded-io-async` traits.
+//!
+//! Enabled with the `embedded-io` feature. These adapters use the same
+//! `Encoder` and `Decoder` traits and buffering as `FramedRead` and
+//! `FramedWrite`, so codecs can be shared between microcontrollers and
+//! servers. Errors of the I/O are converted to `std::io::Error` by their
+//! `ErrorKind`.
+//!
+//! ```
+//! use futures::executor;
+//! use futures_codec::{embedded, LinesCodec};
+//!
+//! executor::block_on(async move {
+//!     let mut buf = Vec::new();
+//!     let mut framed = embedded::FramedWrite::new(&mut buf, LinesCodec::new());
+//!     framed.send("Hello\n".to_owned()).await.unwrap();
+//!
+//!     let mut framed = embedded::FramedRead::new(&buf[..], LinesCodec::new());
+//!     assert_eq!(framed.next().await.unwrap().unwrap(), "Hello\n");
+//!     assert!(framed.next().await.is_none());
+//! })
+//! ```
+use super::{Decoder, Encoder, FrameAccumulator};
+
+use bytes::BytesMut;
+use embedded_io_async::{Error as _, Read, Write};
+use std::io;
+
+const INITIAL_CAPACITY: usize = 8 * 1024;
+
+fn io_error<E: embedded_io_async::Error>(err: E) -> io::Error {
+    io::Error::from(io::ErrorKind::from(err.kind()))
+}
+
+/// Messages decoded from an `embedded_io_async::Read`.
+pub struct FramedRead<T, D> {
+    inner: T,
+    frames: FrameAccumulator<D>,
+}
+
+impl<T, D> FramedRead<T, D>
+where
+    T: Read,
+    D: Decoder,
+{
+    pub fn new(inner: T, decoder: D) -> Self {
+        Self {
+            inner,
+            frames: FrameAccumulator::new(decoder),
+        }
+    }
+
+    /// Decode the next message, or return `None` at the end of the I/O
+    pub async fn next(&mut self) -> Option<Result<D::Item, D::Error>> {
+        let mut buf = [0u8; INITIAL_CAPACITY];
+
+        loop {
+            match self.frames.decode() {
+                Ok(Some(item)) => return Some(Ok(item)),
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
+
+            let n = match self.inner.read(&mut buf).await {
+                Ok(n) => n,
+                Err(e) if e.kind() == embedded_io_async::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(io_error(e).into())),
+            };
+
+            if n == 0 {
+                return self.frames.decode_eof().transpose();
+            }
+            self.frames.extend(&buf[..n]);
+        }
+    }
+
+    /// Release the I/O and Decoder
+    pub fn release(self) -> (T, D) {
+        (self.inner, self.frames.into_parts().0)
+    }
+}
+
+/// Frames encoded to an `embedded_io_async::Write`.
+pub struct FramedWrite<T, E> {
+    inner: T,
+    encoder: E,
+    buffer: BytesMut,
+}
+
+impl<T, E> FramedWrite<T, E>
+where
+    T: Write,
+    E: Encoder,
+{
+    pub fn new(inner: T, encoder: E) -> Self {
+        Self {
+            inner,
+            encoder,
+            buffer: BytesMut::with_capacity(INITIAL_CAPACITY),
+        }
+    }
+
+    /// Encode `item` and flush it to the I/O
+    pub async fn send(&mut self, item: E::Item) -> Result<(), E::Error> {
+        self.feed(item)?;
+        self.flush().await
+    }
+
+    /// Encode `item` into the buffer without writing it
+    pub fn feed(&mut self, item: E::Item) -> Result<(), E::Error> {
+        self.encoder.encode(item, &mut self.buffer)
+    }
+
+    /// Write out all buffered frames and flush the I/O
+    pub async fn flush(&mut self) -> Result<(), E::Error> {
+        self.inner.write_all(&self.buffer).await.map_err(io_error)?;
+        self.buffer.clear();
+        self.inner.flush().await.map_err(io_error)?;
+        Ok(())
+    }
+
+    /// Release the I/O and Encoder
+    pub fn release(self) -> (T, E) {
+        (self.inner, self.encoder)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LinesCodec;
+    use futures::executor;
+
+    #[test]
+    fn incomplete_frame_at_eof() {
+        let mut framed = FramedRead::new(&b"Hello\nWorld"[..], LinesCodec::new());
+
+        executor::block_on(async {
+            assert_eq!(framed.next().await.unwrap().unwrap(), "Hello\n");
+            let err = framed.next().await.unwrap().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        });
+    }
+}
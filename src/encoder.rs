@@ -1,4 +1,4 @@
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
 use std::io::Error;
 use super::framed::Fuse;
 
@@ -11,6 +11,27 @@ pub trait Encoder {
 
     /// Encodes an item into the `BytesMut` provided by dst.
     fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error>;
+
+    /// Encodes an item as a header written to `header` followed by a body that
+    /// is written out as is, without being copied into the write buffer.
+    ///
+    /// The default implementation encodes the whole item into `header`.
+    fn encode_zero_copy(
+        &mut self,
+        item: Self::Item,
+        header: &mut BytesMut,
+    ) -> Result<Option<Bytes>, Self::Error> {
+        self.encode(item, header).map(|()| None)
+    }
+
+    /// Encode the frame announcing the end of the stream to `dst`, for
+    /// protocols that have one.
+    ///
+    /// Called by `close_graceful` before the I/O is closed. The default
+    /// implementation encodes nothing.
+    fn encode_close(&mut self, _dst: &mut BytesMut) -> Result<(), Self::Error> {
+        Ok(())
+    }
 }
 
 impl<T, U: Encoder> Encoder for Fuse<T, U> {
@@ -20,4 +41,16 @@ impl<T, U: Encoder> Encoder for Fuse<T, U> {
     fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
         self.1.encode(item, dst)
     }
+
+    fn encode_zero_copy(
+        &mut self,
+        item: Self::Item,
+        header: &mut BytesMut,
+    ) -> Result<Option<Bytes>, Self::Error> {
+        self.1.encode_zero_copy(item, header)
+    }
+
+    fn encode_close(&mut self, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.1.encode_close(dst)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,90 @@
+use std::{error, fmt, io, str};
+
+/// Errors of the bundled codecs.
+#[derive(Debug)]
+pub enum CodecError {
+    /// The I/O failed, or the stream ended in the middle of a frame.
+    Io(io::Error),
+    /// A frame that must be text is not valid UTF-8.
+    Utf8(str::Utf8Error),
+    /// A frame exceeds the maximum length of the codec.
+    FrameTooLong { max: usize },
+    /// The data violates the protocol.
+    Protocol(String),
+}
+
+impl CodecError {
+    /// The closest `io::ErrorKind`, for code that only cares about that
+    pub fn kind(&self) -> io::ErrorKind {
+        match self {
+            CodecError::Io(e) => e.kind(),
+            _ => io::ErrorKind::InvalidData,
+        }
+    }
+}
+
+impl From<io::Error> for CodecError {
+    fn from(e: io::Error) -> Self {
+        CodecError::Io(e)
+    }
+}
+
+impl From<str::Utf8Error> for CodecError {
+    fn from(e: str::Utf8Error) -> Self {
+        CodecError::Utf8(e)
+    }
+}
+
+impl From<CodecError> for io::Error {
+    fn from(e: CodecError) -> Self {
+        match e {
+            CodecError::Io(e) => e,
+            e => io::Error::new(e.kind(), e),
+        }
+    }
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Io(e) => e.fmt(f),
+            CodecError::Utf8(e) => e.fmt(f),
+            CodecError::FrameTooLong { max } => {
+                write!(f, "frame exceeds the maximum length of {} bytes", max)
+            }
+            CodecError::Protocol(msg) => write!(f, "protocol error: {}", msg),
+        }
+    }
+}
+
+impl error::Error for CodecError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            CodecError::Io(e) => Some(e),
+            CodecError::Utf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Decoder, LinesCodec};
+    use bytes::BytesMut;
+
+    #[test]
+    fn invalid_utf8() {
+        let mut buf = BytesMut::from(&b"\xff\n"[..]);
+        match LinesCodec::new().decode(&mut buf) {
+            Err(CodecError::Utf8(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn into_io_error() {
+        let e = io::Error::from(CodecError::FrameTooLong { max: 8 });
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+}
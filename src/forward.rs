@@ -0,0 +1,125 @@
+use super::{Decoder, Encoder, FramedRead, FramedWrite};
+use futures::future::{self, poll_fn};
+use futures::io::{AsyncRead, AsyncWrite};
+use futures::{ready, Sink, Stream};
+use std::pin::Pin;
+use std::task::Poll;
+
+/// Number of queued bytes above which the frames are flushed before reading
+/// more of them
+const MAX_QUEUED: usize = 64 * 1024;
+
+/// Send every frame decoded by `read` to `write` until `read` ends, then
+/// gracefully close `write`. Returns the number of frames forwarded.
+///
+/// Frames are queued in `write` as long as `read` has more of them ready and
+/// flushed once it has to wait for the I/O, or once more than 64 KiB are
+/// queued whatever the `FlushPolicy` of `write`, so a reader that never has
+/// to wait does not make the queue grow without bound. `read` is not polled
+/// while `write` applies backpressure. Items are handed over as they are, so with an
+/// encoder that writes `Bytes` out as is, like `BytesCodec`, frames are not
+/// copied again.
+///
+/// # Example
+/// ```
+/// use futures::executor;
+/// use futures_codec::{forward_frames, FramedRead, FramedWrite, LinesCodec};
+///
+/// executor::block_on(async move {
+///     let mut read = FramedRead::new(&b"Hello\nWorld\n"[..], LinesCodec::new());
+///     let mut write = FramedWrite::new(Vec::new(), LinesCodec::new());
+///
+///     let frames = forward_frames(&mut read, &mut write).await.unwrap();
+///     assert_eq!(frames, 2);
+///     assert_eq!(&write.release().0[..], b"Hello\nWorld\n");
+/// })
+/// ```
+pub async fn forward_frames<R, D, W, E>(
+    read: &mut FramedRead<R, D>,
+    write: &mut FramedWrite<W, E>,
+) -> Result<u64, E::Error>
+where
+    R: AsyncRead + Unpin,
+    D: Decoder,
+    W: AsyncWrite + Unpin,
+    E: Encoder<Item = D::Item>,
+    E::Error: From<D::Error>,
+{
+    let mut pending = None;
+    let mut frames = 0;
+
+    poll_fn(|cx| loop {
+        if pending.is_some() {
+            ready!(Pin::new(&mut *write).poll_ready(cx))?;
+            Pin::new(&mut *write).start_send(pending.take().unwrap())?;
+            frames += 1;
+        }
+        if write.queued_len() > MAX_QUEUED {
+            ready!(Sink::poll_flush(Pin::new(&mut *write), cx))?;
+        }
+        match Pin::new(&mut *read).poll_next(cx)? {
+            Poll::Ready(Some(item)) => pending = Some(item),
+            Poll::Ready(None) => return Poll::Ready(Ok::<_, E::Error>(())),
+            Poll::Pending => {
+                ready!(Sink::poll_flush(Pin::new(&mut *write), cx))?;
+                return Poll::Pending;
+            }
+        }
+    })
+    .await?;
+
+    write.close_graceful(future::pending()).await?;
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::BytesCodec;
+    use crate::testing::ChunkedMockIo;
+    use futures::{executor, FutureExt};
+    use std::io::{self, Cursor};
+    use std::task::Context;
+
+    /// Writer that never accepts any bytes
+    struct Stalled;
+
+    impl AsyncWrite for Stalled {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Pending
+        }
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Pending
+        }
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn forwards_bytes_frames() {
+        let mut read = FramedRead::new(&b"Hello World!"[..], BytesCodec {});
+        let mut write = FramedWrite::new(Cursor::new(vec![0u8; 16]), BytesCodec {});
+
+        let frames = executor::block_on(forward_frames(&mut read, &mut write)).unwrap();
+        assert_eq!(frames, 1);
+        let (io, _) = write.release();
+        assert_eq!(&io.get_ref()[..12], b"Hello World!");
+    }
+
+    #[test]
+    fn bounds_queue_with_ready_reader() {
+        let io = ChunkedMockIo::builder()
+            .read_chunks(&[0; 1024 * 1024], 1024)
+            .build();
+        let mut read = FramedRead::new(io, BytesCodec {});
+        let mut write = FramedWrite::new(Stalled, BytesCodec {});
+
+        assert!(forward_frames(&mut read, &mut write).now_or_never().is_none());
+        assert!(write.queued_len() <= MAX_QUEUED + 1024);
+    }
+}
@@ -0,0 +1,266 @@
+use super::{CodecError, Decoder, Encoder};
+
+use bytes::BytesMut;
+use std::marker::PhantomData;
+
+/// A struct that is its own frame, decoded and encoded field by field.
+///
+/// With the `derive` feature, `#[derive(FrameCodec)]` implements it for
+/// structs of integers, floats, `bool`, `String`, `Bytes` and `Vec<u8>`,
+/// the fields in the order they are declared. Numbers are big endian,
+/// unless `#[frame(endian = "little")]` is given on the struct or a field.
+/// Strings and bytes are prefixed with their length as a `u16`, or as set
+/// with `#[frame(prefix = "u8")]`, `"u16"`, `"u32"` or `"varint"`. Use the
+/// struct with a [`StructCodec`].
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, Encoder, FrameCodec, StructCodec};
+///
+/// #[derive(FrameCodec, Debug, PartialEq)]
+/// struct Hello {
+///     version: u16,
+///     #[frame(endian = "little")]
+///     flags: u32,
+///     #[frame(prefix = "u8")]
+///     name: String,
+///     #[frame(prefix = "varint")]
+///     payload: Bytes,
+/// }
+///
+/// let mut codec = StructCodec::<Hello>::new();
+/// let hello = Hello { version: 1, flags: 2, name: "node".to_owned(), payload: Bytes::from("hi") };
+/// let mut buf = BytesMut::new();
+/// codec.encode(hello, &mut buf).unwrap();
+/// assert_eq!(&buf[..], b"\x00\x01\x02\x00\x00\x00\x04node\x02hi");
+///
+/// let hello = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(hello.name, "node");
+/// # }
+/// ```
+pub trait FrameCodec: Sized {
+    /// Decode a frame from the start of `src`, returning it with its length,
+    /// or `None` if `src` doesn't hold all of it yet
+    fn decode_frame(src: &[u8]) -> Result<Option<(Self, usize)>, CodecError>;
+
+    /// Append the encoded frame to `dst`
+    fn encode_frame(&self, dst: &mut BytesMut) -> Result<(), CodecError>;
+}
+
+/// A codec for the frames of a [`FrameCodec`] struct.
+#[derive(Debug)]
+pub struct StructCodec<T> {
+    frame: PhantomData<fn(T) -> T>,
+}
+
+impl<T: FrameCodec> StructCodec<T> {
+    pub fn new() -> Self {
+        Self { frame: PhantomData }
+    }
+}
+
+impl<T: FrameCodec> Default for StructCodec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: FrameCodec> Decoder for StructCodec<T> {
+    type Item = T;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match T::decode_frame(src)? {
+            Some((frame, len)) => {
+                src.advance(len);
+                Ok(Some(frame))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl<T: FrameCodec> Encoder for StructCodec<T> {
+    type Item = T;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        item.encode_frame(dst)
+    }
+}
+
+/// Support for the code generated by `#[derive(FrameCodec)]`
+pub mod private {
+    use crate::{CodecError, LengthPrefix};
+    use bytes::{BigEndian, BufMut, ByteOrder, Bytes, LittleEndian};
+
+    pub use bytes::BytesMut;
+
+    /// Why reading a field failed
+    pub enum ReadError {
+        Incomplete,
+        Codec(CodecError),
+    }
+
+    impl From<CodecError> for ReadError {
+        fn from(e: CodecError) -> Self {
+            ReadError::Codec(e)
+        }
+    }
+
+    /// Reads the fields of a frame one by one
+    pub struct Reader<'a> {
+        src: &'a [u8],
+        pos: usize,
+    }
+
+    macro_rules! numbers {
+        ($($ty:ident, $read:ident, $put:ident, $put_be:ident, $put_le:ident, $size:expr;)*) => {
+            impl<'a> Reader<'a> {
+                $(
+                    pub fn $ty(&mut self, little: bool) -> Result<$ty, ReadError> {
+                        let src = self.take($size)?;
+                        Ok(if little {
+                            LittleEndian::$read(src)
+                        } else {
+                            BigEndian::$read(src)
+                        })
+                    }
+                )*
+            }
+
+            $(
+                pub fn $put(dst: &mut BytesMut, value: $ty, little: bool) {
+                    dst.reserve($size);
+                    if little {
+                        dst.$put_le(value);
+                    } else {
+                        dst.$put_be(value);
+                    }
+                }
+            )*
+        };
+    }
+
+    numbers! {
+        u16, read_u16, put_u16, put_u16_be, put_u16_le, 2;
+        i16, read_i16, put_i16, put_i16_be, put_i16_le, 2;
+        u32, read_u32, put_u32, put_u32_be, put_u32_le, 4;
+        i32, read_i32, put_i32, put_i32_be, put_i32_le, 4;
+        u64, read_u64, put_u64, put_u64_be, put_u64_le, 8;
+        i64, read_i64, put_i64, put_i64_be, put_i64_le, 8;
+        f32, read_f32, put_f32, put_f32_be, put_f32_le, 4;
+        f64, read_f64, put_f64, put_f64_be, put_f64_le, 8;
+    }
+
+    impl<'a> Reader<'a> {
+        pub fn new(src: &'a [u8]) -> Self {
+            Self { src, pos: 0 }
+        }
+
+        fn take(&mut self, len: usize) -> Result<&'a [u8], ReadError> {
+            let src = self.src.get(self.pos..self.pos + len);
+            let src = src.ok_or(ReadError::Incomplete)?;
+            self.pos += len;
+            Ok(src)
+        }
+
+        pub fn u8(&mut self) -> Result<u8, ReadError> {
+            Ok(self.take(1)?[0])
+        }
+
+        pub fn i8(&mut self) -> Result<i8, ReadError> {
+            Ok(self.u8()? as i8)
+        }
+
+        pub fn bool(&mut self) -> Result<bool, ReadError> {
+            Ok(self.u8()? != 0)
+        }
+
+        pub fn bytes(&mut self, prefix: LengthPrefix) -> Result<Bytes, ReadError> {
+            let (len, size) = prefix
+                .read(&self.src[self.pos..])?
+                .ok_or(ReadError::Incomplete)?;
+            self.pos += size;
+            Ok(Bytes::from(self.take(len as usize)?))
+        }
+
+        pub fn string(&mut self, prefix: LengthPrefix) -> Result<String, ReadError> {
+            let bytes = self.bytes(prefix)?;
+            Ok(std::str::from_utf8(&bytes)
+                .map_err(CodecError::from)?
+                .to_owned())
+        }
+
+        /// Return the frame with its length, if all of it was read
+        pub fn finish<T>(
+            self,
+            frame: Result<T, ReadError>,
+        ) -> Result<Option<(T, usize)>, CodecError> {
+            match frame {
+                Ok(frame) => Ok(Some((frame, self.pos))),
+                Err(ReadError::Incomplete) => Ok(None),
+                Err(ReadError::Codec(e)) => Err(e),
+            }
+        }
+    }
+
+    pub fn put_u8(dst: &mut BytesMut, value: u8) {
+        dst.reserve(1);
+        dst.put_u8(value);
+    }
+
+    pub fn put_bytes(
+        dst: &mut BytesMut,
+        value: &[u8],
+        prefix: LengthPrefix,
+    ) -> Result<(), CodecError> {
+        if value.len() as u64 > prefix.max() {
+            return Err(CodecError::FrameTooLong {
+                max: prefix.max() as usize,
+            });
+        }
+        prefix.write(dst, value.len());
+        dst.extend_from_slice(value);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod test {
+    use super::*;
+    // The derive macro along with the trait
+    use crate::FrameCodec;
+
+    #[derive(FrameCodec, Debug, PartialEq)]
+    #[frame(endian = "little")]
+    struct Record(
+        u8,
+        i16,
+        #[frame(endian = "big")] f32,
+        bool,
+        #[frame(prefix = "u32")] Vec<u8>,
+    );
+
+    #[test]
+    fn tuple_struct_split_across_reads() {
+        let mut codec = StructCodec::<Record>::new();
+        let record = Record(1, -2, 1.5, true, b"abc".to_vec());
+        let mut encoded = BytesMut::new();
+        codec.encode(record, &mut encoded).unwrap();
+        assert_eq!(
+            &encoded[..],
+            &b"\x01\xfe\xff\x3f\xc0\x00\x00\x01\x03\x00\x00\x00abc"[..]
+        );
+
+        let mut buf = BytesMut::from(&encoded[..encoded.len() - 1]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(&encoded[encoded.len() - 1..]);
+        let record = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(record, Record(1, -2, 1.5, true, b"abc".to_vec()));
+        assert!(buf.is_empty());
+    }
+}
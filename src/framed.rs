@@ -1,24 +1,55 @@
 use super::framed_read::{framed_read_2, FramedRead2};
-use super::framed_write::{framed_write_2, FramedWrite2};
-use super::{Decoder, Encoder};
-use futures::{Sink, Stream, TryStreamExt};
-use futures::io::{AsyncRead, AsyncWrite};
+use super::framed_write::{close_graceful, feed, framed_write_2, send_batch, FlushPolicy, FramedWrite2};
+use super::stats::{Counters, Stats, StatsHandle};
+use super::{AsyncShutdown, Decoder, Encoder, FramedBuilder, IncompleteEof};
+use bytes::{Bytes, BytesMut};
+use futures::future::{poll_fn, Future};
+use futures::stream::FusedStream;
+use futures::{Sink, Stream};
+use futures::io::{AsyncRead, AsyncWrite, IoSlice, IoSliceMut};
 use std::io::Error;
+use pin_project_lite::pin_project;
 use std::marker::Unpin;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
+/// The I/O and the codec. The I/O is structurally pinned, the codec is not.
 pub struct Fuse<T, U>(pub T, pub U);
 
-impl<T: Unpin, U> Fuse<T, U> {
+impl<T, U> Fuse<T, U> {
     pub fn pinned_t<'a>(self: Pin<&'a mut Self>) -> Pin<&'a mut T> {
-        Pin::new(&mut self.get_mut().0)
+        // Safety: `Fuse` is only `Unpin` if `T` is, never moves `T` out of a
+        // pinned `Self` and has no `Drop` impl.
+        unsafe { self.map_unchecked_mut(|fuse| &mut fuse.0) }
     }
 }
 
-impl<T, U> Unpin for Fuse<T, U> {}
+impl<T: Unpin, U> Unpin for Fuse<T, U> {}
 
-impl<T: AsyncRead + Unpin, U> AsyncRead for Fuse<T, U> {
+impl<T: AsyncShutdown, U> AsyncShutdown for Fuse<T, U> {
+    fn poll_shutdown_write(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.pinned_t().poll_shutdown_write(cx)
+    }
+}
+
+/// Access to the codec of a pinned `Fuse`, or of a type wrapping one.
+pub trait CodecMut {
+    type Codec;
+
+    fn codec_mut(self: Pin<&mut Self>) -> &mut Self::Codec;
+}
+
+impl<T, U> CodecMut for Fuse<T, U> {
+    type Codec = U;
+
+    fn codec_mut(self: Pin<&mut Self>) -> &mut U {
+        // Safety: the codec is not structurally pinned.
+        unsafe { &mut self.get_unchecked_mut().1 }
+    }
+}
+
+impl<T: AsyncRead, U> AsyncRead for Fuse<T, U> {
     fn poll_read(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
@@ -26,9 +57,16 @@ impl<T: AsyncRead + Unpin, U> AsyncRead for Fuse<T, U> {
     ) -> Poll<Result<usize, Error>> {
         self.pinned_t().poll_read(cx, buf)
     }
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<Result<usize, Error>> {
+        self.pinned_t().poll_read_vectored(cx, bufs)
+    }
 }
 
-impl<T: AsyncWrite + Unpin, U> AsyncWrite for Fuse<T, U> {
+impl<T: AsyncWrite, U> AsyncWrite for Fuse<T, U> {
     fn poll_write(
         self: Pin<&mut Self>,
         cx: &mut Context,
@@ -36,6 +74,13 @@ impl<T: AsyncWrite + Unpin, U> AsyncWrite for Fuse<T, U> {
     ) -> Poll<Result<usize, Error>> {
         self.pinned_t().poll_write(cx, buf)
     }
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        bufs: &[IoSlice],
+    ) -> Poll<Result<usize, Error>> {
+        self.pinned_t().poll_write_vectored(cx, bufs)
+    }
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Error>> {
         self.pinned_t().poll_flush(cx)
     }
@@ -45,32 +90,35 @@ impl<T: AsyncWrite + Unpin, U> AsyncWrite for Fuse<T, U> {
 }
 
 
-/// A unified `Stream` and `Sink` interface to an underlying I/O object,
-/// using the `Encoder` and `Decoder` traits to encode and decode frames.
-///
-/// # Example
-/// ```
-/// #![feature(async_await, await_macro)]
-/// use bytes::Bytes;
-/// use futures::{executor, SinkExt, TryStreamExt};
-/// use std::io::Cursor;
-/// use futures_codec::{BytesCodec, Framed};
-///
-/// executor::block_on(async move {
-///     let cur = Cursor::new(vec![0u8; 12]);
-///     let mut framed = Framed::new(cur, BytesCodec {});
-///
-///     // Send bytes to `buf` through the `BytesCodec`
-///     let bytes = Bytes::from("Hello world!");
-///     framed.send(bytes).await.unwrap();
-///
-///     // Dispose of the framer and return the I/O and codec
-///     let (cur, _) = framed.release();
-///     assert_eq!(cur.get_ref(), b"Hello world!");
-/// })
-/// ```
-pub struct Framed<T, U> {
-    inner: FramedRead2<FramedWrite2<Fuse<T, U>>>,
+pin_project! {
+    /// A unified `Stream` and `Sink` interface to an underlying I/O object,
+    /// using the `Encoder` and `Decoder` traits to encode and decode frames.
+    ///
+    /// # Example
+    /// ```
+    /// #![feature(async_await, await_macro)]
+    /// use bytes::Bytes;
+    /// use futures::{executor, SinkExt, TryStreamExt};
+    /// use std::io::Cursor;
+    /// use futures_codec::{BytesCodec, Framed};
+    ///
+    /// executor::block_on(async move {
+    ///     let cur = Cursor::new(vec![0u8; 12]);
+    ///     let mut framed = Framed::new(cur, BytesCodec {});
+    ///
+    ///     // Send bytes to `buf` through the `BytesCodec`
+    ///     let bytes = Bytes::from("Hello world!");
+    ///     framed.send(bytes).await.unwrap();
+    ///
+    ///     // Dispose of the framer and return the I/O and codec
+    ///     let (cur, _) = framed.release();
+    ///     assert_eq!(cur.get_ref(), b"Hello world!");
+    /// })
+    /// ```
+    pub struct Framed<T, U> {
+        #[pin]
+        inner: FramedRead2<FramedWrite2<Fuse<T, U>>>,
+    }
 }
 
 impl<T, U> Framed<T, U>
@@ -80,49 +128,343 @@ where
 {
     pub fn new(inner: T, codec: U) -> Self {
         Self {
-            inner: framed_read_2(framed_write_2(Fuse(inner, codec))),
+            inner: {
+                let stats = Arc::new(Counters::default());
+                framed_read_2(framed_write_2(Fuse(inner, codec), stats.clone()), stats)
+            },
         }
     }
 
+    /// Configure a `Framed` with a [`FramedBuilder`]
+    pub fn builder(inner: T, codec: U) -> FramedBuilder<T, U> {
+        FramedBuilder::new(inner, codec)
+    }
+
+    /// Create a `Framed` whose read buffer starts out with `initial`, bytes of
+    /// the stream already read from the I/O by an earlier stage
+    pub fn with_initial(inner: T, codec: U, initial: Bytes) -> Self {
+        let mut framed = Self::new(inner, codec);
+        framed.inner.prepend(&initial);
+        framed
+    }
+
     /// Release the I/O and Codec
-    pub fn release(self: Self) -> (T, U) {
+    ///
+    /// Any bytes read but not yet decoded are dropped, see
+    /// `release_with_buffers` to keep them.
+    pub fn release(self) -> (T, U) {
         let fuse = self.inner.release().release();
         (fuse.0, fuse.1)
     }
+
+    /// Release the I/O, the Codec and the bytes read from the I/O that were
+    /// not decoded yet, for handing the connection over to another protocol
+    pub fn release_with_buffers(self) -> (T, U, BytesMut) {
+        let (write, buffer) = self.inner.release_with_buffer();
+        let fuse = write.release();
+        (fuse.0, fuse.1, buffer)
+    }
+
+    /// A snapshot of the frame and byte counters of both directions
+    pub fn stats(&self) -> Stats {
+        self.inner.stats().snapshot()
+    }
+
+    /// A handle for reading the counters from elsewhere
+    pub fn stats_handle(&self) -> StatsHandle {
+        StatsHandle(self.inner.stats().clone())
+    }
+
+    /// Change when queued frames are written out, see `FlushPolicy`
+    pub fn set_flush_policy(&mut self, policy: FlushPolicy) {
+        self.inner.get_mut().set_flush_policy(policy);
+    }
+
+    pub(crate) fn set_read_capacity(&mut self, capacity: usize) {
+        self.inner.set_capacity(capacity);
+    }
+
+    pub(crate) fn set_write_capacity(&mut self, capacity: usize) {
+        self.inner.get_mut().set_capacity(capacity);
+    }
+
+    pub(crate) fn set_incomplete_eof(&mut self, policy: IncompleteEof) {
+        self.inner.set_incomplete_eof(policy);
+    }
+}
+
+impl<T, U> Framed<T, U>
+where
+    T: AsyncWrite + Unpin,
+    U: Encoder,
+{
+    /// Write all queued frames and the closing frame of the codec, then close
+    /// the I/O.
+    ///
+    /// Fails with `TimedOut` if `timeout` completes first.
+    pub async fn close_graceful<F>(&mut self, timeout: F) -> Result<(), U::Error>
+    where
+        F: Future<Output = ()>,
+    {
+        close_graceful(Pin::new(&mut self.inner).get_pin_mut(), timeout).await
+    }
+
+    /// Encode `item` into the write buffer without flushing it
+    pub async fn feed(&mut self, item: U::Item) -> Result<(), U::Error> {
+        feed(self, item).await
+    }
+
+    /// Encode all `items` into the write buffer, then flush them at once
+    pub async fn send_batch<I>(&mut self, items: I) -> Result<(), U::Error>
+    where
+        I: IntoIterator<Item = U::Item>,
+    {
+        send_batch(self, items).await
+    }
+}
+
+impl<T, U> Framed<T, U>
+where
+    T: AsyncWrite + AsyncShutdown,
+    U: Encoder,
+{
+    /// Write all queued frames, then close the write direction of the I/O.
+    ///
+    /// Frames can still be received afterwards.
+    pub fn poll_close_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), U::Error>> {
+        let inner = self.project().inner.get_pin_mut();
+        inner.poll_close_write(cx).map_err(Into::into)
+    }
+
+    /// Write all queued frames, then close the write direction of the I/O.
+    ///
+    /// Frames can still be received afterwards.
+    pub async fn close_write(&mut self) -> Result<(), U::Error>
+    where
+        T: Unpin,
+    {
+        poll_fn(|cx| Pin::new(&mut *self).poll_close_write(cx)).await
+    }
+}
+
+impl<T: Unpin, U> Framed<T, U> {
+    /// Read and write raw bytes around the codec, for example to tunnel a
+    /// connection after a handshake.
+    ///
+    /// Bytes that were read but not decoded yet are read first, and frames
+    /// that were sent but not written yet are written before any raw bytes.
+    pub fn raw(&mut self) -> RawIo<'_, T, U> {
+        RawIo {
+            inner: &mut self.inner,
+        }
+    }
+}
+
+/// Raw access to the I/O of a `Framed`, created by [`Framed::raw`].
+pub struct RawIo<'a, T, U> {
+    inner: &'a mut FramedRead2<FramedWrite2<Fuse<T, U>>>,
+}
+
+impl<T: AsyncRead + Unpin, U> AsyncRead for RawIo<'_, T, U> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Error>> {
+        Pin::new(&mut *self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin, U> AsyncWrite for RawIo<'_, T, U> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<Result<usize, Error>> {
+        Pin::new(&mut *self.inner).poll_write(cx, buf)
+    }
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Error>> {
+        AsyncWrite::poll_flush(Pin::new(&mut *self.inner), cx)
+    }
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Error>> {
+        AsyncWrite::poll_close(Pin::new(&mut *self.inner), cx)
+    }
 }
 
 impl<T, U> Stream for Framed<T, U>
 where
-    T: AsyncRead + Unpin,
+    T: AsyncRead,
     U: Decoder,
 {
     type Item = Result<U::Item, U::Error>;
 
     fn poll_next(
-        mut self: Pin<&mut Self>,
+        self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        self.inner.try_poll_next_unpin(cx)
+        self.project().inner.poll_next(cx)
+    }
+}
+
+impl<T, U> FusedStream for Framed<T, U>
+where
+    T: AsyncRead,
+    U: Decoder,
+{
+    fn is_terminated(&self) -> bool {
+        self.inner.is_terminated()
     }
 }
 
 impl<T, U> Sink<U::Item> for Framed<T, U>
 where
-    T: AsyncWrite + Unpin,
+    T: AsyncWrite,
     U: Encoder,
 {
     type Error = U::Error;
 
-    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
-        Pin::new(&mut self.inner).poll_ready(cx)
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_ready(cx)
+    }
+    fn start_send(self: Pin<&mut Self>, item: U::Item) -> Result<(), Self::Error> {
+        self.project().inner.start_send(item)
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Sink::poll_flush(self.project().inner, cx)
+    }
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Sink::poll_close(self.project().inner, cx)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LinesCodec;
+
+    use futures::io::{AsyncReadExt, AsyncWriteExt};
+    use std::io::Cursor;
+    use futures::{executor, TryStreamExt};
+
+    /// Reads from a cursor, writes to a vector that can be shut down
+    struct HalfClose {
+        read: Cursor<Vec<u8>>,
+        written: Vec<u8>,
+        shut_down: bool,
     }
-    fn start_send(mut self: Pin<&mut Self>, item: U::Item) -> Result<(), Self::Error> {
-        Pin::new(&mut self.inner).start_send(item)
+
+    impl AsyncRead for HalfClose {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<Result<usize, Error>> {
+            Pin::new(&mut self.read).poll_read(cx, buf)
+        }
     }
-    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
-        Pin::new(&mut self.inner).poll_flush(cx)
+
+    impl AsyncWrite for HalfClose {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context,
+            buf: &[u8],
+        ) -> Poll<Result<usize, Error>> {
+            assert!(!self.shut_down);
+            self.written.extend_from_slice(buf);
+            Poll::Ready(Ok(buf.len()))
+        }
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Result<(), Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Result<(), Error>> {
+            panic!("closed both directions")
+        }
     }
-    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
-        Pin::new(&mut self.inner).poll_close(cx)
+
+    impl AsyncShutdown for HalfClose {
+        fn poll_shutdown_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Error>> {
+            self.shut_down = true;
+            Poll::Ready(Ok(()))
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn read_after_close_write() {
+        let io = HalfClose {
+            read: Cursor::new(b"response\n".to_vec()),
+            written: Vec::new(),
+            shut_down: false,
+        };
+        let mut framed = Framed::new(io, LinesCodec::new());
+
+        executor::block_on(async {
+            Pin::new(&mut framed).start_send("request\n".to_owned()).unwrap();
+            framed.close_write().await.unwrap();
+            assert_eq!(framed.try_next().await.unwrap().unwrap(), "response\n");
+        });
+
+        let (io, _) = framed.release();
+        assert!(io.shut_down);
+        assert_eq!(&io.written[..], b"request\n");
+    }
+
+    #[test]
+    fn raw_read_after_frame() {
+        let io = Cursor::new(b"CONNECT\nraw bytes".to_vec());
+        let mut framed = Framed::new(io, LinesCodec::new());
+
+        executor::block_on(async move {
+            assert_eq!(framed.try_next().await.unwrap().unwrap(), "CONNECT\n");
+            let mut raw = String::new();
+            framed.raw().read_to_string(&mut raw).await.unwrap();
+            assert_eq!(raw, "raw bytes");
+        })
+    }
+
+    #[test]
+    fn raw_read_after_partial_scan() {
+        let io = crate::testing::ChunkedMockIo::builder()
+            .read(b"abcd")
+            .pending()
+            .read(b"\n")
+            .build();
+        let mut framed = Framed::new(io, LinesCodec::new());
+        assert!(futures::FutureExt::now_or_never(framed.try_next()).is_none());
+
+        executor::block_on(async move {
+            let mut raw = [0u8; 3];
+            framed.raw().read_exact(&mut raw).await.unwrap();
+            assert_eq!(&raw, b"abc");
+            assert_eq!(framed.try_next().await.unwrap().unwrap(), "d\n");
+        })
+    }
+
+    #[test]
+    fn release_keeps_buffered_bytes() {
+        let io = Cursor::new(b"UPGRADE\n\x00\x01\x02".to_vec());
+        let mut framed = Framed::new(io, LinesCodec::new());
+
+        let line = executor::block_on(framed.try_next()).unwrap().unwrap();
+        assert_eq!(line, "UPGRADE\n");
+        let (_, _, rest) = framed.release_with_buffers();
+        assert_eq!(&rest[..], b"\x00\x01\x02");
+    }
+
+    #[test]
+    fn raw_write_after_frame() {
+        let mut framed = Framed::new(Cursor::new(vec![0u8; 16]), LinesCodec::new());
+
+        Pin::new(&mut framed).start_send("200 OK\n".to_owned()).unwrap();
+        executor::block_on(async {
+            framed.raw().write_all(b"raw").await.unwrap();
+            framed.raw().flush().await.unwrap();
+        });
+        let (io, _) = framed.release();
+        assert_eq!(&io.get_ref()[..10], b"200 OK\nraw");
+    }
+}
@@ -0,0 +1,103 @@
+use super::{Decoder, FrameAccumulator};
+
+use futures::io::AsyncBufRead;
+use futures::{ready, Stream};
+use pin_project_lite::pin_project;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+pin_project! {
+    /// A `Stream` of messages decoded from an `AsyncBufRead`.
+    ///
+    /// Unlike `FramedRead`, which reads into an intermediate buffer first, this
+    /// appends the reader's own buffer straight to the decode buffer, saving a
+    /// copy for every read.
+    ///
+    /// # Example
+    /// ```
+    /// use futures::{executor, io::BufReader, TryStreamExt};
+    /// use futures_codec::{FramedBufRead, LinesCodec};
+    ///
+    /// let buf = b"Hello\nWorld\n";
+    /// let mut framed = FramedBufRead::new(BufReader::new(&buf[..]), LinesCodec::new());
+    ///
+    /// executor::block_on(async move {
+    ///     assert_eq!(framed.try_next().await.unwrap().unwrap(), "Hello\n");
+    ///     assert_eq!(framed.try_next().await.unwrap().unwrap(), "World\n");
+    ///     assert!(framed.try_next().await.unwrap().is_none());
+    /// })
+    /// ```
+    pub struct FramedBufRead<T, D> {
+        #[pin]
+        inner: T,
+        frames: FrameAccumulator<D>,
+    }
+}
+
+impl<T, D> FramedBufRead<T, D>
+where
+    T: AsyncBufRead,
+    D: Decoder,
+{
+    pub fn new(inner: T, decoder: D) -> Self {
+        Self {
+            inner,
+            frames: FrameAccumulator::new(decoder),
+        }
+    }
+
+    /// Release the I/O and Decoder
+    pub fn release(self) -> (T, D) {
+        (self.inner, self.frames.into_parts().0)
+    }
+}
+
+impl<T, D> Stream for FramedBufRead<T, D>
+where
+    T: AsyncBufRead,
+    D: Decoder,
+{
+    type Item = Result<D::Item, D::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        loop {
+            if let Some(item) = this.frames.decode()? {
+                return Poll::Ready(Some(Ok(item)));
+            }
+
+            let n = {
+                let available = ready!(this.inner.as_mut().poll_fill_buf(cx))?;
+                this.frames.extend(available);
+                available.len()
+            };
+            this.inner.as_mut().consume(n);
+
+            if n == 0 {
+                return Poll::Ready(this.frames.decode_eof().transpose());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FramedBufRead;
+    use crate::LinesCodec;
+
+    use futures::{executor, io::BufReader, TryStreamExt};
+
+    #[test]
+    fn lines_across_fills() {
+        let buf = b"Hello\nWorld\n";
+        let reader = BufReader::with_capacity(4, &buf[..]);
+        let mut framed = FramedBufRead::new(reader, LinesCodec::new());
+
+        let next = executor::block_on(framed.try_next()).unwrap().unwrap();
+        assert_eq!(next, "Hello\n");
+        let next = executor::block_on(framed.try_next()).unwrap().unwrap();
+        assert_eq!(next, "World\n");
+        assert!(executor::block_on(framed.try_next()).unwrap().is_none());
+    }
+}
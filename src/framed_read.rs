@@ -1,34 +1,59 @@
 
-use super::framed::Fuse;
-use super::Decoder;
+use super::accumulator::DecodeBuffer;
+use super::framed::{CodecMut, Fuse};
+use super::stats::{Counters, Stats, StatsHandle};
+use super::framing_error::WithContext;
+use super::index::{FrameIndex, Indexed};
+use super::retry::poll_retry;
+use super::{Decoder, DecoderRef};
 
-use bytes::BytesMut;
-use futures::io::AsyncRead;
-use futures::{ready, Sink, Stream, TryStreamExt};
+use bytes::{Bytes, BytesMut};
+use futures::io::{AsyncRead, AsyncSeek, AsyncWrite, IoSliceMut, SeekFrom};
+use futures::future::poll_fn;
+use futures::stream::FusedStream;
+use futures::{ready, Sink, Stream};
 use std::io;
+use pin_project_lite::pin_project;
 use std::marker::Unpin;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::sync::Arc;
+use std::time::Instant;
 
-/// A `Stream` of messages decoded from an `AsyncRead`.
-///
-/// # Example
-/// ```
-/// #![feature(async_await, await_macro)]
-/// use futures_codec::{BytesCodec, FramedRead};
-/// use futures::{executor, TryStreamExt};
-/// use bytes::Bytes;
-///
-/// let buf = b"Hello World!";
-/// let mut framed = FramedRead::new(&buf[..], BytesCodec {});
-///
-/// executor::block_on(async move {
-///     let msg = framed.try_next().await.unwrap().unwrap();
-///     assert_eq!(msg, Bytes::from(&buf[..]));
-/// })
-/// ```
-pub struct FramedRead<T, D> {
-    inner: FramedRead2<Fuse<T, D>>,
+pin_project! {
+    /// A `Stream` of messages decoded from an `AsyncRead`.
+    ///
+    /// Once the I/O reached its end or failed, the stream is terminated and keeps
+    /// returning `None` without polling the I/O again. Errors of the decoder do not
+    /// terminate the stream.
+    ///
+    /// `&mut FramedRead` is a `Stream` too if the I/O is `Unpin`, so it can be
+    /// lent to helpers such as `forward` and used again afterwards. Otherwise
+    /// pin it and lend `Pin<&mut FramedRead>` instead.
+    ///
+    /// # Example
+    /// ```
+    /// #![feature(async_await, await_macro)]
+    /// use futures_codec::{BytesCodec, FramedRead};
+    /// use futures::{executor, TryStreamExt};
+    /// use bytes::Bytes;
+    ///
+    /// let buf = b"Hello World!";
+    /// let mut framed = FramedRead::new(&buf[..], BytesCodec {});
+    ///
+    /// executor::block_on(async move {
+    ///     let msg = framed.try_next().await.unwrap().unwrap();
+    ///     assert_eq!(msg, Bytes::from(&buf[..]));
+    /// })
+    /// ```
+    pub struct FramedRead<T, D>
+    where
+        D: Decoder,
+    {
+        #[pin]
+        inner: FramedRead2<Fuse<T, D>>,
+        peeked: Option<D::Item>,
+    }
 }
 
 impl<T, D> FramedRead<T, D>
@@ -38,98 +63,1282 @@ where
 {
     pub fn new(inner: T, decoder: D) -> Self {
         Self {
-            inner: framed_read_2(Fuse(inner, decoder)),
+            inner: framed_read_2(Fuse(inner, decoder), Default::default()),
+            peeked: None,
         }
     }
 
+    /// Create a `FramedRead` whose buffer starts out with `initial`, bytes of
+    /// the stream already read from the I/O by an earlier stage
+    ///
+    /// # Example
+    /// ```
+    /// use bytes::Bytes;
+    /// use futures::{executor, TryStreamExt};
+    /// use futures_codec::{FramedRead, LinesCodec};
+    ///
+    /// let sniffed = Bytes::from(&b"GET / HT"[..]);
+    /// let rest = b"TP/1.1\n";
+    /// let mut framed = FramedRead::with_initial(&rest[..], LinesCodec::new(), sniffed);
+    ///
+    /// executor::block_on(async move {
+    ///     assert_eq!(framed.try_next().await.unwrap().unwrap(), "GET / HTTP/1.1\n");
+    /// })
+    /// ```
+    pub fn with_initial(inner: T, decoder: D, initial: Bytes) -> Self {
+        let mut framed = Self::new(inner, decoder);
+        framed.prepend(&initial);
+        framed
+    }
+
+    /// Put bytes in front of the buffered bytes, to be decoded before
+    /// anything else is read from the I/O
+    pub fn prepend(&mut self, data: &[u8]) {
+        self.inner.prepend(data);
+    }
+
     /// Release the I/O and Decoder
-    pub fn release(self: Self) -> (T, D) {
+    ///
+    /// Any bytes read but not yet decoded are dropped, see
+    /// `release_with_buffers` to keep them, as is a frame returned by `peek`.
+    pub fn release(self) -> (T, D) {
         let fuse = self.inner.release();
         (fuse.0, fuse.1)
     }
+
+    /// Release the I/O, the Decoder and the bytes read from the I/O that were
+    /// not decoded yet
+    ///
+    /// # Example
+    /// ```
+    /// use futures::{executor, TryStreamExt};
+    /// use futures_codec::{FramedRead, LinesCodec};
+    ///
+    /// let buf = b"UPGRADE\n\x00\x01";
+    /// let mut framed = FramedRead::new(&buf[..], LinesCodec::new());
+    ///
+    /// executor::block_on(async move {
+    ///     assert_eq!(framed.try_next().await.unwrap().unwrap(), "UPGRADE\n");
+    ///     let (_io, _codec, rest) = framed.release_with_buffers();
+    ///     assert_eq!(&rest[..], b"\x00\x01");
+    /// })
+    /// ```
+    pub fn release_with_buffers(self) -> (T, D, BytesMut) {
+        let (fuse, buffer) = self.inner.release_with_buffer();
+        (fuse.0, fuse.1, buffer)
+    }
+
+    /// A snapshot of the frame and byte counters
+    pub fn stats(&self) -> Stats {
+        self.inner.stats().snapshot()
+    }
+
+    /// Choose what happens to an incomplete frame at the end of the I/O
+    ///
+    /// # Example
+    /// ```
+    /// use futures::{executor, TryStreamExt};
+    /// use futures_codec::{FramedRead, IncompleteEof, LinesCodec};
+    ///
+    /// let buf = b"Hello\nWorld";
+    /// let framed = FramedRead::new(&buf[..], LinesCodec::new())
+    ///     .on_incomplete_eof(IncompleteEof::Decode);
+    ///
+    /// executor::block_on(async move {
+    ///     let lines: Vec<_> = framed.try_collect().await.unwrap();
+    ///     assert_eq!(lines, ["Hello\n", "World"]);
+    /// })
+    /// ```
+    pub fn on_incomplete_eof(mut self, policy: IncompleteEof) -> Self {
+        self.inner.set_incomplete_eof(policy);
+        self
+    }
+
+    /// Allocate the read buffer once with room for `capacity` bytes, and fail
+    /// with `InvalidData` instead of growing it for a larger frame
+    ///
+    /// The buffer stays a `BytesMut`, as `Decoder` needs one. Codecs of the
+    /// `no_std` module decode from storage provided by the caller instead,
+    /// with `no_std::SliceBuffer`.
+    pub fn fixed_capacity(mut self, capacity: usize) -> Self {
+        self.inner.set_capacity(capacity);
+        self
+    }
+
+    /// A handle for reading the counters from elsewhere
+    pub fn stats_handle(&self) -> StatsHandle {
+        StatsHandle(self.inner.stats().clone())
+    }
+
+    /// Decode the next frame and return a reference to it, leaving it to be
+    /// returned by the next call to `poll_next`
+    ///
+    /// Errors are returned here and not again by `poll_next`.
+    pub fn poll_peek(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<&D::Item, D::Error>>> {
+        let this = self.project();
+        if this.peeked.is_none() {
+            match ready!(this.inner.poll_next(cx)) {
+                Some(Ok(item)) => *this.peeked = Some(item),
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => return Poll::Ready(None),
+            }
+        }
+        Poll::Ready(this.peeked.as_ref().map(Ok))
+    }
+
+    /// Wait for the next frame without taking it out of the stream, see
+    /// `poll_peek`
+    ///
+    /// # Example
+    /// ```
+    /// use futures::{executor, TryStreamExt};
+    /// use futures_codec::{FramedRead, LinesCodec};
+    ///
+    /// let buf = b"HELLO v2\nrequest\n";
+    /// let mut framed = FramedRead::new(&buf[..], LinesCodec::new());
+    ///
+    /// executor::block_on(async move {
+    ///     let v2 = framed.peek().await.unwrap().unwrap().ends_with("v2\n");
+    ///     assert!(v2);
+    ///     // Hand `framed` to the v2 handler, which still sees the greeting
+    ///     assert_eq!(framed.try_next().await.unwrap().unwrap(), "HELLO v2\n");
+    /// })
+    /// ```
+    pub async fn peek(&mut self) -> Option<Result<&D::Item, D::Error>>
+    where
+        T: Unpin,
+    {
+        let peeked = poll_fn(|cx| {
+            let peeked = ready!(Pin::new(&mut *self).poll_peek(cx));
+            Poll::Ready(peeked.map(|peeked| peeked.map(|_| ())))
+        });
+        if let Err(e) = peeked.await? {
+            return Some(Err(e));
+        }
+        self.peeked.as_ref().map(Ok)
+    }
 }
 
-impl<T, D> Stream for FramedRead<T, D>
+impl<T, D> FramedRead<T, D>
+where
+    T: AsyncRead + Unpin,
+    D: Decoder + DecoderRef,
+{
+    /// Decode the next frame as an item borrowing from the read buffer
+    ///
+    /// The frame stays in the buffer until the next read from this `FramedRead`.
+    ///
+    /// # Example
+    /// ```
+    /// use futures::executor;
+    /// use futures_codec::{FramedRead, LinesCodec};
+    ///
+    /// let buf = b"Hello\nWorld\n";
+    /// let mut framed = FramedRead::new(&buf[..], LinesCodec::new());
+    ///
+    /// executor::block_on(async move {
+    ///     let line: &str = framed.next_borrowed().await.unwrap().unwrap();
+    ///     assert_eq!(line, "Hello\n");
+    /// })
+    /// ```
+    pub async fn next_borrowed(
+        &mut self,
+    ) -> Option<Result<<D as DecoderRef>::Item<'_>, <D as DecoderRef>::Error>> {
+        let len = match poll_fn(|cx| self.inner.poll_frame_len(cx)).await? {
+            Ok(len) => len,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(self.inner.decode_frame(len))
+    }
+}
+
+impl<T, D> FramedRead<T, D>
 where
     T: AsyncRead + Unpin,
     D: Decoder,
+{
+    /// Read a frame body of `len` bytes that follows the last decoded frame,
+    /// without buffering it.
+    ///
+    /// Decoding resumes after the body. Whatever part of it is not read before
+    /// the returned `FrameBody` is dropped is skipped.
+    ///
+    /// # Example
+    /// ```
+    /// use futures::{executor, AsyncReadExt, TryStreamExt};
+    /// use futures_codec::{FramedRead, LinesCodec};
+    ///
+    /// let buf = b"5\nHello\nnext\n";
+    /// let mut framed = FramedRead::new(&buf[..], LinesCodec::new());
+    ///
+    /// executor::block_on(async move {
+    ///     let header = framed.try_next().await.unwrap().unwrap();
+    ///     let len = header.trim().parse().unwrap();
+    ///
+    ///     let mut body = String::new();
+    ///     framed.body(len).read_to_string(&mut body).await.unwrap();
+    ///     assert_eq!(body, "Hello");
+    ///
+    ///     assert_eq!(framed.try_next().await.unwrap().unwrap(), "\n");
+    /// })
+    /// ```
+    pub fn body(&mut self, len: u64) -> FrameBody<'_, T, D> {
+        self.inner.state.drop_consumed();
+        FrameBody {
+            framed: &mut self.inner,
+            remaining: len,
+        }
+    }
+}
+
+impl<T, D> FramedRead<T, D>
+where
+    T: AsyncRead + AsyncSeek + Unpin,
+    D: Decoder,
+{
+    /// Move the I/O to `offset` and continue decoding from there, returning
+    /// the new position
+    ///
+    /// Buffered bytes and a peeked frame are dropped, and a terminated stream
+    /// resumes. The decoder is only `reset`, so `offset` should be the start
+    /// of a frame.
+    ///
+    /// # Example
+    /// ```
+    /// use futures::{executor, TryStreamExt};
+    /// use futures_codec::{FramedRead, LinesCodec};
+    /// use std::io::Cursor;
+    ///
+    /// let buf = b"Hello\nWorld\n";
+    /// let mut framed = FramedRead::new(Cursor::new(&buf[..]), LinesCodec::new());
+    ///
+    /// executor::block_on(async move {
+    ///     framed.seek_to(6).await.unwrap();
+    ///     assert_eq!(framed.try_next().await.unwrap().unwrap(), "World\n");
+    ///     framed.seek_to(0).await.unwrap();
+    ///     assert_eq!(framed.try_next().await.unwrap().unwrap(), "Hello\n");
+    /// })
+    /// ```
+    pub async fn seek_to(&mut self, offset: u64) -> io::Result<u64> {
+        let io = &mut self.inner.get_mut().0;
+        let seek = poll_fn(|cx| Pin::new(&mut *io).poll_seek(cx, SeekFrom::Start(offset)));
+        let position = seek.await?;
+        self.peeked = None;
+        self.inner.reset(position);
+        Ok(position)
+    }
+}
+
+impl<T, D: Decoder> FramedRead<T, D> {
+    /// Position in the I/O of the first byte of the frame the stream yields
+    /// next
+    ///
+    /// Positions count from where the I/O was when the `FramedRead` was
+    /// created, or from the start after a `seek_to`.
+    pub fn stream_position_of_next_frame(&self) -> u64 {
+        match self.peeked {
+            Some(_) => self.inner.state.last_frame.offset,
+            None => self.inner.next_frame_position(),
+        }
+    }
+
+    /// Yield every item together with the `FrameMeta` of the frame it was
+    /// decoded from.
+    ///
+    /// # Example
+    /// ```
+    /// use futures::{executor, TryStreamExt};
+    /// use futures_codec::{FramedRead, LinesCodec};
+    ///
+    /// let buf = b"Hello\nWorld\n";
+    /// let mut framed = FramedRead::new(&buf[..], LinesCodec::new()).with_metadata();
+    ///
+    /// executor::block_on(async move {
+    ///     framed.try_next().await.unwrap().unwrap();
+    ///     let (meta, line) = framed.try_next().await.unwrap().unwrap();
+    ///     assert_eq!(line, "World\n");
+    ///     assert_eq!((meta.offset, meta.len), (6, 6));
+    /// })
+    /// ```
+    pub fn with_metadata(self) -> WithMetadata<T, D> {
+        WithMetadata { framed: self }
+    }
+
+    /// Record the offset of every decoded frame in a `FrameIndex`
+    ///
+    /// Offsets are positions in the I/O, see `stream_position_of_next_frame`.
+    pub fn indexing(self) -> Indexed<T, D> {
+        self.indexing_with(FrameIndex::new())
+    }
+
+    /// Continue indexing with `index`, built earlier for the same I/O
+    pub fn indexing_with(self, index: FrameIndex) -> Indexed<T, D> {
+        Indexed::new(self, index)
+    }
+
+    /// Wrap errors in a `FramingError` with the index of the failed frame,
+    /// the number of bytes consumed and up to `snapshot` buffered bytes.
+    pub fn with_error_context(self, snapshot: usize) -> WithContext<Self> {
+        WithContext::new(self, snapshot)
+    }
+
+    pub(crate) fn error_context(&self, snapshot: usize) -> (u64, Bytes) {
+        self.inner.error_context(snapshot)
+    }
+}
+
+/// What `FramedRead` does with bytes left in its buffer when the I/O ends in
+/// the middle of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IncompleteEof {
+    /// Fail with an `UnexpectedEof` error, the default.
+    #[default]
+    Error,
+    /// Drop the bytes and end the stream.
+    Discard,
+    /// Pass the bytes to `Decoder::decode_eof` and yield what it returns,
+    /// failing with `UnexpectedEof` if it can not make an item of them.
+    Decode,
+}
+
+/// Where in the stream a frame was found, and when it was decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameMeta {
+    /// Byte offset of the start of the frame from the start of the I/O
+    pub offset: u64,
+    /// Number of bytes the decoder consumed for the frame
+    pub len: usize,
+    /// Time at which the frame was decoded
+    pub decoded_at: Instant,
+}
+
+pin_project! {
+    /// A `Stream` of items paired with their `FrameMeta`.
+    ///
+    /// Created by [`FramedRead::with_metadata`].
+    pub struct WithMetadata<T, D>
+    where
+        D: Decoder,
+    {
+        #[pin]
+        framed: FramedRead<T, D>,
+    }
+}
+
+impl<T, D: Decoder> WithMetadata<T, D> {
+    /// Return the underlying `FramedRead`
+    pub fn into_inner(self) -> FramedRead<T, D> {
+        self.framed
+    }
+}
+
+impl<T, D> Stream for WithMetadata<T, D>
+where
+    T: AsyncRead,
+    D: Decoder,
+{
+    type Item = Result<(FrameMeta, D::Item), D::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut framed = self.project().framed;
+        let item = ready!(framed.as_mut().poll_next(cx));
+        Poll::Ready(item.map(|item| item.map(|item| (framed.inner.state.last_frame, item))))
+    }
+}
+
+/// The body of a frame, read straight from the I/O of a `FramedRead`.
+///
+/// Created by [`FramedRead::body`].
+pub struct FrameBody<'a, T, D> {
+    framed: &'a mut FramedRead2<Fuse<T, D>>,
+    remaining: u64,
+}
+
+impl<T, D> FrameBody<'_, T, D> {
+    /// Number of body bytes not read yet
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+}
+
+impl<T: AsyncRead + Unpin, D> AsyncRead for FrameBody<'_, T, D> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        let this = &mut *self;
+        let max = buf.len().min(this.remaining.min(usize::MAX as u64) as usize);
+        if max == 0 {
+            return Poll::Ready(Ok(0));
+        }
+
+        let n = if !this.framed.state.frames.buffer.is_empty() {
+            let n = max.min(this.framed.state.frames.buffer.len());
+            buf[..n].copy_from_slice(&this.framed.state.frames.buffer[..n]);
+            this.framed.state.frames.buffer.advance(n);
+            this.framed.state.reset_decoder = true;
+            n
+        } else {
+            let inner = &mut this.framed.inner;
+            let read = poll_retry(cx, |cx| Pin::new(&mut *inner).poll_read(cx, &mut buf[..max]));
+            let n = ready!(read)?;
+            if n == 0 {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "frame body ended early",
+                )));
+            }
+            this.framed.state.record_read(n);
+            n
+        };
+        this.remaining -= n as u64;
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl<T, D> Drop for FrameBody<'_, T, D> {
+    fn drop(&mut self) {
+        self.framed.state.discard += self.remaining;
+        self.framed.state.frames.decode_at = 0;
+    }
+}
+
+impl<T, D> Stream for FramedRead<T, D>
+where
+    T: AsyncRead,
+    D: Decoder,
 {
     type Item = Result<D::Item, D::Error>;
 
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.inner.try_poll_next_unpin(cx)
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        if let Some(item) = this.peeked.take() {
+            return Poll::Ready(Some(Ok(item)));
+        }
+        this.inner.poll_next(cx)
+    }
+}
+
+impl<T, D> FusedStream for FramedRead<T, D>
+where
+    T: AsyncRead,
+    D: Decoder,
+{
+    fn is_terminated(&self) -> bool {
+        self.peeked.is_none() && self.inner.is_terminated()
     }
 }
 
-pub struct FramedRead2<T> {
-    inner: T,
-    buffer: BytesMut,
+pin_project! {
+    pub struct FramedRead2<T> {
+        #[pin]
+        inner: T,
+        state: ReadState,
+    }
+}
+
+/// Everything in a `FramedRead2` besides the pinned I/O
+struct ReadState {
+    frames: DecodeBuffer,
+    /// Length of the last borrowed frame, still at the start of the buffer
+    consumed: usize,
+    /// Bytes of an unread frame body to skip before decoding resumes
+    discard: u64,
+    /// Position of the I/O, the number of bytes read from it unless it was
+    /// moved with `seek_to`
+    read_total: u64,
+    /// Metadata of the last frame returned by `poll_next`
+    last_frame: FrameMeta,
+    /// Whether the I/O reached its end or failed
+    terminated: bool,
+    /// Whether the buffer may hold a frame that was not decoded yet
+    decode_first: bool,
+    /// Whether the buffer was changed outside of `decode` since the decoder
+    /// last saw it
+    reset_decoder: bool,
+    stats: Arc<Counters>,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
 }
 
 const INITIAL_CAPACITY: usize = 8 * 1024;
 
-pub fn framed_read_2<T>(inner: T) -> FramedRead2<T> {
+pub fn framed_read_2<T>(inner: T, stats: Arc<Counters>) -> FramedRead2<T> {
     FramedRead2 {
         inner,
-        buffer: BytesMut::with_capacity(INITIAL_CAPACITY),
+        state: ReadState {
+            frames: DecodeBuffer::new(BytesMut::with_capacity(INITIAL_CAPACITY)),
+            consumed: 0,
+            discard: 0,
+            read_total: 0,
+            last_frame: FrameMeta {
+                offset: 0,
+                len: 0,
+                decoded_at: Instant::now(),
+            },
+            terminated: false,
+            decode_first: false,
+            reset_decoder: false,
+            stats,
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::current(),
+        },
     }
 }
 
 
 impl<T> Stream for FramedRead2<T>
 where
-    T: AsyncRead + Decoder + Unpin,
+    T: AsyncRead + CodecMut,
+    T::Codec: Decoder,
 {
-    type Item = Result<T::Item, T::Error>;
+    type Item = Result<<T::Codec as Decoder>::Item, <T::Codec as Decoder>::Error>;
 
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let this = &mut *self;
-        let mut buf = [0u8; INITIAL_CAPACITY];
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let mut inner = this.inner;
+        let this = this.state;
+        if this.terminated {
+            return Poll::Ready(None);
+        }
+        this.drop_consumed();
+        if let Err(e) = ready!(this.poll_discard(inner.as_mut(), cx)) {
+            this.terminated = true;
+            return Poll::Ready(Some(Err(e.into())));
+        }
 
         loop {
-            let n = ready!(Pin::new(&mut this.inner).poll_read(cx, &mut buf))?;
-            this.buffer.extend_from_slice(&buf[..n]);
-
-            match this.inner.decode(&mut this.buffer)? {
-                Some(item) => return Poll::Ready(Some(Ok(item))),
-                None => {
-                    if this.buffer.is_empty() {
-                        return Poll::Ready(None);
-                    } else if n == 0 {
-                        return Poll::Ready(Some(Err(io::Error::new(
-                            io::ErrorKind::UnexpectedEof,
-                            "bytes remaining in stream",
-                        )
-                        .into())));
-                    }
+            // Frames left in the buffer are decoded before reading any more
+            let n = if this.decode_first {
+                this.decode_first = false;
+                None
+            } else {
+                let needed = inner.as_mut().codec_mut().bytes_needed(&this.frames.buffer);
+                match ready!(this.poll_fill(inner.as_mut(), cx, needed)) {
+                    Ok(n) => Some(n),
+                    Err(e) => return Poll::Ready(Some(Err(e.into()))),
+                }
+            };
+            let eof = n == Some(0);
+
+            let codec = inner.as_mut().codec_mut();
+            if this.reset_decoder {
+                this.reset_decoder = false;
+                codec.reset();
+            }
+            let before = this.frames.buffer.len();
+            let decoded = this.frames.decode(codec, eof);
+            this.stats.read_buffer(this.frames.buffer.len());
+            if decoded.is_err() {
+                this.stats.decode_error();
+                #[cfg(feature = "tracing")]
+                tracing::debug!(parent: &this.span, buffered = before, "decode failed");
+            }
+            match decoded? {
+                Some(item) => {
+                    this.decode_first = !this.frames.buffer.is_empty();
+                    this.stats.frame_decoded();
+                    this.last_frame = FrameMeta {
+                        offset: this.read_total - before as u64,
+                        len: before - this.frames.buffer.len(),
+                        decoded_at: Instant::now(),
+                    };
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(
+                        parent: &this.span,
+                        offset = this.last_frame.offset,
+                        len = this.last_frame.len,
+                        "decoded frame"
+                    );
+                    return Poll::Ready(Some(Ok(item)));
+                }
+                None if eof => {
+                    this.terminated = true;
+                    return Poll::Ready(this.frames.end().err().map(Err));
                 }
+                None => {}
             }
         }
     }
 }
 
+impl<T> FusedStream for FramedRead2<T>
+where
+    T: AsyncRead + CodecMut,
+    T::Codec: Decoder,
+{
+    fn is_terminated(&self) -> bool {
+        self.state.terminated
+    }
+}
+
 impl<T, I> Sink<I> for FramedRead2<T>
 where
-    T: Sink<I> + Unpin,
+    T: Sink<I>,
 {
     type Error = T::Error;
 
-    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
-        Pin::new(&mut self.inner).poll_ready(cx)
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_ready(cx)
+    }
+    fn start_send(self: Pin<&mut Self>, item: I) -> Result<(), Self::Error> {
+        self.project().inner.start_send(item)
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_flush(cx)
+    }
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_close(cx)
+    }
+}
+
+/// Raw reads, returning buffered bytes before reading from the I/O
+impl<T: AsyncRead> AsyncRead for FramedRead2<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        let this = self.project();
+        let mut inner = this.inner;
+        let this = this.state;
+        this.drop_consumed();
+        ready!(this.poll_discard(inner.as_mut(), cx))?;
+
+        if this.frames.buffer.is_empty() {
+            let n = ready!(inner.poll_read(cx, buf))?;
+            this.record_read(n);
+            return Poll::Ready(Ok(n));
+        }
+        let n = buf.len().min(this.frames.buffer.len());
+        buf[..n].copy_from_slice(&this.frames.buffer[..n]);
+        this.frames.buffer.advance(n);
+        this.frames.decode_at = 0;
+        this.reset_decoder = true;
+        this.stats.read_buffer(this.frames.buffer.len());
+        Poll::Ready(Ok(n))
     }
-    fn start_send(mut self: Pin<&mut Self>, item: I) -> Result<(), Self::Error> {
-        Pin::new(&mut self.inner).start_send(item)
+}
+
+impl<T: AsyncWrite> AsyncWrite for FramedRead2<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        self.project().inner.poll_write(cx, buf)
     }
-    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
-        Pin::new(&mut self.inner).poll_flush(cx)
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        self.project().inner.poll_flush(cx)
     }
-    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
-        Pin::new(&mut self.inner).poll_close(cx)
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        self.project().inner.poll_close(cx)
     }
 }
 
 impl<T> FramedRead2<T>  {
-    pub fn release(self: Self) -> T {
+    pub fn release(self) -> T {
         self.inner
     }
+
+    /// Limit the buffer to `capacity` bytes, allocating them up front
+    pub fn set_capacity(&mut self, capacity: usize) {
+        let state = &mut self.state;
+        state.drop_consumed();
+        state.frames.set_capacity(capacity);
+    }
+
+    pub fn set_incomplete_eof(&mut self, policy: IncompleteEof) {
+        self.state.frames.incomplete_eof = policy;
+    }
+
+    /// Put bytes in front of the buffered bytes
+    pub fn prepend(&mut self, data: &[u8]) {
+        let state = &mut self.state;
+        state.drop_consumed();
+        if data.is_empty() {
+            return;
+        }
+
+        let mut buffer = BytesMut::with_capacity(data.len() + state.frames.buffer.len());
+        buffer.extend_from_slice(data);
+        buffer.extend_from_slice(&state.frames.buffer);
+        state.frames.buffer = buffer;
+        state.read_total += data.len() as u64;
+        state.frames.decode_at = 0;
+        state.decode_first = true;
+        state.reset_decoder = true;
+    }
+
+    /// Drop all buffered bytes after the I/O was moved to `position`
+    pub fn reset(&mut self, position: u64) {
+        let state = &mut self.state;
+        state.frames.buffer.clear();
+        state.consumed = 0;
+        state.discard = 0;
+        state.frames.decode_at = 0;
+        state.decode_first = false;
+        state.reset_decoder = true;
+        state.terminated = false;
+        state.read_total = position;
+        state.stats.read_buffer(0);
+    }
+
+    /// Position of the first byte that was not decoded yet
+    pub fn next_frame_position(&self) -> u64 {
+        let state = &self.state;
+        let buffered = state.frames.buffer.len() - state.consumed;
+        state.read_total - buffered as u64 + state.discard
+    }
+
+    /// Release the I/O and the buffered bytes that were not decoded yet
+    pub fn release_with_buffer(self) -> (T, BytesMut) {
+        let mut state = self.state;
+        state.drop_consumed();
+        (self.inner, state.frames.buffer)
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut T> {
+        self.project().inner
+    }
+
+    pub fn stats(&self) -> &Arc<Counters> {
+        &self.state.stats
+    }
+
+    /// Bytes consumed from the I/O and the first of the buffered bytes
+    pub fn error_context(&self, snapshot: usize) -> (u64, Bytes) {
+        let buffered = &self.state.frames.buffer[self.state.consumed..];
+        let offset = self.state.read_total - buffered.len() as u64;
+        (offset, Bytes::from(&buffered[..snapshot.min(buffered.len())]))
+    }
+}
+
+impl ReadState {
+    fn record_read(&mut self, n: usize) {
+        self.read_total += n as u64;
+        self.stats.read(n);
+    }
+
+    fn drop_consumed(&mut self) {
+        if self.consumed > 0 {
+            self.frames.buffer.advance(self.consumed);
+            self.consumed = 0;
+        }
+    }
+
+    /// Read from `io` into the buffer, within its capacity and reading the
+    /// `needed` bytes of a large frame straight into it. Returns the number of
+    /// bytes read, and ends the stream on errors.
+    fn poll_fill<R: AsyncRead>(
+        &mut self,
+        mut io: Pin<&mut R>,
+        cx: &mut Context<'_>,
+        needed: Option<usize>,
+    ) -> Poll<io::Result<usize>> {
+        let mut buf = [0u8; INITIAL_CAPACITY];
+        let space = match self.frames.check_space(needed) {
+            Ok(space) => space,
+            Err(e) => {
+                self.terminated = true;
+                return Poll::Ready(Err(e));
+            }
+        };
+        let buf = &mut buf[..space.min(INITIAL_CAPACITY)];
+
+        Poll::Ready(Ok(match needed {
+            // Read the rest of a large frame directly into the buffer, anything past
+            // the end of the frame goes to `buf`. The announced length is not trusted
+            // for allocation, the buffer grows by at most `INITIAL_CAPACITY` per read.
+            Some(needed) if needed > buf.len() => {
+                let start = self.frames.buffer.len();
+                let chunk = needed.min(INITIAL_CAPACITY);
+                self.frames.buffer.reserve(chunk);
+                self.frames.buffer.resize(start + chunk, 0);
+
+                let rest = if chunk == needed {
+                    buf.len().min(space - needed)
+                } else {
+                    0
+                };
+                let mut bufs = [
+                    IoSliceMut::new(&mut self.frames.buffer[start..]),
+                    IoSliceMut::new(&mut buf[..rest]),
+                ];
+                let read = poll_retry(cx, |cx| io.as_mut().poll_read_vectored(cx, &mut bufs));
+                let n = match read {
+                    Poll::Ready(Ok(n)) => n,
+                    Poll::Ready(Err(e)) => {
+                        self.frames.buffer.truncate(start);
+                        self.terminated = true;
+                        return Poll::Ready(Err(e));
+                    }
+                    Poll::Pending => {
+                        self.frames.buffer.truncate(start);
+                        return Poll::Pending;
+                    }
+                };
+                self.record_read(n);
+                self.frames.buffer.truncate(start + n.min(chunk));
+                if n > chunk {
+                    self.frames.buffer.extend_from_slice(&buf[..n - chunk]);
+                }
+                n
+            }
+            _ => {
+                let read = poll_retry(cx, |cx| io.as_mut().poll_read(cx, buf));
+                let n = match ready!(read) {
+                    Ok(n) => n,
+                    Err(e) => {
+                        self.terminated = true;
+                        return Poll::Ready(Err(e));
+                    }
+                };
+                #[cfg(feature = "tracing")]
+                let capacity = self.frames.buffer.capacity();
+                self.frames.buffer.extend_from_slice(&buf[..n]);
+                #[cfg(feature = "tracing")]
+                {
+                    if self.frames.buffer.capacity() > capacity {
+                        let capacity = self.frames.buffer.capacity();
+                        tracing::debug!(parent: &self.span, capacity, "read buffer grew");
+                    }
+                }
+                self.record_read(n);
+                n
+            }
+        }))
+    }
+
+    /// Skip the remainder of a frame body that was not read to the end
+    fn poll_discard<R: AsyncRead>(
+        &mut self,
+        mut io: Pin<&mut R>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        let mut buf = [0u8; INITIAL_CAPACITY];
+
+        while self.discard > 0 {
+            if !self.frames.buffer.is_empty() {
+                let n = self.frames.buffer.len().min(self.discard as usize);
+                self.frames.buffer.advance(n);
+                self.discard -= n as u64;
+                self.reset_decoder = true;
+                continue;
+            }
+
+            let max = buf.len().min(self.discard as usize);
+            let read = poll_retry(cx, |cx| io.as_mut().poll_read(cx, &mut buf[..max]));
+            let n = ready!(read)?;
+            if n == 0 {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "frame body ended early",
+                )));
+            }
+            self.discard -= n as u64;
+            self.record_read(n);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> FramedRead2<T>
+where
+    T: AsyncRead + DecoderRef + Unpin,
+{
+    /// Poll for the length of the next complete frame at the start of the buffer
+    pub fn poll_frame_len(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<usize, T::Error>>> {
+        let state = &mut self.state;
+        if state.terminated {
+            return Poll::Ready(None);
+        }
+        state.drop_consumed();
+        if let Err(e) = ready!(state.poll_discard(Pin::new(&mut self.inner), cx)) {
+            state.terminated = true;
+            return Poll::Ready(Some(Err(e.into())));
+        }
+
+        if state.reset_decoder {
+            state.reset_decoder = false;
+            DecoderRef::reset(&mut self.inner);
+        }
+        loop {
+            if let Some(len) = self.inner.frame_len(&state.frames.buffer)? {
+                state.consumed = len;
+                return Poll::Ready(Some(Ok(len)));
+            }
+
+            let n = match ready!(state.poll_fill(Pin::new(&mut self.inner), cx, None)) {
+                Ok(n) => n,
+                Err(e) => return Poll::Ready(Some(Err(e.into()))),
+            };
+            if n == 0 {
+                state.terminated = true;
+                return Poll::Ready(state.frames.end().err().map(Err));
+            }
+        }
+    }
+
+    /// Decode the frame of `len` bytes at the start of the buffer
+    pub fn decode_frame(&mut self, len: usize) -> Result<T::Item<'_>, T::Error> {
+        self.inner.decode_ref(&self.state.frames.buffer[..len])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use bytes::{BigEndian, ByteOrder};
+    use futures::{executor, StreamExt, TryStreamExt};
+
+    /// Frames prefixed with a big endian u16 length
+    struct U16Prefixed;
+
+    impl Decoder for U16Prefixed {
+        type Item = BytesMut;
+        type Error = io::Error;
+
+        fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+            match self.bytes_needed(src) {
+                Some(0) => {
+                    let len = BigEndian::read_u16(&src[..2]) as usize;
+                    src.advance(2);
+                    Ok(Some(src.split_to(len)))
+                }
+                _ => Ok(None),
+            }
+        }
+
+        fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+            if src.len() < 2 {
+                return None;
+            }
+            let len = BigEndian::read_u16(&src[..2]) as usize;
+            Some((2 + len).saturating_sub(src.len()))
+        }
+    }
+
+    /// Serves its data one byte per read
+    struct OneByte<'a>(&'a [u8]);
+
+    impl AsyncRead for OneByte<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<Result<usize, io::Error>> {
+            let n = self.0.len().min(1);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    /// Counts calls to `decode` before forwarding to `U16Prefixed`
+    struct Counting(usize);
+
+    impl Decoder for Counting {
+        type Item = BytesMut;
+        type Error = io::Error;
+
+        fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+            self.0 += 1;
+            U16Prefixed.decode(src)
+        }
+
+        fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+            U16Prefixed.bytes_needed(src)
+        }
+    }
+
+    #[test]
+    fn decode_skipped_until_bytes_needed() {
+        let input = [0x00, 0x0a, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let mut framed = FramedRead::new(OneByte(&input), Counting(0));
+
+        let next = executor::block_on(framed.try_next()).unwrap().unwrap();
+        assert_eq!(&next[..], &input[2..]);
+
+        let (_, decoder) = framed.release();
+        assert_eq!(decoder.0, 3);
+    }
+
+    #[test]
+    fn unread_body_skipped() {
+        let input = b"body\n0123456789\nafter\n";
+        let mut framed = FramedRead::new(&input[..], crate::LinesCodec::new());
+
+        let next = executor::block_on(framed.try_next()).unwrap().unwrap();
+        assert_eq!(next, "body\n");
+
+        let mut body = framed.body(11);
+        let mut start = [0u8; 4];
+        executor::block_on(futures::AsyncReadExt::read_exact(&mut body, &mut start)).unwrap();
+        assert_eq!(&start, b"0123");
+        assert_eq!(body.remaining(), 7);
+        drop(body);
+
+        let next = executor::block_on(framed.try_next()).unwrap().unwrap();
+        assert_eq!(next, "after\n");
+    }
+
+    #[test]
+    fn metadata_offsets_past_body() {
+        let input = b"body\n0123456789after\n";
+        let mut framed = FramedRead::new(&input[..], crate::LinesCodec::new());
+
+        executor::block_on(framed.try_next()).unwrap().unwrap();
+        drop(framed.body(10));
+
+        let mut framed = framed.with_metadata();
+        let (meta, line) = executor::block_on(framed.try_next()).unwrap().unwrap();
+        assert_eq!(line, "after\n");
+        assert_eq!((meta.offset, meta.len), (15, 6));
+    }
+
+    #[test]
+    fn large_frame_read_into_buffer() {
+        let mut input = vec![0x50, 0x00];
+        input.extend(vec![7u8; 0x5000]);
+        input.extend(&[0x00, 0x02, 1, 2]);
+
+        let mut framed = FramedRead::new(&input[..], U16Prefixed);
+        let next = executor::block_on(framed.try_next()).unwrap().unwrap();
+        assert_eq!(&next[..], &vec![7u8; 0x5000][..]);
+        let next = executor::block_on(framed.try_next()).unwrap().unwrap();
+        assert_eq!(&next[..], &[1, 2]);
+        assert!(executor::block_on(framed.try_next()).unwrap().is_none());
+    }
+
+    /// Announces a frame far larger than will ever arrive
+    struct Huge;
+
+    impl Decoder for Huge {
+        type Item = BytesMut;
+        type Error = io::Error;
+
+        fn decode(&mut self, _src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+            Ok(None)
+        }
+
+        fn bytes_needed(&self, src: &BytesMut) -> Option<usize> {
+            if src.is_empty() {
+                None
+            } else {
+                Some(usize::MAX / 2)
+            }
+        }
+    }
+
+    #[test]
+    fn announced_length_not_allocated() {
+        let input = vec![7u8; 3 * INITIAL_CAPACITY];
+        let mut framed = FramedRead::new(&input[..], Huge);
+        assert!(executor::block_on(framed.try_next()).is_err());
+
+        let (_, _, buffer) = framed.release_with_buffers();
+        assert_eq!(buffer.len(), input.len());
+        assert!(buffer.capacity() < 8 * INITIAL_CAPACITY);
+    }
+
+    pin_project! {
+        /// A reader that must stay pinned
+        struct NotUnpin<R> {
+            #[pin]
+            inner: R,
+            #[pin]
+            _pinned: std::marker::PhantomPinned,
+        }
+    }
+
+    impl<R: AsyncRead> AsyncRead for NotUnpin<R> {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<Result<usize, io::Error>> {
+            self.project().inner.poll_read(cx, buf)
+        }
+    }
+
+    #[test]
+    fn not_unpin_io() {
+        let io = NotUnpin {
+            inner: &b"Hello\n"[..],
+            _pinned: std::marker::PhantomPinned,
+        };
+        let mut framed = Box::pin(FramedRead::new(io, crate::LinesCodec::new()));
+        let next = executor::block_on(framed.as_mut().try_next()).unwrap().unwrap();
+        assert_eq!(next, "Hello\n");
+    }
+
+    #[test]
+    fn retries_interrupted_read() {
+        let io = crate::testing::ChunkedMockIo::builder()
+            .read(b"Hel")
+            .error(io::ErrorKind::Interrupted)
+            .read(b"lo\n")
+            .build();
+        let mut framed = FramedRead::new(io, crate::LinesCodec::new());
+        assert_eq!(executor::block_on(framed.try_next()).unwrap().unwrap(), "Hello\n");
+    }
+
+    #[test]
+    fn prepend_after_partial_scan() {
+        let io = crate::testing::ChunkedMockIo::builder()
+            .read(b"Hel")
+            .pending()
+            .read(b"lo\n")
+            .build();
+        let mut framed = FramedRead::new(io, crate::LinesCodec::new());
+        assert!(futures::FutureExt::now_or_never(framed.try_next()).is_none());
+
+        framed.prepend(b"X\n");
+        executor::block_on(async {
+            assert_eq!(framed.try_next().await.unwrap().unwrap(), "X\n");
+            assert_eq!(framed.try_next().await.unwrap().unwrap(), "Hello\n");
+        });
+    }
+
+    #[test]
+    fn initial_frames_before_read() {
+        let io = crate::testing::ChunkedMockIo::builder().pending().build();
+        let initial = Bytes::from(&b"Hello\nWorld\n"[..]);
+        let mut framed = FramedRead::with_initial(io, crate::LinesCodec::new(), initial);
+
+        executor::block_on(async {
+            assert_eq!(framed.try_next().await.unwrap().unwrap(), "Hello\n");
+            assert_eq!(framed.try_next().await.unwrap().unwrap(), "World\n");
+        });
+    }
+
+    #[test]
+    fn peek_then_next() {
+        let mut framed = FramedRead::new(&b"Hello\nWorld\n"[..], crate::LinesCodec::new());
+
+        executor::block_on(async {
+            assert_eq!(framed.peek().await.unwrap().unwrap(), "Hello\n");
+            assert_eq!(framed.peek().await.unwrap().unwrap(), "Hello\n");
+            assert!(!framed.is_terminated());
+            let lines: Vec<_> = (&mut framed).try_collect().await.unwrap();
+            assert_eq!(lines, ["Hello\n", "World\n"]);
+            assert!(framed.peek().await.is_none());
+        });
+    }
+
+    #[test]
+    fn position_after_seek() {
+        let buf = b"one\ntwo\nthree\n";
+        let mut framed = FramedRead::new(io::Cursor::new(&buf[..]), crate::LinesCodec::new());
+
+        executor::block_on(async {
+            assert_eq!(framed.try_next().await.unwrap().unwrap(), "one\n");
+            assert_eq!(framed.stream_position_of_next_frame(), 4);
+            framed.peek().await.unwrap().unwrap();
+            assert_eq!(framed.stream_position_of_next_frame(), 4);
+
+            let lines: Vec<_> = (&mut framed).try_collect().await.unwrap();
+            assert_eq!(lines, ["two\n", "three\n"]);
+            framed.seek_to(8).await.unwrap();
+            assert_eq!(framed.stream_position_of_next_frame(), 8);
+            assert_eq!(framed.try_next().await.unwrap().unwrap(), "three\n");
+        });
+    }
+
+    #[test]
+    fn seek_after_partial_scan() {
+        let buf = b"a\nbcdefghij";
+        let mut framed = FramedRead::new(io::Cursor::new(&buf[..]), crate::LinesCodec::new());
+
+        executor::block_on(async {
+            assert_eq!(framed.try_next().await.unwrap().unwrap(), "a\n");
+            assert!(framed.try_next().await.is_err());
+            framed.seek_to(0).await.unwrap();
+            assert_eq!(framed.try_next().await.unwrap().unwrap(), "a\n");
+        });
+    }
+
+    #[test]
+    fn body_after_partial_scan() {
+        let io = crate::testing::ChunkedMockIo::builder()
+            .read(b"abcd")
+            .pending()
+            .read(b"\n")
+            .build();
+        let mut framed = FramedRead::new(io, crate::LinesCodec::new());
+        assert!(futures::FutureExt::now_or_never(framed.try_next()).is_none());
+
+        executor::block_on(async {
+            let mut body = [0u8; 3];
+            futures::AsyncReadExt::read_exact(&mut framed.body(3), &mut body).await.unwrap();
+            assert_eq!(&body, b"abc");
+            assert_eq!(framed.try_next().await.unwrap().unwrap(), "d\n");
+        });
+    }
+
+    #[test]
+    fn frame_larger_than_fixed_capacity() {
+        let framed = FramedRead::new(&b"Hey\nHello\n"[..], crate::LinesCodec::new())
+            .fixed_capacity(4);
+        let lines: Vec<_> = executor::block_on(framed.collect::<Vec<_>>());
+        assert_eq!(lines[0].as_ref().unwrap(), "Hey\n");
+        assert_eq!(lines[1].as_ref().unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn borrowed_frame_larger_than_fixed_capacity() {
+        let mut framed = FramedRead::new(&b"Hey\nHello\n"[..], crate::LinesCodec::new())
+            .fixed_capacity(4);
+        executor::block_on(async move {
+            assert_eq!(framed.next_borrowed().await.unwrap().unwrap(), "Hey\n");
+            let err = framed.next_borrowed().await.unwrap().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(framed.next_borrowed().await.is_none());
+        })
+    }
+
+    #[test]
+    fn borrowed_frames_end_at_eof() {
+        let mut framed = FramedRead::new(&b"Hey\nHo"[..], crate::LinesCodec::new());
+        executor::block_on(async move {
+            assert_eq!(framed.next_borrowed().await.unwrap().unwrap(), "Hey\n");
+            let err = framed.next_borrowed().await.unwrap().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+            assert!(framed.next_borrowed().await.is_none());
+        })
+    }
+
+    #[test]
+    fn discard_incomplete_eof() {
+        let framed = FramedRead::new(&b"Hello\nWor"[..], crate::LinesCodec::new())
+            .on_incomplete_eof(IncompleteEof::Discard);
+        let lines: Vec<_> = executor::block_on(framed.try_collect()).unwrap();
+        assert_eq!(lines, ["Hello\n"]);
+    }
+
+    #[test]
+    fn terminated_after_io_error() {
+        let io = crate::testing::ChunkedMockIo::builder()
+            .read(b"Hello\n")
+            .error(io::ErrorKind::BrokenPipe)
+            .build();
+        let mut framed = FramedRead::new(io, crate::LinesCodec::new());
+
+        assert_eq!(executor::block_on(framed.try_next()).unwrap().unwrap(), "Hello\n");
+        assert!(!framed.is_terminated());
+        assert!(executor::block_on(framed.try_next()).is_err());
+        assert!(framed.is_terminated());
+        assert!(executor::block_on(framed.try_next()).unwrap().is_none());
+    }
 }
@@ -0,0 +1,104 @@
+use super::Encoder;
+
+use bytes::{Bytes, BytesMut};
+use futures::Sink;
+use pin_project_lite::pin_project;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+pin_project! {
+    /// A `Sink` of frames encoded into a `Sink` of `Bytes`.
+    ///
+    /// Every item is encoded into its own `Bytes` message, which makes this
+    /// suitable for message based transports such as WebSocket senders or
+    /// channels.
+    ///
+    /// # Example
+    /// ```
+    /// use bytes::Bytes;
+    /// use futures::{channel::mpsc, executor, SinkExt, StreamExt};
+    /// use futures_codec::{FramedSinkWrite, LinesCodec};
+    /// use std::io;
+    ///
+    /// let (tx, mut rx) = mpsc::unbounded::<Bytes>();
+    /// let tx = tx.sink_map_err(|e| io::Error::new(io::ErrorKind::BrokenPipe, e));
+    /// let mut framed = FramedSinkWrite::new(tx, LinesCodec::new());
+    ///
+    /// executor::block_on(async move {
+    ///     framed.send("Hello\n".to_owned()).await.unwrap();
+    ///     assert_eq!(rx.next().await.unwrap(), Bytes::from("Hello\n"));
+    /// })
+    /// ```
+    pub struct FramedSinkWrite<S, E> {
+        #[pin]
+        inner: S,
+        encoder: E,
+        buffer: BytesMut,
+    }
+}
+
+impl<S, E> FramedSinkWrite<S, E>
+where
+    S: Sink<Bytes>,
+    E: Encoder,
+{
+    pub fn new(inner: S, encoder: E) -> Self {
+        Self {
+            inner,
+            encoder,
+            buffer: BytesMut::new(),
+        }
+    }
+
+    /// Release the message sink and Encoder
+    pub fn release(self) -> (S, E) {
+        (self.inner, self.encoder)
+    }
+}
+
+impl<S, E> Sink<E::Item> for FramedSinkWrite<S, E>
+where
+    S: Sink<Bytes>,
+    E: Encoder,
+    E::Error: From<S::Error>,
+{
+    type Error = E::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_ready(cx).map_err(Into::into)
+    }
+    fn start_send(self: Pin<&mut Self>, item: E::Item) -> Result<(), Self::Error> {
+        let this = self.project();
+        this.encoder.encode(item, this.buffer)?;
+        let msg = this.buffer.take().freeze();
+        this.inner.start_send(msg).map_err(Into::into)
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_flush(cx).map_err(Into::into)
+    }
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_close(cx).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FramedSinkWrite;
+    use crate::LinesCodec;
+
+    use bytes::Bytes;
+    use futures::{executor, SinkExt};
+    use std::io;
+
+    #[test]
+    fn one_message_per_item() {
+        let mut sent = Vec::new();
+        {
+            let sink = (&mut sent).sink_map_err(|_| io::Error::from(io::ErrorKind::Other));
+            let mut framed = FramedSinkWrite::new(sink, LinesCodec::new());
+            executor::block_on(framed.send("Hello\n".to_owned())).unwrap();
+            executor::block_on(framed.send("World\n".to_owned())).unwrap();
+        }
+        assert_eq!(sent, vec![Bytes::from("Hello\n"), Bytes::from("World\n")]);
+    }
+}
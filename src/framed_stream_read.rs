@@ -0,0 +1,166 @@
+use super::{Decoder, FrameAccumulator};
+
+use futures::stream::FusedStream;
+use futures::{ready, Stream, TryStream};
+use pin_project_lite::pin_project;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+pin_project! {
+    /// A `Stream` of messages decoded from a `TryStream` of byte chunks.
+    ///
+    /// Useful for message based sources such as WebSocket binary messages, HTTP
+    /// body chunks or channel receivers, where the bytes do not come from an
+    /// `AsyncRead`.
+    ///
+    /// # Example
+    /// ```
+    /// use bytes::Bytes;
+    /// use futures::{executor, stream, TryStreamExt};
+    /// use futures_codec::{FramedStreamRead, LinesCodec};
+    /// use std::io;
+    ///
+    /// let chunks = vec![Bytes::from("Hel"), Bytes::from("lo\nWorld\n")];
+    /// let source = stream::iter(chunks.into_iter().map(Ok::<_, io::Error>));
+    /// let mut framed = FramedStreamRead::new(source, LinesCodec::new());
+    ///
+    /// executor::block_on(async move {
+    ///     assert_eq!(framed.try_next().await.unwrap().unwrap(), "Hello\n");
+    ///     assert_eq!(framed.try_next().await.unwrap().unwrap(), "World\n");
+    ///     assert!(framed.try_next().await.unwrap().is_none());
+    /// })
+    /// ```
+    pub struct FramedStreamRead<S, D> {
+        #[pin]
+        inner: S,
+        frames: FrameAccumulator<D>,
+        // Whether the chunk stream ended
+        eof: bool,
+        // Whether the chunk stream ended or failed and all frames were returned
+        terminated: bool,
+    }
+}
+
+impl<S, D> FramedStreamRead<S, D>
+where
+    S: TryStream,
+    S::Ok: AsRef<[u8]>,
+    D: Decoder,
+{
+    pub fn new(inner: S, decoder: D) -> Self {
+        Self {
+            inner,
+            frames: FrameAccumulator::new(decoder),
+            eof: false,
+            terminated: false,
+        }
+    }
+
+    /// Release the chunk stream and Decoder
+    pub fn release(self) -> (S, D) {
+        (self.inner, self.frames.into_parts().0)
+    }
+}
+
+impl<S, D> Stream for FramedStreamRead<S, D>
+where
+    S: TryStream,
+    S::Ok: AsRef<[u8]>,
+    D: Decoder,
+    D::Error: From<S::Error>,
+{
+    type Item = Result<D::Item, D::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        if *this.terminated {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            if *this.eof {
+                let item = this.frames.decode_eof().transpose();
+                *this.terminated = !matches!(item, Some(Ok(_)));
+                return Poll::Ready(item);
+            }
+            if let Some(item) = this.frames.decode()? {
+                return Poll::Ready(Some(Ok(item)));
+            }
+
+            match ready!(this.inner.as_mut().try_poll_next(cx)) {
+                Some(Ok(chunk)) => this.frames.extend(chunk.as_ref()),
+                Some(Err(e)) => {
+                    *this.terminated = true;
+                    return Poll::Ready(Some(Err(e.into())));
+                }
+                None => *this.eof = true,
+            }
+        }
+    }
+}
+
+impl<S, D> FusedStream for FramedStreamRead<S, D>
+where
+    S: TryStream,
+    S::Ok: AsRef<[u8]>,
+    D: Decoder,
+    D::Error: From<S::Error>,
+{
+    fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FramedStreamRead;
+    use crate::LinesCodec;
+
+    use bytes::Bytes;
+    use futures::stream::FusedStream;
+    use futures::{executor, stream, TryStreamExt};
+    use std::io;
+    use std::task::Poll;
+
+    #[test]
+    fn incomplete_frame_at_end() {
+        let chunks = vec![Bytes::from("Hello\nWor")];
+        let source = stream::iter(chunks.into_iter().map(Ok::<_, io::Error>));
+        let mut framed = FramedStreamRead::new(source, LinesCodec::new());
+
+        let next = executor::block_on(framed.try_next()).unwrap().unwrap();
+        assert_eq!(next, "Hello\n");
+
+        let err = executor::block_on(framed.try_next()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(framed.is_terminated());
+        assert!(executor::block_on(framed.try_next()).unwrap().is_none());
+    }
+
+    #[test]
+    fn source_not_polled_after_end() {
+        let mut chunks = vec![Bytes::from("Hello\n")].into_iter();
+        let mut ended = false;
+        let source = stream::poll_fn(move |_| {
+            assert!(!ended, "polled after the end");
+            let chunk = chunks.next();
+            ended = chunk.is_none();
+            Poll::Ready(chunk.map(Ok::<_, io::Error>))
+        });
+        let mut framed = FramedStreamRead::new(source, LinesCodec::new());
+
+        let next = executor::block_on(framed.try_next()).unwrap().unwrap();
+        assert_eq!(next, "Hello\n");
+        assert!(executor::block_on(framed.try_next()).unwrap().is_none());
+        assert!(executor::block_on(framed.try_next()).unwrap().is_none());
+    }
+
+    #[test]
+    fn chunk_error() {
+        let chunks = vec![Err::<Bytes, _>(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))];
+        let mut framed = FramedStreamRead::new(stream::iter(chunks), LinesCodec::new());
+
+        let err = executor::block_on(framed.try_next()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+}
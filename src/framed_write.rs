@@ -1,34 +1,49 @@
-use super::Encoder;
-use super::framed::Fuse;
-use bytes::BytesMut;
-use futures::{ready, Sink};
-use futures::io::{AsyncRead, AsyncWrite};
+use super::{AsyncShutdown, Encoder};
+use super::framed::{CodecMut, Fuse};
+use super::deadline::WriteDeadline;
+use super::framing_error::WithContext;
+use super::retry::poll_retry;
+use bytes::{Bytes, BytesMut};
+use futures::future::{self, poll_fn, Either, Future};
+use futures::{pin_mut, ready, Sink, SinkExt};
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, IoSlice, IoSliceMut};
+use super::stats::{Counters, Stats, StatsHandle};
+use std::collections::VecDeque;
+use std::sync::Arc;
 use std::io::{Error, ErrorKind};
+use pin_project_lite::pin_project;
 use std::marker::Unpin;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-/// A `Sink` of frames encoded to an `AsyncWrite`.
-///
-/// # Example
-/// ```
-/// #![feature(async_await, await_macro)]
-/// use bytes::Bytes;
-/// use futures_codec::{FramedWrite, BytesCodec};
-/// use futures::{executor, SinkExt};
-///
-/// executor::block_on(async move {
-///     let mut buf = Vec::new();
-///     let mut framed = FramedWrite::new(&mut buf, BytesCodec {});
-///
-///     let msg = Bytes::from("Hello World!");
-///     framed.send(msg.clone()).await.unwrap();
-///
-///     assert_eq!(&buf[..], &msg[..]);
-/// })
-/// ```
-pub struct FramedWrite<T, E> {
-    inner: FramedWrite2<Fuse<T, E>>,
+pin_project! {
+    /// A `Sink` of frames encoded to an `AsyncWrite`.
+    ///
+    /// `&mut FramedWrite` is a `Sink` too if the I/O is `Unpin`, so it can be
+    /// lent to helpers such as `forward` and used again afterwards. Otherwise
+    /// pin it and lend `Pin<&mut FramedWrite>` instead.
+    ///
+    /// # Example
+    /// ```
+    /// #![feature(async_await, await_macro)]
+    /// use bytes::Bytes;
+    /// use futures_codec::{FramedWrite, BytesCodec};
+    /// use futures::{executor, SinkExt};
+    ///
+    /// executor::block_on(async move {
+    ///     let mut buf = Vec::new();
+    ///     let mut framed = FramedWrite::new(&mut buf, BytesCodec {});
+    ///
+    ///     let msg = Bytes::from("Hello World!");
+    ///     framed.send(msg.clone()).await.unwrap();
+    ///
+    ///     assert_eq!(&buf[..], &msg[..]);
+    /// })
+    /// ```
+    pub struct FramedWrite<T, E> {
+        #[pin]
+        inner: FramedWrite2<Fuse<T, E>>,
+    }
 }
 
 impl<T, E> FramedWrite<T, E>
@@ -38,102 +53,587 @@ where
 {
     pub fn new(inner: T, encoder: E) -> Self {
         Self {
-            inner: framed_write_2(Fuse(inner, encoder)),
+            inner: framed_write_2(Fuse(inner, encoder), Default::default()),
         }
     }
 
     /// Release the I/O and Encoder
-    pub fn release(self: Self) -> (T, E) {
+    pub fn release(self) -> (T, E) {
         let fuse = self.inner.release();
         (fuse.0, fuse.1)
     }
+
+    /// A snapshot of the frame and byte counters
+    pub fn stats(&self) -> Stats {
+        self.inner.state.stats.snapshot()
+    }
+
+    /// When queued frames are written out without being flushed
+    pub fn flush_policy(&self) -> FlushPolicy {
+        self.inner.state.flush_policy
+    }
+
+    /// Number of encoded bytes waiting to be written
+    pub(crate) fn queued_len(&self) -> usize {
+        self.inner.state.queued
+    }
+
+    /// Fail with `InvalidData` when an item would make the queued frames
+    /// take up more than `capacity` bytes, instead of buffering without limit
+    ///
+    /// `poll_ready` writes out all queued frames, whatever the `FlushPolicy`,
+    /// so only items fed without waiting for it can fail.
+    pub fn fixed_capacity(mut self, capacity: usize) -> Self {
+        self.inner.set_capacity(capacity);
+        self
+    }
+
+    /// Change when queued frames are written out, see `FlushPolicy`
+    ///
+    /// # Example
+    /// ```
+    /// use futures_codec::{FlushPolicy, FramedWrite, LinesCodec};
+    ///
+    /// let mut framed = FramedWrite::new(Vec::new(), LinesCodec::new());
+    /// framed.set_flush_policy(FlushPolicy::Threshold(64 * 1024));
+    /// ```
+    pub fn set_flush_policy(&mut self, policy: FlushPolicy) {
+        self.inner.set_flush_policy(policy);
+    }
+
+    /// A handle for reading the counters from elsewhere
+    pub fn stats_handle(&self) -> StatsHandle {
+        StatsHandle(self.inner.state.stats.clone())
+    }
+
+    /// Wrap errors in a `FramingError` with the index of the failed frame,
+    /// the number of bytes written and up to `snapshot` queued bytes.
+    pub fn with_error_context(self, snapshot: usize) -> WithContext<Self> {
+        WithContext::new(self, snapshot)
+    }
+
+    /// Fail with a `WriteTimedOut` error if a frame is not written within
+    /// the deadline returned by `timer`, which is called whenever writing a
+    /// frame has to wait for the I/O.
+    ///
+    /// # Example
+    /// ```
+    /// use futures::future;
+    /// use futures_codec::{FramedWrite, LinesCodec};
+    ///
+    /// // Use the delay of your runtime here
+    /// let framed = FramedWrite::new(Vec::new(), LinesCodec::new())
+    ///     .with_write_deadline(|| future::pending::<()>());
+    /// ```
+    pub fn with_write_deadline<D, F>(self, timer: D) -> WriteDeadline<Self, D, F>
+    where
+        D: FnMut() -> F,
+        F: Future<Output = ()>,
+    {
+        WriteDeadline::new(self, timer)
+    }
+
+    pub(crate) fn frames_written(&self) -> u64 {
+        self.inner.frames_written()
+    }
+
+    pub(crate) fn error_context(&self, snapshot: usize) -> (u64, Bytes) {
+        let state = &self.inner.state;
+        let mut buffered = BytesMut::new();
+        for segment in state.queue.iter().chain(Some(&state.buffer.clone().freeze())) {
+            let n = segment.len().min(snapshot - buffered.len());
+            buffered.extend_from_slice(&segment[..n]);
+        }
+        (state.stats.snapshot().bytes_written, buffered.freeze())
+    }
 }
 
-impl<T, E> Sink<E::Item> for FramedWrite<T, E>
+impl<T, E> FramedWrite<T, E>
 where
     T: AsyncWrite + Unpin,
     E: Encoder,
+{
+    /// Send `header` as a frame, then copy a body of `len` bytes from `body`
+    /// straight to the I/O without buffering it.
+    ///
+    /// # Example
+    /// ```
+    /// use futures::executor;
+    /// use futures_codec::{FramedWrite, LinesCodec};
+    ///
+    /// executor::block_on(async move {
+    ///     let mut buf = Vec::new();
+    ///     let mut framed = FramedWrite::new(&mut buf, LinesCodec::new());
+    ///
+    ///     let body = b"Hello World!";
+    ///     framed.send_body("12\n".to_owned(), &body[..], 12).await.unwrap();
+    ///
+    ///     assert_eq!(&buf[..], b"12\nHello World!");
+    /// })
+    /// ```
+    pub async fn send_body<R>(
+        &mut self,
+        header: E::Item,
+        mut body: R,
+        len: u64,
+    ) -> Result<(), E::Error>
+    where
+        R: AsyncRead + Unpin,
+    {
+        self.send(header).await?;
+
+        let mut buf = [0u8; 8 * 1024];
+        let mut remaining = len;
+        while remaining > 0 {
+            let max = buf.len().min(remaining as usize);
+            let n = body.read(&mut buf[..max]).await?;
+            if n == 0 {
+                return Err(Error::new(ErrorKind::UnexpectedEof, "body ended early").into());
+            }
+            self.inner.inner.write_all(&buf[..n]).await?;
+            remaining -= n as u64;
+        }
+        self.inner.inner.flush().await.map_err(Into::into)
+    }
+
+    /// Write all queued frames and the closing frame of the encoder, then
+    /// close the I/O.
+    ///
+    /// Fails with `TimedOut` if `timeout` completes first.
+    ///
+    /// # Example
+    /// ```
+    /// use futures::{executor, future};
+    /// use futures_codec::{FramedWrite, LinesCodec};
+    ///
+    /// executor::block_on(async move {
+    ///     let mut framed = FramedWrite::new(Vec::new(), LinesCodec::new());
+    ///     framed.close_graceful(future::pending()).await.unwrap();
+    /// })
+    /// ```
+    pub async fn close_graceful<F>(&mut self, timeout: F) -> Result<(), E::Error>
+    where
+        F: Future<Output = ()>,
+    {
+        close_graceful(Pin::new(&mut self.inner), timeout).await
+    }
+
+    /// Encode `item` into the write buffer without flushing it
+    ///
+    /// The buffer is only written out early when it is over its limit.
+    pub async fn feed(&mut self, item: E::Item) -> Result<(), E::Error> {
+        feed(self, item).await
+    }
+
+    /// Encode all `items` into the write buffer, then flush them at once
+    ///
+    /// # Example
+    /// ```
+    /// use futures::executor;
+    /// use futures_codec::{FramedWrite, LinesCodec};
+    ///
+    /// executor::block_on(async move {
+    ///     let mut buf = Vec::new();
+    ///     let mut framed = FramedWrite::new(&mut buf, LinesCodec::new());
+    ///
+    ///     let lines = vec!["Hello\n".to_owned(), "World\n".to_owned()];
+    ///     framed.send_batch(lines).await.unwrap();
+    ///
+    ///     assert_eq!(&buf[..], b"Hello\nWorld\n");
+    /// })
+    /// ```
+    pub async fn send_batch<I>(&mut self, items: I) -> Result<(), E::Error>
+    where
+        I: IntoIterator<Item = E::Item>,
+    {
+        send_batch(self, items).await
+    }
+}
+
+/// Wait until `sink` is ready, then start sending `item`
+pub(crate) async fn feed<S, I>(sink: &mut S, item: I) -> Result<(), S::Error>
+where
+    S: Sink<I> + Unpin,
+{
+    poll_fn(|cx| Pin::new(&mut *sink).poll_ready(cx)).await?;
+    Pin::new(sink).start_send(item)
+}
+
+/// Feed all `items` to `sink`, then flush it once
+pub(crate) async fn send_batch<S, I, It>(sink: &mut S, items: It) -> Result<(), S::Error>
+where
+    S: Sink<I> + Unpin,
+    It: IntoIterator<Item = I>,
+{
+    for item in items {
+        feed(sink, item).await?;
+    }
+    poll_fn(|cx| Pin::new(&mut *sink).poll_flush(cx)).await
+}
+
+impl<T, E> FramedWrite<T, E>
+where
+    T: AsyncWrite + AsyncShutdown,
+    E: Encoder,
+{
+    /// Write all queued frames, then close the write direction of the I/O.
+    pub fn poll_close_write(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), E::Error>> {
+        self.project().inner.poll_close_write(cx).map_err(Into::into)
+    }
+
+    /// Write all queued frames, then close the write direction of the I/O.
+    pub async fn close_write(&mut self) -> Result<(), E::Error>
+    where
+        T: Unpin,
+    {
+        poll_fn(|cx| Pin::new(&mut *self).poll_close_write(cx)).await
+    }
+}
+
+impl<T, E> Sink<E::Item> for FramedWrite<T, E>
+where
+    T: AsyncWrite,
+    E: Encoder,
 {
     type Error = E::Error;
 
-    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
-        Pin::new(&mut self.inner).poll_ready(cx)
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_ready(cx)
     }
-    fn start_send(mut self: Pin<&mut Self>, item: E::Item) -> Result<(), Self::Error> {
-        Pin::new(&mut self.inner).start_send(item)
+    fn start_send(self: Pin<&mut Self>, item: E::Item) -> Result<(), Self::Error> {
+        self.project().inner.start_send(item)
     }
-    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
-        Pin::new(&mut self.inner).poll_flush(cx)
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Sink::poll_flush(self.project().inner, cx)
     }
-    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
-        Pin::new(&mut self.inner).poll_close(cx)
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Sink::poll_close(self.project().inner, cx)
+    }
+}
+
+pin_project! {
+    pub struct FramedWrite2<T> {
+        #[pin]
+        pub inner: T,
+        pub state: WriteState,
     }
 }
 
-pub struct FramedWrite2<T> {
-    pub inner: T,
+/// When a `FramedWrite` writes out queued frames other than on `poll_flush`.
+///
+/// Whatever the policy, flushing writes out all queued frames, and
+/// `SinkExt::send` flushes after every item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlushPolicy {
+    /// Write and flush every frame before accepting the next one.
+    EveryFrame,
+    /// Write and flush the queued frames before accepting another one once
+    /// more than this many bytes are queued.
+    Threshold(usize),
+    /// Only write frames when flushed, the default.
+    #[default]
+    Explicit,
+}
+
+/// Everything in a `FramedWrite2` besides the pinned I/O
+pub struct WriteState {
+    /// Staging buffer the encoder writes into
     buffer: BytesMut,
+    /// Encoded frames waiting to be written
+    queue: VecDeque<Bytes>,
+    /// Total length of the frames in `queue`
+    queued: usize,
+    /// Bytes of frames queued and written since the start
+    queued_total: u64,
+    written_total: u64,
+    /// Value of `queued_total` at the end of every frame not written yet
+    frame_ends: VecDeque<u64>,
+    frames_written: u64,
+    flush_policy: FlushPolicy,
+    /// Number of queued bytes not to go beyond
+    capacity: Option<usize>,
+    pub stats: Arc<Counters>,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
 }
 
-pub fn framed_write_2<T>(inner: T) -> FramedWrite2<T> {
+/// Maximum number of queued segments handed to a single vectored write
+const MAX_WRITE_SEGMENTS: usize = 64;
+
+pub fn framed_write_2<T>(inner: T, stats: Arc<Counters>) -> FramedWrite2<T> {
     FramedWrite2 {
         inner,
-        buffer: BytesMut::with_capacity(1028 * 8),
+        state: WriteState {
+            buffer: BytesMut::with_capacity(1028 * 8),
+            queue: VecDeque::new(),
+            queued: 0,
+            queued_total: 0,
+            written_total: 0,
+            frame_ends: VecDeque::new(),
+            frames_written: 0,
+            flush_policy: FlushPolicy::Explicit,
+            capacity: None,
+            stats,
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::current(),
+        },
     }
 }
 
-impl<T> Unpin for FramedWrite2<T> {}
-
-impl<T: AsyncRead + Unpin> AsyncRead for FramedWrite2<T> {
+impl<T: AsyncRead> AsyncRead for FramedWrite2<T> {
     fn poll_read(
-        mut self: Pin<&mut Self>,
+        self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &mut [u8],
     ) -> Poll<Result<usize, Error>> {
-        Pin::new(&mut self.inner).poll_read(cx, buf)
+        self.project().inner.poll_read(cx, buf)
+    }
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<Result<usize, Error>> {
+        self.project().inner.poll_read_vectored(cx, bufs)
+    }
+}
+
+impl<T: CodecMut> CodecMut for FramedWrite2<T> {
+    type Codec = T::Codec;
+
+    fn codec_mut(self: Pin<&mut Self>) -> &mut Self::Codec {
+        self.project().inner.codec_mut()
     }
 }
 
-impl<T> Sink<T::Item> for FramedWrite2<T>
+impl<T> Sink<<T::Codec as Encoder>::Item> for FramedWrite2<T>
 where
-    T: AsyncWrite + Encoder + Unpin,
+    T: AsyncWrite + CodecMut,
+    T::Codec: Encoder,
 {
-    type Error = T::Error;
+    type Error = <T::Codec as Encoder>::Error;
 
-    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        let this = self.project();
+        // The size of the next frame is not known, so with a fixed capacity
+        // make as much room as possible
+        let limit = match this.state.flush_policy {
+            _ if this.state.capacity.is_some() => 0,
+            FlushPolicy::EveryFrame => 0,
+            FlushPolicy::Threshold(max) => max,
+            FlushPolicy::Explicit => return Poll::Ready(Ok(())),
+        };
+        if this.state.queued > limit {
+            ready!(this.state.poll_write_queue(this.inner, cx))?;
+        }
         Poll::Ready(Ok(()))
     }
-    fn start_send(mut self: Pin<&mut Self>, item: T::Item) -> Result<(), Self::Error> {
-        let this = &mut *self;
-        this.inner.encode(item, &mut this.buffer)
-    }
-    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
-        let this = &mut *self;
-        while !this.buffer.is_empty() {
-            let num_write = ready!(Pin::new(&mut this.inner).poll_write(cx, &this.buffer))?;
-
-            if num_write == 0 {
-                return Poll::Ready(Err(
-                    Error::new(ErrorKind::UnexpectedEof, "End of file").into()
-                ));
+    fn start_send(
+        self: Pin<&mut Self>,
+        item: <T::Codec as Encoder>::Item,
+    ) -> Result<(), Self::Error> {
+        let this = self.project();
+        let codec = this.inner.codec_mut();
+        let body = codec.encode_zero_copy(item, &mut this.state.buffer)?;
+        if let Some(capacity) = this.state.capacity {
+            let len = this.state.buffer.len() + body.as_ref().map_or(0, Bytes::len);
+            if this.state.queued + len > capacity {
+                this.state.buffer.clear();
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "frame exceeds the write buffer capacity",
+                )
+                .into());
             }
-
-            let _ = this.buffer.split_to(num_write);
-            ready!(Pin::new(&mut this.inner).poll_flush(cx).map_err(Into::into))?;
         }
-        Poll::Ready(Ok(()))
+        this.state.queue_frame(body);
+        Ok(())
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        let this = self.project();
+        this.state.poll_write_queue(this.inner, cx).map_err(Into::into)
     }
     fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
-        let mut this = &mut *self;
-        ready!(Pin::new(&mut this).poll_flush(cx))?;
-        Pin::new(&mut this.inner).poll_close(cx).map_err(Into::into)
+        ready!(Sink::poll_flush(self.as_mut(), cx))?;
+        self.project().inner.poll_close(cx).map_err(Into::into)
+    }
+}
+
+/// Raw writes, after all queued frames have been written
+impl<T: AsyncWrite> AsyncWrite for FramedWrite2<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<Result<usize, Error>> {
+        let this = self.project();
+        let mut inner = this.inner;
+        ready!(this.state.poll_write_queue(inner.as_mut(), cx))?;
+        let n = ready!(inner.poll_write(cx, buf))?;
+        this.state.stats.written_raw(n);
+        Poll::Ready(Ok(n))
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Error>> {
+        let this = self.project();
+        let mut inner = this.inner;
+        ready!(this.state.poll_write_queue(inner.as_mut(), cx))?;
+        inner.poll_flush(cx)
+    }
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Error>> {
+        let this = self.project();
+        let mut inner = this.inner;
+        ready!(this.state.poll_write_queue(inner.as_mut(), cx))?;
+        inner.poll_close(cx)
     }
 }
 
 impl<T> FramedWrite2<T> {
-    pub fn release(self: Self) -> T {
+    pub fn release(self) -> T {
         self.inner
     }
+
+    pub fn set_flush_policy(&mut self, policy: FlushPolicy) {
+        self.state.flush_policy = policy;
+    }
+
+    /// Limit the queued frames to `capacity` bytes, allocating the staging
+    /// buffer up front
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.state.buffer = BytesMut::with_capacity(capacity);
+        self.state.capacity = Some(capacity);
+    }
+
+    /// Number of frames written to the I/O completely
+    pub fn frames_written(&self) -> u64 {
+        self.state.frames_written
+    }
+}
+
+impl<T: AsyncWrite + AsyncShutdown> FramedWrite2<T> {
+    /// Write all queued frames, then close the write direction of the I/O
+    pub fn poll_close_write(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Error>> {
+        let this = self.project();
+        let mut inner = this.inner;
+        ready!(this.state.poll_write_queue(inner.as_mut(), cx))?;
+        inner.poll_shutdown_write(cx)
+    }
+}
+
+impl<T> FramedWrite2<T>
+where
+    T: CodecMut,
+    T::Codec: Encoder,
+{
+    /// Queue the closing frame of the codec, if it has one
+    pub fn start_close(self: Pin<&mut Self>) -> Result<(), <T::Codec as Encoder>::Error> {
+        let this = self.project();
+        this.inner.codec_mut().encode_close(&mut this.state.buffer)?;
+        if !this.state.buffer.is_empty() {
+            this.state.queue_frame(None);
+        }
+        Ok(())
+    }
+}
+
+/// Send the closing frame of the codec and close the I/O after all queued
+/// frames, failing with `TimedOut` if `timeout` completes first.
+pub async fn close_graceful<T, F>(
+    mut framed: Pin<&mut FramedWrite2<T>>,
+    timeout: F,
+) -> Result<(), <T::Codec as Encoder>::Error>
+where
+    T: AsyncWrite + CodecMut,
+    T::Codec: Encoder,
+    F: Future<Output = ()>,
+{
+    let close = async {
+        framed.as_mut().start_close()?;
+        poll_fn(|cx| Sink::poll_close(framed.as_mut(), cx)).await
+    };
+    pin_mut!(close);
+    pin_mut!(timeout);
+
+    match future::select(close, timeout).await {
+        Either::Left((closed, _)) => closed,
+        Either::Right(((), _)) => {
+            Err(Error::new(ErrorKind::TimedOut, "graceful close timed out").into())
+        }
+    }
+}
+
+impl WriteState {
+    /// Queue the frame in the staging buffer followed by `body`
+    fn queue_frame(&mut self, body: Option<Bytes>) {
+        let mut queued = self.buffer.len();
+        if !self.buffer.is_empty() {
+            self.queue.push_back(self.buffer.take().freeze());
+        }
+        match body {
+            Some(body) if !body.is_empty() => {
+                queued += body.len();
+                self.queue.push_back(body);
+            }
+            _ => {}
+        }
+        self.queued += queued;
+        self.queued_total += queued as u64;
+        self.frame_ends.push_back(self.queued_total);
+        self.stats.frame_encoded(queued);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(parent: &self.span, len = queued, "encoded frame");
+    }
+
+    /// Write out all queued frames
+    fn poll_write_queue<W: AsyncWrite>(
+        &mut self,
+        mut io: Pin<&mut W>,
+        cx: &mut Context,
+    ) -> Poll<Result<(), Error>> {
+        while !self.queue.is_empty() {
+            let num_write = if self.queue.len() == 1 {
+                let segment = &self.queue[0];
+                ready!(poll_retry(cx, |cx| io.as_mut().poll_write(cx, segment)))?
+            } else {
+                let mut slices = [IoSlice::new(&[]); MAX_WRITE_SEGMENTS];
+                let mut count = 0;
+                for (slice, segment) in slices.iter_mut().zip(self.queue.iter()) {
+                    *slice = IoSlice::new(segment);
+                    count += 1;
+                }
+                let slices = &slices[..count];
+                ready!(poll_retry(cx, |cx| io.as_mut().poll_write_vectored(cx, slices)))?
+            };
+
+            if num_write == 0 {
+                return Poll::Ready(Err(Error::new(ErrorKind::UnexpectedEof, "End of file")));
+            }
+
+            self.advance_queue(num_write);
+            #[cfg(feature = "tracing")]
+            tracing::trace!(parent: &self.span, written = num_write, "wrote frames");
+            ready!(poll_retry(cx, |cx| io.as_mut().poll_flush(cx)))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Remove `n` written bytes from the front of the queue
+    fn advance_queue(&mut self, mut n: usize) {
+        self.stats.written(n);
+        self.queued -= n;
+        self.written_total += n as u64;
+        while self.frame_ends.front().is_some_and(|&end| end <= self.written_total) {
+            self.frame_ends.pop_front();
+            self.frames_written += 1;
+        }
+        while n > 0 {
+            let front = self.queue.front_mut().expect("wrote more than was queued");
+            if n < front.len() {
+                front.advance(n);
+                return;
+            }
+            n -= front.len();
+            self.queue.pop_front();
+        }
+    }
 }
 
 #[cfg(test)]
@@ -144,13 +644,14 @@ mod test {
 
     use futures::executor;
     use futures::sink::SinkExt;
+    use futures::stream::{self, StreamExt};
 
-    use crate::LinesCodec;
+    use crate::{BytesCodec, CodecError, LinesCodec};
 
     #[test]
     fn line_write() {
         let curs = Cursor::new(vec![0u8; 16]);
-        let mut framer = FramedWrite::new(curs, LinesCodec {});
+        let mut framer = FramedWrite::new(curs, LinesCodec::new());
         executor::block_on(framer.send("Hello\n".to_owned())).unwrap();
         executor::block_on(framer.send("World\n".to_owned())).unwrap();
         let (curs, _) = framer.release();
@@ -158,14 +659,161 @@ mod test {
         assert_eq!(curs.position(), 12);
     }
 
+    #[test]
+    fn feed_without_flush() {
+        let curs = Cursor::new(vec![0u8; 16]);
+        let mut framer = FramedWrite::new(curs, LinesCodec::new());
+        executor::block_on(framer.feed("Hello\n".to_owned())).unwrap();
+        assert_eq!(framer.stats().bytes_written, 0);
+
+        executor::block_on(framer.send_batch(vec!["World\n".to_owned()])).unwrap();
+        let (curs, _) = framer.release();
+        assert_eq!(&curs.get_ref()[0..12], b"Hello\nWorld\n");
+    }
+
+    #[test]
+    fn flush_over_threshold() {
+        let curs = Cursor::new(vec![0u8; 16]);
+        let mut framer = FramedWrite::new(curs, LinesCodec::new());
+        framer.set_flush_policy(FlushPolicy::Threshold(8));
+
+        executor::block_on(async {
+            framer.feed("Hello\n".to_owned()).await.unwrap();
+            framer.feed("World\n".to_owned()).await.unwrap();
+            assert_eq!(framer.stats().bytes_written, 0);
+            framer.feed("!\n".to_owned()).await.unwrap();
+            assert_eq!(framer.stats().bytes_written, 12);
+        });
+    }
+
+    #[test]
+    fn queue_over_fixed_capacity() {
+        let mut framer = FramedWrite::new(Vec::new(), LinesCodec::new()).fixed_capacity(8);
+        Pin::new(&mut framer).start_send("Hello\n".to_owned()).unwrap();
+        let err = Pin::new(&mut framer).start_send("World\n".to_owned()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        executor::block_on(framer.send("World\n".to_owned())).unwrap();
+        assert_eq!(&framer.release().0[..], b"Hello\nWorld\n");
+    }
+
     #[test]
     fn line_write_to_eof() {
         let curs = Cursor::new(vec![0u8; 16]);
-        let mut framer = FramedWrite::new(curs, LinesCodec {});
+        let mut framer = FramedWrite::new(curs, LinesCodec::new());
         let _err = executor::block_on(framer.send("This will fill up the buffer\n".to_owned()))
             .unwrap_err();
         let (curs, _) = framer.release();
         assert_eq!(curs.position(), 16);
         assert_eq!(&curs.get_ref()[0..16], b"This will fill u");
     }
+
+    #[test]
+    fn partial_writes_across_segments() {
+        let mut framer = framed_write_2(Fuse(Vec::new(), LinesCodec::new()), Default::default());
+        Pin::new(&mut framer).start_send("Hello\n".to_owned()).unwrap();
+        Pin::new(&mut framer).start_send("World\n".to_owned()).unwrap();
+        assert_eq!(framer.state.queue.len(), 2);
+
+        framer.state.advance_queue(8);
+        assert_eq!(framer.state.queue.len(), 1);
+        assert_eq!(&framer.state.queue[0][..], b"rld\n");
+    }
+
+    #[test]
+    fn send_body_ends_early() {
+        let mut framer = FramedWrite::new(Vec::new(), LinesCodec::new());
+        let err = executor::block_on(framer.send_body("4\n".to_owned(), &b"abc"[..], 4))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+        let (buf, _) = framer.release();
+        assert_eq!(&buf[..], b"4\nabc");
+    }
+
+    #[test]
+    fn forward_into_borrowed_sink() {
+        let mut framer = FramedWrite::new(Vec::new(), LinesCodec::new());
+        let lines = vec!["Hello\n".to_owned(), "World\n".to_owned()];
+        let lines = stream::iter(lines.into_iter().map(Ok::<_, CodecError>));
+        executor::block_on(lines.forward(&mut framer)).unwrap();
+        executor::block_on(framer.send("!\n".to_owned())).unwrap();
+
+        let (buf, _) = framer.release();
+        assert_eq!(&buf[..], b"Hello\nWorld\n!\n");
+    }
+
+    /// Lines ending with a goodbye line
+    struct Goodbye;
+
+    impl Encoder for Goodbye {
+        type Item = String;
+        type Error = Error;
+
+        fn encode(&mut self, item: String, dst: &mut BytesMut) -> Result<(), Error> {
+            dst.extend_from_slice(item.as_bytes());
+            Ok(())
+        }
+
+        fn encode_close(&mut self, dst: &mut BytesMut) -> Result<(), Error> {
+            dst.extend_from_slice(b"BYE\n");
+            Ok(())
+        }
+    }
+
+    /// A writer that never makes progress
+    struct Stalled;
+
+    impl AsyncWrite for Stalled {
+        fn poll_write(self: Pin<&mut Self>, _: &mut Context, _: &[u8]) -> Poll<Result<usize, Error>> {
+            Poll::Pending
+        }
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context) -> Poll<Result<(), Error>> {
+            Poll::Pending
+        }
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context) -> Poll<Result<(), Error>> {
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn close_graceful_sends_goodbye() {
+        let mut framer = FramedWrite::new(Vec::new(), Goodbye);
+        executor::block_on(async {
+            Pin::new(&mut framer).start_send("Hello\n".to_owned()).unwrap();
+            framer.close_graceful(future::pending()).await.unwrap();
+        });
+
+        let (buf, _) = framer.release();
+        assert_eq!(&buf[..], b"Hello\nBYE\n");
+    }
+
+    #[test]
+    fn close_graceful_times_out() {
+        let mut framer = FramedWrite::new(Stalled, Goodbye);
+        let err = executor::block_on(framer.close_graceful(future::ready(()))).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn stats_count_frames_and_bytes() {
+        let mut framer = FramedWrite::new(Vec::new(), LinesCodec::new());
+        executor::block_on(framer.send("Hello\n".to_owned())).unwrap();
+        executor::block_on(framer.send("World\n".to_owned())).unwrap();
+
+        let stats = framer.stats();
+        assert_eq!(stats.frames_encoded, 2);
+        assert_eq!(stats.bytes_written, 12);
+        assert_eq!(stats.write_buffer_len, 0);
+    }
+
+    #[test]
+    fn body_not_copied() {
+        let body = Bytes::from(vec![7u8; 1024]);
+        let mut framer = framed_write_2(Fuse(Vec::new(), BytesCodec {}), Default::default());
+        Pin::new(&mut framer).start_send(body.clone()).unwrap();
+
+        assert_eq!(framer.state.queue.len(), 1);
+        assert_eq!(framer.state.queue[0].as_ptr(), body.as_ptr());
+    }
 }
@@ -0,0 +1,211 @@
+use super::{Decoder, Encoder, FramedRead, FramedWrite};
+
+use bytes::Bytes;
+use futures::io::{AsyncRead, AsyncWrite};
+use futures::{ready, Sink, Stream};
+use pin_project_lite::pin_project;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::{error, fmt};
+
+/// An error of a codec together with where in the stream it happened.
+///
+/// Returned by the adapters created with [`FramedRead::with_error_context`]
+/// and [`FramedWrite::with_error_context`].
+#[derive(Debug)]
+pub struct FramingError<E> {
+    error: E,
+    frame: u64,
+    offset: u64,
+    buffered: Bytes,
+}
+
+impl<E> FramingError<E> {
+    /// The underlying error
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+
+    /// Return the underlying error
+    pub fn into_inner(self) -> E {
+        self.error
+    }
+
+    /// Index of the frame that failed, counting from zero
+    ///
+    /// For errors writing to the I/O, this is the last frame that was sent.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Number of bytes consumed from, or written to, the I/O before the error
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// The first bytes that were buffered when the error happened
+    pub fn buffered(&self) -> &Bytes {
+        &self.buffered
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for FramingError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (frame {}, offset {})", self.error, self.frame, self.offset)
+    }
+}
+
+impl<E: error::Error + 'static> error::Error for FramingError<E> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+pin_project! {
+    /// A framer whose errors are wrapped in a `FramingError`.
+    ///
+    /// # Example
+    /// ```
+    /// use futures::{executor, TryStreamExt};
+    /// use futures_codec::{FramedRead, LinesCodec};
+    ///
+    /// let buf = b"Hello\n\xff\n";
+    /// let mut framed = FramedRead::new(&buf[..], LinesCodec::new()).with_error_context(16);
+    ///
+    /// executor::block_on(async move {
+    ///     framed.try_next().await.unwrap();
+    ///     let err = framed.try_next().await.unwrap_err();
+    ///     assert_eq!((err.frame(), err.offset()), (1, 8));
+    /// })
+    /// ```
+    pub struct WithContext<F> {
+        #[pin]
+        framed: F,
+        snapshot: usize,
+        frames: u64,
+    }
+}
+
+impl<F> WithContext<F> {
+    pub(crate) fn new(framed: F, snapshot: usize) -> Self {
+        Self {
+            framed,
+            snapshot,
+            frames: 0,
+        }
+    }
+
+    /// Return the underlying framer
+    pub fn into_inner(self) -> F {
+        self.framed
+    }
+}
+
+impl<T, D> Stream for WithContext<FramedRead<T, D>>
+where
+    T: AsyncRead,
+    D: Decoder,
+{
+    type Item = Result<D::Item, FramingError<D::Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        match ready!(this.framed.as_mut().poll_next(cx)) {
+            Some(Ok(item)) => {
+                *this.frames += 1;
+                Poll::Ready(Some(Ok(item)))
+            }
+            Some(Err(error)) => {
+                let (offset, buffered) = this.framed.error_context(*this.snapshot);
+                Poll::Ready(Some(Err(FramingError {
+                    error,
+                    frame: *this.frames,
+                    offset,
+                    buffered,
+                })))
+            }
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+impl<T, E> WithContext<FramedWrite<T, E>>
+where
+    T: AsyncWrite,
+    E: Encoder,
+{
+    /// Add context to an error of `f`, blaming the frame being sent if
+    /// `encoding`, otherwise the last frame sent
+    fn wrap<R>(
+        self: Pin<&mut Self>,
+        encoding: bool,
+        f: impl FnOnce(Pin<&mut FramedWrite<T, E>>) -> Poll<Result<R, E::Error>>,
+    ) -> Poll<Result<R, FramingError<E::Error>>> {
+        let mut this = self.project();
+        match ready!(f(this.framed.as_mut())) {
+            Ok(r) => Poll::Ready(Ok(r)),
+            Err(error) => {
+                let (offset, buffered) = this.framed.error_context(*this.snapshot);
+                let frame = if encoding {
+                    *this.frames
+                } else {
+                    this.frames.saturating_sub(1)
+                };
+                Poll::Ready(Err(FramingError {
+                    error,
+                    frame,
+                    offset,
+                    buffered,
+                }))
+            }
+        }
+    }
+}
+
+impl<T, E> Sink<E::Item> for WithContext<FramedWrite<T, E>>
+where
+    T: AsyncWrite,
+    E: Encoder,
+{
+    type Error = FramingError<E::Error>;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.wrap(false, |framed| framed.poll_ready(cx))
+    }
+    fn start_send(mut self: Pin<&mut Self>, item: E::Item) -> Result<(), Self::Error> {
+        let sent = self.as_mut().wrap(true, |framed| Poll::Ready(framed.start_send(item)));
+        match sent {
+            Poll::Ready(Ok(())) => {
+                *self.project().frames += 1;
+                Ok(())
+            }
+            Poll::Ready(Err(e)) => Err(e),
+            Poll::Pending => unreachable!(),
+        }
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.wrap(false, |framed| framed.poll_flush(cx))
+    }
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.wrap(false, |framed| framed.poll_close(cx))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{FramedWrite, LinesCodec};
+
+    use futures::{executor, SinkExt};
+    use std::io::Cursor;
+
+    #[test]
+    fn write_error_context() {
+        let io = Cursor::new(vec![0u8; 8]);
+        let mut framed = FramedWrite::new(io, LinesCodec::new()).with_error_context(4);
+
+        executor::block_on(framed.send("Hello\n".to_owned())).unwrap();
+        let err = executor::block_on(framed.send("World\n".to_owned())).unwrap_err();
+        assert_eq!((err.frame(), err.offset()), (1, 8));
+        assert_eq!(&err.buffered()[..], b"rld\n");
+    }
+}
@@ -0,0 +1,187 @@
+use super::{Decoder, FramedRead};
+
+use bytes::{BigEndian, ByteOrder, Bytes, BytesMut};
+use futures::io::{AsyncRead, AsyncSeek};
+use futures::{ready, Stream};
+use pin_project_lite::pin_project;
+use std::io::{self, ErrorKind};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Byte offsets of the frames of a stream, by frame number.
+///
+/// Built by [`Indexed`] while decoding, and stored with `to_bytes` to jump to
+/// any frame of a large file later without decoding the frames in front of
+/// it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrameIndex {
+    offsets: Vec<u64>,
+}
+
+impl FrameIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Byte offset of frame number `frame`, counting from zero
+    pub fn offset(&self, frame: usize) -> Option<u64> {
+        self.offsets.get(frame).copied()
+    }
+
+    /// Number of frames in the index
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Whether no frame was indexed yet
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// Append the offset of the next frame, ignoring offsets that are not
+    /// past the last indexed frame
+    pub fn push(&mut self, offset: u64) {
+        if self.offsets.last().is_none_or(|&last| offset > last) {
+            self.offsets.push(offset);
+        }
+    }
+
+    /// Serialize the index as a big endian `u64` per frame
+    pub fn to_bytes(&self) -> Bytes {
+        let mut buf = BytesMut::with_capacity(self.offsets.len() * 8);
+        for &offset in &self.offsets {
+            let mut word = [0; 8];
+            BigEndian::write_u64(&mut word, offset);
+            buf.extend_from_slice(&word);
+        }
+        buf.freeze()
+    }
+
+    /// Read an index written by `to_bytes`
+    pub fn from_bytes(buf: &[u8]) -> io::Result<Self> {
+        if !buf.len().is_multiple_of(8) {
+            return Err(io::Error::new(ErrorKind::InvalidData, "truncated frame index"));
+        }
+        let mut index = Self::new();
+        for word in buf.chunks(8) {
+            let offset = BigEndian::read_u64(word);
+            if index.offsets.last().is_some_and(|&last| offset <= last) {
+                return Err(io::Error::new(ErrorKind::InvalidData, "frame offsets out of order"));
+            }
+            index.offsets.push(offset);
+        }
+        Ok(index)
+    }
+}
+
+pin_project! {
+    /// A `FramedRead` recording the offset of every frame it decodes in a
+    /// `FrameIndex`.
+    ///
+    /// Created by [`FramedRead::indexing`].
+    ///
+    /// # Example
+    /// ```
+    /// use futures::{executor, TryStreamExt};
+    /// use futures_codec::{FrameIndex, FramedRead, LinesCodec};
+    /// use std::io::Cursor;
+    ///
+    /// let buf = b"one\ntwo\nthree\n";
+    /// let mut framed = FramedRead::new(Cursor::new(&buf[..]), LinesCodec::new()).indexing();
+    ///
+    /// executor::block_on(async move {
+    ///     while let Some(_) = framed.try_next().await.unwrap() {}
+    ///     let stored = framed.index().to_bytes();
+    ///
+    ///     let index = FrameIndex::from_bytes(&stored).unwrap();
+    ///     let mut framed = framed.into_inner().indexing_with(index);
+    ///     framed.seek_to_frame(2).await.unwrap();
+    ///     assert_eq!(framed.try_next().await.unwrap().unwrap(), "three\n");
+    /// })
+    /// ```
+    pub struct Indexed<T, D>
+    where
+        D: Decoder,
+    {
+        #[pin]
+        framed: FramedRead<T, D>,
+        index: FrameIndex,
+    }
+}
+
+impl<T, D: Decoder> Indexed<T, D> {
+    pub(crate) fn new(framed: FramedRead<T, D>, index: FrameIndex) -> Self {
+        Self { framed, index }
+    }
+
+    /// The offsets recorded so far
+    pub fn index(&self) -> &FrameIndex {
+        &self.index
+    }
+
+    /// Return the underlying `FramedRead`, dropping the index
+    pub fn into_inner(self) -> FramedRead<T, D> {
+        self.framed
+    }
+
+    /// Return the underlying `FramedRead` and the index
+    pub fn into_parts(self) -> (FramedRead<T, D>, FrameIndex) {
+        (self.framed, self.index)
+    }
+}
+
+impl<T, D> Indexed<T, D>
+where
+    T: AsyncRead + AsyncSeek + Unpin,
+    D: Decoder,
+{
+    /// Continue decoding at frame number `frame`, which has to be indexed
+    pub async fn seek_to_frame(&mut self, frame: usize) -> io::Result<u64> {
+        let offset = self.index.offset(frame).ok_or_else(|| {
+            io::Error::new(ErrorKind::InvalidInput, "frame is not in the index")
+        })?;
+        self.framed.seek_to(offset).await
+    }
+}
+
+impl<T, D> Stream for Indexed<T, D>
+where
+    T: AsyncRead,
+    D: Decoder,
+{
+    type Item = Result<D::Item, D::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        let offset = this.framed.stream_position_of_next_frame();
+        let item = ready!(this.framed.as_mut().poll_next(cx));
+        if let Some(Ok(_)) = item {
+            this.index.push(offset);
+        }
+        Poll::Ready(item)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LinesCodec;
+    use futures::{executor, TryStreamExt};
+
+    #[test]
+    fn rereading_does_not_duplicate() {
+        let buf = b"one\ntwo\nthree\n";
+        let mut framed = FramedRead::new(io::Cursor::new(&buf[..]), LinesCodec::new()).indexing();
+
+        executor::block_on(async {
+            framed.try_next().await.unwrap();
+            framed.try_next().await.unwrap();
+            framed.seek_to_frame(0).await.unwrap();
+            let lines: Vec<_> = (&mut framed).try_collect().await.unwrap();
+            assert_eq!(lines, ["one\n", "two\n", "three\n"]);
+        });
+        let index = FrameIndex::from_bytes(&framed.index().to_bytes()).unwrap();
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.offset(2), Some(8));
+    }
+}
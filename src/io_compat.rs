@@ -0,0 +1,163 @@
+use futures::io::{AsyncBufRead, AsyncRead, AsyncWrite};
+use pin_project_lite::pin_project;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+pin_project! {
+    /// Bridges the I/O traits of the `futures` preview this crate is built
+    /// on and those of the released `futures-io` 0.3.
+    ///
+    /// Wrapping I/O implementing one set of traits implements the other, so
+    /// `futures-io` 0.3 I/O can be framed and this crate's I/O can be handed
+    /// to libraries built on `futures-io` 0.3.
+    ///
+    /// Enabled with the `wasm` or `compression` feature.
+    #[derive(Debug)]
+    pub struct IoCompat<T> {
+        #[pin]
+        inner: T,
+    }
+}
+
+impl<T> IoCompat<T> {
+    /// Wrap `inner` to implement the other set of I/O traits
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    /// Return a reference to the wrapped I/O
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Return a mutable reference to the wrapped I/O
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Release the wrapped I/O
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: futures_io::AsyncRead> AsyncRead for IoCompat<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        futures_io::AsyncRead::poll_read(self.project().inner, cx, buf)
+    }
+}
+
+impl<T: futures_io::AsyncBufRead> AsyncBufRead for IoCompat<T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<&[u8]>> {
+        futures_io::AsyncBufRead::poll_fill_buf(self.project().inner, cx)
+    }
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        futures_io::AsyncBufRead::consume(self.project().inner, amt)
+    }
+}
+
+impl<T: futures_io::AsyncWrite> AsyncWrite for IoCompat<T> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        futures_io::AsyncWrite::poll_write(self.project().inner, cx, buf)
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        futures_io::AsyncWrite::poll_flush(self.project().inner, cx)
+    }
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        futures_io::AsyncWrite::poll_close(self.project().inner, cx)
+    }
+}
+
+impl<T: AsyncRead> futures_io::AsyncRead for IoCompat<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncRead::poll_read(self.project().inner, cx, buf)
+    }
+}
+
+impl<T: AsyncBufRead> futures_io::AsyncBufRead for IoCompat<T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<&[u8]>> {
+        AsyncBufRead::poll_fill_buf(self.project().inner, cx)
+    }
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        AsyncBufRead::consume(self.project().inner, amt)
+    }
+}
+
+impl<T: AsyncWrite> futures_io::AsyncWrite for IoCompat<T> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write(self.project().inner, cx, buf)
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(self.project().inner, cx)
+    }
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_close(self.project().inner, cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::IoCompat;
+    use crate::{Framed, LinesCodec};
+    use futures::{executor, SinkExt, TryStreamExt};
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    /// Both directions of a `futures-io` 0.3 pipe
+    struct Duplex {
+        read: &'static [u8],
+        written: Vec<u8>,
+    }
+
+    impl futures_io::AsyncRead for Duplex {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.read).poll_read(cx, buf)
+        }
+    }
+
+    impl futures_io::AsyncWrite for Duplex {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.written).poll_write(cx, buf)
+        }
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.written).poll_flush(cx)
+        }
+        fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.written).poll_close(cx)
+        }
+    }
+
+    #[test]
+    fn frames_over_futures_io() {
+        let io = IoCompat::new(Duplex {
+            read: b"Hello\n",
+            written: Vec::new(),
+        });
+        let mut framed = Framed::new(io, LinesCodec::new());
+
+        executor::block_on(async {
+            assert_eq!(framed.try_next().await.unwrap().unwrap(), "Hello\n");
+            framed.send("World\n".to_owned()).await.unwrap();
+        });
+        let (io, _) = framed.release();
+        assert_eq!(io.into_inner().written, b"World\n");
+    }
+}
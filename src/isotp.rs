@@ -0,0 +1,629 @@
+use super::{AsyncDatagram, CodecError, Decoder, Encoder};
+
+use bytes::{BigEndian, BufMut, ByteOrder, Bytes, BytesMut};
+use futures::{ready, Sink, Stream};
+use pin_project_lite::pin_project;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// Data bytes of a classic CAN frame
+const CAN_DATA_LEN: usize = 8;
+/// Longest message whose length fits the 12 bits of a first frame
+const MAX_SHORT_LEN: usize = 4095;
+
+/// A frame of ISO-TP, carried in the data of one CAN frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IsoTpFrame {
+    /// A complete message of up to 7 bytes
+    Single(Bytes),
+    /// The start of a longer message, with the length of the whole message
+    First {
+        len: usize,
+        data: Bytes,
+    },
+    /// A following part of a message, numbered from 1 and wrapping at 15
+    Consecutive {
+        seq: u8,
+        data: Bytes,
+    },
+    FlowControl(IsoTpFlowControl),
+}
+
+/// Flow control, sent by the receiver of a segmented message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IsoTpFlowControl {
+    /// Whether the sender may continue, see the associated constants
+    pub status: u8,
+    /// Number of consecutive frames to send before waiting for the next
+    /// flow control, or zero for all of them
+    pub block_size: u8,
+    /// Encoded minimum time between consecutive frames
+    pub st_min: u8,
+}
+
+impl IsoTpFlowControl {
+    pub const CONTINUE_TO_SEND: u8 = 0;
+    pub const WAIT: u8 = 1;
+    pub const OVERFLOW: u8 = 2;
+
+    /// The minimum time between consecutive frames. Reserved values are
+    /// read as the longest time, 127 milliseconds.
+    pub fn separation_time(&self) -> Duration {
+        match self.st_min {
+            ms @ 0x00..=0x7f => Duration::from_millis(ms.into()),
+            us @ 0xf1..=0xf9 => Duration::from_micros(u64::from(us - 0xf0) * 100),
+            _ => Duration::from_millis(0x7f),
+        }
+    }
+}
+
+/// What a received frame means to the receiving side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IsoTpEvent {
+    /// A message was completed
+    Message(Bytes),
+    /// The peer waits for this flow control before sending more of its
+    /// message
+    SendFlowControl(IsoTpFlowControl),
+    /// The peer sent flow control for the message being sent to it
+    FlowControl(IsoTpFlowControl),
+}
+
+/// A message being reassembled
+#[derive(Debug)]
+struct Reassembly {
+    buf: BytesMut,
+    len: usize,
+    seq: u8,
+    /// Consecutive frames left until flow control is due
+    block_left: u8,
+}
+
+/// A codec for the segmentation and reassembly of ISO-TP (ISO 15765-2)
+/// messages over classic CAN.
+///
+/// The decoder takes the data of one CAN frame per call and keeps the state
+/// of the message being received. It returns [`IsoTpEvent`]s, telling when a
+/// message is complete and when the peer waits for flow control. The
+/// encoder writes the data of one CAN frame, and [`segment`](Self::segment)
+/// splits a message into its frames. Use [`IsoTp`] to run the protocol over
+/// a CAN socket.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{Decoder, Encoder, IsoTpCodec, IsoTpEvent, IsoTpFlowControl};
+///
+/// let mut codec = IsoTpCodec::new();
+/// let frames = codec.segment(Bytes::from("Hello, World")).unwrap();
+/// assert_eq!(frames.len(), 2);
+///
+/// let mut events = Vec::new();
+/// for frame in frames {
+///     let mut can_frame = BytesMut::new();
+///     codec.encode(frame, &mut can_frame).unwrap();
+///     events.extend(codec.decode(&mut can_frame).unwrap());
+/// }
+/// let flow_control = IsoTpFlowControl { status: 0, block_size: 0, st_min: 0 };
+/// assert_eq!(events[0], IsoTpEvent::SendFlowControl(flow_control));
+/// assert_eq!(events[1], IsoTpEvent::Message(Bytes::from("Hello, World")));
+/// ```
+#[derive(Debug)]
+pub struct IsoTpCodec {
+    block_size: u8,
+    st_min: u8,
+    max_len: usize,
+    padding: Option<u8>,
+    rx: Option<Reassembly>,
+}
+
+impl IsoTpCodec {
+    pub fn new() -> Self {
+        Self {
+            block_size: 0,
+            st_min: 0,
+            max_len: MAX_SHORT_LEN,
+            padding: None,
+            rx: None,
+        }
+    }
+
+    /// Ask the peer for flow control after every `block_size` consecutive
+    /// frames, and to wait `st_min` (encoded as in flow control) between
+    /// them
+    pub fn flow_control(mut self, block_size: u8, st_min: u8) -> Self {
+        self.block_size = block_size;
+        self.st_min = st_min;
+        self
+    }
+
+    /// Accept messages of up to `max` bytes, refusing longer ones with an
+    /// overflow. Lengths above 4095 bytes use the escape sequence of ISO
+    /// 15765-2:2016.
+    pub fn max_message_len(mut self, max: usize) -> Self {
+        self.max_len = max;
+        self
+    }
+
+    /// Pad every encoded frame to 8 bytes with `byte`, as many ECUs require
+    pub fn padding(mut self, byte: u8) -> Self {
+        self.padding = Some(byte);
+        self
+    }
+
+    /// Split `message` into the frames sending it
+    pub fn segment(&self, mut message: Bytes) -> Result<Vec<IsoTpFrame>, CodecError> {
+        let len = message.len();
+        if len < CAN_DATA_LEN {
+            return Ok(vec![IsoTpFrame::Single(message)]);
+        }
+        if len > u32::MAX as usize {
+            return Err(CodecError::FrameTooLong {
+                max: u32::MAX as usize,
+            });
+        }
+        let first_len = if len > MAX_SHORT_LEN { 2 } else { 6 };
+        let data = message.split_to(first_len);
+        let mut frames = vec![IsoTpFrame::First { len, data }];
+        let mut seq = 0;
+        while !message.is_empty() {
+            seq = (seq + 1) & 0x0f;
+            let data = message.split_to(message.len().min(CAN_DATA_LEN - 1));
+            frames.push(IsoTpFrame::Consecutive { seq, data });
+        }
+        Ok(frames)
+    }
+
+    fn continue_to_send(&self) -> IsoTpEvent {
+        IsoTpEvent::SendFlowControl(IsoTpFlowControl {
+            status: IsoTpFlowControl::CONTINUE_TO_SEND,
+            block_size: self.block_size,
+            st_min: self.st_min,
+        })
+    }
+}
+
+impl Default for IsoTpCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+impl Decoder for IsoTpCodec {
+    type Item = IsoTpEvent;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+        let frame = src.take();
+        let low = usize::from(frame[0] & 0x0f);
+        match frame[0] >> 4 {
+            0 => {
+                if low == 0 || low >= frame.len() {
+                    return Err(invalid("invalid single frame length"));
+                }
+                // A new message ends the one being received
+                self.rx = None;
+                Ok(Some(IsoTpEvent::Message(frame.freeze().slice(1, 1 + low))))
+            }
+            1 => {
+                let (len, start) = match (low << 8) | usize::from(*frame.get(1).unwrap_or(&0)) {
+                    0 if frame.len() >= 6 => (BigEndian::read_u32(&frame[2..6]) as usize, 6),
+                    len if frame.len() >= 2 => (len, 2),
+                    _ => return Err(invalid("truncated first frame")),
+                };
+                if len < CAN_DATA_LEN {
+                    return Err(invalid("first frame of a single frame message"));
+                }
+                self.rx = None;
+                if len > self.max_len {
+                    return Ok(Some(IsoTpEvent::SendFlowControl(IsoTpFlowControl {
+                        status: IsoTpFlowControl::OVERFLOW,
+                        block_size: 0,
+                        st_min: 0,
+                    })));
+                }
+                let mut buf = BytesMut::with_capacity(len);
+                buf.extend_from_slice(&frame[start..frame.len().min(start + len)]);
+                self.rx = Some(Reassembly {
+                    buf,
+                    len,
+                    seq: 1,
+                    block_left: self.block_size,
+                });
+                Ok(Some(self.continue_to_send()))
+            }
+            2 => {
+                // Consecutive frames without a first frame are ignored
+                let rx = match &mut self.rx {
+                    Some(rx) => rx,
+                    None => return Ok(None),
+                };
+                if low as u8 != rx.seq {
+                    self.rx = None;
+                    return Err(invalid("consecutive frame out of sequence"));
+                }
+                let n = (rx.len - rx.buf.len()).min(frame.len() - 1);
+                rx.buf.extend_from_slice(&frame[1..1 + n]);
+                rx.seq = (rx.seq + 1) & 0x0f;
+                if rx.buf.len() == rx.len {
+                    let message = self.rx.take().unwrap().buf.freeze();
+                    return Ok(Some(IsoTpEvent::Message(message)));
+                }
+                if self.block_size != 0 {
+                    rx.block_left -= 1;
+                    if rx.block_left == 0 {
+                        rx.block_left = self.block_size;
+                        return Ok(Some(self.continue_to_send()));
+                    }
+                }
+                Ok(None)
+            }
+            3 if frame.len() >= 3 && low <= 2 => {
+                Ok(Some(IsoTpEvent::FlowControl(IsoTpFlowControl {
+                    status: low as u8,
+                    block_size: frame[1],
+                    st_min: frame[2],
+                })))
+            }
+            _ => Err(invalid("invalid frame type")),
+        }
+    }
+}
+
+impl Encoder for IsoTpCodec {
+    type Item = IsoTpFrame;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let start = dst.len();
+        dst.reserve(CAN_DATA_LEN);
+        match item {
+            IsoTpFrame::Single(data) => {
+                if data.is_empty() || data.len() >= CAN_DATA_LEN {
+                    return Err(invalid("invalid single frame length"));
+                }
+                dst.put_u8(data.len() as u8);
+                dst.put_slice(&data);
+            }
+            IsoTpFrame::First { len, data } => {
+                let escaped = len > MAX_SHORT_LEN;
+                let room = if escaped { 2 } else { 6 };
+                if data.len() != room || len > u32::MAX as usize {
+                    return Err(invalid("invalid first frame"));
+                }
+                if escaped {
+                    dst.put_u16_be(0x1000);
+                    dst.put_u32_be(len as u32);
+                } else {
+                    dst.put_u16_be(0x1000 | len as u16);
+                }
+                dst.put_slice(&data);
+            }
+            IsoTpFrame::Consecutive { seq, data } => {
+                if data.is_empty() || data.len() >= CAN_DATA_LEN {
+                    return Err(invalid("invalid consecutive frame length"));
+                }
+                dst.put_u8(0x20 | (seq & 0x0f));
+                dst.put_slice(&data);
+            }
+            IsoTpFrame::FlowControl(fc) => {
+                dst.put_u8(0x30 | (fc.status & 0x0f));
+                dst.put_u8(fc.block_size);
+                dst.put_u8(fc.st_min);
+            }
+        }
+        if let Some(byte) = self.padding {
+            while dst.len() - start < CAN_DATA_LEN {
+                dst.put_u8(byte);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where the message being sent is at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SendState {
+    /// Frames may be sent, `block_left` more until flow control is due, or
+    /// any number if zero
+    Sending {
+        block_left: u8,
+        st_min: Duration,
+    },
+    WaitingForFlowControl,
+}
+
+pin_project! {
+    /// A unified `Stream` and `Sink` of ISO-TP messages exchanged with one
+    /// peer over a CAN socket.
+    ///
+    /// The socket is an [`AsyncDatagram`] sending and receiving the data of
+    /// one CAN frame at a time, with CAN ids as addresses. Frames are sent
+    /// with id `tx_id` and frames with id `rx_id` are received, others are
+    /// dropped. Flow control is answered while receiving and awaited while
+    /// sending, and the minimum time the peer asks for between consecutive
+    /// frames is waited with the futures returned by `timer`. While a
+    /// message is sent, messages received are kept for the `Stream`.
+    ///
+    /// The timeouts of ISO 15765-2 are not enforced, wrap the futures in
+    /// the timeout of your runtime for them.
+    pub struct IsoTp<T, D, F> {
+        #[pin]
+        socket: T,
+        codec: IsoTpCodec,
+        tx_id: u32,
+        rx_id: u32,
+        timer: D,
+        #[pin]
+        delay: Option<F>,
+        read_buf: [u8; 64],
+        received: VecDeque<Bytes>,
+        // Flow control to send to the peer
+        replies: VecDeque<IsoTpFrame>,
+        outgoing: VecDeque<IsoTpFrame>,
+        state: SendState,
+    }
+}
+
+impl<T, D, F> IsoTp<T, D, F>
+where
+    T: AsyncDatagram<Addr = u32>,
+    D: FnMut(Duration) -> F,
+    F: Future<Output = ()>,
+{
+    /// Exchange messages with the peer sending frames with id `rx_id` and
+    /// receiving those with id `tx_id`
+    pub fn new(socket: T, codec: IsoTpCodec, tx_id: u32, rx_id: u32, timer: D) -> Self {
+        Self {
+            socket,
+            codec,
+            tx_id,
+            rx_id,
+            timer,
+            delay: None,
+            read_buf: [0; 64],
+            received: VecDeque::new(),
+            replies: VecDeque::new(),
+            outgoing: VecDeque::new(),
+            state: SendState::WaitingForFlowControl,
+        }
+    }
+
+    /// Release the socket and codec, dropping messages not yet sent or
+    /// returned
+    pub fn release(self) -> (T, IsoTpCodec) {
+        (self.socket, self.codec)
+    }
+
+    /// Send `frame` with the id of this side
+    fn poll_send_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        frame: IsoTpFrame,
+    ) -> Poll<Result<(), CodecError>> {
+        let this = self.project();
+        let mut buf = BytesMut::new();
+        this.codec.encode(frame, &mut buf)?;
+        ready!(this.socket.poll_send_to(cx, &buf, this.tx_id))?;
+        Poll::Ready(Ok(()))
+    }
+
+    /// Send the flow control due to the peer
+    fn poll_replies(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), CodecError>> {
+        while let Some(frame) = self.replies.front().cloned() {
+            ready!(self.as_mut().poll_send_frame(cx, frame))?;
+            self.as_mut().project().replies.pop_front();
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Receive a CAN frame and act on it
+    fn poll_receive(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), CodecError>> {
+        let this = self.project();
+        let (n, id) = ready!(this.socket.poll_recv_from(cx, &mut this.read_buf[..]))?;
+        if id != *this.rx_id {
+            return Poll::Ready(Ok(()));
+        }
+        let mut frame = BytesMut::from(&this.read_buf[..n]);
+        match this.codec.decode(&mut frame)? {
+            Some(IsoTpEvent::Message(message)) => this.received.push_back(message),
+            Some(IsoTpEvent::SendFlowControl(fc)) => {
+                this.replies.push_back(IsoTpFrame::FlowControl(fc));
+            }
+            Some(IsoTpEvent::FlowControl(fc))
+                if *this.state == SendState::WaitingForFlowControl =>
+            {
+                match fc.status {
+                    IsoTpFlowControl::CONTINUE_TO_SEND => {
+                        *this.state = SendState::Sending {
+                            block_left: fc.block_size,
+                            st_min: fc.separation_time(),
+                        };
+                    }
+                    IsoTpFlowControl::WAIT => {}
+                    _ => {
+                        this.outgoing.clear();
+                        return Poll::Ready(Err(invalid("message too long for the peer")));
+                    }
+                }
+            }
+            Some(IsoTpEvent::FlowControl(_)) | None => {}
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T, D, F> Stream for IsoTp<T, D, F>
+where
+    T: AsyncDatagram<Addr = u32>,
+    D: FnMut(Duration) -> F,
+    F: Future<Output = ()>,
+{
+    type Item = Result<Bytes, CodecError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            ready!(self.as_mut().poll_replies(cx))?;
+            if let Some(message) = self.as_mut().project().received.pop_front() {
+                return Poll::Ready(Some(Ok(message)));
+            }
+            ready!(self.as_mut().poll_receive(cx))?;
+        }
+    }
+}
+
+impl<T, D, F> Sink<Bytes> for IsoTp<T, D, F>
+where
+    T: AsyncDatagram<Addr = u32>,
+    D: FnMut(Duration) -> F,
+    F: Future<Output = ()>,
+{
+    type Error = CodecError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.poll_flush(cx)
+    }
+    fn start_send(self: Pin<&mut Self>, item: Bytes) -> Result<(), Self::Error> {
+        let this = self.project();
+        this.outgoing.extend(this.codec.segment(item)?);
+        *this.state = SendState::Sending {
+            block_left: 0,
+            st_min: Duration::from_secs(0),
+        };
+        Ok(())
+    }
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        loop {
+            ready!(self.as_mut().poll_replies(cx))?;
+            let (block_left, st_min) = match self.state {
+                _ if self.outgoing.is_empty() => return Poll::Ready(Ok(())),
+                SendState::WaitingForFlowControl => {
+                    ready!(self.as_mut().poll_receive(cx))?;
+                    continue;
+                }
+                SendState::Sending { block_left, st_min } => (block_left, st_min),
+            };
+            if let Some(delay) = self.as_mut().project().delay.as_pin_mut() {
+                ready!(delay.poll(cx));
+                self.as_mut().project().delay.set(None);
+            }
+
+            let frame = self.outgoing.front().cloned().unwrap();
+            ready!(self.as_mut().poll_send_frame(cx, frame.clone()))?;
+            let mut this = self.as_mut().project();
+            this.outgoing.pop_front();
+            match frame {
+                IsoTpFrame::First { .. } => *this.state = SendState::WaitingForFlowControl,
+                IsoTpFrame::Consecutive { .. } => {
+                    if block_left == 1 {
+                        *this.state = SendState::WaitingForFlowControl;
+                    } else if block_left > 1 {
+                        *this.state = SendState::Sending {
+                            block_left: block_left - 1,
+                            st_min,
+                        };
+                    }
+                    if st_min > Duration::from_secs(0) && !this.outgoing.is_empty() {
+                        this.delay.set(Some((this.timer)(st_min)));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.poll_flush(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::{executor, future, SinkExt, TryStreamExt};
+    use std::cell::RefCell;
+    use std::io::Error;
+    use std::rc::Rc;
+    use std::task::Waker;
+
+    #[derive(Default)]
+    struct Bus {
+        frames: VecDeque<(Vec<u8>, u32)>,
+        waker: Option<Waker>,
+    }
+
+    /// One end of a CAN bus between two nodes
+    struct Node {
+        tx: Rc<RefCell<Bus>>,
+        rx: Rc<RefCell<Bus>>,
+    }
+
+    impl AsyncDatagram for Node {
+        type Addr = u32;
+
+        fn poll_send_to(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+            addr: &u32,
+        ) -> Poll<Result<usize, Error>> {
+            let mut bus = self.tx.borrow_mut();
+            bus.frames.push_back((buf.to_vec(), *addr));
+            if let Some(waker) = bus.waker.take() {
+                waker.wake();
+            }
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_recv_from(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<Result<(usize, u32), Error>> {
+            let mut bus = self.rx.borrow_mut();
+            match bus.frames.pop_front() {
+                Some((frame, id)) => {
+                    buf[..frame.len()].copy_from_slice(&frame);
+                    Poll::Ready(Ok((frame.len(), id)))
+                }
+                None => {
+                    bus.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn segmented_message_with_flow_control() {
+        let (a, b) = (Rc::default(), Rc::default());
+        let tester = Node {
+            tx: Rc::clone(&a),
+            rx: Rc::clone(&b),
+        };
+        let ecu = Node { tx: b, rx: a };
+        let timer = |_| future::ready(());
+        let mut tester = IsoTp::new(tester, IsoTpCodec::new(), 0x7e0, 0x7e8, timer);
+        let codec = IsoTpCodec::new().flow_control(2, 0xf5).padding(0xaa);
+        let mut ecu = IsoTp::new(ecu, codec, 0x7e8, 0x7e0, timer);
+
+        let message = Bytes::from(&b"a message spanning five frames"[..]);
+        let (sent, received) =
+            executor::block_on(future::join(tester.send(message.clone()), ecu.try_next()));
+        sent.unwrap();
+        assert_eq!(received.unwrap(), Some(message));
+
+        let (tester_bus, codec) = tester.release();
+        assert!(tester_bus.tx.borrow().frames.is_empty());
+        assert!(codec.rx.is_none());
+    }
+}
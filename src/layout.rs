@@ -0,0 +1,361 @@
+use super::{CodecError, Decoder, Encoder};
+
+use bytes::{BigEndian, ByteOrder, Bytes, BytesMut, LittleEndian};
+
+/// Default limit on the length of a frame
+const MAX_FRAME: usize = 8 * 1024 * 1024;
+
+/// A binary frame layout described at runtime, for wire formats that are
+/// only known from configuration.
+///
+/// A layout is a sequence of named fields, integers of fixed size and
+/// byte strings whose length is fixed or given by an earlier integer
+/// field. [`build`](Self::build) checks the layout and turns it into a
+/// [`LayoutCodec`], which decodes frames into a [`LayoutFrame`] of the
+/// field values. When encoding, integer fields giving the length of a byte
+/// string may be left out, they are filled in from the byte string.
+///
+/// # Example
+/// ```
+/// use bytes::{Bytes, BytesMut};
+/// use futures_codec::{field, Decoder, Encoder, FrameLayout, LayoutFrame, LayoutLength};
+///
+/// let mut codec = FrameLayout::new()
+///     .u8("kind")
+///     .u16_be("len")
+///     .bytes("payload", field("len"))
+///     .bytes("crc", LayoutLength::Fixed(2))
+///     .build()
+///     .unwrap();
+///
+/// let frame = LayoutFrame::new()
+///     .uint("kind", 7)
+///     .bytes("payload", Bytes::from("hello"))
+///     .bytes("crc", Bytes::from("\x12\x34"));
+/// let mut buf = BytesMut::new();
+/// codec.encode(frame, &mut buf).unwrap();
+/// assert_eq!(&buf[..], b"\x07\x00\x05hello\x12\x34");
+///
+/// let frame = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(frame.get_uint("len"), Some(5));
+/// assert_eq!(frame.get_bytes("payload"), Some(&Bytes::from("hello")));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FrameLayout {
+    fields: Vec<(String, Kind)>,
+}
+
+/// The length of a byte string field of a [`FrameLayout`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutLength {
+    Fixed(usize),
+    /// The value of an earlier integer field
+    Field(String),
+}
+
+/// Length given by the earlier integer field `name`
+pub fn field(name: &str) -> LayoutLength {
+    LayoutLength::Field(name.to_owned())
+}
+
+#[derive(Debug, Clone)]
+enum Kind {
+    Uint { size: usize, little: bool },
+    Bytes(LayoutLength),
+}
+
+impl FrameLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn uint(mut self, name: &str, size: usize, little: bool) -> Self {
+        self.fields
+            .push((name.to_owned(), Kind::Uint { size, little }));
+        self
+    }
+
+    pub fn u8(self, name: &str) -> Self {
+        self.uint(name, 1, false)
+    }
+
+    pub fn u16_be(self, name: &str) -> Self {
+        self.uint(name, 2, false)
+    }
+
+    pub fn u16_le(self, name: &str) -> Self {
+        self.uint(name, 2, true)
+    }
+
+    pub fn u32_be(self, name: &str) -> Self {
+        self.uint(name, 4, false)
+    }
+
+    pub fn u32_le(self, name: &str) -> Self {
+        self.uint(name, 4, true)
+    }
+
+    pub fn u64_be(self, name: &str) -> Self {
+        self.uint(name, 8, false)
+    }
+
+    pub fn u64_le(self, name: &str) -> Self {
+        self.uint(name, 8, true)
+    }
+
+    /// A byte string of length `len`
+    pub fn bytes(mut self, name: &str, len: LayoutLength) -> Self {
+        self.fields.push((name.to_owned(), Kind::Bytes(len)));
+        self
+    }
+
+    /// Check that field names are unique and lengths refer to earlier
+    /// integer fields, and create the codec
+    pub fn build(self) -> Result<LayoutCodec, CodecError> {
+        for (i, (name, kind)) in self.fields.iter().enumerate() {
+            let earlier = &self.fields[..i];
+            if earlier.iter().any(|(earlier, _)| earlier == name) {
+                return Err(invalid(&format!("duplicate field {}", name)));
+            }
+            if let Kind::Bytes(LayoutLength::Field(len)) = kind {
+                match earlier.iter().find(|(earlier, _)| earlier == len) {
+                    Some((_, Kind::Uint { .. })) => {}
+                    _ => {
+                        let msg = format!("length of {} is not an earlier integer field", name);
+                        return Err(invalid(&msg));
+                    }
+                }
+            }
+        }
+        Ok(LayoutCodec {
+            layout: self,
+            max_frame_len: MAX_FRAME,
+        })
+    }
+}
+
+/// A value of a [`LayoutFrame`] field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutValue {
+    Uint(u64),
+    Bytes(Bytes),
+}
+
+/// The field values of a frame of a [`FrameLayout`], in layout order when
+/// decoded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LayoutFrame {
+    pub fields: Vec<(String, LayoutValue)>,
+}
+
+impl LayoutFrame {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set an integer field
+    pub fn uint(mut self, name: &str, value: u64) -> Self {
+        self.fields
+            .push((name.to_owned(), LayoutValue::Uint(value)));
+        self
+    }
+
+    /// Set a byte string field
+    pub fn bytes(mut self, name: &str, value: Bytes) -> Self {
+        self.fields
+            .push((name.to_owned(), LayoutValue::Bytes(value)));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LayoutValue> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn get_uint(&self, name: &str) -> Option<u64> {
+        match self.get(name) {
+            Some(LayoutValue::Uint(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_bytes(&self, name: &str) -> Option<&Bytes> {
+        match self.get(name) {
+            Some(LayoutValue::Bytes(value)) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// A codec for the frames of a [`FrameLayout`].
+///
+/// Created by [`FrameLayout::build`].
+#[derive(Debug, Clone)]
+pub struct LayoutCodec {
+    layout: FrameLayout,
+    max_frame_len: usize,
+}
+
+impl LayoutCodec {
+    /// Fail with `FrameTooLong` on frames longer than `max` bytes
+    pub fn max_frame_len(mut self, max: usize) -> Self {
+        self.max_frame_len = max;
+        self
+    }
+}
+
+fn invalid(msg: &str) -> CodecError {
+    CodecError::Protocol(msg.to_owned())
+}
+
+fn read_uint(src: &[u8], little: bool) -> u64 {
+    match (src.len(), little) {
+        (1, _) => u64::from(src[0]),
+        (_, true) => LittleEndian::read_uint(src, src.len()),
+        (_, false) => BigEndian::read_uint(src, src.len()),
+    }
+}
+
+impl Decoder for LayoutCodec {
+    type Item = LayoutFrame;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        // Find the end of the frame, reading integers as lengths need them
+        let fields = &self.layout.fields;
+        let mut uints = Vec::with_capacity(fields.len());
+        let mut lens = Vec::with_capacity(fields.len());
+        let mut at = 0;
+        for (_, kind) in fields {
+            let len = match kind {
+                Kind::Uint { size, .. } => *size,
+                Kind::Bytes(LayoutLength::Fixed(len)) => *len,
+                Kind::Bytes(LayoutLength::Field(name)) => {
+                    let i = fields.iter().position(|(field, _)| field == name);
+                    // An earlier integer field, checked by `build`
+                    uints[i.unwrap()] as usize
+                }
+            };
+            if len > self.max_frame_len.saturating_sub(at) {
+                return Err(CodecError::FrameTooLong {
+                    max: self.max_frame_len,
+                });
+            }
+            if src.len() < at + len {
+                src.reserve(at + len - src.len());
+                return Ok(None);
+            }
+            uints.push(match kind {
+                Kind::Uint { little, .. } => read_uint(&src[at..at + len], *little),
+                Kind::Bytes(_) => 0,
+            });
+            lens.push(len);
+            at += len;
+        }
+
+        let frame = src.split_to(at).freeze();
+        let mut at = 0;
+        let mut values = Vec::with_capacity(fields.len());
+        for (((name, kind), uint), len) in fields.iter().zip(uints).zip(lens) {
+            let value = match kind {
+                Kind::Uint { .. } => LayoutValue::Uint(uint),
+                Kind::Bytes(_) => LayoutValue::Bytes(frame.slice(at, at + len)),
+            };
+            values.push((name.clone(), value));
+            at += len;
+        }
+        Ok(Some(LayoutFrame { fields: values }))
+    }
+}
+
+impl Encoder for LayoutCodec {
+    type Item = LayoutFrame;
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let missing = |name: &str| invalid(&format!("missing field {}", name));
+        let start = dst.len();
+        for (name, kind) in &self.layout.fields {
+            match kind {
+                Kind::Uint { size, little } => {
+                    let value = match item.get_uint(name) {
+                        Some(value) => value,
+                        // A length filled in from the byte string
+                        None => {
+                            let string = self.layout.fields.iter().find(|(_, kind)| match kind {
+                                Kind::Bytes(LayoutLength::Field(len)) => len == name,
+                                _ => false,
+                            });
+                            let string = string.and_then(|(string, _)| item.get_bytes(string));
+                            string.ok_or_else(|| missing(name))?.len() as u64
+                        }
+                    };
+                    if *size < 8 && value >> (8 * size) != 0 {
+                        return Err(invalid(&format!("{} does not fit field {}", value, name)));
+                    }
+                    let mut buf = [0; 8];
+                    if *little {
+                        LittleEndian::write_uint(&mut buf, value, 8);
+                        dst.extend_from_slice(&buf[..*size]);
+                    } else {
+                        BigEndian::write_uint(&mut buf, value, 8);
+                        dst.extend_from_slice(&buf[8 - size..]);
+                    }
+                }
+                Kind::Bytes(len) => {
+                    let value = item.get_bytes(name).ok_or_else(|| missing(name))?;
+                    let expected = match len {
+                        LayoutLength::Fixed(len) => *len as u64,
+                        LayoutLength::Field(len) => match item.get_uint(len) {
+                            Some(len) => len,
+                            None => value.len() as u64,
+                        },
+                    };
+                    if value.len() as u64 != expected {
+                        return Err(invalid(&format!("field {} has the wrong length", name)));
+                    }
+                    dst.extend_from_slice(value);
+                }
+            }
+        }
+        if dst.len() - start > self.max_frame_len {
+            dst.truncate(start);
+            return Err(CodecError::FrameTooLong {
+                max: self.max_frame_len,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn little_endian_and_partial_frames() {
+        let mut codec = FrameLayout::new()
+            .u32_le("len")
+            .bytes("data", field("len"))
+            .u16_le("tail")
+            .build()
+            .unwrap();
+        let mut buf = BytesMut::from(&b"\x03\x00\x00\x00ab"[..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(b"c\x01\x02");
+        let frame = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(frame.get_bytes("data"), Some(&Bytes::from("abc")));
+        assert_eq!(frame.get_uint("tail"), Some(0x0201));
+        assert!(buf.is_empty());
+
+        let too_big = LayoutFrame::new()
+            .bytes("data", Bytes::new())
+            .uint("tail", 0x1_0000);
+        assert!(codec.encode(too_big, &mut buf).is_err());
+
+        let forward = FrameLayout::new().bytes("data", field("len")).u8("len");
+        assert!(forward.build().is_err());
+    }
+}
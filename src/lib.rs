@@ -4,6 +4,13 @@
 //! and [`AsyncWrite`](futures::io::AsyncWrite), to framed streams implementing [`Sink`](futures::Sink) and [`Stream`](futures::Stream).
 //! Framed streams are also known as `transports`.
 //!
+//! With the `tracing` feature, decoding, encoding and writing emit `tracing`
+//! events. They use the span that was current when the framing type was
+//! created as parent, so create it inside the connection's span.
+//!
+//! Without the default `std` feature only the [`no_std`] module is built, for
+//! targets with `alloc` but without `std`.
+//!
 //! ```
 //! # #![feature(async_await, await_macro)]
 //! # use futures::{executor, SinkExt, TryStreamExt};
@@ -14,7 +21,7 @@
 //!     # let mut buf = vec![];
 //!     # let stream = Cursor::new(&mut buf);
 //!     // let stream = ...
-//!     let mut framed = Framed::new(stream, LinesCodec {});
+//!     let mut framed = Framed::new(stream, LinesCodec::new());
 //!
 //!     while let Some(line) = framed.try_next().await.unwrap() {
 //!         println!("{:?}", line);
@@ -22,20 +29,154 @@
 //! };
 //! ```
 
-mod codec;
-pub use codec::{BytesCodec, LinesCodec};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+// Lets the code generated by `#[derive(FrameCodec)]` name this crate here
+extern crate self as futures_codec;
+
+pub mod no_std;
+
+/// Declare items that are only built with the `std` feature
+macro_rules! cfg_std {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "std")]
+            $item
+        )*
+    };
+}
+
+cfg_std! {
+    mod codec;
+    pub use codec::{
+        AdbMessage, AdbMessageCodec, AisCodec, AisMessage, ArtDmx, ArtNetCodec, ArtNetPacket,
+        BytesCodec, BytesLinesCodec, CLICKHOUSE_REVISION, CharCodec, ClickHouseFrameCodec,
+        ClickHousePacket, CoapTcpCodec, CoapTcpMessage, DltCodec, DltMessage, DltStorageHeader,
+        DockerStdCopyCodec, DotTerminatedCodec, Elm327Codec, EscapedFieldCodec, FragmentingCodec,
+        FtpControlCodec, FtpReply, GraphiteCodec, GraphiteMetric, ImapCodec, InfluxLineCodec,
+        InfluxPoint, InfluxValue, LengthPrefix, LinesCodec, LogfmtCodec, LogfmtRecord,
+        MemcachedBinaryCodec, MemcachedFrame, MemcachedRequest, MemcachedResponse, MidiCodec, MimeCodec,
+        MimeFrame, MimeHeaders, NmeaCodec, NmeaSentence, PrefixedStringCodec, PromRecord, PromSample,
+        PromTextCodec, ProxyCommand, ProxyFrame, ProxyHeader, ProxyProtocolCodec, RconCodec, RconPacket,
+        RtpCodec, RtpPacket, RtspCodec, RtspFrame, RtspMessage, SacnCodec, SacnData, SacnPacket,
+        ScanCodec, SemtechPacket, SemtechUdpCodec, SequenceError, SequencedCodec, SipCodec, SipMessage,
+        SmlCodec, SmtpCodec, SmtpFrame, SmtpReply, StatsdCodec, StatsdDatagramCodec, StatsdKind,
+        StatsdMetric, StdStream, TarEntry, TarEntryCodec, TarHeader, TdsMessage, TdsPacketCodec,
+        WalRecordCodec, WaylandMessage, WaylandMessageCodec, XbeeApiCodec, XbeeFrame,
+    };
+    #[cfg(feature = "tracing")]
+    pub use codec::LoggingCodec;
+    #[cfg(feature = "regex")]
+    pub use codec::RegexDelimiterCodec;
+    #[cfg(feature = "encoding")]
+    pub use codec::EncodedLinesCodec;
+    #[cfg(feature = "minecraft")]
+    pub use codec::{McPacket, McPacketCodec};
+
+    mod error;
+    pub use error::CodecError;
+
+    mod decoder;
+    pub use decoder::{DecodeIter, Decoder, DecoderRef};
+
+    mod encoder;
+    pub use encoder::Encoder;
+
+    mod accumulator;
+    pub use accumulator::FrameAccumulator;
+
+    mod framed;
+    pub use framed::{Framed, RawIo};
+
+    mod builder;
+    pub use builder::FramedBuilder;
+
+    mod framed_read;
+    pub use framed_read::{FrameBody, FrameMeta, FramedRead, IncompleteEof, WithMetadata};
+
+    mod index;
+    pub use index::{FrameIndex, Indexed};
+
+    mod framed_buf_read;
+    pub use framed_buf_read::FramedBufRead;
+
+    mod framed_write;
+    pub use framed_write::{FlushPolicy, FramedWrite};
+
+    mod deadline;
+    pub use deadline::{WriteDeadline, WriteTimedOut};
+
+    mod forward;
+    pub use forward::forward_frames;
+
+    mod one_shot;
+    pub use one_shot::{read_frame, write_frame};
+
+    mod framing_error;
+    pub use framing_error::{FramingError, WithContext};
+
+    mod retry;
+
+    mod stats;
+    pub use stats::{Stats, StatsHandle};
+
+    mod framed_stream_read;
+    pub use framed_stream_read::FramedStreamRead;
+
+    mod framed_sink_write;
+    pub use framed_sink_write::FramedSinkWrite;
+
+    mod offload;
+    pub use offload::{OffloadCodec, OffloadDecode, OffloadEncode, OffloadJob};
+
+    mod prioritized;
+    pub use prioritized::PrioritizedFramedWrite;
+
+    mod throttle;
+    pub use throttle::Throttled;
+
+    mod shutdown;
+    pub use shutdown::AsyncShutdown;
+
+    mod datagram;
+    pub use datagram::{AsyncDatagram, DatagramFramed};
+
+    mod isotp;
+    pub use isotp::{IsoTp, IsoTpCodec, IsoTpEvent, IsoTpFlowControl, IsoTpFrame};
+
+    mod frame_codec;
+    pub use frame_codec::{FrameCodec, StructCodec};
+    #[doc(hidden)]
+    pub use frame_codec::private as __private;
+    #[cfg(feature = "derive")]
+    pub use futures_codec_derive::FrameCodec;
+
+    mod layout;
+    pub use layout::{field, FrameLayout, LayoutCodec, LayoutFrame, LayoutLength, LayoutValue};
+
+    #[cfg(feature = "futures-io")]
+    mod io_compat;
+    #[cfg(feature = "futures-io")]
+    pub use io_compat::IoCompat;
+
+    pub mod blocking;
+
+    pub mod testing;
 
-mod decoder;
-pub use decoder::Decoder;
+    #[cfg(feature = "tokio")]
+    pub mod compat;
 
-mod encoder;
-pub use encoder::Encoder;
+    #[cfg(feature = "embedded-io")]
+    pub mod embedded;
 
-mod framed;
-pub use framed::Framed;
+    #[cfg(feature = "wasm")]
+    pub mod wasm;
 
-mod framed_read;
-pub use framed_read::FramedRead;
+    #[cfg(feature = "body")]
+    pub mod body;
 
-mod framed_write;
-pub use framed_write::FramedWrite;
\ No newline at end of file
+    #[cfg(feature = "compression")]
+    pub mod compression;
+}
@@ -0,0 +1,124 @@
+use super::Error;
+use bytes1::{Buf, BytesMut};
+
+/// Storage for the bytes the `no_std` codecs decode from and encode to.
+///
+/// Bytes are appended at the end and consumed from the start. A buffer with
+/// a fixed capacity fails with `Error::BufferFull` instead of growing.
+pub trait Buffer {
+    /// The bytes in the buffer
+    fn bytes(&self) -> &[u8];
+
+    /// Drop the first `n` bytes
+    fn consume(&mut self, n: usize);
+
+    /// Append `data` after the bytes in the buffer
+    fn extend_from_slice(&mut self, data: &[u8]) -> Result<(), Error>;
+
+    /// Number of bytes that can still be appended
+    ///
+    /// Encoders check it before writing a frame, so a full buffer doesn't
+    /// end in part of one. The default is unlimited, for buffers that grow.
+    fn remaining_capacity(&self) -> usize {
+        usize::MAX
+    }
+
+    fn len(&self) -> usize {
+        self.bytes().len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Buffer for BytesMut {
+    fn bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.advance(n);
+    }
+
+    fn extend_from_slice(&mut self, data: &[u8]) -> Result<(), Error> {
+        BytesMut::extend_from_slice(self, data);
+        Ok(())
+    }
+}
+
+/// A `Buffer` in storage provided by the caller, such as a static array.
+///
+/// # Example
+/// ```
+/// use futures_codec::no_std::{Buffer, Decoder, Error, LinesCodec, SliceBuffer};
+///
+/// let mut storage = [0u8; 8];
+/// let mut buf = SliceBuffer::new(&mut storage);
+/// let mut codec = LinesCodec::new();
+///
+/// buf.extend_from_slice(b"Hello\nWo").unwrap();
+/// assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "Hello\n");
+/// buf.extend_from_slice(b"rld\n").unwrap();
+/// assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "World\n");
+///
+/// assert_eq!(buf.extend_from_slice(b"Too long"), Ok(()));
+/// assert_eq!(buf.extend_from_slice(b"!"), Err(Error::BufferFull));
+/// ```
+#[derive(Debug)]
+pub struct SliceBuffer<'a> {
+    storage: &'a mut [u8],
+    /// Range of `storage` holding the bytes in the buffer
+    start: usize,
+    end: usize,
+}
+
+impl<'a> SliceBuffer<'a> {
+    /// An empty buffer in `storage`
+    pub fn new(storage: &'a mut [u8]) -> Self {
+        Self {
+            storage,
+            start: 0,
+            end: 0,
+        }
+    }
+
+    /// Number of bytes the buffer can hold
+    pub fn capacity(&self) -> usize {
+        self.storage.len()
+    }
+}
+
+impl Buffer for SliceBuffer<'_> {
+    fn bytes(&self) -> &[u8] {
+        &self.storage[self.start..self.end]
+    }
+
+    fn consume(&mut self, n: usize) {
+        assert!(n <= self.len(), "consumed past the end of the buffer");
+        self.start += n;
+        if self.start == self.end {
+            self.start = 0;
+            self.end = 0;
+        }
+    }
+
+    fn extend_from_slice(&mut self, data: &[u8]) -> Result<(), Error> {
+        if data.len() > self.remaining_capacity() {
+            return Err(Error::BufferFull);
+        }
+        // Move the bytes to the front if the space after them is too small
+        if data.len() > self.storage.len() - self.end {
+            self.storage.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+        self.storage[self.end..self.end + data.len()].copy_from_slice(data);
+        self.end += data.len();
+        Ok(())
+    }
+
+    fn remaining_capacity(&self) -> usize {
+        self.storage.len() - self.len()
+    }
+}
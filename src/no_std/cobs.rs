@@ -0,0 +1,182 @@
+use super::{Buffer, Decoder, Encoder, Error};
+use alloc::vec::Vec;
+
+/// Default limit on the length of a decoded frame
+const MAX_FRAME: usize = 64 * 1024;
+
+/// A codec for frames in Consistent Overhead Byte Stuffing, each ended by a
+/// zero byte.
+///
+/// Empty frames between two zero bytes are skipped, so a sender can start
+/// with a zero byte to resynchronize the receiver.
+///
+/// # Example
+/// ```
+/// use bytes1::BytesMut;
+/// use futures_codec::no_std::{CobsCodec, Decoder, Encoder};
+///
+/// let mut codec = CobsCodec::new();
+/// let mut buf = BytesMut::new();
+/// codec.encode(b"\x11\x00\x22".to_vec(), &mut buf).unwrap();
+/// assert_eq!(&buf[..], b"\x02\x11\x02\x22\x00");
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), b"\x11\x00\x22");
+/// ```
+#[derive(Debug)]
+pub struct CobsCodec {
+    max_length: usize,
+}
+
+impl CobsCodec {
+    pub fn new() -> Self {
+        Self {
+            max_length: MAX_FRAME,
+        }
+    }
+
+    /// Fail on frames longer than `max` bytes once decoded, 64 KiB by
+    /// default
+    pub fn max_length(mut self, max: usize) -> Self {
+        self.max_length = max;
+        self
+    }
+
+    /// Longest encoded frame, without the zero byte, for a decoded frame of
+    /// `max_length` bytes
+    fn max_encoded(&self) -> usize {
+        self.max_length
+            .saturating_add(self.max_length / 254)
+            .saturating_add(1)
+    }
+}
+
+impl Default for CobsCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Encoder for CobsCodec {
+    type Item = Vec<u8>;
+    type Error = Error;
+
+    fn encode<B: Buffer + ?Sized>(&mut self, item: Vec<u8>, dst: &mut B) -> Result<(), Error> {
+        if item.len() > self.max_length {
+            return Err(Error::FrameTooLong {
+                max: self.max_length,
+            });
+        }
+        // Every block is the offset of the next zero byte followed by up to
+        // 254 bytes that are not zero
+        let blocks = || {
+            item.split(|&b| b == 0)
+                .flat_map(|run| run.chunks(254).chain(long_end(run)))
+        };
+        let encoded_len = blocks().map(|block| block.len() + 1).sum::<usize>() + 1;
+        if encoded_len > dst.remaining_capacity() {
+            return Err(Error::BufferFull);
+        }
+        for block in blocks() {
+            dst.extend_from_slice(&[block.len() as u8 + 1])?;
+            dst.extend_from_slice(block)?;
+        }
+        dst.extend_from_slice(&[0])
+    }
+}
+
+/// An empty block after `run` if it is empty, which `chunks` skips, or ends
+/// in a block of 254 bytes
+fn long_end(run: &[u8]) -> Option<&[u8]> {
+    if run.len().is_multiple_of(254) {
+        Some(&[])
+    } else {
+        None
+    }
+}
+
+impl Decoder for CobsCodec {
+    type Item = Vec<u8>;
+    type Error = Error;
+
+    fn decode<B: Buffer + ?Sized>(&mut self, src: &mut B) -> Result<Option<Vec<u8>>, Error> {
+        loop {
+            let end = match src.bytes().iter().position(|&b| b == 0) {
+                Some(end) => end,
+                None if src.len() > self.max_encoded() => {
+                    return Err(Error::FrameTooLong {
+                        max: self.max_length,
+                    })
+                }
+                None => return Ok(None),
+            };
+            if end == 0 {
+                src.consume(1);
+                continue;
+            }
+            if end > self.max_encoded() {
+                return Err(Error::FrameTooLong {
+                    max: self.max_length,
+                });
+            }
+
+            let frame = decode_block(&src.bytes()[..end], self.max_length);
+            src.consume(end + 1);
+            return frame.map(Some);
+        }
+    }
+}
+
+/// Undo the byte stuffing of a frame without its zero byte
+fn decode_block(encoded: &[u8], max: usize) -> Result<Vec<u8>, Error> {
+    let mut frame = Vec::with_capacity(encoded.len());
+    let mut at = 0;
+    while at < encoded.len() {
+        let code = encoded[at] as usize;
+        let next = at + code;
+        if code == 0 || next > encoded.len() {
+            return Err(Error::InvalidData(
+                "COBS block runs past the end of the frame",
+            ));
+        }
+        frame.extend_from_slice(&encoded[at + 1..next]);
+        at = next;
+        if code < 0xff && at < encoded.len() {
+            frame.push(0);
+        }
+    }
+    if frame.len() > max {
+        return Err(Error::FrameTooLong { max });
+    }
+    Ok(frame)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::no_std::SliceBuffer;
+
+    #[test]
+    fn long_runs_and_resync() {
+        let long: Vec<u8> = (1..=254).collect();
+        let mut storage = [0u8; 300];
+        let mut buf = SliceBuffer::new(&mut storage);
+        let mut codec = CobsCodec::new();
+        buf.extend_from_slice(b"\x00\x00").unwrap();
+        codec.encode(long.clone(), &mut buf).unwrap();
+        codec.encode(b"\x00".to_vec(), &mut buf).unwrap();
+        assert_eq!(buf.len(), 2 + 257 + 3);
+        assert_eq!(
+            codec.encode(alloc::vec![7; 40], &mut buf),
+            Err(Error::BufferFull)
+        );
+        assert_eq!(buf.len(), 2 + 257 + 3);
+
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), long);
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), b"\x00");
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+
+        buf.extend_from_slice(b"\x05\x11\x00").unwrap();
+        assert!(codec.decode(&mut buf).is_err());
+        assert!(buf.is_empty());
+    }
+}
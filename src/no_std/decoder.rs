@@ -0,0 +1,25 @@
+use super::{Buffer, Error};
+
+/// Decoding of frames via buffers, without `std`.
+pub trait Decoder {
+    /// The type of items returned by `decode`
+    type Item;
+    /// The type of decoding errors.
+    type Error: From<Error>;
+
+    /// Decode an item from the start of `src`, consuming its bytes
+    fn decode<B: Buffer + ?Sized>(
+        &mut self,
+        src: &mut B,
+    ) -> Result<Option<Self::Item>, Self::Error>;
+
+    /// Decode an item from what is left in `src` after the input has ended
+    ///
+    /// The default implementation is `decode`.
+    fn decode_eof<B: Buffer + ?Sized>(
+        &mut self,
+        src: &mut B,
+    ) -> Result<Option<Self::Item>, Self::Error> {
+        self.decode(src)
+    }
+}
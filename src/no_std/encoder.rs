@@ -0,0 +1,16 @@
+use super::{Buffer, Error};
+
+/// Encoding of messages as bytes, without `std`.
+pub trait Encoder {
+    /// The type of items consumed by `encode`
+    type Item;
+    /// The type of encoding errors.
+    type Error: From<Error>;
+
+    /// Encodes an item to the end of `dst`
+    fn encode<B: Buffer + ?Sized>(
+        &mut self,
+        item: Self::Item,
+        dst: &mut B,
+    ) -> Result<(), Self::Error>;
+}
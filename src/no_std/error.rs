@@ -0,0 +1,61 @@
+use core::{fmt, str};
+
+/// Errors of the `no_std` codecs, without `std::io::Error`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A frame that must be text is not valid UTF-8.
+    Utf8(str::Utf8Error),
+    /// A frame exceeds the maximum length of the codec.
+    FrameTooLong { max: usize },
+    /// The data is not a valid frame.
+    InvalidData(&'static str),
+    /// A buffer of fixed capacity can't take any more bytes.
+    BufferFull,
+}
+
+impl From<str::Utf8Error> for Error {
+    fn from(e: str::Utf8Error) -> Self {
+        Error::Utf8(e)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Utf8(e) => e.fmt(f),
+            Error::FrameTooLong { max } => {
+                write!(f, "frame exceeds the maximum length of {} bytes", max)
+            }
+            Error::InvalidData(msg) => write!(f, "invalid frame: {}", msg),
+            Error::BufferFull => f.write_str("buffer is full"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Utf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    fn from(e: Error) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for crate::CodecError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Utf8(e) => crate::CodecError::Utf8(e),
+            Error::FrameTooLong { max } => crate::CodecError::FrameTooLong { max },
+            e => crate::CodecError::Io(e.into()),
+        }
+    }
+}
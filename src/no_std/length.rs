@@ -0,0 +1,105 @@
+use super::{Buffer, Decoder, Encoder, Error};
+use alloc::vec::Vec;
+
+/// Default limit on the length of a frame
+const MAX_FRAME: usize = 8 * 1024 * 1024;
+
+/// A codec for frames prefixed with their length as a big endian `u32`.
+///
+/// # Example
+/// ```
+/// use bytes1::BytesMut;
+/// use futures_codec::no_std::{Decoder, Encoder, LengthDelimitedCodec};
+///
+/// let mut codec = LengthDelimitedCodec::new();
+/// let mut buf = BytesMut::new();
+/// codec.encode(b"Hello".to_vec(), &mut buf).unwrap();
+/// assert_eq!(&buf[..], b"\x00\x00\x00\x05Hello");
+///
+/// assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), b"Hello");
+/// ```
+#[derive(Debug)]
+pub struct LengthDelimitedCodec {
+    max_length: usize,
+}
+
+impl LengthDelimitedCodec {
+    pub fn new() -> Self {
+        Self {
+            max_length: MAX_FRAME,
+        }
+    }
+
+    /// Fail on frames longer than `max` bytes, 8 MiB by default
+    pub fn max_length(mut self, max: usize) -> Self {
+        self.max_length = max;
+        self
+    }
+}
+
+impl Default for LengthDelimitedCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Encoder for LengthDelimitedCodec {
+    type Item = Vec<u8>;
+    type Error = Error;
+
+    fn encode<B: Buffer + ?Sized>(&mut self, item: Vec<u8>, dst: &mut B) -> Result<(), Error> {
+        if item.len() > self.max_length || item.len() > u32::MAX as usize {
+            return Err(Error::FrameTooLong {
+                max: self.max_length,
+            });
+        }
+        if 4 + item.len() > dst.remaining_capacity() {
+            return Err(Error::BufferFull);
+        }
+        dst.extend_from_slice(&(item.len() as u32).to_be_bytes())?;
+        dst.extend_from_slice(&item)
+    }
+}
+
+impl Decoder for LengthDelimitedCodec {
+    type Item = Vec<u8>;
+    type Error = Error;
+
+    fn decode<B: Buffer + ?Sized>(&mut self, src: &mut B) -> Result<Option<Vec<u8>>, Error> {
+        let bytes = src.bytes();
+        if bytes.len() < 4 {
+            return Ok(None);
+        }
+        let len = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+        if len > self.max_length {
+            return Err(Error::FrameTooLong {
+                max: self.max_length,
+            });
+        }
+        if bytes.len() - 4 < len {
+            return Ok(None);
+        }
+        let frame = bytes[4..4 + len].to_vec();
+        src.consume(4 + len);
+        Ok(Some(frame))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytes1::BytesMut;
+
+    #[test]
+    fn frame_too_long() {
+        let mut codec = LengthDelimitedCodec::new().max_length(4);
+        let mut buf = BytesMut::from(&b"\x00\x00\x00\x05Hello"[..]);
+        assert_eq!(codec.decode(&mut buf), Err(Error::FrameTooLong { max: 4 }));
+        assert!(codec.encode(b"Hello".to_vec(), &mut buf).is_err());
+
+        let mut buf = BytesMut::from(&b"\x00\x00\x00\x02H"[..]);
+        assert_eq!(codec.decode(&mut buf), Ok(None));
+        buf.extend_from_slice(b"i");
+        assert_eq!(codec.decode(&mut buf), Ok(Some(b"Hi".to_vec())));
+    }
+}
@@ -0,0 +1,67 @@
+use super::{Buffer, Decoder, Encoder, Error};
+use alloc::string::String;
+
+/// A codec that splits up data into lines, including the trailing newline.
+#[derive(Debug, Default)]
+pub struct LinesCodec {
+    /// Index into the buffer up to which no newline has been found yet
+    next_index: usize,
+}
+
+impl LinesCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Encoder for LinesCodec {
+    type Item = String;
+    type Error = Error;
+
+    fn encode<B: Buffer + ?Sized>(&mut self, item: Self::Item, dst: &mut B) -> Result<(), Error> {
+        dst.extend_from_slice(item.as_bytes())
+    }
+}
+
+impl Decoder for LinesCodec {
+    type Item = String;
+    type Error = Error;
+
+    fn decode<B: Buffer + ?Sized>(&mut self, src: &mut B) -> Result<Option<String>, Error> {
+        let bytes = src.bytes();
+        let start = self.next_index.min(bytes.len());
+        match bytes[start..].iter().position(|b| b == &b'\n') {
+            Some(offset) => {
+                self.next_index = 0;
+                let len = start + offset + 1;
+                let line = String::from(core::str::from_utf8(&bytes[..len])?);
+                src.consume(len);
+                Ok(Some(line))
+            }
+            None => {
+                self.next_index = bytes.len();
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytes1::BytesMut;
+
+    #[test]
+    fn resumes_scan() {
+        let mut codec = LinesCodec::new();
+        let mut buf = BytesMut::from(&b"Hel"[..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+
+        buf.extend_from_slice(b"lo\n\xff\n");
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "Hello\n");
+        match codec.decode(&mut buf) {
+            Err(Error::Utf8(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! Codecs that only need `core` and `alloc`.
+//!
+//! The `Decoder` and `Encoder` traits of this module mirror the ones at the
+//! crate root, but work on any [`Buffer`] and need their errors to convert
+//! from the core-only [`Error`] instead of `std::io::Error`. They build with
+//! `default-features = false`, for embedded targets without `std`, and the
+//! codecs can be driven by whatever runs the I/O there. Buffers are the
+//! `BytesMut` of `bytes` 1, or a [`SliceBuffer`] in fixed storage provided by
+//! the caller, which fails instead of growing when it is full.
+//!
+//! # Example
+//! ```
+//! use bytes1::BytesMut;
+//! use futures_codec::no_std::{Decoder, Encoder, LinesCodec};
+//!
+//! let mut codec = LinesCodec::new();
+//! let mut buf = BytesMut::new();
+//! codec.encode("Hello\n".into(), &mut buf).unwrap();
+//!
+//! assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "Hello\n");
+//! ```
+
+mod error;
+pub use self::error::Error;
+
+mod buffer;
+pub use self::buffer::{Buffer, SliceBuffer};
+
+mod decoder;
+pub use self::decoder::Decoder;
+
+mod encoder;
+pub use self::encoder::Encoder;
+
+mod lines;
+pub use self::lines::LinesCodec;
+
+mod length;
+pub use self::length::LengthDelimitedCodec;
+
+mod cobs;
+pub use self::cobs::CobsCodec;
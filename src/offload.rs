@@ -0,0 +1,284 @@
+use super::{Decoder, Encoder};
+
+use bytes::{Bytes, BytesMut};
+use futures::channel::oneshot;
+use futures::{ready, Sink, Stream};
+use pin_project_lite::pin_project;
+use std::future::Future;
+use std::io::{Error, ErrorKind};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A job handed to the spawn hook of an `OffloadCodec`.
+pub type OffloadJob = Box<dyn FnOnce() + Send>;
+
+/// Runs an expensive codec away from the task polling it, through a
+/// `spawn_blocking` style hook.
+///
+/// The codec is applied to frames that were already split off the I/O, so
+/// pair it with a cheap codec that only finds frame boundaries, such as
+/// `BytesLinesCodec` or a length prefix codec. `decode` turns a `Stream` of
+/// such frames into a `Stream` of items, `encode` a `Sink` of encoded frames
+/// into a `Sink` of items. One frame is processed at a time, so frames keep
+/// their order.
+///
+/// # Example
+/// ```
+/// use futures::{executor, TryStreamExt};
+/// use futures_codec::{BytesLinesCodec, FramedRead, LinesCodec, OffloadCodec};
+/// use std::thread;
+///
+/// let buf = b"Hello\nWorld\n";
+/// let frames = FramedRead::new(&buf[..], BytesLinesCodec::new());
+/// let spawn = |job| drop(thread::spawn(job));
+/// let lines = OffloadCodec::new(LinesCodec::new(), spawn).decode(frames);
+///
+/// executor::block_on(async move {
+///     let lines: Vec<_> = lines.try_collect().await.unwrap();
+///     assert_eq!(lines, ["Hello\n", "World\n"]);
+/// })
+/// ```
+#[derive(Debug)]
+pub struct OffloadCodec<C, H> {
+    codec: C,
+    spawn: H,
+}
+
+impl<C, H> OffloadCodec<C, H>
+where
+    C: Send + 'static,
+    H: Fn(OffloadJob),
+{
+    /// Run `codec` in the jobs given to `spawn`
+    pub fn new(codec: C, spawn: H) -> Self {
+        Self { codec, spawn }
+    }
+
+    /// Decode every frame of `frames` into an item
+    pub fn decode<S>(self, frames: S) -> OffloadDecode<S, C, H>
+    where
+        C: Decoder,
+    {
+        OffloadDecode {
+            frames,
+            codec: Some(self.codec),
+            spawn: self.spawn,
+            job: None,
+        }
+    }
+
+    /// Encode every item into a frame sent to `sink`
+    pub fn encode<S>(self, sink: S) -> OffloadEncode<S, C, H>
+    where
+        C: Encoder,
+    {
+        OffloadEncode {
+            sink,
+            codec: Some(self.codec),
+            spawn: self.spawn,
+            job: None,
+            encoded: None,
+        }
+    }
+}
+
+type Job<C, R> = oneshot::Receiver<(C, R)>;
+
+/// Run `f` on `codec` in a job given to `spawn`, returning the codec along
+/// with the result.
+fn offload<C, R, H, F>(spawn: &H, mut codec: C, f: F) -> Job<C, R>
+where
+    C: Send + 'static,
+    R: Send + 'static,
+    H: Fn(OffloadJob),
+    F: FnOnce(&mut C) -> R + Send + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+    spawn(Box::new(move || {
+        let result = f(&mut codec);
+        let _ = tx.send((codec, result));
+    }));
+    rx
+}
+
+/// Wait for `job` to finish and put the codec back
+fn poll_job<C, R>(
+    job: &mut Option<Job<C, R>>,
+    codec: &mut Option<C>,
+    cx: &mut Context,
+) -> Poll<Result<Option<R>, Error>> {
+    let done = match job {
+        Some(job) => ready!(Pin::new(job).poll(cx)),
+        None => return Poll::Ready(Ok(None)),
+    };
+    *job = None;
+    match done {
+        Ok((returned, result)) => {
+            *codec = Some(returned);
+            Poll::Ready(Ok(Some(result)))
+        }
+        Err(oneshot::Canceled) => Poll::Ready(Err(lost_codec())),
+    }
+}
+
+fn lost_codec() -> Error {
+    Error::other("the codec was lost in a dropped job")
+}
+
+pin_project! {
+    /// A `Stream` of items decoded from frames by an offloaded codec.
+    ///
+    /// Created by [`OffloadCodec::decode`].
+    pub struct OffloadDecode<S, C, H>
+    where
+        C: Decoder,
+    {
+        #[pin]
+        frames: S,
+        codec: Option<C>,
+        spawn: H,
+        job: Option<Job<C, Result<Option<C::Item>, C::Error>>>,
+    }
+}
+
+impl<S, C: Decoder, H> OffloadDecode<S, C, H> {
+    /// Return the `Stream` of frames, dropping the codec if a job is running
+    pub fn into_inner(self) -> S {
+        self.frames
+    }
+}
+
+impl<S, B, C, H> Stream for OffloadDecode<S, C, H>
+where
+    S: Stream<Item = Result<B, C::Error>>,
+    B: Into<BytesMut>,
+    C: Decoder + Send + 'static,
+    C::Item: Send,
+    C::Error: Send,
+    H: Fn(OffloadJob),
+{
+    type Item = Result<C::Item, C::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            if let Some(decoded) = ready!(poll_job(this.job, this.codec, cx))? {
+                let item = decoded.and_then(|item| {
+                    item.ok_or_else(|| Error::new(ErrorKind::InvalidData, "incomplete frame").into())
+                });
+                return Poll::Ready(Some(item));
+            }
+
+            let mut frame = match ready!(this.frames.as_mut().poll_next(cx)) {
+                Some(Ok(frame)) => frame.into(),
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => return Poll::Ready(None),
+            };
+            let codec = this.codec.take().ok_or_else(lost_codec)?;
+            *this.job = Some(offload(this.spawn, codec, move |codec: &mut C| {
+                codec.decode_eof(&mut frame)
+            }));
+        }
+    }
+}
+
+pin_project! {
+    /// A `Sink` of items encoded by an offloaded codec into frames for an
+    /// inner `Sink`.
+    ///
+    /// Created by [`OffloadCodec::encode`].
+    pub struct OffloadEncode<S, C, H>
+    where
+        C: Encoder,
+    {
+        #[pin]
+        sink: S,
+        codec: Option<C>,
+        spawn: H,
+        job: Option<Job<C, Result<Bytes, C::Error>>>,
+        encoded: Option<Bytes>,
+    }
+}
+
+impl<S, C: Encoder, H> OffloadEncode<S, C, H> {
+    /// Return the inner `Sink`, dropping a frame still being encoded
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+}
+
+impl<S, C, H> OffloadEncode<S, C, H>
+where
+    S: Sink<Bytes, Error = C::Error>,
+    C: Encoder,
+{
+    /// Wait for the frame being encoded and pass it on to the inner sink
+    fn poll_encoded(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), C::Error>> {
+        let mut this = self.project();
+        if let Some(encoded) = ready!(poll_job(this.job, this.codec, cx))? {
+            *this.encoded = Some(encoded?);
+        }
+        if this.encoded.is_some() {
+            ready!(this.sink.as_mut().poll_ready(cx))?;
+            this.sink.as_mut().start_send(this.encoded.take().unwrap())?;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S, C, H> Sink<C::Item> for OffloadEncode<S, C, H>
+where
+    S: Sink<Bytes, Error = C::Error>,
+    C: Encoder + Send + 'static,
+    C::Item: Send + 'static,
+    C::Error: Send,
+    H: Fn(OffloadJob),
+{
+    type Error = C::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.poll_encoded(cx)
+    }
+    fn start_send(self: Pin<&mut Self>, item: C::Item) -> Result<(), Self::Error> {
+        let this = self.project();
+        let codec = this.codec.take().ok_or_else(lost_codec)?;
+        *this.job = Some(offload(this.spawn, codec, move |codec: &mut C| {
+            let mut frame = BytesMut::new();
+            codec.encode(item, &mut frame).map(|()| frame.freeze())
+        }));
+        Ok(())
+    }
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        ready!(self.as_mut().poll_encoded(cx))?;
+        self.project().sink.poll_flush(cx)
+    }
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        ready!(self.as_mut().poll_encoded(cx))?;
+        self.project().sink.poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{BytesCodec, CodecError, FramedWrite, LinesCodec};
+    use futures::{executor, SinkExt};
+    use std::thread;
+
+    #[test]
+    fn encodes_in_order() {
+        let spawn = |job| drop(thread::spawn(job));
+        let framed = FramedWrite::new(Vec::new(), BytesCodec {});
+        let mut lines = OffloadCodec::new(LinesCodec::new(), spawn).encode(framed);
+
+        executor::block_on(async {
+            for line in &["one\n", "two\n", "three\n"] {
+                lines.send(line.to_string()).await?;
+            }
+            Ok::<_, CodecError>(())
+        })
+        .unwrap();
+        let (buf, _) = lines.into_inner().release();
+        assert_eq!(&buf[..], b"one\ntwo\nthree\n");
+    }
+}
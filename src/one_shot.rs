@@ -0,0 +1,110 @@
+use super::{Decoder, Encoder};
+
+use bytes::BytesMut;
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use std::io::{Error, ErrorKind};
+
+/// Size of the reads of `read_frame`
+const READ_CHUNK: usize = 8 * 1024;
+
+/// Read and decode a single frame from `io`, without a `FramedRead`.
+///
+/// `buf` holds the bytes read but not decoded yet. Bytes read past the end
+/// of the frame stay in it, so pass the same buffer to the next call, or
+/// hand it to the next stage, for example with `FramedRead::with_initial`.
+/// Returns `None` if `io` ends before a frame started, and fails with
+/// `UnexpectedEof` if it ends in the middle of one, as `FramedRead` does by
+/// default. `Decoder::decode_eof` is not called, the remaining bytes are left
+/// in `buf`.
+///
+/// # Example
+/// ```
+/// use bytes::BytesMut;
+/// use futures::executor;
+/// use futures_codec::{read_frame, write_frame, LinesCodec};
+///
+/// executor::block_on(async move {
+///     let mut io = Vec::new();
+///     let mut buf = BytesMut::new();
+///     write_frame(&mut io, &mut LinesCodec::new(), "HELLO\n".to_owned(), &mut buf)
+///         .await
+///         .unwrap();
+///     io.extend_from_slice(b"binary");
+///
+///     let mut io = &io[..];
+///     let mut buf = BytesMut::new();
+///     let hello = read_frame(&mut io, &mut LinesCodec::new(), &mut buf).await.unwrap();
+///     assert_eq!(hello.unwrap(), "HELLO\n");
+///     assert_eq!(&buf[..], b"binary");
+/// })
+/// ```
+pub async fn read_frame<T, D>(
+    io: &mut T,
+    codec: &mut D,
+    buf: &mut BytesMut,
+) -> Result<Option<D::Item>, D::Error>
+where
+    T: AsyncRead + Unpin,
+    D: Decoder,
+{
+    let mut chunk = [0; READ_CHUNK];
+    loop {
+        if let Some(item) = codec.decode(buf)? {
+            return Ok(Some(item));
+        }
+        let n = match io.read(&mut chunk).await {
+            Ok(n) => n,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if n == 0 && buf.is_empty() {
+            return Ok(None);
+        } else if n == 0 {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "bytes remaining in stream").into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+}
+
+/// Encode `item` and write it to `io` as a single frame, then flush `io`,
+/// without a `FramedWrite`.
+///
+/// The frame is encoded into `buf`, which is cleared first, so the same
+/// buffer can be reused for every frame.
+pub async fn write_frame<T, E>(
+    io: &mut T,
+    codec: &mut E,
+    item: E::Item,
+    buf: &mut BytesMut,
+) -> Result<(), E::Error>
+where
+    T: AsyncWrite + Unpin,
+    E: Encoder,
+{
+    buf.clear();
+    codec.encode(item, buf)?;
+    io.write_all(buf).await?;
+    io.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LinesCodec;
+    use futures::executor;
+
+    #[test]
+    fn unterminated_last_frame() {
+        executor::block_on(async move {
+            let mut io = &b"one\ntw"[..];
+            let mut codec = LinesCodec::new();
+            let mut buf = BytesMut::new();
+            let one = read_frame(&mut io, &mut codec, &mut buf).await.unwrap();
+            assert_eq!(one.unwrap(), "one\n");
+            let err = read_frame(&mut io, &mut codec, &mut buf).await.unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+            assert_eq!(&buf[..], b"tw");
+        })
+    }
+}
@@ -0,0 +1,224 @@
+use super::retry::poll_retry;
+use super::Encoder;
+
+use bytes::{Bytes, BytesMut};
+use futures::io::{AsyncWrite, IoSlice};
+use futures::{ready, Sink};
+use pin_project_lite::pin_project;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{Error, ErrorKind};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Default number of queued bytes above which `poll_ready` flushes
+const FLUSH_THRESHOLD: usize = 64 * 1024;
+
+pin_project! {
+    /// A `Sink` of `(priority, item)` pairs encoded to an `AsyncWrite`, which
+    /// writes queued frames of a higher priority first.
+    ///
+    /// Frames are queued one by one, so a control frame sent after a batch of
+    /// bulk frames is written as soon as the frame currently being written is
+    /// complete, no matter how many bulk frames wait in front of it. Frames of
+    /// the same priority keep their order.
+    ///
+    /// A frame is never preempted: once its first byte is written, it is
+    /// written to the end before any other frame, whatever its priority, as
+    /// the bytes of two frames can not be interleaved. Split large messages
+    /// with a codec such as `FragmentingCodec` if a single frame may hold up
+    /// the connection for too long.
+    ///
+    /// `poll_ready` flushes once more than 64 KiB are queued, see
+    /// `flush_threshold`, so feeding frames without flushing is not buffered
+    /// without limit.
+    ///
+    /// # Example
+    /// ```
+    /// use futures::{executor, Sink, SinkExt};
+    /// use futures_codec::{LinesCodec, PrioritizedFramedWrite};
+    /// use std::pin::Pin;
+    ///
+    /// executor::block_on(async move {
+    ///     let mut buf = Vec::new();
+    ///     let mut framed = PrioritizedFramedWrite::new(&mut buf, LinesCodec::new());
+    ///
+    ///     Pin::new(&mut framed).start_send((0, "bulk\n".to_owned())).unwrap();
+    ///     Pin::new(&mut framed).start_send((1, "ping\n".to_owned())).unwrap();
+    ///     framed.flush().await.unwrap();
+    ///
+    ///     assert_eq!(&buf[..], b"ping\nbulk\n");
+    /// })
+    /// ```
+    pub struct PrioritizedFramedWrite<T, E, P> {
+        #[pin]
+        inner: T,
+        encoder: E,
+        buffer: BytesMut,
+        // Encoded frames waiting to be written, by priority
+        queues: BTreeMap<P, VecDeque<Frame>>,
+        // Frame being written, which has to be completed before any other
+        current: Option<Frame>,
+        // Bytes of the frames in `queues` and `current` not written yet
+        queued_bytes: usize,
+        flush_threshold: usize,
+    }
+}
+
+/// The header and zero copy body of an encoded frame
+#[derive(Debug)]
+struct Frame {
+    header: Bytes,
+    body: Bytes,
+}
+
+impl<T, E, P> PrioritizedFramedWrite<T, E, P>
+where
+    T: AsyncWrite,
+    E: Encoder,
+    P: Ord,
+{
+    pub fn new(inner: T, encoder: E) -> Self {
+        Self {
+            inner,
+            encoder,
+            buffer: BytesMut::new(),
+            queues: BTreeMap::new(),
+            current: None,
+            queued_bytes: 0,
+            flush_threshold: FLUSH_THRESHOLD,
+        }
+    }
+
+    /// Flush in `poll_ready` once more than `bytes` are queued, 64 KiB by
+    /// default
+    pub fn flush_threshold(mut self, bytes: usize) -> Self {
+        self.flush_threshold = bytes;
+        self
+    }
+
+    /// Number of frames waiting to be written
+    pub fn queued(&self) -> usize {
+        self.queues.values().map(VecDeque::len).sum::<usize>() + self.current.iter().count()
+    }
+
+    /// Release the I/O and Encoder, dropping any frames that were not written
+    pub fn release(self) -> (T, E) {
+        (self.inner, self.encoder)
+    }
+}
+
+impl<T, E, P> Sink<(P, E::Item)> for PrioritizedFramedWrite<T, E, P>
+where
+    T: AsyncWrite,
+    E: Encoder,
+    P: Ord,
+{
+    type Error = E::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        if self.queued_bytes > self.flush_threshold {
+            return self.poll_flush(cx);
+        }
+        Poll::Ready(Ok(()))
+    }
+    fn start_send(self: Pin<&mut Self>, (priority, item): (P, E::Item)) -> Result<(), Self::Error> {
+        let this = self.project();
+        let body = this.encoder.encode_zero_copy(item, this.buffer)?;
+        let frame = Frame {
+            header: this.buffer.take().freeze(),
+            body: body.unwrap_or_default(),
+        };
+        *this.queued_bytes += frame.header.len() + frame.body.len();
+        this.queues.entry(priority).or_default().push_back(frame);
+        Ok(())
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        let this = self.project();
+        let mut io = this.inner;
+        loop {
+            if this.current.is_none() {
+                *this.current = next_frame(this.queues);
+            }
+            let frame = match this.current {
+                Some(frame) => frame,
+                None => break,
+            };
+
+            let n = if frame.header.is_empty() || frame.body.is_empty() {
+                let segment = if frame.header.is_empty() { &frame.body } else { &frame.header };
+                ready!(poll_retry(cx, |cx| io.as_mut().poll_write(cx, segment)))?
+            } else {
+                let slices = [IoSlice::new(&frame.header), IoSlice::new(&frame.body)];
+                ready!(poll_retry(cx, |cx| io.as_mut().poll_write_vectored(cx, &slices)))?
+            };
+            if n == 0 {
+                return Poll::Ready(Err(Error::new(ErrorKind::UnexpectedEof, "End of file").into()));
+            }
+
+            let from_header = n.min(frame.header.len());
+            frame.header.advance(from_header);
+            frame.body.advance(n - from_header);
+            *this.queued_bytes -= n;
+            if frame.header.is_empty() && frame.body.is_empty() {
+                *this.current = None;
+            }
+        }
+        ready!(poll_retry(cx, |cx| io.as_mut().poll_flush(cx)))?;
+        Poll::Ready(Ok(()))
+    }
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        self.project().inner.poll_close(cx).map_err(Into::into)
+    }
+}
+
+/// Take the first frame of the highest priority out of `queues`
+fn next_frame<P: Ord>(queues: &mut BTreeMap<P, VecDeque<Frame>>) -> Option<Frame> {
+    let mut entry = queues.last_entry()?;
+    let frame = entry.get_mut().pop_front();
+    if entry.get().is_empty() {
+        entry.remove();
+    }
+    frame
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LinesCodec;
+    use futures::future::poll_fn;
+    use futures::{executor, SinkExt};
+
+    #[test]
+    fn control_frame_overtakes_queued_bulk() {
+        let mut framed = PrioritizedFramedWrite::new(Vec::new(), LinesCodec::new());
+
+        Pin::new(&mut framed).start_send((0, "bulk 1\n".to_owned())).unwrap();
+        Pin::new(&mut framed).start_send((0, "bulk 2\n".to_owned())).unwrap();
+        Pin::new(&mut framed).start_send((9, "ping\n".to_owned())).unwrap();
+        assert_eq!(framed.queued(), 3);
+
+        executor::block_on(framed.flush()).unwrap();
+        assert_eq!(framed.queued(), 0);
+        let (buf, _) = framed.release();
+        assert_eq!(&buf[..], b"ping\nbulk 1\nbulk 2\n");
+    }
+
+    #[test]
+    fn poll_ready_flushes_over_threshold() {
+        let mut framed =
+            PrioritizedFramedWrite::new(Vec::new(), LinesCodec::new()).flush_threshold(8);
+        let mut feed = |line: &str| {
+            executor::block_on(poll_fn(|cx| Pin::new(&mut framed).poll_ready(cx))).unwrap();
+            Pin::new(&mut framed).start_send((0, line.to_owned())).unwrap();
+        };
+
+        feed("bulk 1\n");
+        feed("bulk 2\n");
+        feed("bulk 3\n");
+        assert_eq!(framed.queued(), 1);
+
+        let (buf, _) = framed.release();
+        assert_eq!(&buf[..], b"bulk 1\nbulk 2\n");
+    }
+}
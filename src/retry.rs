@@ -0,0 +1,50 @@
+use std::io::{Error, ErrorKind};
+use std::task::{Context, Poll};
+
+/// Poll `f` again when it fails with `Interrupted`.
+///
+/// `WouldBlock` from I/O that wraps a non-blocking file descriptor without
+/// registering for readiness is not a frame error, the framed types would
+/// end on it. It is turned into `Pending` with an immediate wakeup instead,
+/// so the operation is retried on the next poll. Such I/O has no way to
+/// wake the task once it is ready, so until then the task is polled over
+/// and over, busy waiting on the descriptor.
+pub(crate) fn poll_retry<T>(
+    cx: &mut Context<'_>,
+    mut f: impl FnMut(&mut Context<'_>) -> Poll<Result<T, Error>>,
+) -> Poll<Result<T, Error>> {
+    loop {
+        match f(cx) {
+            Poll::Ready(Err(ref e)) if e.kind() == ErrorKind::Interrupted => continue,
+            Poll::Ready(Err(ref e)) if e.kind() == ErrorKind::WouldBlock => {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            poll => return poll,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::ChunkedMockIo;
+    use crate::{FramedRead, LinesCodec};
+    use futures::{executor, TryStreamExt};
+    use std::io::ErrorKind;
+
+    #[test]
+    fn retries_interrupted_and_would_block() {
+        let io = ChunkedMockIo::builder()
+            .error(ErrorKind::Interrupted)
+            .read(b"Hi\n")
+            .error(ErrorKind::WouldBlock)
+            .read(b"Ho\n")
+            .build();
+        let mut framed = FramedRead::new(io, LinesCodec::new());
+
+        let line = executor::block_on(framed.try_next()).unwrap().unwrap();
+        assert_eq!(line, "Hi\n");
+        let line = executor::block_on(framed.try_next()).unwrap().unwrap();
+        assert_eq!(line, "Ho\n");
+    }
+}
@@ -0,0 +1,37 @@
+use std::io::Error;
+use std::marker::Unpin;
+use std::ops::DerefMut;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// I/O that can close its write direction while the read direction stays
+/// open, like `shutdown(SHUT_WR)` on a TCP socket.
+///
+/// Used by `close_write` on `Framed` and `FramedWrite` to signal the end of a
+/// request to the peer while still decoding its response.
+pub trait AsyncShutdown {
+    /// Attempt to close the write direction
+    fn poll_shutdown_write(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>>;
+}
+
+impl<T: AsyncShutdown + Unpin + ?Sized> AsyncShutdown for &mut T {
+    fn poll_shutdown_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Pin::new(&mut **self).poll_shutdown_write(cx)
+    }
+}
+
+impl<T: AsyncShutdown + Unpin + ?Sized> AsyncShutdown for Box<T> {
+    fn poll_shutdown_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Pin::new(&mut **self).poll_shutdown_write(cx)
+    }
+}
+
+impl<P> AsyncShutdown for Pin<P>
+where
+    P: DerefMut + Unpin,
+    P::Target: AsyncShutdown,
+{
+    fn poll_shutdown_write(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.get_mut().as_mut().poll_shutdown_write(cx)
+    }
+}
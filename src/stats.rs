@@ -0,0 +1,89 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A snapshot of the counters of a framing type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Frames successfully decoded
+    pub frames_decoded: u64,
+    /// Frames encoded
+    pub frames_encoded: u64,
+    /// Bytes read from the I/O
+    pub bytes_read: u64,
+    /// Bytes written to the I/O
+    pub bytes_written: u64,
+    /// Errors returned by the decoder
+    pub decode_errors: u64,
+    /// Bytes currently waiting in the read buffer
+    pub read_buffer_len: usize,
+    /// Bytes currently waiting to be written
+    pub write_buffer_len: usize,
+}
+
+/// A cloneable handle to the live counters of a framing type, for reading them
+/// from other tasks.
+#[derive(Debug, Clone)]
+pub struct StatsHandle(pub(crate) Arc<Counters>);
+
+impl StatsHandle {
+    /// Take a snapshot of the counters
+    pub fn get(&self) -> Stats {
+        self.0.snapshot()
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    frames_decoded: AtomicU64,
+    frames_encoded: AtomicU64,
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    decode_errors: AtomicU64,
+    read_buffer_len: AtomicUsize,
+    write_buffer_len: AtomicUsize,
+}
+
+impl Counters {
+    pub(crate) fn snapshot(&self) -> Stats {
+        Stats {
+            frames_decoded: self.frames_decoded.load(Ordering::Relaxed),
+            frames_encoded: self.frames_encoded.load(Ordering::Relaxed),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            decode_errors: self.decode_errors.load(Ordering::Relaxed),
+            read_buffer_len: self.read_buffer_len.load(Ordering::Relaxed),
+            write_buffer_len: self.write_buffer_len.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn frame_decoded(&self) {
+        self.frames_decoded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn decode_error(&self) {
+        self.decode_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn read(&self, n: usize) {
+        self.bytes_read.fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn read_buffer(&self, len: usize) {
+        self.read_buffer_len.store(len, Ordering::Relaxed);
+    }
+
+    pub(crate) fn frame_encoded(&self, queued: usize) {
+        self.frames_encoded.fetch_add(1, Ordering::Relaxed);
+        self.write_buffer_len.fetch_add(queued, Ordering::Relaxed);
+    }
+
+    pub(crate) fn written(&self, n: usize) {
+        self.bytes_written.fetch_add(n as u64, Ordering::Relaxed);
+        self.write_buffer_len.fetch_sub(n, Ordering::Relaxed);
+    }
+
+    /// Bytes written around the encoder, which were never queued
+    pub(crate) fn written_raw(&self, n: usize) {
+        self.bytes_written.fetch_add(n as u64, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,186 @@
+use futures::io::{AsyncRead, AsyncWrite};
+use std::collections::VecDeque;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+#[derive(Debug)]
+enum Action {
+    Read(Vec<u8>),
+    Pending,
+    Error(io::ErrorKind),
+}
+
+/// An I/O object serving a script of reads and recording all writes.
+///
+/// Every scripted read is returned by exactly one call to `poll_read`, unless
+/// the caller's buffer is smaller. Once the script is exhausted, reads return
+/// end of file.
+///
+/// # Example
+/// ```
+/// use futures::{executor, TryStreamExt};
+/// use futures_codec::testing::ChunkedMockIo;
+/// use futures_codec::{FramedRead, LinesCodec};
+///
+/// let io = ChunkedMockIo::builder()
+///     .read(b"Hel")
+///     .pending()
+///     .read(b"lo\n")
+///     .build();
+///
+/// let mut framed = FramedRead::new(io, LinesCodec::new());
+/// let line = executor::block_on(framed.try_next()).unwrap().unwrap();
+/// assert_eq!(line, "Hello\n");
+/// ```
+#[derive(Debug)]
+pub struct ChunkedMockIo {
+    script: VecDeque<Action>,
+    max_write: usize,
+    written: Vec<u8>,
+}
+
+impl ChunkedMockIo {
+    pub fn builder() -> ChunkedMockIoBuilder {
+        ChunkedMockIoBuilder {
+            script: VecDeque::new(),
+            max_write: usize::MAX,
+        }
+    }
+
+    /// All bytes written so far
+    pub fn written(&self) -> &[u8] {
+        &self.written
+    }
+
+    /// Whether all scripted actions have been consumed
+    pub fn is_done(&self) -> bool {
+        self.script.is_empty()
+    }
+}
+
+/// Builds the script of a `ChunkedMockIo`.
+#[derive(Debug)]
+pub struct ChunkedMockIoBuilder {
+    script: VecDeque<Action>,
+    max_write: usize,
+}
+
+impl ChunkedMockIoBuilder {
+    /// Serve `data` in a single read
+    pub fn read(mut self, data: &[u8]) -> Self {
+        self.script.push_back(Action::Read(data.to_vec()));
+        self
+    }
+
+    /// Serve `data` in reads of at most `size` bytes
+    pub fn read_chunks(mut self, data: &[u8], size: usize) -> Self {
+        for chunk in data.chunks(size.max(1)) {
+            self.script.push_back(Action::Read(chunk.to_vec()));
+        }
+        self
+    }
+
+    /// Return a read of zero bytes
+    pub fn eof(mut self) -> Self {
+        self.script.push_back(Action::Read(Vec::new()));
+        self
+    }
+
+    /// Return `Poll::Pending` once, waking the task right away
+    pub fn pending(mut self) -> Self {
+        self.script.push_back(Action::Pending);
+        self
+    }
+
+    /// Fail a read with an error of `kind`
+    pub fn error(mut self, kind: io::ErrorKind) -> Self {
+        self.script.push_back(Action::Error(kind));
+        self
+    }
+
+    /// Accept at most `max` bytes per write
+    pub fn max_write(mut self, max: usize) -> Self {
+        self.max_write = max;
+        self
+    }
+
+    pub fn build(self) -> ChunkedMockIo {
+        ChunkedMockIo {
+            script: self.script,
+            max_write: self.max_write,
+            written: Vec::new(),
+        }
+    }
+}
+
+impl AsyncRead for ChunkedMockIo {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.script.pop_front() {
+            None => Poll::Ready(Ok(0)),
+            Some(Action::Pending) => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            Some(Action::Error(kind)) => Poll::Ready(Err(kind.into())),
+            Some(Action::Read(mut data)) => {
+                let n = buf.len().min(data.len());
+                buf[..n].copy_from_slice(&data[..n]);
+                if n < data.len() {
+                    data.drain(..n);
+                    self.script.push_front(Action::Read(data));
+                }
+                Poll::Ready(Ok(n))
+            }
+        }
+    }
+}
+
+impl AsyncWrite for ChunkedMockIo {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let n = buf.len().min(self.max_write);
+        self.written.extend_from_slice(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Framed, LinesCodec};
+    use futures::{executor, SinkExt, TryStreamExt};
+
+    #[test]
+    fn scripted_reads_and_short_writes() {
+        let io = ChunkedMockIo::builder()
+            .read_chunks(b"Hello\nWorld", 2)
+            .error(io::ErrorKind::ConnectionReset)
+            .max_write(3)
+            .build();
+        let mut framed = Framed::new(io, LinesCodec::new());
+
+        let next = executor::block_on(framed.try_next()).unwrap().unwrap();
+        assert_eq!(next, "Hello\n");
+        let err = executor::block_on(framed.try_next()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+
+        executor::block_on(framed.send("Bye\n".to_owned())).unwrap();
+        let (io, _) = framed.release();
+        assert_eq!(io.written(), b"Bye\n");
+        assert!(io.is_done());
+    }
+}
@@ -0,0 +1,10 @@
+//! Utilities for testing codecs and framed transports.
+
+mod record;
+pub use self::record::{RecordingIo, ReplayIo};
+
+mod mock;
+pub use self::mock::{ChunkedMockIo, ChunkedMockIoBuilder};
+
+mod roundtrip;
+pub use self::roundtrip::assert_roundtrip;
@@ -0,0 +1,199 @@
+use bytes::{BigEndian, ByteOrder};
+use futures::io::{AsyncRead, AsyncWrite};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::marker::Unpin;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Tag of a chunk that was read from the I/O
+const READ: u8 = b'R';
+/// Tag of a chunk that was written to the I/O
+const WRITE: u8 = b'W';
+
+/// Wraps an I/O object and records every chunk read from or written to it.
+///
+/// Chunks are stored in `log` as records of a one byte tag (`R` or `W`), a
+/// big endian `u32` length and the bytes of the chunk. Use `ReplayIo` to play
+/// a recording back.
+///
+/// # Example
+/// ```
+/// use futures::{executor, TryStreamExt};
+/// use futures_codec::testing::{RecordingIo, ReplayIo};
+/// use futures_codec::{FramedRead, LinesCodec};
+///
+/// let mut log = Vec::new();
+/// let io = RecordingIo::new(&b"Hello\n"[..], &mut log);
+/// let mut framed = FramedRead::new(io, LinesCodec::new());
+/// executor::block_on(framed.try_next()).unwrap();
+/// drop(framed);
+///
+/// let replay = ReplayIo::from_reader(&log[..]).unwrap();
+/// let mut framed = FramedRead::new(replay, LinesCodec::new());
+/// let line = executor::block_on(framed.try_next()).unwrap().unwrap();
+/// assert_eq!(line, "Hello\n");
+/// ```
+pub struct RecordingIo<T, W> {
+    inner: T,
+    log: W,
+}
+
+impl<T, W: Write> RecordingIo<T, W> {
+    pub fn new(inner: T, log: W) -> Self {
+        Self { inner, log }
+    }
+
+    /// Release the I/O and the log
+    pub fn release(self) -> (T, W) {
+        (self.inner, self.log)
+    }
+
+    fn record(&mut self, tag: u8, chunk: &[u8]) -> io::Result<()> {
+        let mut header = [tag, 0, 0, 0, 0];
+        BigEndian::write_u32(&mut header[1..], chunk.len() as u32);
+        self.log.write_all(&header)?;
+        self.log.write_all(chunk)
+    }
+}
+
+impl<T: AsyncRead + Unpin, W: Write + Unpin> AsyncRead for RecordingIo<T, W> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let n = futures::ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+        self.record(READ, &buf[..n])?;
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl<T: AsyncWrite + Unpin, W: Write + Unpin> AsyncWrite for RecordingIo<T, W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let n = futures::ready!(Pin::new(&mut self.inner).poll_write(cx, buf))?;
+        self.record(WRITE, &buf[..n])?;
+        Poll::Ready(Ok(n))
+    }
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.log.flush()?;
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.log.flush()?;
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+/// Plays back the chunks read in a recording made by `RecordingIo`, with their
+/// original boundaries.
+///
+/// Every read returns at most one recorded chunk. Writes are accepted and
+/// collected, so they can be compared to the recorded ones.
+pub struct ReplayIo {
+    reads: VecDeque<Vec<u8>>,
+    recorded_writes: Vec<u8>,
+    written: Vec<u8>,
+}
+
+impl ReplayIo {
+    /// Load a recording
+    pub fn from_reader<R: Read>(mut recording: R) -> io::Result<Self> {
+        let mut replay = Self {
+            reads: VecDeque::new(),
+            recorded_writes: Vec::new(),
+            written: Vec::new(),
+        };
+
+        let mut header = [0u8; 5];
+        loop {
+            match recording.read_exact(&mut header) {
+                Ok(()) => {}
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(replay),
+                Err(e) => return Err(e),
+            }
+            let mut chunk = vec![0; BigEndian::read_u32(&header[1..]) as usize];
+            recording.read_exact(&mut chunk)?;
+
+            match header[0] {
+                READ => replay.reads.push_back(chunk),
+                WRITE => replay.recorded_writes.extend_from_slice(&chunk),
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "unknown chunk tag")),
+            }
+        }
+    }
+
+    /// All bytes written to the recorded I/O
+    pub fn recorded_writes(&self) -> &[u8] {
+        &self.recorded_writes
+    }
+
+    /// All bytes written to this `ReplayIo`
+    pub fn written(&self) -> &[u8] {
+        &self.written
+    }
+}
+
+impl AsyncRead for ReplayIo {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let chunk = match self.reads.front_mut() {
+            Some(chunk) => chunk,
+            None => return Poll::Ready(Ok(0)),
+        };
+
+        let n = buf.len().min(chunk.len());
+        buf[..n].copy_from_slice(&chunk[..n]);
+        chunk.drain(..n);
+        if chunk.is_empty() {
+            self.reads.pop_front();
+        }
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl AsyncWrite for ReplayIo {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.written.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::{executor, AsyncReadExt};
+
+    #[test]
+    fn replays_chunk_boundaries() {
+        let mut log = Vec::new();
+        {
+            let mut io = RecordingIo::new(&b"Hello World"[..], &mut log);
+            let mut buf = [0u8; 4];
+            executor::block_on(io.read_exact(&mut buf)).unwrap();
+        }
+
+        let mut replay = ReplayIo::from_reader(&log[..]).unwrap();
+        let mut buf = [0u8; 16];
+        assert_eq!(executor::block_on(replay.read(&mut buf)).unwrap(), 4);
+        assert_eq!(&buf[..4], b"Hell");
+        assert_eq!(executor::block_on(replay.read(&mut buf)).unwrap(), 0);
+    }
+}
@@ -0,0 +1,72 @@
+use super::ChunkedMockIo;
+use crate::{Decoder, Encoder, FramedRead};
+
+use bytes::BytesMut;
+use futures::{executor, TryStreamExt};
+use std::fmt::Debug;
+
+/// Encode `items` with `codec`, decode the result again while feeding it in
+/// reads sized after `chunk_pattern`, and assert the same items come out.
+///
+/// The pattern is repeated until all bytes are fed, so `&[1]` feeds a byte at
+/// a time and `&[3, 1, 7]` mixes sizes. A size of zero is treated as one.
+///
+/// # Panics
+/// If encoding or decoding fails, or the decoded items differ.
+///
+/// # Example
+/// ```
+/// use futures_codec::{testing::assert_roundtrip, LinesCodec};
+///
+/// let lines = vec!["Hello\n".to_owned(), "World\n".to_owned()];
+/// assert_roundtrip(LinesCodec::new(), lines, &[1, 4]);
+/// ```
+pub fn assert_roundtrip<C, I>(mut codec: C, items: Vec<I>, chunk_pattern: &[usize])
+where
+    C: Encoder<Item = I> + Decoder<Item = I>,
+    <C as Encoder>::Error: Debug,
+    <C as Decoder>::Error: Debug,
+    I: Clone + PartialEq + Debug,
+{
+    let mut encoded = BytesMut::new();
+    for item in items.iter().cloned() {
+        codec.encode(item, &mut encoded).expect("encode failed");
+    }
+
+    let pattern = if chunk_pattern.is_empty() { &[usize::MAX][..] } else { chunk_pattern };
+    let mut io = ChunkedMockIo::builder();
+    let mut rest = &encoded[..];
+    for &size in pattern.iter().cycle() {
+        if rest.is_empty() {
+            break;
+        }
+        let (chunk, tail) = rest.split_at(size.max(1).min(rest.len()));
+        io = io.read(chunk);
+        rest = tail;
+    }
+
+    let framed = FramedRead::new(io.build(), codec);
+    let decoded: Vec<I> = executor::block_on(framed.try_collect()).expect("decode failed");
+    assert_eq!(decoded, items, "decoded items differ from encoded items");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{BytesLinesCodec, FragmentingCodec, SequencedCodec};
+    use bytes::Bytes;
+
+    #[test]
+    fn wrapped_codecs() {
+        let lines = vec![Bytes::from("Hello\n"), Bytes::from("World, how are you?\n")];
+        assert_roundtrip(BytesLinesCodec::new(), lines.clone(), &[1]);
+        assert_roundtrip(FragmentingCodec::new(BytesLinesCodec::new(), 12), lines.clone(), &[5, 2]);
+        assert_roundtrip(SequencedCodec::new_u32(BytesLinesCodec::new()), lines, &[3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "decode failed")]
+    fn incomplete_frame() {
+        assert_roundtrip(BytesLinesCodec::new(), vec![Bytes::from("no newline")], &[]);
+    }
+}
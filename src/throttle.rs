@@ -0,0 +1,212 @@
+use futures::{ready, Sink};
+use pin_project_lite::pin_project;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+pin_project! {
+    /// A `Sink` that limits the rate at which items are passed on to an
+    /// inner `Sink`, such as a `FramedWrite`.
+    ///
+    /// Frames and bytes per second are limited with token buckets that hold
+    /// up to one second worth of traffic. `poll_ready` waits until both have
+    /// room again, using the futures returned by `timer` to sleep. The bytes
+    /// of an item are its length as a byte slice.
+    ///
+    /// # Example
+    /// ```
+    /// use futures::{executor, future, SinkExt};
+    /// use futures_codec::{FramedWrite, LinesCodec, Throttled};
+    ///
+    /// let framed = FramedWrite::new(Vec::new(), LinesCodec::new());
+    /// // Use the delay of your runtime here, `future::ready` spins instead
+    /// let mut throttled = Throttled::new(framed, |_| future::ready(()))
+    ///     .frames_per_sec(100)
+    ///     .bytes_per_sec(64 * 1024);
+    ///
+    /// executor::block_on(async move {
+    ///     throttled.send("Hello\n".to_owned()).await.unwrap();
+    /// })
+    /// ```
+    pub struct Throttled<S, T, F> {
+        #[pin]
+        inner: S,
+        timer: T,
+        #[pin]
+        delay: Option<F>,
+        frames: Option<Bucket>,
+        bytes: Option<Bucket>,
+    }
+}
+
+impl<S, T, F> Throttled<S, T, F>
+where
+    T: FnMut(Duration) -> F,
+    F: Future<Output = ()>,
+{
+    /// Wrap `inner` without any limits, sleeping with the futures returned
+    /// by `timer`
+    pub fn new(inner: S, timer: T) -> Self {
+        Self {
+            inner,
+            timer,
+            delay: None,
+            frames: None,
+            bytes: None,
+        }
+    }
+
+    /// Pass on at most `rate` items per second
+    ///
+    /// # Panics
+    /// If `rate` is zero.
+    pub fn frames_per_sec(mut self, rate: u32) -> Self {
+        assert!(rate > 0, "rate must not be zero");
+        self.frames = Some(Bucket::new(f64::from(rate)));
+        self
+    }
+
+    /// Pass on at most `rate` bytes per second
+    ///
+    /// # Panics
+    /// If `rate` is zero.
+    pub fn bytes_per_sec(mut self, rate: u32) -> Self {
+        assert!(rate > 0, "rate must not be zero");
+        self.bytes = Some(Bucket::new(f64::from(rate)));
+        self
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Return the inner `Sink`
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, T, F, I> Sink<I> for Throttled<S, T, F>
+where
+    S: Sink<I>,
+    T: FnMut(Duration) -> F,
+    F: Future<Output = ()>,
+    I: AsRef<[u8]>,
+{
+    type Error = S::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        let mut this = self.project();
+        loop {
+            if let Some(delay) = this.delay.as_mut().as_pin_mut() {
+                ready!(delay.poll(cx));
+                this.delay.set(None);
+            }
+
+            let now = Instant::now();
+            // Bytes may go into debt by one item, as its length is only known
+            // once it is sent
+            let frames = wait_for(this.frames, now, 1.0);
+            let wait = frames.max(wait_for(this.bytes, now, f64::MIN_POSITIVE));
+            if wait == Duration::from_secs(0) {
+                return this.inner.poll_ready(cx);
+            }
+            this.delay.set(Some((this.timer)(wait)));
+        }
+    }
+    fn start_send(self: Pin<&mut Self>, item: I) -> Result<(), Self::Error> {
+        let this = self.project();
+        if let Some(frames) = this.frames {
+            frames.tokens -= 1.0;
+        }
+        if let Some(bytes) = this.bytes {
+            bytes.tokens -= item.as_ref().len() as f64;
+        }
+        this.inner.start_send(item)
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_flush(cx)
+    }
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_close(cx)
+    }
+}
+
+/// A token bucket refilled at `rate` tokens per second, holding at most
+/// `rate` tokens.
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl Bucket {
+    fn new(rate: f64) -> Self {
+        Self {
+            rate,
+            tokens: rate,
+            refilled: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.refilled = now;
+    }
+}
+
+/// How long to wait until `bucket` holds `tokens`
+fn wait_for(bucket: &mut Option<Bucket>, now: Instant, tokens: f64) -> Duration {
+    match bucket {
+        Some(bucket) => {
+            bucket.refill(now);
+            if bucket.tokens >= tokens {
+                Duration::from_secs(0)
+            } else {
+                Duration::from_secs_f64((tokens - bucket.tokens) / bucket.rate)
+            }
+        }
+        None => Duration::from_secs(0),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::channel::mpsc;
+    use futures::{executor, future, SinkExt};
+
+    #[test]
+    fn waits_when_bucket_is_empty() {
+        let (tx, _rx) = mpsc::unbounded::<Vec<u8>>();
+        let mut waits = Vec::new();
+        let timer = |wait| {
+            waits.push(wait);
+            future::ready(())
+        };
+        let mut throttled = Throttled::new(tx, timer).frames_per_sec(1000);
+
+        executor::block_on(async {
+            for _ in 0..1001 {
+                throttled.send(vec![0]).await.unwrap();
+            }
+        });
+        drop(throttled);
+        assert!(!waits.is_empty());
+        assert!(waits[0] <= Duration::from_millis(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "rate must not be zero")]
+    fn zero_rate() {
+        let (tx, _rx) = mpsc::unbounded::<Vec<u8>>();
+        let _ = Throttled::new(tx, |_| future::ready(())).bytes_per_sec(0);
+    }
+}
@@ -0,0 +1,43 @@
+//! Adapters for browser streams.
+//!
+//! Enabled with the `wasm` feature. `wasm-streams` exposes a byte
+//! `ReadableStream` and a `WritableStream` as `AsyncRead` and `AsyncWrite`
+//! of the released `futures-io` 0.3. [`readable`] and [`writable`] wrap them
+//! in [`IoCompat`], so `Framed`, `FramedRead` and `FramedWrite` work in the
+//! browser.
+//!
+//! # Example
+//! ```no_run
+//! use futures::TryStreamExt;
+//! use futures_codec::{wasm, FramedRead, LinesCodec};
+//! use wasm_streams::readable::sys::ReadableStream;
+//!
+//! async fn lines(body: ReadableStream) {
+//!     let mut framed = FramedRead::new(wasm::readable(body), LinesCodec::new());
+//!     while let Some(line) = framed.try_next().await.unwrap() {
+//!         println!("{:?}", line);
+//!     }
+//! }
+//! ```
+use crate::IoCompat;
+
+use wasm_streams::readable::{sys::ReadableStream, IntoAsyncRead};
+use wasm_streams::writable::{sys::WritableStream, IntoAsyncWrite};
+use wasm_streams::{ReadableStream as Readable, WritableStream as Writable};
+
+/// Read from a browser byte `ReadableStream`, such as the body of a `fetch`
+/// response.
+///
+/// # Panics
+/// If `stream` is not a readable byte stream.
+pub fn readable(stream: ReadableStream) -> IoCompat<IntoAsyncRead<'static>> {
+    IoCompat::new(Readable::from_raw(stream).into_async_read())
+}
+
+/// Write to a browser `WritableStream` that accepts `Uint8Array` chunks.
+///
+/// # Panics
+/// If `stream` is locked.
+pub fn writable(stream: WritableStream) -> IoCompat<IntoAsyncWrite<'static>> {
+    IoCompat::new(Writable::from_raw(stream).into_async_write())
+}
@@ -26,7 +26,7 @@ fn receive_twice()
 		let mut incoming     = listener.incoming();
 		let stream           = incoming.next().await.expect( "get stream" ).expect( "get stream" );
 
-		let mut framed = Framed::new( stream, LinesCodec {} );
+		let mut framed = Framed::new( stream, LinesCodec::new() );
 
 		framed.send( "A line\n"       .to_string() ).await.expect( "Send a line"        );
 		framed.send( "A second line\n".to_string() ).await.expect( "Send a second line" );
@@ -38,7 +38,7 @@ fn receive_twice()
 		let     socket_addr  = "127.0.0.1:3323".parse().expect( "parse address" );
 		let stream = TcpStream::connect(&socket_addr).await.expect( "connect tcp" );
 
-		let mut framed = Framed::new( stream, LinesCodec {} );
+		let mut framed = Framed::new( stream, LinesCodec::new() );
 
 
 		let res = framed.next().await.expect( "Receive some" ).expect( "Receive a line" );